@@ -0,0 +1,375 @@
+mod state;
+
+use std::error::Error;
+use std::path::PathBuf;
+
+use anchor_lang::prelude::Pubkey;
+use clap::{Parser, Subcommand};
+use comptoir::{Collection, Comptoir};
+use comptoir_client::{pda, Client, CollectionParams, CollectionUpdate};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signature, Signer};
+use solana_sdk::transaction::Transaction;
+
+use crate::state::{fetch_buy_offers, fetch_collection, fetch_comptoir, fetch_sell_orders, is_expired};
+
+/// Administers Comptoir marketplaces: comptoirs, collections, listings and escrows
+#[derive(Parser)]
+#[clap(version)]
+struct Args {
+    /// RPC node, a local validator by default
+    #[clap(long, default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// Keypair signing and paying for the transactions
+    #[clap(long, default_value = "~/.config/solana/id.json")]
+    keypair: String,
+    /// Comptoir program id, the one declared by the program by default
+    #[clap(long)]
+    program_id: Option<Pubkey>,
+    /// Comptoir to administer, the one owned by the keypair by default
+    #[clap(long)]
+    comptoir: Option<Pubkey>,
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Creates the comptoir owned by the keypair
+    CreateComptoir {
+        #[clap(long)]
+        mint: Pubkey,
+        /// In basis points
+        #[clap(long)]
+        fees: u16,
+        #[clap(long)]
+        fees_destination: Pubkey,
+    },
+    /// Updates the comptoir, options left out are not changed
+    UpdateComptoir {
+        #[clap(long)]
+        fees: Option<u16>,
+        #[clap(long)]
+        fees_destination: Option<Pubkey>,
+        #[clap(long)]
+        authority: Option<Pubkey>,
+    },
+    /// Prints the comptoir
+    ShowComptoir,
+    /// Creates a collection of the comptoir
+    CreateCollection {
+        #[clap(long)]
+        name: String,
+        #[clap(long)]
+        symbol: String,
+        #[clap(long)]
+        required_verifier: Pubkey,
+        /// In basis points, takes priority over the comptoir fees
+        #[clap(long)]
+        fee: Option<u16>,
+        #[clap(long)]
+        ignore_creator_fee: bool,
+    },
+    /// Updates a collection of the comptoir, options left out are not changed
+    UpdateCollection {
+        #[clap(long)]
+        name: String,
+        #[clap(long)]
+        fee: Option<u16>,
+        #[clap(long)]
+        symbol: Option<String>,
+        #[clap(long)]
+        required_verifier: Option<Pubkey>,
+        #[clap(long)]
+        ignore_creator_fee: Option<bool>,
+    },
+    /// Prints a collection of the comptoir
+    ShowCollection {
+        #[clap(long)]
+        name: String,
+    },
+    /// Lists the sell orders of a mint, across comptoirs
+    SellOrders {
+        #[clap(long)]
+        mint: Pubkey,
+    },
+    /// Lists the buy offers of a mint, across comptoirs
+    BuyOffers {
+        #[clap(long)]
+        mint: Pubkey,
+    },
+    /// Computes the split of a sale without touching the chain
+    Fees {
+        /// Unit price
+        #[clap(long)]
+        price: u64,
+        #[clap(long, default_value_t = 1)]
+        quantity: u64,
+        #[clap(long)]
+        comptoir_fees: u16,
+        #[clap(long)]
+        collection_fee: Option<u16>,
+        /// Royalties of the metadata
+        #[clap(long, default_value_t = 0)]
+        seller_fee_basis_points: u16,
+        #[clap(long)]
+        ignore_creator_fee: bool,
+        /// Share of each creator of the metadata, in percents
+        #[clap(long)]
+        creator_share: Vec<u8>,
+    },
+    /// Closes the expired sell orders and buy offers of a mint, returning items and escrowed funds to their owners
+    SweepExpired {
+        #[clap(long)]
+        mint: Pubkey,
+    },
+    /// Moves the items of the sell orders of a mint listed before vaults were per comptoir
+    MigrateVaults {
+        #[clap(long)]
+        mint: Pubkey,
+    },
+    /// Switches the comptoir to a new mint and creates its escrow, offers funded in the previous mint stay reachable
+    MigrateEscrow {
+        #[clap(long)]
+        mint: Pubkey,
+        #[clap(long)]
+        fees_destination: Pubkey,
+    },
+}
+
+fn read_keypair(path: &str) -> Result<Keypair, Box<dyn Error>> {
+    let mut path = PathBuf::from(path);
+    if let Ok(stripped) = path.strip_prefix("~") {
+        path = PathBuf::from(std::env::var("HOME")?).join(stripped);
+    }
+    read_keypair_file(&path).map_err(|err| format!("cannot read keypair {}: {}", path.display(), err).into())
+}
+
+fn send(rpc: &RpcClient, payer: &Keypair, instructions: &[Instruction]) -> Result<Signature, Box<dyn Error>> {
+    let blockhash = rpc.get_latest_blockhash()?;
+    let transaction = Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &[payer], blockhash);
+    Ok(rpc.send_and_confirm_transaction(&transaction)?)
+}
+
+fn format_expiration(expires_at: Option<i64>) -> String {
+    match expires_at {
+        Some(expiration) => expiration.to_string(),
+        None => "never".to_string(),
+    }
+}
+
+// Only the fee fields of the accounts matter to the program split, the rest is left empty
+fn fee_terms(comptoir_fees: u16, collection_fee: Option<u16>, ignore_creator_fee: bool) -> (Comptoir, Collection) {
+    let comptoir = Comptoir {
+        fees: comptoir_fees,
+        fees_destination: Pubkey::default(),
+        authority: Pubkey::default(),
+        mint: Pubkey::default(),
+    };
+    let collection = Collection {
+        comptoir_key: Pubkey::default(),
+        name: String::new(),
+        symbol: String::new(),
+        required_verifier: Pubkey::default(),
+        fees: collection_fee,
+        ignore_creator_fee,
+        traits_root: None,
+        certified_collection: None,
+        custom_currencies: false,
+    };
+    (comptoir, collection)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let Args { url, keypair, program_id, comptoir: comptoir_address, command } = Args::parse();
+    let client = Client::new(program_id.unwrap_or(comptoir::ID));
+    let rpc = RpcClient::new_with_commitment(url, CommitmentConfig::confirmed());
+    // Only commands sending transactions or targeting the comptoir need the keypair
+    let signer = || -> Result<(Keypair, Pubkey), Box<dyn Error>> {
+        let payer = read_keypair(&keypair)?;
+        let comptoir = comptoir_address.unwrap_or_else(|| pda::comptoir(&client.program_id, &payer.pubkey()));
+        Ok((payer, comptoir))
+    };
+
+    match command {
+        Command::CreateComptoir { mint, fees, fees_destination } => {
+            // The created comptoir is always the one owned by the keypair
+            let (payer, _) = signer()?;
+            let token_program = rpc.get_account(&mint)?.owner;
+            let signature = send(&rpc, &payer, &[client.create_comptoir(&payer.pubkey(), &mint, &token_program, fees, &fees_destination)])?;
+            println!("Created comptoir {} in {}", pda::comptoir(&client.program_id, &payer.pubkey()), signature);
+        }
+        Command::UpdateComptoir { fees, fees_destination, authority } => {
+            let (payer, comptoir) = signer()?;
+            let signature = send(&rpc, &payer, &[client.update_comptoir(&payer.pubkey(), &comptoir, fees, fees_destination, authority)])?;
+            println!("Updated comptoir {} in {}", comptoir, signature);
+        }
+        Command::ShowComptoir => {
+            let (_, comptoir) = signer()?;
+            let account = fetch_comptoir(&rpc, &comptoir)?;
+            println!("comptoir:         {}", comptoir);
+            println!("authority:        {}", account.authority);
+            println!("mint:             {}", account.mint);
+            println!("escrow:           {}", pda::escrow(&client.program_id, &comptoir, &account.mint));
+            println!("fees:             {}", account.fees);
+            println!("fees destination: {}", account.fees_destination);
+        }
+        Command::CreateCollection { name, symbol, required_verifier, fee, ignore_creator_fee } => {
+            let (payer, comptoir) = signer()?;
+            let params = CollectionParams {
+                name,
+                symbol,
+                required_verifier,
+                fee,
+                ignore_creator_fee,
+            };
+            let signature = send(&rpc, &payer, &[client.create_collection(&payer.pubkey(), &comptoir, &params)])?;
+            println!("Created collection {} in {}", pda::collection(&client.program_id, &comptoir, &params.name), signature);
+        }
+        Command::UpdateCollection { name, fee, symbol, required_verifier, ignore_creator_fee } => {
+            let (payer, comptoir) = signer()?;
+            let collection = pda::collection(&client.program_id, &comptoir, &name);
+            let update = CollectionUpdate {
+                fee,
+                symbol,
+                required_verifier,
+                ignore_creator_fee,
+            };
+            let signature = send(&rpc, &payer, &[client.update_collection(&payer.pubkey(), &comptoir, &collection, &update)])?;
+            println!("Updated collection {} in {}", collection, signature);
+        }
+        Command::ShowCollection { name } => {
+            let (_, comptoir) = signer()?;
+            let collection = pda::collection(&client.program_id, &comptoir, &name);
+            let account = fetch_collection(&rpc, &collection)?;
+            println!("collection:           {}", collection);
+            println!("name:                 {}", account.name);
+            println!("symbol:               {}", account.symbol);
+            println!("required verifier:    {}", account.required_verifier);
+            match account.fees {
+                Some(fee) => println!("fees:                 {}", fee),
+                None => println!("fees:                 comptoir fees"),
+            }
+            println!("ignore creator fee:   {}", account.ignore_creator_fee);
+            println!("traits root:          {}", account.traits_root.is_some());
+            if let Some(certified_collection) = account.certified_collection {
+                println!("certified collection: {}", certified_collection);
+            }
+            println!("custom currencies:    {}", account.custom_currencies);
+        }
+        Command::SellOrders { mint } => {
+            for (address, order) in fetch_sell_orders(&rpc, &client.program_id, &mint)? {
+                println!(
+                    "{} comptoir {} seller {} price {} quantity {} payment mint {} expires {}{}",
+                    address,
+                    order.comptoir,
+                    order.authority,
+                    order.price,
+                    order.quantity,
+                    order.payment_mint,
+                    format_expiration(order.expires_at),
+                    if order.vault_migrated { "" } else { " (legacy vault)" },
+                );
+            }
+        }
+        Command::BuyOffers { mint } => {
+            for (address, offer) in fetch_buy_offers(&rpc, &client.program_id, &mint)? {
+                println!(
+                    "{} comptoir {} buyer {} price {} quantity {} payment mint {} expires {}",
+                    address,
+                    offer.comptoir,
+                    offer.authority,
+                    offer.proposed_price,
+                    offer.quantity,
+                    offer.payment_mint,
+                    format_expiration(offer.expires_at),
+                );
+            }
+        }
+        Command::Fees { price, quantity, comptoir_fees, collection_fee, seller_fee_basis_points, ignore_creator_fee, creator_share } => {
+            let total = price.checked_mul(quantity).ok_or("amounts overflow")?;
+            let (comptoir, collection) = fee_terms(comptoir_fees, collection_fee, ignore_creator_fee);
+            let comptoir_fee = comptoir::get_comptoir_fee(&comptoir, &collection);
+            let (seller, comptoir_share, creators) = comptoir::split_amount(total, comptoir_fee, &collection, seller_fee_basis_points)?;
+            println!("total:    {}", total);
+            println!("seller:   {}", seller);
+            println!("comptoir: {}", comptoir_share);
+            println!("creators: {}", creators);
+            for (index, share) in creator_share.iter().enumerate() {
+                println!("  creator {}: {}", index, comptoir::calculate_fee(creators, *share as u16, 100)?);
+            }
+        }
+        Command::SweepExpired { mint } => {
+            let (payer, _) = signer()?;
+            let now = rpc.get_block_time(rpc.get_slot()?)?;
+            for (address, order) in fetch_sell_orders(&rpc, &client.program_id, &mint)? {
+                // Legacy vault orders have to be migrated before they can be closed
+                if !is_expired(order.expires_at, now) || !order.vault_migrated {
+                    continue;
+                }
+                let instruction = client.close_expired_sell_order(&order.authority, &order.comptoir, &address, &order.mint);
+                println!("Closed sell order {} in {}", address, send(&rpc, &payer, &[instruction])?);
+            }
+            for (address, offer) in fetch_buy_offers(&rpc, &client.program_id, &mint)? {
+                if !is_expired(offer.expires_at, now) {
+                    continue;
+                }
+                let instruction = client.close_expired_buy_offer(&offer.authority, &offer.comptoir, &address, &offer.payment_mint);
+                println!("Closed buy offer {} in {}", address, send(&rpc, &payer, &[instruction])?);
+            }
+        }
+        Command::MigrateVaults { mint } => {
+            let (payer, _) = signer()?;
+            for (address, order) in fetch_sell_orders(&rpc, &client.program_id, &mint)? {
+                if order.vault_migrated {
+                    continue;
+                }
+                let instruction = client.migrate_sell_order_vault(&payer.pubkey(), &order.comptoir, &address, &order.mint);
+                println!("Migrated sell order {} in {}", address, send(&rpc, &payer, &[instruction])?);
+            }
+        }
+        Command::MigrateEscrow { mint, fees_destination } => {
+            let (payer, comptoir) = signer()?;
+            let token_program = rpc.get_account(&mint)?.owner;
+            let instruction = client.update_comptoir_mint(&payer.pubkey(), &comptoir, &mint, &token_program, &fees_destination);
+            let signature = send(&rpc, &payer, &[instruction])?;
+            println!("Comptoir {} now uses escrow {} in {}", comptoir, pda::escrow(&client.program_id, &comptoir, &mint), signature);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collection_fee_takes_priority() {
+        let (comptoir, collection) = fee_terms(200, Some(100), false);
+        assert_eq!(comptoir::get_comptoir_fee(&comptoir, &collection), 100);
+        let (comptoir, collection) = fee_terms(200, None, false);
+        assert_eq!(comptoir::get_comptoir_fee(&comptoir, &collection), 200);
+    }
+
+    #[test]
+    fn ignored_creator_fees_go_to_the_seller() {
+        let (_, collection) = fee_terms(200, None, false);
+        assert_eq!(comptoir::split_amount(10_000, 200, &collection, 500).unwrap(), (9_300, 200, 500));
+        let (_, collection) = fee_terms(200, None, true);
+        assert_eq!(comptoir::split_amount(10_000, 200, &collection, 500).unwrap(), (9_800, 200, 0));
+    }
+
+    #[test]
+    fn parses_the_fees_command() {
+        let args = Args::try_parse_from(["comptoir", "fees", "--price", "1000", "--comptoir-fees", "200", "--creator-share", "60", "--creator-share", "40"]).unwrap();
+        match args.command {
+            Command::Fees { price, quantity, creator_share, .. } => {
+                assert_eq!((price, quantity), (1000, 1));
+                assert_eq!(creator_share, vec![60, 40]);
+            }
+            _ => panic!("expected the fees command"),
+        }
+    }
+}
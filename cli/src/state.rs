@@ -0,0 +1,174 @@
+use std::error::Error;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, Discriminator};
+use comptoir::{BuyOffer, Collection, Comptoir, SellOrder};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType};
+
+// Offsets of the mint in the accounts data, after the 8 bytes discriminator
+const SELL_ORDER_MINT_OFFSET: usize = 8 + 32 + 8 + 8;
+const BUY_OFFER_MINT_OFFSET: usize = 8 + 32;
+
+// Accounts are allocated with some slack, the trailing bytes are ignored
+fn decode<T: AccountDeserialize>(data: &[u8]) -> Result<T, Box<dyn Error>> {
+    Ok(T::try_deserialize(&mut &data[..])?)
+}
+
+pub fn fetch_comptoir(rpc: &RpcClient, address: &Pubkey) -> Result<Comptoir, Box<dyn Error>> {
+    decode(&rpc.get_account_data(address)?)
+}
+
+pub fn fetch_collection(rpc: &RpcClient, address: &Pubkey) -> Result<Collection, Box<dyn Error>> {
+    decode(&rpc.get_account_data(address)?)
+}
+
+// Address of an account that could not be decoded, with the reason
+type Skipped = (Pubkey, String);
+
+// Accounts that cannot be decoded, like legacy ones not migrated yet, are returned apart
+fn decode_all<T: AccountDeserialize>(accounts: Vec<(Pubkey, Vec<u8>)>) -> (Vec<(Pubkey, T)>, Vec<Skipped>) {
+    let mut decoded = Vec::new();
+    let mut skipped = Vec::new();
+    for (address, data) in accounts {
+        match decode(&data) {
+            Ok(account) => decoded.push((address, account)),
+            Err(err) => skipped.push((address, err.to_string())),
+        }
+    }
+    (decoded, skipped)
+}
+
+fn fetch_by_mint<T: AccountDeserialize + Discriminator>(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    mint_offset: usize,
+    mint: &Pubkey,
+) -> Result<Vec<(Pubkey, T)>, Box<dyn Error>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::Memcmp(Memcmp {
+                offset: 0,
+                bytes: MemcmpEncodedBytes::Base58(bs58::encode(T::discriminator()).into_string()),
+                encoding: None,
+            }),
+            RpcFilterType::Memcmp(Memcmp {
+                offset: mint_offset,
+                bytes: MemcmpEncodedBytes::Base58(mint.to_string()),
+                encoding: None,
+            }),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        with_context: None,
+    };
+    let accounts = rpc.get_program_accounts_with_config(program_id, config)?;
+    let (decoded, skipped) = decode_all(accounts.into_iter().map(|(address, account)| (address, account.data)).collect());
+    for (address, reason) in skipped {
+        eprintln!("Skipped {}, cannot be decoded: {}", address, reason);
+    }
+    Ok(decoded)
+}
+
+pub fn fetch_sell_orders(rpc: &RpcClient, program_id: &Pubkey, mint: &Pubkey) -> Result<Vec<(Pubkey, SellOrder)>, Box<dyn Error>> {
+    fetch_by_mint(rpc, program_id, SELL_ORDER_MINT_OFFSET, mint)
+}
+
+pub fn fetch_buy_offers(rpc: &RpcClient, program_id: &Pubkey, mint: &Pubkey) -> Result<Vec<(Pubkey, BuyOffer)>, Box<dyn Error>> {
+    fetch_by_mint(rpc, program_id, BUY_OFFER_MINT_OFFSET, mint)
+}
+
+pub fn is_expired(expires_at: Option<i64>, now: i64) -> bool {
+    match expires_at {
+        Some(expiration) => now >= expiration,
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::AccountSerialize;
+
+    fn key(byte: u8) -> Pubkey {
+        Pubkey::new_from_array([byte; 32])
+    }
+
+    fn sell_order() -> SellOrder {
+        SellOrder {
+            comptoir: key(1),
+            price: 1500,
+            quantity: 2,
+            mint: key(2),
+            authority: key(3),
+            destination: key(4),
+            price_schedule: None,
+            expires_at: Some(100),
+            vault_migrated: true,
+            payment_mint: key(5),
+            oracle_price: None,
+        }
+    }
+
+    fn buy_offer() -> BuyOffer {
+        BuyOffer {
+            comptoir: key(1),
+            mint: key(2),
+            proposed_price: 1500,
+            authority: key(3),
+            destination: key(4),
+            expires_at: None,
+            collection: key(6),
+            quantity: 1,
+            trait_hash: None,
+            payment_mint: key(5),
+        }
+    }
+
+    fn serialize<T: AccountSerialize>(account: &T) -> Vec<u8> {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn filters_on_the_mint_offsets() {
+        let data = serialize(&sell_order());
+        assert_eq!(&data[SELL_ORDER_MINT_OFFSET..SELL_ORDER_MINT_OFFSET + 32], key(2).as_ref());
+        let data = serialize(&buy_offer());
+        assert_eq!(&data[BUY_OFFER_MINT_OFFSET..BUY_OFFER_MINT_OFFSET + 32], key(2).as_ref());
+    }
+
+    #[test]
+    fn ignores_the_trailing_bytes() {
+        let mut data = serialize(&sell_order());
+        data.extend_from_slice(&[0; 64]);
+        let order: SellOrder = decode(&data).unwrap();
+        assert_eq!(order.price, 1500);
+        assert_eq!(order.expires_at, Some(100));
+    }
+
+    #[test]
+    fn skips_the_accounts_it_cannot_decode() {
+        let data = serialize(&sell_order());
+        // Legacy orders stop before the vault_migrated field
+        let legacy = data[..SELL_ORDER_MINT_OFFSET + 32 * 3 + 1 + 9].to_vec();
+        let accounts = vec![(key(7), data.clone()), (key(8), legacy), (key(9), serialize(&buy_offer()))];
+        let (decoded, skipped): (Vec<(Pubkey, SellOrder)>, _) = decode_all(accounts);
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].0, key(7));
+        let skipped: Vec<Pubkey> = skipped.into_iter().map(|(address, _)| address).collect();
+        assert_eq!(skipped, vec![key(8), key(9)]);
+    }
+
+    #[test]
+    fn expires_at_the_expiration() {
+        assert!(!is_expired(None, i64::MAX));
+        assert!(!is_expired(Some(100), 99));
+        assert!(is_expired(Some(100), 100));
+    }
+}
@@ -0,0 +1,2280 @@
+use std::str::FromStr;
+
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::{system_program, sysvar};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use comptoir::compressed::CompressedMetadata;
+use comptoir::constant::{BUBBLEGUM_PROGRAM, SPL_ACCOUNT_COMPRESSION_PROGRAM, SPL_NOOP_PROGRAM, TOKEN_2022_PROGRAM};
+use comptoir::{accounts, instruction, OraclePrice, PriceSchedule, SwapAsk};
+
+use crate::pda;
+use crate::remaining::{self, BundleItemFill, SellOrderFill, SwapGift, TransferHook};
+
+const AUTHORIZATION_RULES_PROGRAM: &str = "auth9SigNpDKz4sJJ1DfCTuZrZNSAgh9sFD3rboVmgg";
+
+pub struct CollectionParams {
+    pub name: String,
+    pub symbol: String,
+    pub required_verifier: Pubkey,
+    pub fee: Option<u16>, // Takes priority over the comptoir fees
+    pub ignore_creator_fee: bool,
+}
+
+// Fields left to None are not changed
+#[derive(Default)]
+pub struct CollectionUpdate {
+    pub fee: Option<u16>,
+    pub symbol: Option<String>,
+    pub required_verifier: Option<Pubkey>,
+    pub ignore_creator_fee: Option<bool>,
+}
+
+pub struct SellOrderParams {
+    pub price: u64,
+    pub quantity: u64,
+    pub destination: Pubkey,
+    pub price_schedule: Option<PriceSchedule>,
+    pub expires_at: Option<i64>,
+    pub currency: Option<Pubkey>, // Registered currency account when not selling in the comptoir mint
+}
+
+pub struct SellOrderId {
+    pub order_id: u64,
+    pub use_counter: bool, // Enforces sequential ids through the seller order counter
+}
+
+pub struct BuyParams {
+    pub quantity: u64,
+    pub max_total_price: u64,
+    pub max_unit_price: Option<u64>,
+    pub payment_mint: Pubkey,
+    pub fees_destination: Pubkey,
+    pub creators: Vec<Pubkey>, // Creators wallets in the metadata order, empty when the collection ignores them
+    pub fills: Vec<SellOrderFill>,
+    pub currency: Option<Pubkey>,
+}
+
+pub struct OfferParams {
+    pub price: u64, // Unit price
+    pub quantity: u64,
+    pub expires_at: Option<i64>,
+    pub currency: Option<Pubkey>,
+}
+
+pub struct ExecuteOfferParams {
+    pub buyer: Pubkey,
+    pub nft_mint: Pubkey,
+    pub buyer_nft_destination: Pubkey,
+    pub payment_mint: Pubkey,
+    pub fees_destination: Pubkey,
+    pub seller_funds_destination: Pubkey,
+    pub quantity: u64,
+    pub trait_proof: Vec<[u8; 32]>,
+    pub creators: Vec<Pubkey>,
+    pub currency: Option<Pubkey>,
+}
+
+// Orders of a single item at a fixed price, such as compressed and programmable NFTs
+pub struct ListingParams {
+    pub price: u64,
+    pub destination: Pubkey,
+    pub expires_at: Option<i64>,
+}
+
+// Crosses the best ask and the best bid of the order book of the mint
+pub struct MatchParams {
+    pub sell_order: Pubkey,
+    pub seller_funds_destination: Pubkey,
+    pub buy_offer: Pubkey,
+    pub buyer: Pubkey,
+    pub buyer_nft_destination: Pubkey,
+    pub payment_mint: Pubkey,
+    pub fees_destination: Pubkey,
+    pub creators: Vec<Pubkey>,
+    pub currency: Option<Pubkey>,
+}
+
+// An order book with what decides whether its currency is still accepted
+pub struct BookParams {
+    pub comptoir: Pubkey,
+    pub collection: Pubkey,
+    pub order_book: Pubkey,
+    pub currency: Option<Pubkey>,
+}
+
+pub struct BundleParams {
+    pub bundle_id: u64,
+    pub price: u64,
+    pub destination: Pubkey,
+    pub expires_at: Option<i64>,
+    pub currency: Option<Pubkey>, // Currency account of the comptoir when not selling in the comptoir mint
+}
+
+pub struct BuyBundleParams {
+    pub seller: Pubkey,
+    pub seller_funds_destination: Pubkey,
+    pub payment_mint: Pubkey,
+    pub fees_destination: Pubkey,
+    pub max_price: u64,
+    pub items: Vec<BundleItemFill>, // In bundle order
+    pub currency: Option<Pubkey>,
+}
+
+pub struct SwapOfferParams {
+    pub swap_id: u64,
+    pub requested: Vec<SwapAsk>,
+    pub token_amount: u64, // Sent along the offered items, in the payment mint
+    pub payment_mint: Pubkey,
+    pub expires_at: Option<i64>,
+    pub currency: Option<Pubkey>, // Currency account of the comptoir when not paying in the comptoir mint
+}
+
+pub struct AcceptSwapParams {
+    pub authority: Pubkey,
+    pub counterparty_funds_account: Pubkey,
+    pub payment_mint: Pubkey,
+    pub fees_destination: Pubkey,
+    pub offered_mints: Vec<Pubkey>,
+    pub gifts: Vec<SwapGift>, // In the order of the requested items
+    pub currency: Option<Pubkey>,
+}
+
+pub struct AuctionParams {
+    pub reserve_price: u64,
+    pub min_bid_increment: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub quantity: u64,
+    pub destination: Pubkey,
+}
+
+pub struct SettleAuctionParams {
+    pub seller: Pubkey,
+    pub destination: Pubkey,
+    pub winner: Pubkey,
+    pub winning_bid: Pubkey,
+    pub payment_mint: Pubkey,
+    pub fees_destination: Pubkey,
+    pub creators: Vec<Pubkey>,
+    pub currency: Option<Pubkey>,
+}
+
+// A leaf of a Bubblegum tree with what is needed to prove it, the root and proof nodes being the current ones
+pub struct CompressedItem {
+    pub merkle_tree: Pubkey,
+    pub nonce: u64,
+    pub index: u32,
+    pub root: [u8; 32],
+    pub proof: Vec<[u8; 32]>,
+    pub metadata: CompressedMetadata,
+}
+
+impl CompressedItem {
+    pub fn asset_id(&self) -> Pubkey {
+        pda::asset_id(&self.merkle_tree, self.nonce)
+    }
+}
+
+pub struct CompressedSaleParams {
+    pub counterparty: Pubkey, // The seller when buying, the buyer when executing an offer
+    pub seller_funds_destination: Pubkey,
+    pub payment_mint: Pubkey,
+    pub fees_destination: Pubkey,
+    pub price: u64, // Maximum price when buying, price of the offer when executing it
+    pub creators: Vec<Pubkey>,
+    pub currency: Option<Pubkey>,
+}
+
+// A programmable NFT and the rule set of its metadata, if any
+pub struct PnftItem {
+    pub mint: Pubkey,
+    pub rule_set: Option<Pubkey>,
+}
+
+pub struct PnftSaleParams {
+    pub counterparty: Pubkey, // The seller when buying, the buyer when executing an offer
+    pub seller_funds_destination: Pubkey,
+    pub payment_mint: Pubkey,
+    pub fees_destination: Pubkey,
+    pub max_price: u64, // Only used when buying
+    pub trait_proof: Vec<[u8; 32]>, // Only used when executing an offer
+    pub creators: Vec<Pubkey>,
+    pub currency: Option<Pubkey>,
+}
+
+pub struct Token22BuyParams {
+    pub seller_funds_destination: Pubkey,
+    pub payment_mint: Pubkey,
+    pub payment_token_program: Pubkey, // Owner of the payment mint, Token-2022 or the token program
+    pub fees_destination: Pubkey,
+    pub quantity: u64,
+    pub max_total_price: u64,
+    pub creators: Vec<Pubkey>,
+    pub currency: Option<Pubkey>,
+    pub item_hook: Option<TransferHook>,
+    pub currency_hook: Option<TransferHook>,
+}
+
+// A Token-2022 currency with the accounts of its transfer hook when it has one
+pub struct Token22Payment {
+    pub mint: Pubkey,
+    pub hook: Option<TransferHook>,
+}
+
+// Wallets pay native SOL directly and tokens from their associated token account
+pub fn paying_account(wallet: &Pubkey, payment_mint: &Pubkey) -> Pubkey {
+    if *payment_mint == spl_token::native_mint::id() {
+        return *wallet;
+    }
+    get_associated_token_address(wallet, payment_mint)
+}
+
+fn token_2022_program() -> Pubkey {
+    Pubkey::from_str(TOKEN_2022_PROGRAM).unwrap()
+}
+
+// Wallets pay and are paid Token-2022 currencies on their associated account under Token-2022
+fn token22_account(wallet: &Pubkey, payment_mint: &Pubkey) -> Pubkey {
+    pda::associated_token_account(wallet, payment_mint, &token_2022_program())
+}
+
+fn authorization_rules_program() -> Pubkey {
+    Pubkey::from_str(AUTHORIZATION_RULES_PROGRAM).unwrap()
+}
+
+// Items without a rule set pass the token metadata program in its place
+fn authorization_rules(item: &PnftItem) -> Pubkey {
+    item.rule_set.unwrap_or_else(mpl_token_metadata::id)
+}
+
+pub struct Client {
+    pub program_id: Pubkey,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Client { program_id: comptoir::ID }
+    }
+}
+
+impl Client {
+    pub fn new(program_id: Pubkey) -> Client {
+        Client { program_id }
+    }
+
+    fn instruction<A: ToAccountMetas, D: InstructionData>(&self, accounts: A, data: D, remaining_accounts: Vec<AccountMeta>) -> Instruction {
+        let mut metas = accounts.to_account_metas(None);
+        metas.extend(remaining_accounts);
+        Instruction {
+            program_id: self.program_id,
+            accounts: metas,
+            data: data.data(),
+        }
+    }
+
+    // The owner pays for and administers the comptoir. The token program is the owner of the mint, the escrow being
+    // created under it
+    pub fn create_comptoir(&self, owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey, fees: u16, fees_destination: &Pubkey) -> Instruction {
+        let comptoir = pda::comptoir(&self.program_id, owner);
+        self.instruction(
+            accounts::CreateComptoir {
+                payer: *owner,
+                comptoir,
+                mint: *mint,
+                escrow: pda::escrow(&self.program_id, &comptoir, mint),
+                system_program: system_program::ID,
+                token_program: *token_program,
+                rent: sysvar::rent::ID,
+            },
+            instruction::CreateComptoir {
+                mint: *mint,
+                fees,
+                fees_destination: *fees_destination,
+                authority: *owner,
+            },
+            vec![],
+        )
+    }
+
+    pub fn update_comptoir(
+        &self,
+        authority: &Pubkey,
+        comptoir: &Pubkey,
+        fees: Option<u16>,
+        fees_destination: Option<Pubkey>,
+        new_authority: Option<Pubkey>,
+    ) -> Instruction {
+        self.instruction(
+            accounts::UpdateComptoir {
+                authority: *authority,
+                comptoir: *comptoir,
+            },
+            instruction::UpdateComptoir {
+                optional_fees: fees,
+                optional_fees_destination: fees_destination,
+                optional_authority: new_authority,
+            },
+            vec![],
+        )
+    }
+
+    pub fn update_comptoir_mint(
+        &self,
+        authority: &Pubkey,
+        comptoir: &Pubkey,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+        fees_destination: &Pubkey,
+    ) -> Instruction {
+        self.instruction(
+            accounts::UpdateComptoirMint {
+                authority: *authority,
+                comptoir: *comptoir,
+                mint: *mint,
+                escrow: pda::escrow(&self.program_id, comptoir, mint),
+                system_program: system_program::ID,
+                token_program: *token_program,
+                rent: sysvar::rent::ID,
+            },
+            instruction::UpdateComptoirMint {
+                mint: *mint,
+                fees_destination: *fees_destination,
+            },
+            vec![],
+        )
+    }
+
+    pub fn create_collection(&self, authority: &Pubkey, comptoir: &Pubkey, params: &CollectionParams) -> Instruction {
+        self.instruction(
+            accounts::CreateCollection {
+                authority: *authority,
+                comptoir: *comptoir,
+                collection: pda::collection(&self.program_id, comptoir, &params.name),
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::CreateCollection {
+                name: params.name.clone(),
+                symbol: params.symbol.clone(),
+                required_verifier: params.required_verifier,
+                fee: params.fee,
+                ignore_fee: params.ignore_creator_fee,
+            },
+            vec![],
+        )
+    }
+
+    pub fn update_collection(&self, authority: &Pubkey, comptoir: &Pubkey, collection: &Pubkey, update: &CollectionUpdate) -> Instruction {
+        self.instruction(
+            accounts::UpdateCollection {
+                authority: *authority,
+                comptoir: *comptoir,
+                collection: *collection,
+            },
+            instruction::UpdateCollection {
+                optional_fee: update.fee,
+                optional_symbol: update.symbol.clone(),
+                optional_required_verifier: update.required_verifier,
+                optional_ignore_creator_fee: update.ignore_creator_fee,
+            },
+            vec![],
+        )
+    }
+
+    // Token-2022 mints are added with their own token program, which creates their escrow
+    pub fn add_currency(&self, authority: &Pubkey, comptoir: &Pubkey, mint: &Pubkey, token_program: &Pubkey, fees_destination: &Pubkey) -> Instruction {
+        self.instruction(
+            accounts::AddCurrency {
+                authority: *authority,
+                comptoir: *comptoir,
+                mint: *mint,
+                currency: pda::currency(&self.program_id, comptoir, mint),
+                escrow: pda::escrow(&self.program_id, comptoir, mint),
+                system_program: system_program::ID,
+                token_program: *token_program,
+                rent: sysvar::rent::ID,
+            },
+            instruction::AddCurrency {
+                fees_destination: *fees_destination,
+            },
+            vec![],
+        )
+    }
+
+    // Lists the items held by the seller associated token account
+    pub fn create_sell_order(
+        &self,
+        seller: &Pubkey,
+        comptoir: &Pubkey,
+        collection: &Pubkey,
+        nft_mint: &Pubkey,
+        params: &SellOrderParams,
+    ) -> Instruction {
+        let seller_nft_token_account = get_associated_token_address(seller, nft_mint);
+        self.instruction(
+            accounts::CreateSellOrder {
+                payer: *seller,
+                seller_nft_token_account,
+                comptoir: *comptoir,
+                collection: *collection,
+                mint: *nft_mint,
+                metadata: pda::metadata(nft_mint),
+                vault: pda::vault(&self.program_id, comptoir, nft_mint),
+                sell_order: pda::sell_order(&self.program_id, &seller_nft_token_account, params.price),
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::CreateSellOrder {
+                price: params.price,
+                quantity: params.quantity,
+                destination: params.destination,
+                price_schedule: params.price_schedule,
+                expires_at: params.expires_at,
+            },
+            remaining::currency(params.currency.as_ref()),
+        )
+    }
+
+    pub fn create_seller_order_counter(&self, seller: &Pubkey) -> Instruction {
+        self.instruction(
+            accounts::CreateSellerOrderCounter {
+                payer: *seller,
+                counter: pda::seller_order_counter(&self.program_id, seller),
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::CreateSellerOrderCounter {},
+            vec![],
+        )
+    }
+
+    pub fn create_sell_order_with_id(
+        &self,
+        seller: &Pubkey,
+        comptoir: &Pubkey,
+        collection: &Pubkey,
+        nft_mint: &Pubkey,
+        id: &SellOrderId,
+        params: &SellOrderParams,
+    ) -> Instruction {
+        let seller_nft_token_account = get_associated_token_address(seller, nft_mint);
+        // The counter goes first and the currency last
+        let mut remaining_accounts = Vec::new();
+        if id.use_counter {
+            remaining_accounts.push(AccountMeta::new(pda::seller_order_counter(&self.program_id, seller), false));
+        }
+        remaining_accounts.extend(remaining::currency(params.currency.as_ref()));
+        self.instruction(
+            accounts::CreateSellOrderWithId {
+                payer: *seller,
+                seller_nft_token_account,
+                comptoir: *comptoir,
+                collection: *collection,
+                mint: *nft_mint,
+                metadata: pda::metadata(nft_mint),
+                vault: pda::vault(&self.program_id, comptoir, nft_mint),
+                sell_order: pda::sell_order_with_id(&self.program_id, &seller_nft_token_account, id.order_id),
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::CreateSellOrderWithId {
+                order_id: id.order_id,
+                price: params.price,
+                quantity: params.quantity,
+                destination: params.destination,
+                price_schedule: params.price_schedule,
+                expires_at: params.expires_at,
+            },
+            remaining_accounts,
+        )
+    }
+
+    pub fn remove_sell_order(&self, seller: &Pubkey, comptoir: &Pubkey, sell_order: &Pubkey, nft_mint: &Pubkey, quantity: u64) -> Instruction {
+        self.instruction(
+            accounts::RemoveSellOrder {
+                authority: *seller,
+                seller_nft_token_account: get_associated_token_address(seller, nft_mint),
+                sell_order: *sell_order,
+                vault: pda::vault(&self.program_id, comptoir, nft_mint),
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::RemoveSellOrder {
+                quantity_to_unlist: quantity,
+            },
+            vec![],
+        )
+    }
+
+    pub fn add_quantity_to_sell_order(&self, seller: &Pubkey, comptoir: &Pubkey, sell_order: &Pubkey, nft_mint: &Pubkey, quantity: u64) -> Instruction {
+        self.instruction(
+            accounts::SellOrderAddQuantity {
+                authority: *seller,
+                seller_nft_token_account: get_associated_token_address(seller, nft_mint),
+                sell_order: *sell_order,
+                vault: pda::vault(&self.program_id, comptoir, nft_mint),
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::AddQuantityToSellOrder {
+                quantity_to_add: quantity,
+            },
+            vec![],
+        )
+    }
+
+    // Permissionless, moves the items of an order listed before vaults were per comptoir
+    pub fn migrate_sell_order_vault(&self, payer: &Pubkey, comptoir: &Pubkey, sell_order: &Pubkey, nft_mint: &Pubkey) -> Instruction {
+        self.instruction(
+            accounts::MigrateSellOrderVault {
+                payer: *payer,
+                comptoir: *comptoir,
+                sell_order: *sell_order,
+                mint: *nft_mint,
+                legacy_vault: pda::legacy_vault(&self.program_id, nft_mint),
+                vault: pda::vault(&self.program_id, comptoir, nft_mint),
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::MigrateSellOrderVault {},
+            vec![],
+        )
+    }
+
+    // Permissionless, the items go back to the seller associated token account
+    pub fn close_expired_sell_order(&self, seller: &Pubkey, comptoir: &Pubkey, sell_order: &Pubkey, nft_mint: &Pubkey) -> Instruction {
+        self.instruction(
+            accounts::CloseExpiredSellOrder {
+                authority: *seller,
+                seller_nft_token_account: get_associated_token_address(seller, nft_mint),
+                sell_order: *sell_order,
+                vault: pda::vault(&self.program_id, comptoir, nft_mint),
+                token_program: spl_token::ID,
+            },
+            instruction::CloseExpiredSellOrder {},
+            vec![],
+        )
+    }
+
+    pub fn buy(&self, buyer: &Pubkey, comptoir: &Pubkey, collection: &Pubkey, nft_mint: &Pubkey, params: &BuyParams) -> Instruction {
+        self.instruction(
+            accounts::Buy {
+                buyer: *buyer,
+                buyer_nft_token_account: get_associated_token_address(buyer, nft_mint),
+                buyer_paying_token_account: paying_account(buyer, &params.payment_mint),
+                comptoir: *comptoir,
+                comptoir_dest_account: params.fees_destination,
+                collection: *collection,
+                metadata: pda::metadata(nft_mint),
+                vault: pda::vault(&self.program_id, comptoir, nft_mint),
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+            },
+            instruction::Buy {
+                ask_quantity: params.quantity,
+                max_total_price: params.max_total_price,
+                max_unit_price: params.max_unit_price,
+            },
+            remaining::buy(&params.creators, &params.payment_mint, &params.fills, params.currency.as_ref()),
+        )
+    }
+
+    // The offered items are received on the buyer associated token account
+    pub fn create_buy_offer(
+        &self,
+        buyer: &Pubkey,
+        comptoir: &Pubkey,
+        collection: &Pubkey,
+        nft_mint: &Pubkey,
+        payment_mint: &Pubkey,
+        params: &OfferParams,
+    ) -> Instruction {
+        self.instruction(
+            accounts::CreateBuyOffer {
+                payer: *buyer,
+                nft_mint: *nft_mint,
+                metadata: pda::metadata(nft_mint),
+                comptoir: *comptoir,
+                collection: *collection,
+                escrow: pda::escrow(&self.program_id, comptoir, payment_mint),
+                buyer_paying_account: paying_account(buyer, payment_mint),
+                buyer_nft_account: get_associated_token_address(buyer, nft_mint),
+                buy_offer: pda::buy_offer(&self.program_id, comptoir, buyer, nft_mint, params.price),
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                associated_token_program: anchor_spl::associated_token::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::CreateBuyOffer {
+                price_proposition: params.price,
+                quantity: params.quantity,
+                expires_at: params.expires_at,
+            },
+            remaining::currency(params.currency.as_ref()),
+        )
+    }
+
+    pub fn create_collection_buy_offer(
+        &self,
+        buyer: &Pubkey,
+        comptoir: &Pubkey,
+        collection: &Pubkey,
+        payment_mint: &Pubkey,
+        params: &OfferParams,
+    ) -> Instruction {
+        self.instruction(
+            accounts::CreateCollectionBuyOffer {
+                payer: *buyer,
+                comptoir: *comptoir,
+                collection: *collection,
+                escrow: pda::escrow(&self.program_id, comptoir, payment_mint),
+                buyer_paying_account: paying_account(buyer, payment_mint),
+                buy_offer: pda::collection_buy_offer(&self.program_id, comptoir, buyer, collection, params.price),
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::CreateCollectionBuyOffer {
+                price_proposition: params.price,
+                quantity: params.quantity,
+                expires_at: params.expires_at,
+            },
+            remaining::currency(params.currency.as_ref()),
+        )
+    }
+
+    pub fn create_trait_buy_offer(
+        &self,
+        buyer: &Pubkey,
+        comptoir: &Pubkey,
+        collection: &Pubkey,
+        payment_mint: &Pubkey,
+        trait_hash: [u8; 32],
+        params: &OfferParams,
+    ) -> Instruction {
+        self.instruction(
+            accounts::CreateTraitBuyOffer {
+                payer: *buyer,
+                comptoir: *comptoir,
+                collection: *collection,
+                escrow: pda::escrow(&self.program_id, comptoir, payment_mint),
+                buyer_paying_account: paying_account(buyer, payment_mint),
+                buy_offer: pda::trait_buy_offer(&self.program_id, comptoir, buyer, collection, &trait_hash, params.price),
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::CreateTraitBuyOffer {
+                price_proposition: params.price,
+                quantity: params.quantity,
+                trait_hash,
+                expires_at: params.expires_at,
+            },
+            remaining::currency(params.currency.as_ref()),
+        )
+    }
+
+    pub fn remove_buy_offer(&self, buyer: &Pubkey, comptoir: &Pubkey, buy_offer: &Pubkey, payment_mint: &Pubkey) -> Instruction {
+        self.instruction(
+            accounts::RemoveBuyOffer {
+                buyer: *buyer,
+                buyer_paying_account: paying_account(buyer, payment_mint),
+                comptoir: *comptoir,
+                escrow: pda::escrow(&self.program_id, comptoir, payment_mint),
+                buy_offer: *buy_offer,
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::RemoveBuyOffer {},
+            vec![],
+        )
+    }
+
+    // Permissionless, the funds go back to the buyer
+    pub fn close_expired_buy_offer(&self, buyer: &Pubkey, comptoir: &Pubkey, buy_offer: &Pubkey, payment_mint: &Pubkey) -> Instruction {
+        self.instruction(
+            accounts::CloseExpiredBuyOffer {
+                buyer: *buyer,
+                buyer_paying_account: paying_account(buyer, payment_mint),
+                comptoir: *comptoir,
+                escrow: pda::escrow(&self.program_id, comptoir, payment_mint),
+                buy_offer: *buy_offer,
+                token_program: spl_token::ID,
+            },
+            instruction::CloseExpiredBuyOffer {},
+            vec![],
+        )
+    }
+
+    // The seller gives the items from their associated token account
+    pub fn execute_offer(
+        &self,
+        seller: &Pubkey,
+        comptoir: &Pubkey,
+        collection: &Pubkey,
+        buy_offer: &Pubkey,
+        params: &ExecuteOfferParams,
+    ) -> Instruction {
+        self.instruction(
+            accounts::ExecuteOffer {
+                seller: *seller,
+                buyer: params.buyer,
+                comptoir: *comptoir,
+                collection: *collection,
+                comptoir_dest_account: params.fees_destination,
+                escrow: pda::escrow(&self.program_id, comptoir, &params.payment_mint),
+                seller_funds_dest_account: params.seller_funds_destination,
+                destination: params.buyer_nft_destination,
+                seller_nft_account: get_associated_token_address(seller, &params.nft_mint),
+                metadata: pda::metadata(&params.nft_mint),
+                buy_offer: *buy_offer,
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::ExecuteOffer {
+                quantity: params.quantity,
+                trait_proof: params.trait_proof.clone(),
+            },
+            remaining::execute_offer(&params.creators, &params.payment_mint, params.currency.as_ref()),
+        )
+    }
+
+    pub fn set_collection_traits_root(&self, authority: &Pubkey, comptoir: &Pubkey, collection: &Pubkey, traits_root: Option<[u8; 32]>) -> Instruction {
+        self.instruction(
+            accounts::UpdateCollection {
+                authority: *authority,
+                comptoir: *comptoir,
+                collection: *collection,
+            },
+            instruction::SetCollectionTraitsRoot { traits_root },
+            vec![],
+        )
+    }
+
+    pub fn set_certified_collection(&self, authority: &Pubkey, comptoir: &Pubkey, collection: &Pubkey, certified_collection: Option<Pubkey>) -> Instruction {
+        self.instruction(
+            accounts::UpdateCollection {
+                authority: *authority,
+                comptoir: *comptoir,
+                collection: *collection,
+            },
+            instruction::SetCertifiedCollection { certified_collection },
+            vec![],
+        )
+    }
+
+    pub fn set_collection_custom_currencies(&self, authority: &Pubkey, comptoir: &Pubkey, collection: &Pubkey, custom_currencies: bool) -> Instruction {
+        self.instruction(
+            accounts::UpdateCollection {
+                authority: *authority,
+                comptoir: *comptoir,
+                collection: *collection,
+            },
+            instruction::SetCollectionCustomCurrencies { custom_currencies },
+            vec![],
+        )
+    }
+
+    // Permissionless, grows a collection created before the collection fields were added
+    pub fn migrate_collection(&self, payer: &Pubkey, collection: &Pubkey) -> Instruction {
+        self.instruction(
+            accounts::MigrateCollection {
+                payer: *payer,
+                collection: *collection,
+                system_program: system_program::ID,
+            },
+            instruction::MigrateCollection {},
+            vec![],
+        )
+    }
+
+    // Only used by the collection when it replaces the comptoir currencies with its own
+    pub fn add_collection_currency(
+        &self,
+        authority: &Pubkey,
+        comptoir: &Pubkey,
+        collection: &Pubkey,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+        fees_destination: &Pubkey,
+    ) -> Instruction {
+        self.instruction(
+            accounts::AddCollectionCurrency {
+                authority: *authority,
+                comptoir: *comptoir,
+                collection: *collection,
+                mint: *mint,
+                currency: pda::currency(&self.program_id, collection, mint),
+                escrow: pda::escrow(&self.program_id, comptoir, mint),
+                system_program: system_program::ID,
+                token_program: *token_program,
+                rent: sysvar::rent::ID,
+            },
+            instruction::AddCollectionCurrency {
+                fees_destination: *fees_destination,
+            },
+            vec![],
+        )
+    }
+
+    pub fn add_token22_currency(&self, authority: &Pubkey, comptoir: &Pubkey, mint: &Pubkey, fees_destination: &Pubkey) -> Instruction {
+        self.instruction(
+            accounts::AddToken22Currency {
+                authority: *authority,
+                comptoir: *comptoir,
+                mint: *mint,
+                currency: pda::currency(&self.program_id, comptoir, mint),
+                token_program: token_2022_program(),
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::AddToken22Currency {
+                fees_destination: *fees_destination,
+            },
+            vec![],
+        )
+    }
+
+    pub fn update_currency(&self, authority: &Pubkey, comptoir: &Pubkey, currency: &Pubkey, fees_destination: &Pubkey) -> Instruction {
+        self.instruction(
+            accounts::UpdateCurrency {
+                authority: *authority,
+                comptoir: *comptoir,
+                currency: *currency,
+            },
+            instruction::UpdateCurrency {
+                fees_destination: *fees_destination,
+            },
+            vec![],
+        )
+    }
+
+    pub fn remove_currency(&self, authority: &Pubkey, comptoir: &Pubkey, currency: &Pubkey) -> Instruction {
+        self.instruction(
+            accounts::RemoveCurrency {
+                authority: *authority,
+                comptoir: *comptoir,
+                currency: *currency,
+            },
+            instruction::RemoveCurrency {},
+            vec![],
+        )
+    }
+
+    // The order price is in the reference unit of the price feed, with the decimals of the order currency
+    pub fn create_oracle_sell_order(
+        &self,
+        seller: &Pubkey,
+        comptoir: &Pubkey,
+        collection: &Pubkey,
+        nft_mint: &Pubkey,
+        params: &SellOrderParams,
+        oracle_price: OraclePrice,
+    ) -> Instruction {
+        let seller_nft_token_account = get_associated_token_address(seller, nft_mint);
+        self.instruction(
+            accounts::CreateSellOrder {
+                payer: *seller,
+                seller_nft_token_account,
+                comptoir: *comptoir,
+                collection: *collection,
+                mint: *nft_mint,
+                metadata: pda::metadata(nft_mint),
+                vault: pda::vault(&self.program_id, comptoir, nft_mint),
+                sell_order: pda::sell_order(&self.program_id, &seller_nft_token_account, params.price),
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::CreateOracleSellOrder {
+                price: params.price,
+                quantity: params.quantity,
+                destination: params.destination,
+                price_schedule: params.price_schedule,
+                expires_at: params.expires_at,
+                oracle_price,
+            },
+            remaining::currency(params.currency.as_ref()),
+        )
+    }
+
+    // Permissionless, grows a sell order listed before the order fields were added
+    pub fn migrate_sell_order(&self, payer: &Pubkey, comptoir: &Pubkey, sell_order: &Pubkey) -> Instruction {
+        self.instruction(
+            accounts::MigrateSellOrder {
+                payer: *payer,
+                comptoir: *comptoir,
+                sell_order: *sell_order,
+                system_program: system_program::ID,
+            },
+            instruction::MigrateSellOrder {},
+            vec![],
+        )
+    }
+
+    // The buyer ties the legacy offer to the collection of its item
+    pub fn migrate_buy_offer(&self, buyer: &Pubkey, comptoir: &Pubkey, collection: &Pubkey, buy_offer: &Pubkey, nft_mint: &Pubkey) -> Instruction {
+        self.instruction(
+            accounts::MigrateBuyOffer {
+                buyer: *buyer,
+                metadata: pda::metadata(nft_mint),
+                comptoir: *comptoir,
+                collection: *collection,
+                buy_offer: *buy_offer,
+                system_program: system_program::ID,
+            },
+            instruction::MigrateBuyOffer {},
+            vec![],
+        )
+    }
+
+    // The currency account is required when the book is not in the comptoir mint
+    pub fn create_order_book(&self, authority: &Pubkey, comptoir: &Pubkey, collection: &Pubkey, nft_mint: &Pubkey, payment_mint: &Pubkey, currency: Option<&Pubkey>) -> Instruction {
+        self.instruction(
+            accounts::CreateOrderBook {
+                authority: *authority,
+                comptoir: *comptoir,
+                collection: *collection,
+                mint: *nft_mint,
+                metadata: pda::metadata(nft_mint),
+                order_book: pda::order_book(&self.program_id, comptoir, nft_mint, payment_mint),
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::CreateOrderBook { payment_mint: *payment_mint },
+            remaining::currency(currency),
+        )
+    }
+
+    pub fn add_sell_order_to_book(&self, seller: &Pubkey, order_book: &Pubkey, sell_order: &Pubkey) -> Instruction {
+        self.instruction(
+            accounts::AddSellOrderToBook {
+                authority: *seller,
+                order_book: *order_book,
+                sell_order: *sell_order,
+            },
+            instruction::AddSellOrderToBook {},
+            vec![],
+        )
+    }
+
+    pub fn add_buy_offer_to_book(&self, buyer: &Pubkey, order_book: &Pubkey, buy_offer: &Pubkey) -> Instruction {
+        self.instruction(
+            accounts::AddBuyOfferToBook {
+                authority: *buyer,
+                order_book: *order_book,
+                buy_offer: *buy_offer,
+            },
+            instruction::AddBuyOfferToBook {},
+            vec![],
+        )
+    }
+
+    // Anyone can remove closed, filled or expired orders and the orders of a book whose currency was removed,
+    // live ones only by their authority. The currency account is passed, even once removed, when not the comptoir mint
+    pub fn remove_from_order_book(&self, authority: &Pubkey, book: &BookParams, order: &Pubkey) -> Instruction {
+        self.instruction(
+            accounts::RemoveFromOrderBook {
+                authority: *authority,
+                comptoir: book.comptoir,
+                collection: book.collection,
+                order_book: book.order_book,
+                order: *order,
+            },
+            instruction::RemoveFromOrderBook {},
+            remaining::currency(book.currency.as_ref()),
+        )
+    }
+
+    // Permissionless crank, the buyer is refunded on their paying account. The collection is the one of the buy offer
+    pub fn match_orders(&self, comptoir: &Pubkey, collection: &Pubkey, nft_mint: &Pubkey, params: &MatchParams) -> Instruction {
+        let mut remaining_accounts = remaining::creators(&params.creators, &params.payment_mint);
+        remaining_accounts.push(AccountMeta::new(params.sell_order, false));
+        remaining_accounts.push(AccountMeta::new(params.seller_funds_destination, false));
+        remaining_accounts.push(AccountMeta::new(params.buy_offer, false));
+        remaining_accounts.push(AccountMeta::new(params.buyer_nft_destination, false));
+        remaining_accounts.push(AccountMeta::new(paying_account(&params.buyer, &params.payment_mint), false));
+        remaining_accounts.push(AccountMeta::new(params.buyer, false));
+        remaining_accounts.extend(remaining::currency(params.currency.as_ref()));
+        self.instruction(
+            accounts::MatchOrders {
+                comptoir: *comptoir,
+                collection: *collection,
+                order_book: pda::order_book(&self.program_id, comptoir, nft_mint, &params.payment_mint),
+                comptoir_dest_account: params.fees_destination,
+                metadata: pda::metadata(nft_mint),
+                vault: pda::vault(&self.program_id, comptoir, nft_mint),
+                escrow: pda::escrow(&self.program_id, comptoir, &params.payment_mint),
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+            },
+            instruction::MatchOrders {},
+            remaining_accounts,
+        )
+    }
+
+    pub fn create_bundle_order(&self, seller: &Pubkey, comptoir: &Pubkey, params: &BundleParams) -> Instruction {
+        self.instruction(
+            accounts::CreateBundleOrder {
+                payer: *seller,
+                comptoir: *comptoir,
+                bundle_order: pda::bundle_order(&self.program_id, comptoir, seller, params.bundle_id),
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::CreateBundleOrder {
+                _bundle_id: params.bundle_id,
+                price: params.price,
+                destination: params.destination,
+                expires_at: params.expires_at,
+            },
+            remaining::currency(params.currency.as_ref()),
+        )
+    }
+
+    // The item leaves the seller associated token account for its vault
+    pub fn add_bundle_item(&self, seller: &Pubkey, comptoir: &Pubkey, collection: &Pubkey, bundle_order: &Pubkey, nft_mint: &Pubkey) -> Instruction {
+        self.instruction(
+            accounts::AddBundleItem {
+                payer: *seller,
+                seller_nft_token_account: get_associated_token_address(seller, nft_mint),
+                comptoir: *comptoir,
+                collection: *collection,
+                bundle_order: *bundle_order,
+                mint: *nft_mint,
+                metadata: pda::metadata(nft_mint),
+                vault: pda::vault(&self.program_id, comptoir, nft_mint),
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::AddBundleItem {},
+            vec![],
+        )
+    }
+
+    // The mints are the ones of the bundle, in bundle order
+    pub fn remove_bundle_order(&self, seller: &Pubkey, comptoir: &Pubkey, bundle_order: &Pubkey, mints: &[Pubkey]) -> Instruction {
+        self.instruction(
+            accounts::RemoveBundleOrder {
+                authority: *seller,
+                comptoir: *comptoir,
+                bundle_order: *bundle_order,
+                token_program: spl_token::ID,
+            },
+            instruction::RemoveBundleOrder {},
+            remaining::vaults(&self.program_id, comptoir, seller, mints),
+        )
+    }
+
+    pub fn buy_bundle(&self, buyer: &Pubkey, comptoir: &Pubkey, bundle_order: &Pubkey, params: &BuyBundleParams) -> Instruction {
+        self.instruction(
+            accounts::BuyBundle {
+                buyer: *buyer,
+                buyer_paying_token_account: paying_account(buyer, &params.payment_mint),
+                comptoir: *comptoir,
+                comptoir_dest_account: params.fees_destination,
+                seller_funds_dest_account: params.seller_funds_destination,
+                authority: params.seller,
+                bundle_order: *bundle_order,
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+            },
+            instruction::BuyBundle {
+                max_price: params.max_price,
+            },
+            remaining::buy_bundle(&self.program_id, comptoir, buyer, &params.payment_mint, &spl_token::ID, &params.items, params.currency.as_ref()),
+        )
+    }
+
+    pub fn create_swap_offer(&self, owner: &Pubkey, comptoir: &Pubkey, params: &SwapOfferParams) -> Instruction {
+        self.instruction(
+            accounts::CreateSwapOffer {
+                payer: *owner,
+                payer_paying_account: paying_account(owner, &params.payment_mint),
+                comptoir: *comptoir,
+                escrow: pda::escrow(&self.program_id, comptoir, &params.payment_mint),
+                swap_offer: pda::swap_offer(&self.program_id, comptoir, owner, params.swap_id),
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::CreateSwapOffer {
+                _swap_id: params.swap_id,
+                requested: params.requested.clone(),
+                token_amount: params.token_amount,
+                expires_at: params.expires_at,
+            },
+            remaining::currency(params.currency.as_ref()),
+        )
+    }
+
+    pub fn add_swap_item(&self, owner: &Pubkey, comptoir: &Pubkey, swap_offer: &Pubkey, nft_mint: &Pubkey) -> Instruction {
+        self.instruction(
+            accounts::AddSwapItem {
+                payer: *owner,
+                payer_nft_token_account: get_associated_token_address(owner, nft_mint),
+                comptoir: *comptoir,
+                swap_offer: *swap_offer,
+                mint: *nft_mint,
+                vault: pda::vault(&self.program_id, comptoir, nft_mint),
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::AddSwapItem {},
+            vec![],
+        )
+    }
+
+    // The offered mints are the ones of the swap offer, in offer order
+    pub fn remove_swap_offer(
+        &self,
+        owner: &Pubkey,
+        comptoir: &Pubkey,
+        swap_offer: &Pubkey,
+        payment_mint: &Pubkey,
+        offered_mints: &[Pubkey],
+    ) -> Instruction {
+        self.instruction(
+            accounts::RemoveSwapOffer {
+                authority: *owner,
+                authority_paying_account: paying_account(owner, payment_mint),
+                comptoir: *comptoir,
+                escrow: pda::escrow(&self.program_id, comptoir, payment_mint),
+                swap_offer: *swap_offer,
+                token_program: spl_token::ID,
+            },
+            instruction::RemoveSwapOffer {},
+            remaining::vaults(&self.program_id, comptoir, owner, offered_mints),
+        )
+    }
+
+    pub fn accept_swap_offer(&self, counterparty: &Pubkey, comptoir: &Pubkey, swap_offer: &Pubkey, params: &AcceptSwapParams) -> Instruction {
+        self.instruction(
+            accounts::AcceptSwapOffer {
+                counterparty: *counterparty,
+                counterparty_funds_account: params.counterparty_funds_account,
+                comptoir: *comptoir,
+                comptoir_dest_account: params.fees_destination,
+                escrow: pda::escrow(&self.program_id, comptoir, &params.payment_mint),
+                authority: params.authority,
+                swap_offer: *swap_offer,
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+            },
+            instruction::AcceptSwapOffer {},
+            remaining::accept_swap(&self.program_id, comptoir, counterparty, &params.authority, &params.offered_mints, &params.gifts, params.currency.as_ref()),
+        )
+    }
+
+    // The items are locked from the seller associated token account, which also keys the auction
+    pub fn create_auction(&self, seller: &Pubkey, comptoir: &Pubkey, collection: &Pubkey, nft_mint: &Pubkey, params: &AuctionParams) -> Instruction {
+        let seller_nft_token_account = get_associated_token_address(seller, nft_mint);
+        self.instruction(
+            accounts::CreateAuction {
+                payer: *seller,
+                seller_nft_token_account,
+                comptoir: *comptoir,
+                collection: *collection,
+                mint: *nft_mint,
+                metadata: pda::metadata(nft_mint),
+                vault: pda::vault(&self.program_id, comptoir, nft_mint),
+                auction: pda::auction(&self.program_id, &seller_nft_token_account),
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::CreateAuction {
+                reserve_price: params.reserve_price,
+                min_bid_increment: params.min_bid_increment,
+                start_time: params.start_time,
+                end_time: params.end_time,
+                quantity: params.quantity,
+                destination: params.destination,
+            },
+            vec![],
+        )
+    }
+
+    // The previous highest bid, if any, is marked as outbid. Bidders raising their own highest bid pass none
+    pub fn place_bid(
+        &self,
+        bidder: &Pubkey,
+        comptoir: &Pubkey,
+        auction: &Pubkey,
+        payment_mint: &Pubkey,
+        amount: u64,
+        previous_bid: Option<&Pubkey>,
+    ) -> Instruction {
+        let remaining_accounts = previous_bid.map(|bid| vec![AccountMeta::new(*bid, false)]).unwrap_or_default();
+        self.instruction(
+            accounts::PlaceBid {
+                bidder: *bidder,
+                bidder_paying_account: get_associated_token_address(bidder, payment_mint),
+                comptoir: *comptoir,
+                escrow: pda::escrow(&self.program_id, comptoir, payment_mint),
+                auction: *auction,
+                bid: pda::bid(&self.program_id, auction, bidder),
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::PlaceBid { amount },
+            remaining_accounts,
+        )
+    }
+
+    pub fn claim_bid_refund(&self, bidder: &Pubkey, comptoir: &Pubkey, bid: &Pubkey, payment_mint: &Pubkey) -> Instruction {
+        self.instruction(
+            accounts::ClaimBidRefund {
+                bidder: *bidder,
+                bidder_refund_account: get_associated_token_address(bidder, payment_mint),
+                escrow: pda::escrow(&self.program_id, comptoir, payment_mint),
+                bid: *bid,
+                token_program: spl_token::ID,
+            },
+            instruction::ClaimBidRefund {},
+            vec![],
+        )
+    }
+
+    pub fn cancel_auction(&self, seller: &Pubkey, comptoir: &Pubkey, nft_mint: &Pubkey) -> Instruction {
+        let seller_nft_token_account = get_associated_token_address(seller, nft_mint);
+        self.instruction(
+            accounts::CancelAuction {
+                authority: *seller,
+                seller_nft_token_account,
+                auction: pda::auction(&self.program_id, &seller_nft_token_account),
+                vault: pda::vault(&self.program_id, comptoir, nft_mint),
+                token_program: spl_token::ID,
+            },
+            instruction::CancelAuction {},
+            vec![],
+        )
+    }
+
+    // Permissionless once ended, the payer covers the winner associated token account when it has to be created.
+    // Auctions in another currency than the comptoir mint pass its currency account, even once removed
+    pub fn settle_auction(&self, payer: &Pubkey, comptoir: &Pubkey, collection: &Pubkey, nft_mint: &Pubkey, params: &SettleAuctionParams) -> Instruction {
+        let seller_nft_token_account = get_associated_token_address(&params.seller, nft_mint);
+        self.instruction(
+            accounts::SettleAuction {
+                payer: *payer,
+                authority: params.seller,
+                comptoir: *comptoir,
+                collection: *collection,
+                comptoir_dest_account: params.fees_destination,
+                escrow: pda::escrow(&self.program_id, comptoir, &params.payment_mint),
+                destination: params.destination,
+                seller_nft_token_account,
+                mint: *nft_mint,
+                winner: params.winner,
+                winner_nft_account: get_associated_token_address(&params.winner, nft_mint),
+                winning_bid: params.winning_bid,
+                metadata: pda::metadata(nft_mint),
+                auction: pda::auction(&self.program_id, &seller_nft_token_account),
+                vault: pda::vault(&self.program_id, comptoir, nft_mint),
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                associated_token_program: anchor_spl::associated_token::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::SettleAuction {},
+            remaining::execute_offer(&params.creators, &params.payment_mint, params.currency.as_ref()),
+        )
+    }
+
+    // The leaf moves from the seller to the vault of the asset, the seller being its delegate too
+    pub fn create_compressed_sell_order(
+        &self,
+        seller: &Pubkey,
+        comptoir: &Pubkey,
+        collection: &Pubkey,
+        item: &CompressedItem,
+        params: &ListingParams,
+        currency: Option<&Pubkey>,
+    ) -> Instruction {
+        let asset_id = item.asset_id();
+        let mut remaining_accounts = remaining::proof(&item.proof);
+        remaining_accounts.extend(remaining::currency(currency));
+        self.instruction(
+            accounts::CreateCompressedSellOrder {
+                payer: *seller,
+                comptoir: *comptoir,
+                collection: *collection,
+                vault: pda::compressed_vault(&self.program_id, comptoir, &asset_id),
+                sell_order: pda::compressed_sell_order(&self.program_id, &asset_id),
+                tree_authority: pda::tree_authority(&item.merkle_tree),
+                leaf_delegate: *seller,
+                merkle_tree: item.merkle_tree,
+                log_wrapper: Pubkey::from_str(SPL_NOOP_PROGRAM).unwrap(),
+                compression_program: Pubkey::from_str(SPL_ACCOUNT_COMPRESSION_PROGRAM).unwrap(),
+                bubblegum_program: Pubkey::from_str(BUBBLEGUM_PROGRAM).unwrap(),
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::CreateCompressedSellOrder {
+                asset_id,
+                nonce: item.nonce,
+                index: item.index,
+                root: item.root,
+                metadata: item.metadata.clone(),
+                price: params.price,
+                destination: params.destination,
+                expires_at: params.expires_at,
+            },
+            remaining_accounts,
+        )
+    }
+
+    pub fn remove_compressed_sell_order(&self, seller: &Pubkey, comptoir: &Pubkey, item: &CompressedItem) -> Instruction {
+        let asset_id = item.asset_id();
+        self.instruction(
+            accounts::RemoveCompressedSellOrder {
+                authority: *seller,
+                sell_order: pda::compressed_sell_order(&self.program_id, &asset_id),
+                vault: pda::compressed_vault(&self.program_id, comptoir, &asset_id),
+                tree_authority: pda::tree_authority(&item.merkle_tree),
+                merkle_tree: item.merkle_tree,
+                log_wrapper: Pubkey::from_str(SPL_NOOP_PROGRAM).unwrap(),
+                compression_program: Pubkey::from_str(SPL_ACCOUNT_COMPRESSION_PROGRAM).unwrap(),
+                bubblegum_program: Pubkey::from_str(BUBBLEGUM_PROGRAM).unwrap(),
+                system_program: system_program::ID,
+            },
+            instruction::RemoveCompressedSellOrder { root: item.root },
+            remaining::proof(&item.proof),
+        )
+    }
+
+    // The leaf goes to the buyer wallet, creators accounts come before the proof nodes and the currency after them
+    pub fn buy_compressed(&self, buyer: &Pubkey, comptoir: &Pubkey, collection: &Pubkey, item: &CompressedItem, params: &CompressedSaleParams) -> Instruction {
+        let asset_id = item.asset_id();
+        let mut remaining_accounts = remaining::creators(&params.creators, &params.payment_mint);
+        remaining_accounts.extend(remaining::proof(&item.proof));
+        remaining_accounts.extend(remaining::currency(params.currency.as_ref()));
+        self.instruction(
+            accounts::BuyCompressed {
+                buyer: *buyer,
+                buyer_paying_token_account: paying_account(buyer, &params.payment_mint),
+                comptoir: *comptoir,
+                comptoir_dest_account: params.fees_destination,
+                collection: *collection,
+                authority: params.counterparty,
+                seller_funds_dest_account: params.seller_funds_destination,
+                sell_order: pda::compressed_sell_order(&self.program_id, &asset_id),
+                vault: pda::compressed_vault(&self.program_id, comptoir, &asset_id),
+                tree_authority: pda::tree_authority(&item.merkle_tree),
+                merkle_tree: item.merkle_tree,
+                log_wrapper: Pubkey::from_str(SPL_NOOP_PROGRAM).unwrap(),
+                compression_program: Pubkey::from_str(SPL_ACCOUNT_COMPRESSION_PROGRAM).unwrap(),
+                bubblegum_program: Pubkey::from_str(BUBBLEGUM_PROGRAM).unwrap(),
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+            },
+            instruction::BuyCompressed {
+                root: item.root,
+                metadata: item.metadata.clone(),
+                max_price: params.price,
+            },
+            remaining_accounts,
+        )
+    }
+
+    // Offers on compressed items are for a single asset, the quantity of the params is not used
+    pub fn create_compressed_buy_offer(
+        &self,
+        buyer: &Pubkey,
+        comptoir: &Pubkey,
+        collection: &Pubkey,
+        payment_mint: &Pubkey,
+        asset_id: &Pubkey,
+        params: &OfferParams,
+    ) -> Instruction {
+        self.instruction(
+            accounts::CreateCompressedBuyOffer {
+                payer: *buyer,
+                comptoir: *comptoir,
+                collection: *collection,
+                escrow: pda::escrow(&self.program_id, comptoir, payment_mint),
+                buyer_paying_account: paying_account(buyer, payment_mint),
+                buy_offer: pda::buy_offer(&self.program_id, comptoir, buyer, asset_id, params.price),
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::CreateCompressedBuyOffer {
+                asset_id: *asset_id,
+                price_proposition: params.price,
+                expires_at: params.expires_at,
+            },
+            remaining::currency(params.currency.as_ref()),
+        )
+    }
+
+    pub fn execute_compressed_offer(
+        &self,
+        seller: &Pubkey,
+        comptoir: &Pubkey,
+        collection: &Pubkey,
+        item: &CompressedItem,
+        params: &CompressedSaleParams,
+    ) -> Instruction {
+        let asset_id = item.asset_id();
+        let mut remaining_accounts = remaining::creators(&params.creators, &params.payment_mint);
+        remaining_accounts.extend(remaining::proof(&item.proof));
+        remaining_accounts.extend(remaining::currency(params.currency.as_ref()));
+        self.instruction(
+            accounts::ExecuteCompressedOffer {
+                seller: *seller,
+                buyer: params.counterparty,
+                comptoir: *comptoir,
+                collection: *collection,
+                comptoir_dest_account: params.fees_destination,
+                escrow: pda::escrow(&self.program_id, comptoir, &params.payment_mint),
+                seller_funds_dest_account: params.seller_funds_destination,
+                buy_offer: pda::buy_offer(&self.program_id, comptoir, &params.counterparty, &asset_id, params.price),
+                tree_authority: pda::tree_authority(&item.merkle_tree),
+                leaf_delegate: *seller,
+                merkle_tree: item.merkle_tree,
+                log_wrapper: Pubkey::from_str(SPL_NOOP_PROGRAM).unwrap(),
+                compression_program: Pubkey::from_str(SPL_ACCOUNT_COMPRESSION_PROGRAM).unwrap(),
+                bubblegum_program: Pubkey::from_str(BUBBLEGUM_PROGRAM).unwrap(),
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+            },
+            instruction::ExecuteCompressedOffer {
+                nonce: item.nonce,
+                index: item.index,
+                root: item.root,
+                metadata: item.metadata.clone(),
+            },
+            remaining_accounts,
+        )
+    }
+
+    // The item moves from the seller associated token account to the one of the vault authority
+    pub fn create_pnft_sell_order(
+        &self,
+        seller: &Pubkey,
+        comptoir: &Pubkey,
+        collection: &Pubkey,
+        item: &PnftItem,
+        params: &ListingParams,
+        currency: Option<&Pubkey>,
+    ) -> Instruction {
+        let seller_nft_token_account = get_associated_token_address(seller, &item.mint);
+        let vault = pda::associated_vault(&self.program_id, comptoir, &item.mint, &spl_token::ID);
+        self.instruction(
+            accounts::CreatePnftSellOrder {
+                payer: *seller,
+                seller_nft_token_account,
+                comptoir: *comptoir,
+                collection: *collection,
+                mint: item.mint,
+                metadata: pda::metadata(&item.mint),
+                edition: pda::edition(&item.mint),
+                seller_token_record: pda::token_record(&item.mint, &seller_nft_token_account),
+                vault_authority: pda::vault(&self.program_id, comptoir, &item.mint),
+                vault,
+                vault_token_record: pda::token_record(&item.mint, &vault),
+                sell_order: pda::sell_order(&self.program_id, &seller_nft_token_account, params.price),
+                token_metadata_program: mpl_token_metadata::id(),
+                authorization_rules_program: authorization_rules_program(),
+                authorization_rules: authorization_rules(item),
+                sysvar_instructions: sysvar::instructions::ID,
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                associated_token_program: anchor_spl::associated_token::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::CreatePnftSellOrder {
+                price: params.price,
+                destination: params.destination,
+                expires_at: params.expires_at,
+            },
+            remaining::currency(currency),
+        )
+    }
+
+    pub fn remove_pnft_sell_order(&self, seller: &Pubkey, comptoir: &Pubkey, sell_order: &Pubkey, item: &PnftItem) -> Instruction {
+        let seller_nft_token_account = get_associated_token_address(seller, &item.mint);
+        let vault = pda::associated_vault(&self.program_id, comptoir, &item.mint, &spl_token::ID);
+        self.instruction(
+            accounts::RemovePnftSellOrder {
+                authority: *seller,
+                seller_nft_token_account,
+                sell_order: *sell_order,
+                mint: item.mint,
+                metadata: pda::metadata(&item.mint),
+                edition: pda::edition(&item.mint),
+                seller_token_record: pda::token_record(&item.mint, &seller_nft_token_account),
+                vault_authority: pda::vault(&self.program_id, comptoir, &item.mint),
+                vault,
+                vault_token_record: pda::token_record(&item.mint, &vault),
+                token_metadata_program: mpl_token_metadata::id(),
+                authorization_rules_program: authorization_rules_program(),
+                authorization_rules: authorization_rules(item),
+                sysvar_instructions: sysvar::instructions::ID,
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                associated_token_program: anchor_spl::associated_token::ID,
+            },
+            instruction::RemovePnftSellOrder {},
+            vec![],
+        )
+    }
+
+    // Bought items always land in the buyer associated token account
+    pub fn buy_pnft(
+        &self,
+        buyer: &Pubkey,
+        comptoir: &Pubkey,
+        collection: &Pubkey,
+        sell_order: &Pubkey,
+        item: &PnftItem,
+        params: &PnftSaleParams,
+    ) -> Instruction {
+        let buyer_nft_token_account = get_associated_token_address(buyer, &item.mint);
+        let vault = pda::associated_vault(&self.program_id, comptoir, &item.mint, &spl_token::ID);
+        self.instruction(
+            accounts::BuyPnft {
+                buyer: *buyer,
+                buyer_nft_token_account,
+                buyer_paying_token_account: paying_account(buyer, &params.payment_mint),
+                comptoir: *comptoir,
+                comptoir_dest_account: params.fees_destination,
+                collection: *collection,
+                authority: params.counterparty,
+                seller_funds_dest_account: params.seller_funds_destination,
+                sell_order: *sell_order,
+                mint: item.mint,
+                metadata: pda::metadata(&item.mint),
+                edition: pda::edition(&item.mint),
+                buyer_token_record: pda::token_record(&item.mint, &buyer_nft_token_account),
+                vault_authority: pda::vault(&self.program_id, comptoir, &item.mint),
+                vault,
+                vault_token_record: pda::token_record(&item.mint, &vault),
+                token_metadata_program: mpl_token_metadata::id(),
+                authorization_rules_program: authorization_rules_program(),
+                authorization_rules: authorization_rules(item),
+                sysvar_instructions: sysvar::instructions::ID,
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                associated_token_program: anchor_spl::associated_token::ID,
+            },
+            instruction::BuyPnft {
+                max_price: params.max_price,
+            },
+            remaining::execute_offer(&params.creators, &params.payment_mint, params.currency.as_ref()),
+        )
+    }
+
+    // The seller gives the item from their associated token account to the one of the buyer
+    pub fn execute_pnft_offer(
+        &self,
+        seller: &Pubkey,
+        comptoir: &Pubkey,
+        collection: &Pubkey,
+        buy_offer: &Pubkey,
+        item: &PnftItem,
+        params: &PnftSaleParams,
+    ) -> Instruction {
+        let seller_nft_account = get_associated_token_address(seller, &item.mint);
+        let destination = get_associated_token_address(&params.counterparty, &item.mint);
+        self.instruction(
+            accounts::ExecutePnftOffer {
+                seller: *seller,
+                buyer: params.counterparty,
+                comptoir: *comptoir,
+                collection: *collection,
+                comptoir_dest_account: params.fees_destination,
+                escrow: pda::escrow(&self.program_id, comptoir, &params.payment_mint),
+                seller_funds_dest_account: params.seller_funds_destination,
+                destination,
+                seller_nft_account,
+                mint: item.mint,
+                metadata: pda::metadata(&item.mint),
+                edition: pda::edition(&item.mint),
+                seller_token_record: pda::token_record(&item.mint, &seller_nft_account),
+                destination_token_record: pda::token_record(&item.mint, &destination),
+                buy_offer: *buy_offer,
+                token_metadata_program: mpl_token_metadata::id(),
+                authorization_rules_program: authorization_rules_program(),
+                authorization_rules: authorization_rules(item),
+                sysvar_instructions: sysvar::instructions::ID,
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                associated_token_program: anchor_spl::associated_token::ID,
+            },
+            instruction::ExecutePnftOffer {
+                trait_proof: params.trait_proof.clone(),
+            },
+            remaining::execute_offer(&params.creators, &params.payment_mint, params.currency.as_ref()),
+        )
+    }
+
+    // Token-2022 orders have a fixed price, the price schedule of the params is not used
+    pub fn create_token22_sell_order(
+        &self,
+        seller: &Pubkey,
+        comptoir: &Pubkey,
+        collection: &Pubkey,
+        nft_mint: &Pubkey,
+        params: &SellOrderParams,
+        hook: Option<&TransferHook>,
+    ) -> Instruction {
+        let token_program = token_2022_program();
+        let seller_nft_token_account = pda::associated_token_account(seller, nft_mint, &token_program);
+        self.instruction(
+            accounts::CreateToken22SellOrder {
+                payer: *seller,
+                seller_nft_token_account,
+                comptoir: *comptoir,
+                collection: *collection,
+                mint: *nft_mint,
+                metadata: pda::metadata(nft_mint),
+                vault_authority: pda::vault(&self.program_id, comptoir, nft_mint),
+                vault: pda::associated_vault(&self.program_id, comptoir, nft_mint, &token_program),
+                sell_order: pda::sell_order(&self.program_id, &seller_nft_token_account, params.price),
+                token_program,
+                associated_token_program: anchor_spl::associated_token::ID,
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::CreateToken22SellOrder {
+                price: params.price,
+                quantity: params.quantity,
+                destination: params.destination,
+                expires_at: params.expires_at,
+            },
+            remaining::hooked(nft_mint, hook, remaining::currency(params.currency.as_ref())),
+        )
+    }
+
+    pub fn remove_token22_sell_order(
+        &self,
+        seller: &Pubkey,
+        comptoir: &Pubkey,
+        sell_order: &Pubkey,
+        nft_mint: &Pubkey,
+        quantity: u64,
+        hook: Option<&TransferHook>,
+    ) -> Instruction {
+        let token_program = token_2022_program();
+        self.instruction(
+            accounts::RemoveToken22SellOrder {
+                authority: *seller,
+                seller_nft_token_account: pda::associated_token_account(seller, nft_mint, &token_program),
+                sell_order: *sell_order,
+                mint: *nft_mint,
+                vault_authority: pda::vault(&self.program_id, comptoir, nft_mint),
+                vault: pda::associated_vault(&self.program_id, comptoir, nft_mint, &token_program),
+                token_program,
+            },
+            instruction::RemoveToken22SellOrder {
+                quantity_to_unlist: quantity,
+            },
+            remaining::transfer_hook(nft_mint, hook),
+        )
+    }
+
+    // The buyer pays from their associated token account of the payment mint under its own token program
+    pub fn buy_token22(
+        &self,
+        buyer: &Pubkey,
+        comptoir: &Pubkey,
+        collection: &Pubkey,
+        sell_order: &Pubkey,
+        nft_mint: &Pubkey,
+        params: &Token22BuyParams,
+    ) -> Instruction {
+        let token_program = token_2022_program();
+        let mut buyer_paying_token_account = pda::associated_token_account(buyer, &params.payment_mint, &params.payment_token_program);
+        if params.payment_mint == spl_token::native_mint::id() {
+            buyer_paying_token_account = *buyer;
+        }
+        self.instruction(
+            accounts::BuyToken22 {
+                buyer: *buyer,
+                buyer_nft_token_account: pda::associated_token_account(buyer, nft_mint, &token_program),
+                buyer_paying_token_account,
+                comptoir: *comptoir,
+                comptoir_dest_account: params.fees_destination,
+                collection: *collection,
+                seller_funds_dest_account: params.seller_funds_destination,
+                sell_order: *sell_order,
+                mint: *nft_mint,
+                metadata: pda::metadata(nft_mint),
+                vault_authority: pda::vault(&self.program_id, comptoir, nft_mint),
+                vault: pda::associated_vault(&self.program_id, comptoir, nft_mint, &token_program),
+                payment_mint: params.payment_mint,
+                token_program,
+                payment_token_program: params.payment_token_program,
+                system_program: system_program::ID,
+            },
+            instruction::BuyToken22 {
+                quantity: params.quantity,
+                max_total_price: params.max_total_price,
+            },
+            remaining::hooked(
+                nft_mint,
+                params.item_hook.as_ref(),
+                remaining::hooked(
+                    &params.payment_mint,
+                    params.currency_hook.as_ref(),
+                    remaining::creators_and_currency(&params.creators, &params.payment_mint, &params.payment_token_program, params.currency.as_ref()),
+                ),
+            ),
+        )
+    }
+
+    // Token-2022 offers are funded from the buyer associated account under Token-2022, the buyer paying the transfer
+    // fee so the escrow holds the whole offer
+    pub fn create_token22_buy_offer(
+        &self,
+        buyer: &Pubkey,
+        comptoir: &Pubkey,
+        collection: &Pubkey,
+        nft_mint: &Pubkey,
+        payment: &Token22Payment,
+        params: &OfferParams,
+    ) -> Instruction {
+        let payment_mint = &payment.mint;
+        self.instruction(
+            accounts::CreateToken22BuyOffer {
+                payer: *buyer,
+                nft_mint: *nft_mint,
+                metadata: pda::metadata(nft_mint),
+                comptoir: *comptoir,
+                collection: *collection,
+                escrow: pda::escrow(&self.program_id, comptoir, payment_mint),
+                buyer_paying_account: token22_account(buyer, payment_mint),
+                buyer_nft_account: get_associated_token_address(buyer, nft_mint),
+                buy_offer: pda::buy_offer(&self.program_id, comptoir, buyer, nft_mint, params.price),
+                payment_mint: *payment_mint,
+                payment_token_program: token_2022_program(),
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                associated_token_program: anchor_spl::associated_token::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::CreateToken22BuyOffer {
+                price_proposition: params.price,
+                quantity: params.quantity,
+                expires_at: params.expires_at,
+            },
+            remaining::hooked(payment_mint, payment.hook.as_ref(), remaining::currency(params.currency.as_ref())),
+        )
+    }
+
+    pub fn create_token22_collection_buy_offer(
+        &self,
+        buyer: &Pubkey,
+        comptoir: &Pubkey,
+        collection: &Pubkey,
+        payment: &Token22Payment,
+        params: &OfferParams,
+    ) -> Instruction {
+        let payment_mint = &payment.mint;
+        self.instruction(
+            accounts::CreateToken22CollectionBuyOffer {
+                payer: *buyer,
+                comptoir: *comptoir,
+                collection: *collection,
+                escrow: pda::escrow(&self.program_id, comptoir, payment_mint),
+                buyer_paying_account: token22_account(buyer, payment_mint),
+                buy_offer: pda::collection_buy_offer(&self.program_id, comptoir, buyer, collection, params.price),
+                payment_mint: *payment_mint,
+                payment_token_program: token_2022_program(),
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::CreateToken22CollectionBuyOffer {
+                price_proposition: params.price,
+                quantity: params.quantity,
+                expires_at: params.expires_at,
+            },
+            remaining::hooked(payment_mint, payment.hook.as_ref(), remaining::currency(params.currency.as_ref())),
+        )
+    }
+
+    pub fn create_token22_trait_buy_offer(
+        &self,
+        buyer: &Pubkey,
+        comptoir: &Pubkey,
+        collection: &Pubkey,
+        payment: &Token22Payment,
+        trait_hash: [u8; 32],
+        params: &OfferParams,
+    ) -> Instruction {
+        let payment_mint = &payment.mint;
+        self.instruction(
+            accounts::CreateToken22TraitBuyOffer {
+                payer: *buyer,
+                comptoir: *comptoir,
+                collection: *collection,
+                escrow: pda::escrow(&self.program_id, comptoir, payment_mint),
+                buyer_paying_account: token22_account(buyer, payment_mint),
+                buy_offer: pda::trait_buy_offer(&self.program_id, comptoir, buyer, collection, &trait_hash, params.price),
+                payment_mint: *payment_mint,
+                payment_token_program: token_2022_program(),
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::CreateToken22TraitBuyOffer {
+                price_proposition: params.price,
+                quantity: params.quantity,
+                trait_hash,
+                expires_at: params.expires_at,
+            },
+            remaining::hooked(payment_mint, payment.hook.as_ref(), remaining::currency(params.currency.as_ref())),
+        )
+    }
+
+    // Permissionless, the funds go back to the buyer
+    pub fn close_expired_token22_buy_offer(&self, buyer: &Pubkey, comptoir: &Pubkey, buy_offer: &Pubkey, payment: &Token22Payment) -> Instruction {
+        let payment_mint = &payment.mint;
+        self.instruction(
+            accounts::CloseExpiredToken22BuyOffer {
+                buyer: *buyer,
+                buyer_paying_account: token22_account(buyer, payment_mint),
+                comptoir: *comptoir,
+                escrow: pda::escrow(&self.program_id, comptoir, payment_mint),
+                buy_offer: *buy_offer,
+                payment_mint: *payment_mint,
+                payment_token_program: token_2022_program(),
+            },
+            instruction::CloseExpiredToken22BuyOffer {},
+            remaining::transfer_hook(payment_mint, payment.hook.as_ref()),
+        )
+    }
+
+    pub fn remove_token22_buy_offer(
+        &self,
+        buyer: &Pubkey,
+        comptoir: &Pubkey,
+        buy_offer: &Pubkey,
+        payment: &Token22Payment,
+    ) -> Instruction {
+        let payment_mint = &payment.mint;
+        self.instruction(
+            accounts::RemoveToken22BuyOffer {
+                buyer: *buyer,
+                buyer_paying_account: token22_account(buyer, payment_mint),
+                comptoir: *comptoir,
+                escrow: pda::escrow(&self.program_id, comptoir, payment_mint),
+                buy_offer: *buy_offer,
+                payment_mint: *payment_mint,
+                payment_token_program: token_2022_program(),
+            },
+            instruction::RemoveToken22BuyOffer {},
+            remaining::transfer_hook(payment_mint, payment.hook.as_ref()),
+        )
+    }
+
+    // The seller is paid on params.seller_funds_destination, an account of the payment mint under Token-2022
+    pub fn execute_token22_offer(
+        &self,
+        seller: &Pubkey,
+        comptoir: &Pubkey,
+        collection: &Pubkey,
+        buy_offer: &Pubkey,
+        params: &ExecuteOfferParams,
+        hook: Option<&TransferHook>,
+    ) -> Instruction {
+        let token_program = token_2022_program();
+        self.instruction(
+            accounts::ExecuteToken22Offer {
+                seller: *seller,
+                buyer: params.buyer,
+                comptoir: *comptoir,
+                collection: *collection,
+                comptoir_dest_account: params.fees_destination,
+                escrow: pda::escrow(&self.program_id, comptoir, &params.payment_mint),
+                seller_funds_dest_account: params.seller_funds_destination,
+                destination: params.buyer_nft_destination,
+                seller_nft_account: get_associated_token_address(seller, &params.nft_mint),
+                metadata: pda::metadata(&params.nft_mint),
+                buy_offer: *buy_offer,
+                payment_mint: params.payment_mint,
+                payment_token_program: token_program,
+                token_program: spl_token::ID,
+            },
+            instruction::ExecuteToken22Offer {
+                quantity: params.quantity,
+                trait_proof: params.trait_proof.clone(),
+            },
+            remaining::hooked(
+                &params.payment_mint,
+                hook,
+                remaining::creators_and_currency(&params.creators, &params.payment_mint, &token_program, params.currency.as_ref()),
+            ),
+        )
+    }
+
+    // The previous highest bid, if any, is marked as outbid. Bidders raising their own highest bid pass none
+    pub fn place_token22_bid(
+        &self,
+        bidder: &Pubkey,
+        comptoir: &Pubkey,
+        auction: &Pubkey,
+        payment: &Token22Payment,
+        amount: u64,
+        previous_bid: Option<&Pubkey>,
+    ) -> Instruction {
+        let payment_mint = &payment.mint;
+        let previous_bid = previous_bid.map(|bid| vec![AccountMeta::new(*bid, false)]).unwrap_or_default();
+        self.instruction(
+            accounts::PlaceToken22Bid {
+                bidder: *bidder,
+                bidder_paying_account: token22_account(bidder, payment_mint),
+                comptoir: *comptoir,
+                escrow: pda::escrow(&self.program_id, comptoir, payment_mint),
+                auction: *auction,
+                bid: pda::bid(&self.program_id, auction, bidder),
+                payment_mint: *payment_mint,
+                payment_token_program: token_2022_program(),
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::PlaceToken22Bid { amount },
+            remaining::hooked(payment_mint, payment.hook.as_ref(), previous_bid),
+        )
+    }
+
+    pub fn claim_token22_bid_refund(
+        &self,
+        bidder: &Pubkey,
+        comptoir: &Pubkey,
+        bid: &Pubkey,
+        payment: &Token22Payment,
+    ) -> Instruction {
+        let payment_mint = &payment.mint;
+        self.instruction(
+            accounts::ClaimToken22BidRefund {
+                bidder: *bidder,
+                bidder_refund_account: token22_account(bidder, payment_mint),
+                escrow: pda::escrow(&self.program_id, comptoir, payment_mint),
+                bid: *bid,
+                payment_mint: *payment_mint,
+                payment_token_program: token_2022_program(),
+            },
+            instruction::ClaimToken22BidRefund {},
+            remaining::transfer_hook(payment_mint, payment.hook.as_ref()),
+        )
+    }
+
+    pub fn settle_token22_auction(
+        &self,
+        payer: &Pubkey,
+        comptoir: &Pubkey,
+        collection: &Pubkey,
+        nft_mint: &Pubkey,
+        params: &SettleAuctionParams,
+        hook: Option<&TransferHook>,
+    ) -> Instruction {
+        let token_program = token_2022_program();
+        let seller_nft_token_account = get_associated_token_address(&params.seller, nft_mint);
+        self.instruction(
+            accounts::SettleToken22Auction {
+                payer: *payer,
+                authority: params.seller,
+                comptoir: *comptoir,
+                collection: *collection,
+                comptoir_dest_account: params.fees_destination,
+                escrow: pda::escrow(&self.program_id, comptoir, &params.payment_mint),
+                destination: params.destination,
+                seller_nft_token_account,
+                mint: *nft_mint,
+                winner: params.winner,
+                winner_nft_account: get_associated_token_address(&params.winner, nft_mint),
+                winning_bid: params.winning_bid,
+                metadata: pda::metadata(nft_mint),
+                auction: pda::auction(&self.program_id, &seller_nft_token_account),
+                vault: pda::vault(&self.program_id, comptoir, nft_mint),
+                payment_mint: params.payment_mint,
+                payment_token_program: token_program,
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                associated_token_program: anchor_spl::associated_token::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::SettleToken22Auction {},
+            remaining::hooked(
+                &params.payment_mint,
+                hook,
+                remaining::creators_and_currency(&params.creators, &params.payment_mint, &token_program, params.currency.as_ref()),
+            ),
+        )
+    }
+
+    pub fn buy_token22_bundle(
+        &self,
+        buyer: &Pubkey,
+        comptoir: &Pubkey,
+        bundle_order: &Pubkey,
+        params: &BuyBundleParams,
+        hook: Option<&TransferHook>,
+    ) -> Instruction {
+        let token_program = token_2022_program();
+        self.instruction(
+            accounts::BuyToken22Bundle {
+                buyer: *buyer,
+                buyer_paying_account: token22_account(buyer, &params.payment_mint),
+                comptoir: *comptoir,
+                comptoir_dest_account: params.fees_destination,
+                seller_funds_dest_account: params.seller_funds_destination,
+                authority: params.seller,
+                bundle_order: *bundle_order,
+                payment_mint: params.payment_mint,
+                payment_token_program: token_program,
+                token_program: spl_token::ID,
+            },
+            instruction::BuyToken22Bundle {
+                max_price: params.max_price,
+            },
+            remaining::hooked(
+                &params.payment_mint,
+                hook,
+                remaining::buy_bundle(&self.program_id, comptoir, buyer, &params.payment_mint, &token_program, &params.items, params.currency.as_ref()),
+            ),
+        )
+    }
+
+    pub fn create_token22_swap_offer(
+        &self,
+        owner: &Pubkey,
+        comptoir: &Pubkey,
+        params: &SwapOfferParams,
+        hook: Option<&TransferHook>,
+    ) -> Instruction {
+        self.instruction(
+            accounts::CreateToken22SwapOffer {
+                payer: *owner,
+                payer_paying_account: token22_account(owner, &params.payment_mint),
+                comptoir: *comptoir,
+                escrow: pda::escrow(&self.program_id, comptoir, &params.payment_mint),
+                swap_offer: pda::swap_offer(&self.program_id, comptoir, owner, params.swap_id),
+                payment_mint: params.payment_mint,
+                payment_token_program: token_2022_program(),
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::CreateToken22SwapOffer {
+                _swap_id: params.swap_id,
+                requested: params.requested.clone(),
+                token_amount: params.token_amount,
+                expires_at: params.expires_at,
+            },
+            remaining::hooked(&params.payment_mint, hook, remaining::currency(params.currency.as_ref())),
+        )
+    }
+
+    pub fn remove_token22_swap_offer(
+        &self,
+        owner: &Pubkey,
+        comptoir: &Pubkey,
+        swap_offer: &Pubkey,
+        payment: &Token22Payment,
+        offered_mints: &[Pubkey],
+    ) -> Instruction {
+        let payment_mint = &payment.mint;
+        self.instruction(
+            accounts::RemoveToken22SwapOffer {
+                authority: *owner,
+                authority_paying_account: token22_account(owner, payment_mint),
+                comptoir: *comptoir,
+                escrow: pda::escrow(&self.program_id, comptoir, payment_mint),
+                swap_offer: *swap_offer,
+                payment_mint: *payment_mint,
+                payment_token_program: token_2022_program(),
+                token_program: spl_token::ID,
+            },
+            instruction::RemoveToken22SwapOffer {},
+            remaining::hooked(payment_mint, payment.hook.as_ref(), remaining::vaults(&self.program_id, comptoir, owner, offered_mints)),
+        )
+    }
+
+    pub fn accept_token22_swap_offer(
+        &self,
+        counterparty: &Pubkey,
+        comptoir: &Pubkey,
+        swap_offer: &Pubkey,
+        params: &AcceptSwapParams,
+        hook: Option<&TransferHook>,
+    ) -> Instruction {
+        self.instruction(
+            accounts::AcceptToken22SwapOffer {
+                counterparty: *counterparty,
+                counterparty_funds_account: params.counterparty_funds_account,
+                comptoir: *comptoir,
+                comptoir_dest_account: params.fees_destination,
+                escrow: pda::escrow(&self.program_id, comptoir, &params.payment_mint),
+                authority: params.authority,
+                swap_offer: *swap_offer,
+                payment_mint: params.payment_mint,
+                payment_token_program: token_2022_program(),
+                token_program: spl_token::ID,
+            },
+            instruction::AcceptToken22SwapOffer {},
+            remaining::hooked(
+                &params.payment_mint,
+                hook,
+                remaining::accept_swap(&self.program_id, comptoir, counterparty, &params.authority, &params.offered_mints, &params.gifts, params.currency.as_ref()),
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> Pubkey {
+        Pubkey::new_from_array([byte; 32])
+    }
+
+    fn item() -> CompressedItem {
+        CompressedItem {
+            merkle_tree: key(10),
+            nonce: 4,
+            index: 4,
+            root: [1; 32],
+            proof: vec![[2; 32], [3; 32]],
+            metadata: CompressedMetadata {
+                name: "Item".to_string(),
+                symbol: "ITEM".to_string(),
+                uri: String::new(),
+                seller_fee_basis_points: 500,
+                primary_sale_happened: true,
+                is_mutable: false,
+                edition_nonce: None,
+                token_standard: None,
+                collection: None,
+                uses: None,
+                token_program_version: 0,
+                creators: vec![],
+            },
+        }
+    }
+
+    fn has(instruction: &Instruction, account: &Pubkey) -> bool {
+        instruction.accounts.iter().any(|meta| meta.pubkey == *account)
+    }
+
+    #[test]
+    fn compressed_orders_use_the_asset_accounts() {
+        let client = Client::default();
+        let (seller, comptoir, collection) = (key(1), key(2), key(3));
+        let item = item();
+        let asset_id = item.asset_id();
+        let listing = ListingParams {
+            price: 1500,
+            destination: seller,
+            expires_at: None,
+        };
+        let create = client.create_compressed_sell_order(&seller, &comptoir, &collection, &item, &listing, None);
+        let remove = client.remove_compressed_sell_order(&seller, &comptoir, &item);
+        for instruction in [&create, &remove] {
+            assert!(has(instruction, &pda::compressed_sell_order(&client.program_id, &asset_id)));
+            assert!(has(instruction, &pda::compressed_vault(&client.program_id, &comptoir, &asset_id)));
+            assert!(has(instruction, &pda::tree_authority(&item.merkle_tree)));
+            // The proof nodes close the accounts
+            let proof: Vec<Pubkey> = instruction.accounts.iter().rev().take(2).map(|meta| meta.pubkey).collect();
+            assert_eq!(proof, vec![Pubkey::new_from_array([3; 32]), Pubkey::new_from_array([2; 32])]);
+        }
+    }
+
+    #[test]
+    fn compressed_orders_pass_the_currency_after_the_proof() {
+        let client = Client::default();
+        let (seller, comptoir, collection, currency) = (key(1), key(2), key(3), key(5));
+        let item = item();
+        let listing = ListingParams {
+            price: 1500,
+            destination: seller,
+            expires_at: None,
+        };
+        let create = client.create_compressed_sell_order(&seller, &comptoir, &collection, &item, &listing, Some(&currency));
+        let sale = CompressedSaleParams {
+            counterparty: seller,
+            seller_funds_destination: seller,
+            payment_mint: key(6),
+            fees_destination: key(7),
+            price: 1500,
+            creators: vec![],
+            currency: Some(currency),
+        };
+        let buy = client.buy_compressed(&key(8), &comptoir, &collection, &item, &sale);
+        for instruction in [&create, &buy] {
+            let last: Vec<Pubkey> = instruction.accounts.iter().rev().take(3).map(|meta| meta.pubkey).collect();
+            assert_eq!(last, vec![currency, Pubkey::new_from_array([3; 32]), Pubkey::new_from_array([2; 32])]);
+        }
+    }
+
+    #[test]
+    fn pnft_orders_use_the_associated_vault() {
+        let client = Client::default();
+        let (seller, comptoir, collection, mint) = (key(1), key(2), key(3), key(4));
+        let item = PnftItem { mint, rule_set: None };
+        let listing = ListingParams {
+            price: 1500,
+            destination: seller,
+            expires_at: None,
+        };
+        let instruction = client.create_pnft_sell_order(&seller, &comptoir, &collection, &item, &listing, None);
+        let vault = pda::associated_vault(&client.program_id, &comptoir, &mint, &spl_token::ID);
+        assert!(has(&instruction, &vault));
+        assert!(has(&instruction, &pda::token_record(&mint, &vault)));
+        // Without a rule set the token metadata program stands for the rules account
+        let metadata_program_count = instruction.accounts.iter().filter(|meta| meta.pubkey == mpl_token_metadata::id()).count();
+        assert_eq!(metadata_program_count, 2);
+    }
+
+    #[test]
+    fn token22_comptoirs_create_their_escrow_under_token_2022() {
+        let client = Client::default();
+        let (owner, mint) = (key(1), key(4));
+        let instruction = client.create_comptoir(&owner, &mint, &token_2022_program(), 100, &key(7));
+        let comptoir = pda::comptoir(&client.program_id, &owner);
+        assert!(has(&instruction, &pda::escrow(&client.program_id, &comptoir, &mint)));
+        assert!(has(&instruction, &token_2022_program()));
+        assert!(!has(&instruction, &spl_token::ID));
+    }
+
+    #[test]
+    fn token22_offers_pass_the_hook_accounts_before_the_currency() {
+        let client = Client::default();
+        let (buyer, comptoir, collection, payment_mint, currency) = (key(1), key(2), key(3), key(6), key(5));
+        let hook = TransferHook {
+            program: key(9),
+            extra_accounts: vec![AccountMeta::new(key(11), false)],
+        };
+        let params = OfferParams {
+            price: 1500,
+            quantity: 1,
+            expires_at: None,
+            currency: Some(currency),
+        };
+        let validation = pda::extra_account_metas(&hook.program, &payment_mint);
+        let payment = Token22Payment { mint: payment_mint, hook: Some(hook) };
+        let instruction = client.create_token22_collection_buy_offer(&buyer, &comptoir, &collection, &payment, &params);
+        let last: Vec<Pubkey> = instruction.accounts.iter().rev().take(4).map(|meta| meta.pubkey).collect();
+        assert_eq!(last, vec![currency, key(11), validation, key(9)]);
+        assert!(has(&instruction, &token22_account(&buyer, &payment_mint)));
+
+        let payment = Token22Payment { mint: payment_mint, hook: None };
+        let refund = client.remove_token22_buy_offer(&buyer, &comptoir, &key(12), &payment);
+        assert!(refund.accounts.iter().all(|meta| meta.pubkey != key(9)));
+    }
+}
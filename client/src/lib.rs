@@ -0,0 +1,7 @@
+pub mod instructions;
+pub mod pda;
+pub mod remaining;
+
+pub use comptoir::{accounts, instruction};
+pub use instructions::*;
+pub use remaining::{BundleItemFill, SellOrderFill, SwapGift, TransferHook};
@@ -0,0 +1,327 @@
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use comptoir::constant::{AUCTION, BID, BUBBLEGUM_PROGRAM, BUNDLE, COMPRESSED, CURRENCY, ESCROW, ORDER_BOOK, ORDER_COUNTER, ORDER_ID, PREFIX, SWAP};
+use mpl_token_metadata::state::{EDITION, PREFIX as METAPLEX_PREFIX};
+
+pub fn comptoir(program_id: &Pubkey, owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[PREFIX.as_bytes(), owner.as_ref()], program_id).0
+}
+
+// Token account holding the funds of the offers, bids and swaps of one currency
+pub fn escrow(program_id: &Pubkey, comptoir: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), comptoir.as_ref(), mint.as_ref(), ESCROW.as_bytes()],
+        program_id,
+    ).0
+}
+
+pub fn collection(program_id: &Pubkey, comptoir: &Pubkey, name: &str) -> Pubkey {
+    Pubkey::find_program_address(&[PREFIX.as_bytes(), name.as_bytes(), comptoir.as_ref()], program_id).0
+}
+
+// Scope is the comptoir or the collection the currency is registered for
+pub fn currency(program_id: &Pubkey, scope: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), scope.as_ref(), mint.as_ref(), CURRENCY.as_bytes()],
+        program_id,
+    ).0
+}
+
+// Also the authority of the vaults of programmable and Token-2022 items, which are its associated token accounts
+pub fn vault(program_id: &Pubkey, comptoir: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), "vault".as_bytes(), comptoir.as_ref(), mint.as_ref()],
+        program_id,
+    ).0
+}
+
+// Associated token account of the vault authority under the token program of the item
+pub fn associated_vault(program_id: &Pubkey, comptoir: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    associated_token_account(&vault(program_id, comptoir, mint), mint, token_program)
+}
+
+// Derived for any token program, Token-2022 accounts included
+pub fn associated_token_account(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &anchor_spl::associated_token::ID,
+    ).0
+}
+
+// Vault shared by every comptoir, only still used by orders waiting for migrate_sell_order_vault
+pub fn legacy_vault(program_id: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[PREFIX.as_bytes(), "vault".as_bytes(), mint.as_ref()], program_id).0
+}
+
+// The price is seeded as its decimal string
+pub fn sell_order(program_id: &Pubkey, seller_nft_token_account: &Pubkey, price: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), seller_nft_token_account.as_ref(), price.to_string().as_bytes()],
+        program_id,
+    ).0
+}
+
+pub fn sell_order_with_id(program_id: &Pubkey, seller_nft_token_account: &Pubkey, order_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), seller_nft_token_account.as_ref(), ORDER_ID.as_bytes(), order_id.to_le_bytes().as_ref()],
+        program_id,
+    ).0
+}
+
+pub fn seller_order_counter(program_id: &Pubkey, seller: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[PREFIX.as_bytes(), seller.as_ref(), ORDER_COUNTER.as_bytes()], program_id).0
+}
+
+pub fn buy_offer(program_id: &Pubkey, comptoir: &Pubkey, buyer: &Pubkey, mint: &Pubkey, price: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            comptoir.as_ref(),
+            buyer.as_ref(),
+            mint.as_ref(),
+            price.to_string().as_bytes(),
+            ESCROW.as_bytes(),
+        ],
+        program_id,
+    ).0
+}
+
+pub fn collection_buy_offer(program_id: &Pubkey, comptoir: &Pubkey, buyer: &Pubkey, collection: &Pubkey, price: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            comptoir.as_ref(),
+            buyer.as_ref(),
+            collection.as_ref(),
+            price.to_string().as_bytes(),
+            ESCROW.as_bytes(),
+        ],
+        program_id,
+    ).0
+}
+
+pub fn trait_buy_offer(
+    program_id: &Pubkey,
+    comptoir: &Pubkey,
+    buyer: &Pubkey,
+    collection: &Pubkey,
+    trait_hash: &[u8; 32],
+    price: u64,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            comptoir.as_ref(),
+            buyer.as_ref(),
+            collection.as_ref(),
+            trait_hash.as_ref(),
+            price.to_string().as_bytes(),
+            ESCROW.as_bytes(),
+        ],
+        program_id,
+    ).0
+}
+
+pub fn order_book(program_id: &Pubkey, comptoir: &Pubkey, mint: &Pubkey, payment_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), comptoir.as_ref(), mint.as_ref(), payment_mint.as_ref(), ORDER_BOOK.as_bytes()],
+        program_id,
+    ).0
+}
+
+pub fn bundle_order(program_id: &Pubkey, comptoir: &Pubkey, seller: &Pubkey, bundle_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), comptoir.as_ref(), seller.as_ref(), BUNDLE.as_bytes(), bundle_id.to_le_bytes().as_ref()],
+        program_id,
+    ).0
+}
+
+pub fn swap_offer(program_id: &Pubkey, comptoir: &Pubkey, owner: &Pubkey, swap_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), comptoir.as_ref(), owner.as_ref(), SWAP.as_bytes(), swap_id.to_le_bytes().as_ref()],
+        program_id,
+    ).0
+}
+
+pub fn auction(program_id: &Pubkey, seller_nft_token_account: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), seller_nft_token_account.as_ref(), AUCTION.as_bytes()],
+        program_id,
+    ).0
+}
+
+pub fn bid(program_id: &Pubkey, auction: &Pubkey, bidder: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), auction.as_ref(), bidder.as_ref(), BID.as_bytes()],
+        program_id,
+    ).0
+}
+
+pub fn compressed_sell_order(program_id: &Pubkey, asset_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[PREFIX.as_bytes(), asset_id.as_ref(), COMPRESSED.as_bytes()], program_id).0
+}
+
+// Owner of the listed leaves, it holds no account of its own
+pub fn compressed_vault(program_id: &Pubkey, comptoir: &Pubkey, asset_id: &Pubkey) -> Pubkey {
+    vault(program_id, comptoir, asset_id)
+}
+
+pub fn asset_id(merkle_tree: &Pubkey, nonce: u64) -> Pubkey {
+    comptoir::compressed::get_asset_id(merkle_tree, nonce)
+}
+
+pub fn tree_authority(merkle_tree: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[merkle_tree.as_ref()], &Pubkey::from_str(BUBBLEGUM_PROGRAM).unwrap()).0
+}
+
+pub fn edition(mint: &Pubkey) -> Pubkey {
+    let metadata_program = mpl_token_metadata::id();
+    Pubkey::find_program_address(
+        &[METAPLEX_PREFIX.as_bytes(), metadata_program.as_ref(), mint.as_ref(), EDITION.as_bytes()],
+        &metadata_program,
+    ).0
+}
+
+// Validation account listing the extra accounts the transfer hook of a Token-2022 mint needs
+pub fn extra_account_metas(hook_program: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&["extra-account-metas".as_bytes(), mint.as_ref()], hook_program).0
+}
+
+// Token record of a programmable NFT held by the token account
+pub fn token_record(mint: &Pubkey, token_account: &Pubkey) -> Pubkey {
+    let metadata_program = mpl_token_metadata::id();
+    Pubkey::find_program_address(
+        &[METAPLEX_PREFIX.as_bytes(), metadata_program.as_ref(), mint.as_ref(), "token_record".as_bytes(), token_account.as_ref()],
+        &metadata_program,
+    ).0
+}
+
+pub fn metadata(mint: &Pubkey) -> Pubkey {
+    let metadata_program = mpl_token_metadata::id();
+    Pubkey::find_program_address(
+        &[METAPLEX_PREFIX.as_bytes(), metadata_program.as_ref(), mint.as_ref()],
+        &metadata_program,
+    ).0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_spl::associated_token::get_associated_token_address;
+
+    fn key(byte: u8) -> Pubkey {
+        Pubkey::new_from_array([byte; 32])
+    }
+
+    // Seeds are spelled out as the program and the TypeScript tests write them, not through the shared constants
+    fn derive(seeds: &[&[u8]], program_id: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(seeds, program_id).0
+    }
+
+    #[test]
+    fn derives_the_comptoir_accounts() {
+        let program_id = comptoir::ID;
+        let (owner, comptoir, mint) = (key(1), key(2), key(3));
+        assert_eq!(super::comptoir(&program_id, &owner), derive(&[b"COMPTOIR", owner.as_ref()], &program_id));
+        assert_eq!(
+            escrow(&program_id, &comptoir, &mint),
+            derive(&[b"COMPTOIR", comptoir.as_ref(), mint.as_ref(), b"ESCROW"], &program_id),
+        );
+        assert_eq!(collection(&program_id, &comptoir, "AURY"), derive(&[b"COMPTOIR", b"AURY", comptoir.as_ref()], &program_id));
+        assert_eq!(
+            currency(&program_id, &comptoir, &mint),
+            derive(&[b"COMPTOIR", comptoir.as_ref(), mint.as_ref(), b"CURRENCY"], &program_id),
+        );
+        assert_eq!(
+            vault(&program_id, &comptoir, &mint),
+            derive(&[b"COMPTOIR", b"vault", comptoir.as_ref(), mint.as_ref()], &program_id),
+        );
+        assert_eq!(legacy_vault(&program_id, &mint), derive(&[b"COMPTOIR", b"vault", mint.as_ref()], &program_id));
+    }
+
+    #[test]
+    fn derives_the_orders() {
+        let program_id = comptoir::ID;
+        let (comptoir, wallet, mint, token_account) = (key(1), key(2), key(3), key(4));
+        assert_eq!(
+            sell_order(&program_id, &token_account, 1500),
+            derive(&[b"COMPTOIR", token_account.as_ref(), b"1500"], &program_id),
+        );
+        assert_eq!(
+            sell_order_with_id(&program_id, &token_account, 7),
+            derive(&[b"COMPTOIR", token_account.as_ref(), b"ORDER_ID", &7u64.to_le_bytes()], &program_id),
+        );
+        assert_eq!(
+            seller_order_counter(&program_id, &wallet),
+            derive(&[b"COMPTOIR", wallet.as_ref(), b"ORDER_COUNTER"], &program_id),
+        );
+        assert_eq!(
+            buy_offer(&program_id, &comptoir, &wallet, &mint, 1500),
+            derive(&[b"COMPTOIR", comptoir.as_ref(), wallet.as_ref(), mint.as_ref(), b"1500", b"ESCROW"], &program_id),
+        );
+        assert_eq!(
+            trait_buy_offer(&program_id, &comptoir, &wallet, &mint, &[5; 32], 1500),
+            derive(&[b"COMPTOIR", comptoir.as_ref(), wallet.as_ref(), mint.as_ref(), &[5; 32], b"1500", b"ESCROW"], &program_id),
+        );
+        let payment_mint = key(5);
+        assert_eq!(
+            order_book(&program_id, &comptoir, &mint, &payment_mint),
+            derive(&[b"COMPTOIR", comptoir.as_ref(), mint.as_ref(), payment_mint.as_ref(), b"ORDER_BOOK"], &program_id),
+        );
+        assert_eq!(
+            bundle_order(&program_id, &comptoir, &wallet, 3),
+            derive(&[b"COMPTOIR", comptoir.as_ref(), wallet.as_ref(), b"BUNDLE", &3u64.to_le_bytes()], &program_id),
+        );
+        assert_eq!(
+            swap_offer(&program_id, &comptoir, &wallet, 3),
+            derive(&[b"COMPTOIR", comptoir.as_ref(), wallet.as_ref(), b"SWAP", &3u64.to_le_bytes()], &program_id),
+        );
+    }
+
+    #[test]
+    fn derives_the_auction_accounts() {
+        let program_id = comptoir::ID;
+        let (token_account, bidder) = (key(1), key(2));
+        let auction = auction(&program_id, &token_account);
+        assert_eq!(auction, derive(&[b"COMPTOIR", token_account.as_ref(), b"AUCTION"], &program_id));
+        assert_eq!(
+            bid(&program_id, &auction, &bidder),
+            derive(&[b"COMPTOIR", auction.as_ref(), bidder.as_ref(), b"BID"], &program_id),
+        );
+    }
+
+    #[test]
+    fn derives_the_compressed_accounts() {
+        let program_id = comptoir::ID;
+        let bubblegum = Pubkey::from_str(BUBBLEGUM_PROGRAM).unwrap();
+        let (comptoir, merkle_tree) = (key(1), key(2));
+        let asset_id = asset_id(&merkle_tree, 4);
+        assert_eq!(asset_id, derive(&[b"asset", merkle_tree.as_ref(), &4u64.to_le_bytes()], &bubblegum));
+        assert_eq!(tree_authority(&merkle_tree), derive(&[merkle_tree.as_ref()], &bubblegum));
+        assert_eq!(
+            compressed_sell_order(&program_id, &asset_id),
+            derive(&[b"COMPTOIR", asset_id.as_ref(), b"COMPRESSED"], &program_id),
+        );
+        assert_eq!(
+            compressed_vault(&program_id, &comptoir, &asset_id),
+            derive(&[b"COMPTOIR", b"vault", comptoir.as_ref(), asset_id.as_ref()], &program_id),
+        );
+    }
+
+    #[test]
+    fn derives_the_metaplex_and_token_accounts() {
+        let metadata_program = mpl_token_metadata::id();
+        let (owner, mint, token_account) = (key(1), key(2), key(3));
+        assert_eq!(metadata(&mint), derive(&[b"metadata", metadata_program.as_ref(), mint.as_ref()], &metadata_program));
+        assert_eq!(
+            edition(&mint),
+            derive(&[b"metadata", metadata_program.as_ref(), mint.as_ref(), b"edition"], &metadata_program),
+        );
+        assert_eq!(
+            token_record(&mint, &token_account),
+            derive(&[b"metadata", metadata_program.as_ref(), mint.as_ref(), b"token_record", token_account.as_ref()], &metadata_program),
+        );
+        assert_eq!(associated_token_account(&owner, &mint, &spl_token::ID), get_associated_token_address(&owner, &mint));
+    }
+}
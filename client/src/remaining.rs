@@ -0,0 +1,181 @@
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use anchor_spl::associated_token::get_associated_token_address;
+
+use crate::pda;
+
+// Creators are paid on their associated token account of the payment mint, or on their wallet in native SOL
+pub fn creators(creators: &[Pubkey], payment_mint: &Pubkey) -> Vec<AccountMeta> {
+    let is_native = *payment_mint == spl_token::native_mint::id();
+    creators
+        .iter()
+        .map(|creator| {
+            if is_native {
+                AccountMeta::new(*creator, false)
+            } else {
+                AccountMeta::new(get_associated_token_address(creator, payment_mint), false)
+            }
+        })
+        .collect()
+}
+
+// A sell order to buy from, its price feed is required for oracle priced orders
+pub struct SellOrderFill {
+    pub sell_order: Pubkey,
+    pub seller_funds_destination: Pubkey,
+    pub price_feed: Option<Pubkey>,
+}
+
+// Creators first, then each sell order followed by its price feed and the seller destination,
+// and the currency account last when paying in a registered currency
+pub fn buy(
+    creator_wallets: &[Pubkey],
+    payment_mint: &Pubkey,
+    fills: &[SellOrderFill],
+    currency: Option<&Pubkey>,
+) -> Vec<AccountMeta> {
+    let mut accounts = creators(creator_wallets, payment_mint);
+    for fill in fills {
+        accounts.push(AccountMeta::new(fill.sell_order, false));
+        if let Some(price_feed) = fill.price_feed {
+            accounts.push(AccountMeta::new_readonly(price_feed, false));
+        }
+        accounts.push(AccountMeta::new(fill.seller_funds_destination, false));
+    }
+    if let Some(currency) = currency {
+        accounts.push(AccountMeta::new_readonly(*currency, false));
+    }
+    accounts
+}
+
+// Creators first and the currency account last when the offer is in a registered currency
+pub fn execute_offer(creator_wallets: &[Pubkey], payment_mint: &Pubkey, currency: Option<&Pubkey>) -> Vec<AccountMeta> {
+    let mut accounts = creators(creator_wallets, payment_mint);
+    if let Some(currency) = currency {
+        accounts.push(AccountMeta::new_readonly(*currency, false));
+    }
+    accounts
+}
+
+pub fn currency(currency: Option<&Pubkey>) -> Vec<AccountMeta> {
+    currency.map(|currency| vec![AccountMeta::new_readonly(*currency, false)]).unwrap_or_default()
+}
+
+// Creators paid in a currency of any token program, Token-2022 currencies included
+pub fn creators_of(creators: &[Pubkey], payment_mint: &Pubkey, token_program: &Pubkey) -> Vec<AccountMeta> {
+    if *token_program == spl_token::ID {
+        return self::creators(creators, payment_mint);
+    }
+    creators
+        .iter()
+        .map(|creator| AccountMeta::new(pda::associated_token_account(creator, payment_mint, token_program), false))
+        .collect()
+}
+
+// Creators first and the currency account last when paying in a registered currency
+pub fn creators_and_currency(creator_wallets: &[Pubkey], payment_mint: &Pubkey, token_program: &Pubkey, currency: Option<&Pubkey>) -> Vec<AccountMeta> {
+    let mut accounts = creators_of(creator_wallets, payment_mint, token_program);
+    accounts.extend(self::currency(currency));
+    accounts
+}
+
+// Merkle proof nodes of a compressed leaf, passed as read only accounts
+pub fn proof(nodes: &[[u8; 32]]) -> Vec<AccountMeta> {
+    nodes.iter().map(|node| AccountMeta::new_readonly(Pubkey::new_from_array(*node), false)).collect()
+}
+
+// The vault of every item followed by the token account it goes back to
+pub fn vaults(program_id: &Pubkey, comptoir: &Pubkey, owner: &Pubkey, mints: &[Pubkey]) -> Vec<AccountMeta> {
+    let mut accounts = Vec::new();
+    for mint in mints {
+        accounts.push(AccountMeta::new(pda::vault(program_id, comptoir, mint), false));
+        accounts.push(AccountMeta::new(get_associated_token_address(owner, mint), false));
+    }
+    accounts
+}
+
+// An item of a bundle, with the creators wallets of its metadata when its collection pays them
+pub struct BundleItemFill {
+    pub mint: Pubkey,
+    pub collection: Pubkey,
+    pub creators: Vec<Pubkey>,
+}
+
+// For every item in bundle order: its collection, metadata, vault, the buyer token account and the creators accounts,
+// then the currency account when paying in a registered currency
+pub fn buy_bundle(
+    program_id: &Pubkey,
+    comptoir: &Pubkey,
+    buyer: &Pubkey,
+    payment_mint: &Pubkey,
+    token_program: &Pubkey,
+    items: &[BundleItemFill],
+    currency: Option<&Pubkey>,
+) -> Vec<AccountMeta> {
+    let mut accounts = Vec::new();
+    for item in items {
+        accounts.push(AccountMeta::new_readonly(item.collection, false));
+        accounts.push(AccountMeta::new_readonly(pda::metadata(&item.mint), false));
+        accounts.push(AccountMeta::new(pda::vault(program_id, comptoir, &item.mint), false));
+        accounts.push(AccountMeta::new(get_associated_token_address(buyer, &item.mint), false));
+        accounts.extend(creators_of(&item.creators, payment_mint, token_program));
+    }
+    accounts.extend(self::currency(currency));
+    accounts
+}
+
+// An item given by the counterparty, the collection is set when the ask is for any item of a collection
+pub struct SwapGift {
+    pub mint: Pubkey,
+    pub collection: Option<Pubkey>,
+}
+
+// The vault and the counterparty token account of every offered item, then for every requested item the counterparty
+// and authority token accounts, followed by the metadata and the collection of collection asks, and the currency account last
+pub fn accept_swap(
+    program_id: &Pubkey,
+    comptoir: &Pubkey,
+    counterparty: &Pubkey,
+    authority: &Pubkey,
+    offered_mints: &[Pubkey],
+    gifts: &[SwapGift],
+    currency: Option<&Pubkey>,
+) -> Vec<AccountMeta> {
+    let mut accounts = vaults(program_id, comptoir, counterparty, offered_mints);
+    for gift in gifts {
+        accounts.push(AccountMeta::new(get_associated_token_address(counterparty, &gift.mint), false));
+        accounts.push(AccountMeta::new(get_associated_token_address(authority, &gift.mint), false));
+        if let Some(collection) = gift.collection {
+            accounts.push(AccountMeta::new_readonly(pda::metadata(&gift.mint), false));
+            accounts.push(AccountMeta::new_readonly(collection, false));
+        }
+    }
+    accounts.extend(self::currency(currency));
+    accounts
+}
+
+// The transfer hook program of a Token-2022 mint, with the extra accounts its validation account lists in their order
+pub struct TransferHook {
+    pub program: Pubkey,
+    pub extra_accounts: Vec<AccountMeta>,
+}
+
+// The hook program, its validation account for the mint and the extra accounts, none for mints without a hook
+pub fn transfer_hook(mint: &Pubkey, hook: Option<&TransferHook>) -> Vec<AccountMeta> {
+    let hook = match hook {
+        Some(hook) => hook,
+        None => return vec![],
+    };
+    let mut accounts = vec![
+        AccountMeta::new_readonly(hook.program, false),
+        AccountMeta::new_readonly(pda::extra_account_metas(&hook.program, mint), false),
+    ];
+    accounts.extend(hook.extra_accounts.iter().cloned());
+    accounts
+}
+
+// Transfer hook accounts go before the other remaining accounts of the instruction
+pub fn hooked(mint: &Pubkey, hook: Option<&TransferHook>, accounts: Vec<AccountMeta>) -> Vec<AccountMeta> {
+    let mut hooked = transfer_hook(mint, hook);
+    hooked.extend(accounts);
+    hooked
+}
@@ -0,0 +1,130 @@
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use comptoir::{
+    AuctionSettledEvent, BoughtSellOrderEvent, BuyOfferCreatedEvent, BuyOfferExecutedEvent,
+    BuyOfferRemovedEvent, CollectionCreatedEvent, CollectionUpdatedEvent, ComptoirCreatedEvent,
+    ComptoirUpdatedEvent, SellOrderCreatedEvent, SellOrderQuantityUpdatedEvent, SellOrderRemovedEvent,
+};
+
+pub enum ComptoirEvent {
+    ComptoirCreated(ComptoirCreatedEvent),
+    ComptoirUpdated(ComptoirUpdatedEvent),
+    CollectionCreated(CollectionCreatedEvent),
+    CollectionUpdated(CollectionUpdatedEvent),
+    SellOrderCreated(SellOrderCreatedEvent),
+    SellOrderQuantityUpdated(SellOrderQuantityUpdatedEvent),
+    SellOrderRemoved(SellOrderRemovedEvent),
+    BoughtSellOrder(BoughtSellOrderEvent),
+    BuyOfferCreated(BuyOfferCreatedEvent),
+    BuyOfferRemoved(BuyOfferRemovedEvent),
+    BuyOfferExecuted(BuyOfferExecutedEvent),
+    AuctionSettled(AuctionSettledEvent),
+}
+
+macro_rules! decode {
+    ($discriminator:expr, $payload:expr, $($variant:ident($event:ty)),* $(,)?) => {
+        $(
+            if $discriminator == &<$event>::discriminator()[..] {
+                return <$event>::deserialize($payload).ok().map(ComptoirEvent::$variant);
+            }
+        )*
+    };
+}
+
+// Events are logged as their 8 bytes discriminator followed by the borsh serialized event
+pub fn decode_event(data: &[u8]) -> Option<ComptoirEvent> {
+    if data.len() < 8 {
+        return None;
+    }
+    let discriminator = &data[..8];
+    let payload = &mut &data[8..];
+    decode!(
+        discriminator,
+        payload,
+        ComptoirCreated(ComptoirCreatedEvent),
+        ComptoirUpdated(ComptoirUpdatedEvent),
+        CollectionCreated(CollectionCreatedEvent),
+        CollectionUpdated(CollectionUpdatedEvent),
+        SellOrderCreated(SellOrderCreatedEvent),
+        SellOrderQuantityUpdated(SellOrderQuantityUpdatedEvent),
+        SellOrderRemoved(SellOrderRemovedEvent),
+        BoughtSellOrder(BoughtSellOrderEvent),
+        BuyOfferCreated(BuyOfferCreatedEvent),
+        BuyOfferRemoved(BuyOfferRemovedEvent),
+        BuyOfferExecuted(BuyOfferExecutedEvent),
+        AuctionSettled(AuctionSettledEvent),
+    );
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logs::{parse_logs, LogEntry};
+    use anchor_lang::prelude::Pubkey;
+
+    fn events(logs: &str) -> Vec<ComptoirEvent> {
+        let logs: Vec<String> = logs.lines().map(str::to_string).collect();
+        parse_logs(&logs, &comptoir::ID)
+            .into_iter()
+            .filter_map(|entry| match entry {
+                LogEntry::Event(data) => decode_event(&data),
+                LogEntry::Instruction(_) => None,
+            })
+            .collect()
+    }
+
+    fn key(byte: u8) -> Pubkey {
+        Pubkey::new_from_array([byte; 32])
+    }
+
+    #[test]
+    fn decodes_a_sale() {
+        let events = events(include_str!("../tests/fixtures/buy.log"));
+        assert_eq!(events.len(), 1);
+        match &events[0] {
+            ComptoirEvent::BoughtSellOrder(event) => {
+                assert_eq!(event.sell_order, key(1));
+                assert_eq!(event.quantity, 1);
+                assert_eq!(event.buyer, key(2));
+                assert_eq!(event.mint, key(3));
+                assert_eq!(event.price, 200);
+                assert_eq!(event.payment_mint, key(4));
+                assert_eq!(event.seller_amount, 176);
+                assert_eq!(event.comptoir_amount, 4);
+                assert_eq!(event.creators.len(), 1);
+                assert_eq!(event.creators[0].destination, key(5));
+                assert_eq!(event.creators[0].amount, 20);
+            }
+            _ => panic!("expected a sale"),
+        }
+    }
+
+    #[test]
+    fn decodes_the_events_of_a_match_in_order() {
+        let events = events(include_str!("../tests/fixtures/match_orders.log"));
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[0], ComptoirEvent::BoughtSellOrder(event) if event.sell_order == key(1)));
+        match &events[1] {
+            ComptoirEvent::BuyOfferExecuted(event) => {
+                assert_eq!(event.buy_offer, key(6));
+                assert_eq!(event.seller, key(7));
+                assert_eq!(event.mint, key(3));
+                assert_eq!(event.quantity, 1);
+                assert_eq!(event.price, 200);
+                assert_eq!(event.seller_amount, 176);
+                assert_eq!(event.comptoir_amount, 4);
+                assert_eq!(event.creators[0].amount, 20);
+            }
+            _ => panic!("expected an offer execution"),
+        }
+    }
+
+    #[test]
+    fn ignores_unknown_and_truncated_data() {
+        assert!(decode_event(&[]).is_none());
+        assert!(decode_event(&[0; 40]).is_none());
+        let mut truncated = BoughtSellOrderEvent::discriminator().to_vec();
+        truncated.extend_from_slice(&[0; 8]);
+        assert!(decode_event(&truncated).is_none());
+    }
+}
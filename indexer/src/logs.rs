@@ -0,0 +1,99 @@
+use anchor_lang::prelude::Pubkey;
+
+const PROGRAM_LOG: &str = "Program log: ";
+const PROGRAM_DATA: &str = "Program data: ";
+const INSTRUCTION: &str = "Instruction: ";
+
+pub enum LogEntry {
+    Instruction(String),
+    Event(Vec<u8>),
+}
+
+// Follows the invocation stack so that only what the program itself logged is kept, not its CPIs or callers
+pub fn parse_logs(logs: &[String], program_id: &Pubkey) -> Vec<LogEntry> {
+    let program_id = program_id.to_string();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut entries = Vec::new();
+
+    for log in logs {
+        let in_program = stack.last() == Some(&program_id.as_str());
+        if let Some(message) = log.strip_prefix(PROGRAM_LOG) {
+            if let Some(name) = message.strip_prefix(INSTRUCTION) {
+                if in_program {
+                    entries.push(LogEntry::Instruction(name.to_string()));
+                }
+            }
+        } else if let Some(data) = log.strip_prefix(PROGRAM_DATA) {
+            if in_program {
+                if let Ok(bytes) = base64::decode(data) {
+                    entries.push(LogEntry::Event(bytes));
+                }
+            }
+        } else if let Some(rest) = log.strip_prefix("Program ") {
+            let mut words = rest.split_whitespace();
+            let program = words.next().unwrap_or_default();
+            match words.next() {
+                Some("invoke") => stack.push(program),
+                Some("success") | Some("failed:") => {
+                    stack.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(logs: &str) -> Vec<String> {
+        logs.lines().map(str::to_string).collect()
+    }
+
+    fn instructions(entries: &[LogEntry]) -> Vec<&str> {
+        entries
+            .iter()
+            .filter_map(|entry| match entry {
+                LogEntry::Instruction(name) => Some(name.as_str()),
+                LogEntry::Event(_) => None,
+            })
+            .collect()
+    }
+
+    fn events_count(entries: &[LogEntry]) -> usize {
+        entries.iter().filter(|entry| matches!(entry, LogEntry::Event(_))).count()
+    }
+
+    #[test]
+    fn skips_the_logs_of_cpis_and_callers() {
+        let logs = fixture(include_str!("../tests/fixtures/buy.log"));
+        let entries = parse_logs(&logs, &comptoir::ID);
+        assert_eq!(instructions(&entries), vec!["Buy"]);
+        assert_eq!(events_count(&entries), 1);
+
+        // The caller only sees what it logged itself
+        let caller = Pubkey::new_from_array([9; 32]);
+        let entries = parse_logs(&logs, &caller);
+        assert_eq!(instructions(&entries), vec!["Route"]);
+        assert_eq!(events_count(&entries), 1);
+    }
+
+    #[test]
+    fn keeps_every_event_of_the_instruction() {
+        let logs = fixture(include_str!("../tests/fixtures/match_orders.log"));
+        let entries = parse_logs(&logs, &comptoir::ID);
+        assert_eq!(instructions(&entries), vec!["MatchOrders"]);
+        assert_eq!(events_count(&entries), 2);
+    }
+
+    #[test]
+    fn leaves_the_stack_on_failure() {
+        let mut logs = fixture(include_str!("../tests/fixtures/failed.log"));
+        logs.extend(fixture(include_str!("../tests/fixtures/match_orders.log")));
+        let entries = parse_logs(&logs, &comptoir::ID);
+        assert_eq!(instructions(&entries), vec!["CreateSellOrder", "MatchOrders"]);
+        assert_eq!(events_count(&entries), 2);
+    }
+}
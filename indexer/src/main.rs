@@ -0,0 +1,63 @@
+mod events;
+mod logs;
+mod source;
+mod store;
+
+use std::error::Error;
+use std::path::PathBuf;
+
+use anchor_lang::prelude::Pubkey;
+use clap::{Parser, Subcommand};
+
+use crate::source::{fetch_from_rpc, read_dump};
+use crate::store::Store;
+
+/// Rebuilds the Comptoir listings, offers, sales and fee payouts from the program logs into SQLite
+#[derive(Parser)]
+#[clap(version)]
+struct Args {
+    /// SQLite database, created when missing
+    #[clap(long, default_value = "comptoir.db")]
+    database: PathBuf,
+    /// Comptoir program id, the one declared by the program by default
+    #[clap(long)]
+    program_id: Option<Pubkey>,
+    #[clap(subcommand)]
+    source: Source,
+}
+
+#[derive(Subcommand)]
+enum Source {
+    /// Replays a dump of JSON lines {"signature", "slot", "logs", "failed"}, oldest transaction first
+    File { path: PathBuf },
+    /// Fetches every transaction of the program from an RPC node
+    Rpc {
+        #[clap(long, default_value = "http://127.0.0.1:8899")]
+        url: String,
+        /// Signatures requested per page
+        #[clap(long, default_value_t = 1000)]
+        limit: usize,
+    },
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let program_id = args.program_id.unwrap_or(comptoir::ID);
+    let transactions = match args.source {
+        Source::File { path } => read_dump(&path)?,
+        Source::Rpc { url, limit } => fetch_from_rpc(&url, &program_id, limit)?,
+    };
+
+    let mut store = Store::open(&args.database)?;
+    let mut indexed = 0;
+    for transaction in transactions.iter() {
+        // Failed transactions did not change anything, indexed ones were already applied
+        if transaction.failed || store.is_indexed(&transaction.signature)? {
+            continue;
+        }
+        store.index_transaction(transaction, &program_id)?;
+        indexed += 1;
+    }
+    eprintln!("Indexed {} new transactions out of {}", indexed, transactions.len());
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use serde::Deserialize;
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::UiTransactionEncoding;
+
+#[derive(Deserialize)]
+pub struct LoggedTransaction {
+    pub signature: String,
+    pub slot: u64,
+    #[serde(default)]
+    pub failed: bool,
+    pub logs: Vec<String>,
+}
+
+// One JSON transaction per line, in the order they were executed
+pub fn read_dump(path: &Path) -> Result<Vec<LoggedTransaction>, Box<dyn Error>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut transactions = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        transactions.push(serde_json::from_str(&line)?);
+    }
+    Ok(transactions)
+}
+
+// Signatures are fetched by pages of `limit`, each page starting before the oldest signature of the previous one,
+// until the whole history of the program has been read
+pub fn fetch_from_rpc(url: &str, program_id: &Pubkey, limit: usize) -> Result<Vec<LoggedTransaction>, Box<dyn Error>> {
+    let client = RpcClient::new_with_commitment(url.to_string(), CommitmentConfig::confirmed());
+    let mut statuses = Vec::new();
+    let mut before = None;
+    loop {
+        let page = client.get_signatures_for_address_with_config(
+            program_id,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                limit: Some(limit),
+                ..GetConfirmedSignaturesForAddress2Config::default()
+            },
+        )?;
+        match page.last() {
+            Some(oldest) => before = Some(Signature::from_str(&oldest.signature)?),
+            None => break,
+        }
+        statuses.extend(page);
+    }
+
+    // Signatures come newest first while the state has to be rebuilt oldest first
+    let mut transactions = Vec::new();
+    for status in statuses.into_iter().rev() {
+        let transaction = client.get_transaction(&Signature::from_str(&status.signature)?, UiTransactionEncoding::Json)?;
+        let logs = transaction.transaction.meta.and_then(|meta| meta.log_messages).unwrap_or_default();
+        transactions.push(LoggedTransaction {
+            signature: status.signature,
+            slot: status.slot,
+            failed: status.err.is_some(),
+            logs,
+        });
+    }
+    Ok(transactions)
+}
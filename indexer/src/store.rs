@@ -0,0 +1,408 @@
+use std::path::Path;
+
+use anchor_lang::prelude::Pubkey;
+use comptoir::CreatorPayment;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+
+use crate::events::{decode_event, ComptoirEvent};
+use crate::logs::{parse_logs, LogEntry};
+use crate::source::LoggedTransaction;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS transactions (
+    signature TEXT PRIMARY KEY,
+    slot INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS instructions (
+    signature TEXT NOT NULL,
+    slot INTEGER NOT NULL,
+    name TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS comptoirs (
+    address TEXT PRIMARY KEY,
+    authority TEXT NOT NULL,
+    mint TEXT NOT NULL,
+    fees INTEGER NOT NULL,
+    fees_destination TEXT NOT NULL,
+    updated_slot INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS collections (
+    address TEXT PRIMARY KEY,
+    comptoir TEXT,
+    name TEXT,
+    symbol TEXT NOT NULL,
+    required_verifier TEXT NOT NULL,
+    fees INTEGER,
+    ignore_creator_fee INTEGER NOT NULL,
+    custom_currencies INTEGER NOT NULL DEFAULT 0,
+    updated_slot INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS listings (
+    sell_order TEXT PRIMARY KEY,
+    comptoir TEXT NOT NULL,
+    collection TEXT NOT NULL,
+    mint TEXT NOT NULL,
+    authority TEXT NOT NULL,
+    price INTEGER NOT NULL,
+    quantity INTEGER NOT NULL,
+    payment_mint TEXT NOT NULL,
+    status TEXT NOT NULL,
+    created_slot INTEGER NOT NULL,
+    updated_slot INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS listings_by_collection ON listings (comptoir, collection, status);
+CREATE TABLE IF NOT EXISTS offers (
+    buy_offer TEXT PRIMARY KEY,
+    comptoir TEXT NOT NULL,
+    collection TEXT NOT NULL,
+    mint TEXT,
+    authority TEXT NOT NULL,
+    price INTEGER NOT NULL,
+    quantity INTEGER NOT NULL,
+    payment_mint TEXT NOT NULL,
+    status TEXT NOT NULL,
+    created_slot INTEGER NOT NULL,
+    updated_slot INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS offers_by_collection ON offers (comptoir, collection, status);
+CREATE TABLE IF NOT EXISTS sales (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    signature TEXT NOT NULL,
+    slot INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    order_address TEXT NOT NULL,
+    comptoir TEXT,
+    collection TEXT,
+    mint TEXT,
+    buyer TEXT,
+    seller TEXT,
+    quantity INTEGER NOT NULL,
+    price INTEGER NOT NULL,
+    payment_mint TEXT,
+    seller_amount INTEGER,
+    comptoir_amount INTEGER
+);
+CREATE INDEX IF NOT EXISTS sales_by_collection ON sales (comptoir, collection);
+CREATE TABLE IF NOT EXISTS payouts (
+    sale_id INTEGER NOT NULL REFERENCES sales (id),
+    destination TEXT NOT NULL,
+    amount INTEGER NOT NULL
+);
+";
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: &Path) -> Result<Store> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Store { conn })
+    }
+
+    pub fn is_indexed(&self, signature: &str) -> Result<bool> {
+        let found = self.conn
+            .query_row("SELECT 1 FROM transactions WHERE signature = ?1", params![signature], |_| Ok(()))
+            .optional()?;
+        Ok(found.is_some())
+    }
+
+    // A transaction is indexed all at once, so the store never holds half of one
+    pub fn index_transaction(&mut self, transaction: &LoggedTransaction, program_id: &Pubkey) -> Result<()> {
+        let db = self.conn.transaction()?;
+        db.execute(
+            "INSERT INTO transactions (signature, slot) VALUES (?1, ?2)",
+            params![transaction.signature, transaction.slot],
+        )?;
+        for entry in parse_logs(&transaction.logs, program_id) {
+            match entry {
+                LogEntry::Instruction(name) => {
+                    db.execute(
+                        "INSERT INTO instructions (signature, slot, name) VALUES (?1, ?2, ?3)",
+                        params![transaction.signature, transaction.slot, name],
+                    )?;
+                }
+                LogEntry::Event(data) => {
+                    if let Some(event) = decode_event(&data) {
+                        apply_event(&db, &transaction.signature, transaction.slot, event)?;
+                    }
+                }
+            }
+        }
+        db.commit()
+    }
+}
+
+fn apply_event(db: &Connection, signature: &str, slot: u64, event: ComptoirEvent) -> Result<()> {
+    match event {
+        ComptoirEvent::ComptoirCreated(event) => {
+            upsert_comptoir(db, slot, &event.comptoir, &event.authority, &event.mint, event.fees, &event.fees_destination)?;
+        }
+        ComptoirEvent::ComptoirUpdated(event) => {
+            upsert_comptoir(db, slot, &event.comptoir, &event.authority, &event.mint, event.fees, &event.fees_destination)?;
+        }
+        ComptoirEvent::CollectionCreated(event) => {
+            db.execute(
+                "INSERT INTO collections (address, comptoir, name, symbol, required_verifier, fees, ignore_creator_fee, updated_slot)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                ON CONFLICT (address) DO UPDATE SET comptoir = ?2, name = ?3, symbol = ?4, required_verifier = ?5,
+                fees = ?6, ignore_creator_fee = ?7, updated_slot = ?8",
+                params![
+                    event.collection.to_string(),
+                    event.comptoir.to_string(),
+                    event.name,
+                    event.symbol,
+                    event.required_verifier.to_string(),
+                    event.fees,
+                    event.ignore_creator_fee,
+                    slot,
+                ],
+            )?;
+        }
+        ComptoirEvent::CollectionUpdated(event) => {
+            db.execute(
+                "INSERT INTO collections (address, symbol, required_verifier, fees, ignore_creator_fee, custom_currencies, updated_slot)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                ON CONFLICT (address) DO UPDATE SET symbol = ?2, required_verifier = ?3, fees = ?4,
+                ignore_creator_fee = ?5, custom_currencies = ?6, updated_slot = ?7",
+                params![
+                    event.collection.to_string(),
+                    event.symbol,
+                    event.required_verifier.to_string(),
+                    event.fees,
+                    event.ignore_creator_fee,
+                    event.custom_currencies,
+                    slot,
+                ],
+            )?;
+        }
+        ComptoirEvent::SellOrderCreated(event) => {
+            db.execute(
+                "INSERT OR REPLACE INTO listings
+                (sell_order, comptoir, collection, mint, authority, price, quantity, payment_mint, status, created_slot, updated_slot)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 'open', ?9, ?9)",
+                params![
+                    event.sell_order.to_string(),
+                    event.comptoir.to_string(),
+                    event.collection.to_string(),
+                    event.mint.to_string(),
+                    event.authority.to_string(),
+                    event.price,
+                    event.quantity,
+                    event.payment_mint.to_string(),
+                    slot,
+                ],
+            )?;
+        }
+        ComptoirEvent::SellOrderQuantityUpdated(event) => {
+            db.execute(
+                "UPDATE listings SET quantity = ?2, updated_slot = ?3 WHERE sell_order = ?1",
+                params![event.sell_order.to_string(), event.quantity, slot],
+            )?;
+        }
+        ComptoirEvent::SellOrderRemoved(event) => {
+            db.execute(
+                "UPDATE listings SET quantity = 0, status = 'removed', updated_slot = ?2 WHERE sell_order = ?1",
+                params![event.sell_order.to_string(), slot],
+            )?;
+        }
+        ComptoirEvent::BoughtSellOrder(event) => {
+            let sell_order = event.sell_order.to_string();
+            let listing: Option<(String, String, String)> = db
+                .query_row(
+                    "SELECT comptoir, collection, authority FROM listings WHERE sell_order = ?1",
+                    params![sell_order],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+                .optional()?;
+            let (comptoir, collection, seller) = split_option(listing);
+            let sale_id = insert_sale(db, signature, slot, &Sale {
+                kind: "sell_order",
+                order: sell_order.clone(),
+                comptoir,
+                collection,
+                mint: event.mint,
+                buyer: Some(event.buyer.to_string()),
+                seller,
+                quantity: event.quantity,
+                price: event.price,
+                payment_mint: event.payment_mint,
+                seller_amount: event.seller_amount,
+                comptoir_amount: event.comptoir_amount,
+            })?;
+            insert_payouts(db, sale_id, &event.creators)?;
+
+            db.execute(
+                "UPDATE listings SET quantity = MAX(quantity - ?2, 0), updated_slot = ?3,
+                status = CASE WHEN quantity - ?2 <= 0 THEN 'sold' ELSE status END
+                WHERE sell_order = ?1",
+                params![sell_order, event.quantity, slot],
+            )?;
+        }
+        ComptoirEvent::BuyOfferCreated(event) => {
+            // Collection offers have no mint
+            let mut mint = None;
+            if event.mint != Pubkey::default() {
+                mint = Some(event.mint.to_string());
+            }
+            db.execute(
+                "INSERT OR REPLACE INTO offers
+                (buy_offer, comptoir, collection, mint, authority, price, quantity, payment_mint, status, created_slot, updated_slot)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 'open', ?9, ?9)",
+                params![
+                    event.buy_offer.to_string(),
+                    event.comptoir.to_string(),
+                    event.collection.to_string(),
+                    mint,
+                    event.authority.to_string(),
+                    event.price,
+                    event.quantity,
+                    event.payment_mint.to_string(),
+                    slot,
+                ],
+            )?;
+        }
+        ComptoirEvent::BuyOfferRemoved(event) => {
+            db.execute(
+                "UPDATE offers SET quantity = 0, status = 'removed', updated_slot = ?2 WHERE buy_offer = ?1",
+                params![event.buy_offer.to_string(), slot],
+            )?;
+        }
+        ComptoirEvent::BuyOfferExecuted(event) => {
+            let buy_offer = event.buy_offer.to_string();
+            let offer: Option<(String, String, String)> = db
+                .query_row(
+                    "SELECT comptoir, collection, authority FROM offers WHERE buy_offer = ?1",
+                    params![buy_offer],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+                .optional()?;
+            let (comptoir, collection, buyer) = split_option(offer);
+            // Order book matches report the sale of the sell order first, it is not recorded twice
+            let matched: Option<i64> = db
+                .query_row(
+                    "SELECT id FROM sales WHERE signature = ?1 AND kind = 'sell_order' AND mint = ?2 AND quantity = ?3 AND price = ?4",
+                    params![signature, event.mint.to_string(), event.quantity, event.price],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if matched.is_none() {
+                let sale_id = insert_sale(db, signature, slot, &Sale {
+                    kind: "buy_offer",
+                    order: buy_offer.clone(),
+                    comptoir,
+                    collection,
+                    mint: event.mint,
+                    buyer,
+                    seller: Some(event.seller.to_string()),
+                    quantity: event.quantity,
+                    price: event.price,
+                    payment_mint: event.payment_mint,
+                    seller_amount: event.seller_amount,
+                    comptoir_amount: event.comptoir_amount,
+                })?;
+                insert_payouts(db, sale_id, &event.creators)?;
+            }
+
+            db.execute(
+                "UPDATE offers SET quantity = MAX(quantity - ?2, 0), updated_slot = ?3,
+                status = CASE WHEN quantity - ?2 <= 0 THEN 'filled' ELSE status END
+                WHERE buy_offer = ?1",
+                params![buy_offer, event.quantity, slot],
+            )?;
+        }
+        ComptoirEvent::AuctionSettled(event) => {
+            // Settlement events carry no breakdown, only what the winner paid
+            db.execute(
+                "INSERT INTO sales (signature, slot, kind, order_address, buyer, quantity, price)
+                VALUES (?1, ?2, 'auction', ?3, ?4, ?5, ?6)",
+                params![signature, slot, event.auction.to_string(), event.winner.to_string(), event.quantity, event.price],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+struct Sale {
+    kind: &'static str,
+    order: String,
+    comptoir: Option<String>,
+    collection: Option<String>,
+    mint: Pubkey,
+    buyer: Option<String>,
+    seller: Option<String>,
+    quantity: u64,
+    price: u64,
+    payment_mint: Pubkey,
+    seller_amount: u64,
+    comptoir_amount: u64,
+}
+
+fn upsert_comptoir(
+    db: &Connection,
+    slot: u64,
+    comptoir: &Pubkey,
+    authority: &Pubkey,
+    mint: &Pubkey,
+    fees: u16,
+    fees_destination: &Pubkey,
+) -> Result<()> {
+    db.execute(
+        "INSERT INTO comptoirs (address, authority, mint, fees, fees_destination, updated_slot)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        ON CONFLICT (address) DO UPDATE SET authority = ?2, mint = ?3, fees = ?4, fees_destination = ?5, updated_slot = ?6",
+        params![
+            comptoir.to_string(),
+            authority.to_string(),
+            mint.to_string(),
+            fees,
+            fees_destination.to_string(),
+            slot,
+        ],
+    )?;
+    Ok(())
+}
+
+fn insert_sale(db: &Connection, signature: &str, slot: u64, sale: &Sale) -> Result<i64> {
+    db.execute(
+        "INSERT INTO sales
+        (signature, slot, kind, order_address, comptoir, collection, mint, buyer, seller, quantity, price, payment_mint, seller_amount, comptoir_amount)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        params![
+            signature,
+            slot,
+            sale.kind,
+            sale.order,
+            sale.comptoir,
+            sale.collection,
+            sale.mint.to_string(),
+            sale.buyer,
+            sale.seller,
+            sale.quantity,
+            sale.price,
+            sale.payment_mint.to_string(),
+            sale.seller_amount,
+            sale.comptoir_amount,
+        ],
+    )?;
+    Ok(db.last_insert_rowid())
+}
+
+fn insert_payouts(db: &Connection, sale_id: i64, creators: &[CreatorPayment]) -> Result<()> {
+    for creator in creators {
+        db.execute(
+            "INSERT INTO payouts (sale_id, destination, amount) VALUES (?1, ?2, ?3)",
+            params![sale_id, creator.destination.to_string(), creator.amount],
+        )?;
+    }
+    Ok(())
+}
+
+// Orders created before the indexer started are unknown, their sales are kept without comptoir or collection
+fn split_option(row: Option<(String, String, String)>) -> (Option<String>, Option<String>, Option<String>) {
+    match row {
+        Some((a, b, c)) => (Some(a), Some(b), Some(c)),
+        None => (None, None, None),
+    }
+}
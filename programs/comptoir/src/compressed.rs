@@ -0,0 +1,141 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::keccak;
+use anchor_lang::solana_program::program::invoke_signed;
+use mpl_token_metadata::state::Creator;
+use std::str::FromStr;
+use crate::constant::BUBBLEGUM_PROGRAM;
+
+const TRANSFER_DISCRIMINATOR: [u8; 8] = [163, 52, 200, 231, 140, 3, 69, 186];
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CompressedCreator {
+    pub address: Pubkey,
+    pub verified: bool,
+    pub share: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CompressedCollection {
+    pub verified: bool,
+    pub key: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CompressedUses {
+    pub use_method: u8,
+    pub remaining: u64,
+    pub total: u64,
+}
+
+// Same layout as the bubblegum MetadataArgs, enums are passed as their u8 variant index
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CompressedMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    pub primary_sale_happened: bool,
+    pub is_mutable: bool,
+    pub edition_nonce: Option<u8>,
+    pub token_standard: Option<u8>,
+    pub collection: Option<CompressedCollection>,
+    pub uses: Option<CompressedUses>,
+    pub token_program_version: u8,
+    pub creators: Vec<CompressedCreator>,
+}
+
+impl CompressedMetadata {
+    pub fn data_hash(&self) -> [u8; 32] {
+        let metadata_hash = keccak::hashv(&[self.try_to_vec().unwrap().as_slice()]);
+        keccak::hashv(&[&metadata_hash.to_bytes(), &self.seller_fee_basis_points.to_le_bytes()]).to_bytes()
+    }
+
+    pub fn creator_hash(&self) -> [u8; 32] {
+        let creators_data = self.creators
+            .iter()
+            .map(|c| [c.address.as_ref(), &[c.verified as u8], &[c.share]].concat())
+            .collect::<Vec<_>>();
+        keccak::hashv(&creators_data.iter().map(|c| c.as_slice()).collect::<Vec<&[u8]>>()).to_bytes()
+    }
+
+    pub fn metaplex_creators(&self) -> Vec<Creator> {
+        self.creators
+            .iter()
+            .map(|c| Creator { address: c.address, verified: c.verified, share: c.share })
+            .collect()
+    }
+}
+
+#[derive(AnchorSerialize)]
+pub struct Leaf {
+    pub root: [u8; 32],
+    pub data_hash: [u8; 32],
+    pub creator_hash: [u8; 32],
+    pub nonce: u64,
+    pub index: u32,
+}
+
+pub struct TransferCompressed<'info> {
+    pub tree_authority: AccountInfo<'info>,
+    pub leaf_owner: AccountInfo<'info>,
+    pub leaf_delegate: AccountInfo<'info>,
+    pub new_leaf_owner: AccountInfo<'info>,
+    pub merkle_tree: AccountInfo<'info>,
+    pub log_wrapper: AccountInfo<'info>,
+    pub compression_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+pub fn get_asset_id(merkle_tree: &Pubkey, nonce: u64) -> Pubkey {
+    let bubblegum_program = Pubkey::from_str(BUBBLEGUM_PROGRAM).unwrap();
+    Pubkey::find_program_address(
+        &["asset".as_bytes(), merkle_tree.as_ref(), &nonce.to_le_bytes()],
+        &bubblegum_program,
+    ).0
+}
+
+// The leaf owner always signs the transfer, either directly or through the signer seeds when it is a PDA
+pub fn transfer_compressed<'info>(
+    bubblegum_program: AccountInfo<'info>,
+    accounts: TransferCompressed<'info>,
+    proof: &[AccountInfo<'info>],
+    leaf: Leaf,
+    signer: &[&[&[u8]]],
+) -> Result<()> {
+    let mut account_metas = vec![
+        AccountMeta::new_readonly(accounts.tree_authority.key(), false),
+        AccountMeta::new_readonly(accounts.leaf_owner.key(), true),
+        AccountMeta::new_readonly(accounts.leaf_delegate.key(), false),
+        AccountMeta::new_readonly(accounts.new_leaf_owner.key(), false),
+        AccountMeta::new(accounts.merkle_tree.key(), false),
+        AccountMeta::new_readonly(accounts.log_wrapper.key(), false),
+        AccountMeta::new_readonly(accounts.compression_program.key(), false),
+        AccountMeta::new_readonly(accounts.system_program.key(), false),
+    ];
+    let mut account_infos = vec![
+        accounts.tree_authority,
+        accounts.leaf_owner,
+        accounts.leaf_delegate,
+        accounts.new_leaf_owner,
+        accounts.merkle_tree,
+        accounts.log_wrapper,
+        accounts.compression_program,
+        accounts.system_program,
+    ];
+    for node in proof {
+        account_metas.push(AccountMeta::new_readonly(node.key(), false));
+        account_infos.push(node.clone());
+    }
+
+    let mut data = TRANSFER_DISCRIMINATOR.to_vec();
+    data.extend(leaf.try_to_vec()?);
+
+    let ix = Instruction {
+        program_id: bubblegum_program.key(),
+        accounts: account_metas,
+        data,
+    };
+    invoke_signed(&ix, &account_infos, signer)?;
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use std::io::Cursor;
+use crate::{BuyOffer, Collection, ErrorCode, SellOrder};
+
+// Sizes of the accounts created before their layout was extended, discriminator included
+pub const LEGACY_SELL_ORDER_LEN: usize = 152;
+pub const LEGACY_BUY_OFFER_LEN: usize = 144;
+pub const LEGACY_COLLECTION_LEN: usize = 110;
+
+// Current sizes of the same accounts, used both to create them and to grow the legacy ones
+pub const SELL_ORDER_LEN: usize = 271;
+pub const BUY_OFFER_LEN: usize = 258;
+pub const COLLECTION_LEN: usize = 177;
+
+// Sell orders as created before dutch auctions, expiration, vaults per comptoir, currencies and oracle prices
+#[derive(AnchorDeserialize)]
+pub struct LegacySellOrder {
+    pub comptoir: Pubkey,
+    pub price: u64,
+    pub quantity: u64,
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+    pub destination: Pubkey,
+}
+
+impl LegacySellOrder {
+    // Legacy orders were priced in the comptoir currency and still have their items in the shared vault
+    pub fn upgrade(self, payment_mint: Pubkey) -> SellOrder {
+        SellOrder {
+            comptoir: self.comptoir,
+            price: self.price,
+            quantity: self.quantity,
+            mint: self.mint,
+            authority: self.authority,
+            destination: self.destination,
+            price_schedule: None,
+            expires_at: None,
+            vault_migrated: false,
+            payment_mint,
+            oracle_price: None,
+        }
+    }
+}
+
+// Buy offers as created before expiration, collection, trait and partial offers and currencies
+#[derive(AnchorDeserialize)]
+pub struct LegacyBuyOffer {
+    pub comptoir: Pubkey,
+    pub mint: Pubkey,
+    pub proposed_price: u64,
+    pub authority: Pubkey,
+    pub destination: Pubkey,
+}
+
+impl LegacyBuyOffer {
+    // Legacy offers were for a single item, funded in the comptoir currency
+    pub fn upgrade(self, collection: Pubkey, payment_mint: Pubkey) -> BuyOffer {
+        BuyOffer {
+            comptoir: self.comptoir,
+            mint: self.mint,
+            proposed_price: self.proposed_price,
+            authority: self.authority,
+            destination: self.destination,
+            expires_at: None,
+            collection,
+            quantity: 1,
+            trait_hash: None,
+            payment_mint,
+        }
+    }
+}
+
+// Collections as created before traits, certified collections and collection currencies
+#[derive(AnchorDeserialize)]
+pub struct LegacyCollection {
+    pub comptoir_key: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub required_verifier: Pubkey,
+    pub fees: Option<u16>,
+    pub ignore_creator_fee: bool,
+}
+
+impl LegacyCollection {
+    pub fn upgrade(self) -> Collection {
+        Collection {
+            comptoir_key: self.comptoir_key,
+            name: self.name,
+            symbol: self.symbol,
+            required_verifier: self.required_verifier,
+            fees: self.fees,
+            ignore_creator_fee: self.ignore_creator_fee,
+            traits_root: None,
+            certified_collection: None,
+            custom_currencies: false,
+        }
+    }
+}
+
+pub fn is_legacy(account: &AccountInfo, legacy_len: usize) -> bool {
+    account.owner == &crate::ID && account.data_len() == legacy_len
+}
+
+// Anchor cannot load these accounts anymore, the owner, size and discriminator are checked by hand
+pub fn read_legacy<T: AnchorDeserialize>(account: &AccountInfo, discriminator: [u8; 8], legacy_len: usize) -> Result<T> {
+    if !is_legacy(account, legacy_len) {
+        return Err(error!(ErrorCode::ErrWrongLegacyAccount));
+    }
+    let data = account.try_borrow_data()?;
+    if data[..8] != discriminator {
+        return Err(error!(ErrorCode::ErrWrongLegacyAccount));
+    }
+    T::deserialize(&mut &data[8..]).map_err(|_| error!(ErrorCode::ErrWrongLegacyAccount))
+}
+
+// Grows the account to its current size, the payer covering the extra rent, and writes the upgraded content
+pub fn write_upgraded<'info, T: AccountSerialize>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    upgraded: &T,
+    len: usize,
+) -> Result<()> {
+    let missing_rent = Rent::get()?.minimum_balance(len).saturating_sub(account.lamports());
+    if missing_rent > 0 {
+        let cpi_accounts = system_program::Transfer {
+            from: payer.clone(),
+            to: account.clone(),
+        };
+        system_program::transfer(CpiContext::new(system_program.clone(), cpi_accounts), missing_rent)?;
+    }
+    account.realloc(len, true)?;
+
+    let mut data = account.try_borrow_mut_data()?;
+    let mut cursor = Cursor::new(&mut data[..]);
+    upgraded.try_serialize(&mut cursor)
+}
@@ -1,21 +1,41 @@
+// Anchor errors are large by design, every instruction returns them
+#![allow(clippy::result_large_err)]
+
 mod transfer;
+mod merkle;
+mod oracle;
+mod legacy;
+mod pnft;
+mod token22;
+pub mod compressed;
 
 use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
 use anchor_lang::prelude::*;
 use anchor_lang::AccountsClose;
-use metaplex_token_metadata::state::PREFIX as METAPLEX_PREFIX;
-use metaplex_token_metadata::state::{Creator, Metadata};
+use anchor_lang::Discriminator;
+use anchor_lang::solana_program::sysvar;
+use mpl_token_metadata::state::PREFIX as METAPLEX_PREFIX;
+use mpl_token_metadata::state::{Creator, Metadata};
 use std::str::FromStr;
 use anchor_spl::associated_token::AssociatedToken;
-use metaplex_token_metadata::utils::{assert_derivation};
-use crate::constant::{ASSOCIATED_TOKEN_PROGRAM};
-use crate::constant::{PREFIX, ESCROW};
+use mpl_token_metadata::utils::{assert_derivation};
+use crate::constant::{ASSOCIATED_TOKEN_PROGRAM, TOKEN_2022_PROGRAM};
+use crate::constant::{PREFIX, ESCROW, AUCTION, BID, COMPRESSED, ORDER_ID, ORDER_COUNTER, CURRENCY, ORDER_BOOK, ORDER_BOOK_CAPACITY, ORDER_BOOK_OWNER_CAPACITY};
+use crate::constant::{BUNDLE, BUNDLE_CAPACITY, SWAP, SWAP_CAPACITY};
+use crate::constant::{BUBBLEGUM_PROGRAM, SPL_ACCOUNT_COMPRESSION_PROGRAM, SPL_NOOP_PROGRAM};
+use crate::compressed::{get_asset_id, transfer_compressed, CompressedMetadata, Leaf, TransferCompressed};
+use crate::oracle::{load_price_feed, PriceFeed};
+use crate::legacy::{is_legacy, read_legacy, write_upgraded, LegacyBuyOffer, LegacyCollection, LegacySellOrder};
+use crate::legacy::{BUY_OFFER_LEN, COLLECTION_LEN, LEGACY_BUY_OFFER_LEN, LEGACY_COLLECTION_LEN, LEGACY_SELL_ORDER_LEN, SELL_ORDER_LEN};
+use crate::pnft::{load_programmable_metadata, TransferProgrammable};
+use crate::token22::{create_associated_account, create_escrow, load_mint_of, load_token22_currency, split_hook_accounts, transfer_checked};
+use crate::transfer::{pay, pay_from_wallet, pay_with_signer};
 
 declare_id!("FCoMPzD3cihsM7EBSbXtorF2yHL4jJ6vrbWtdVaN7qZc");
 
 #[program]
 pub mod comptoir {
-    use crate::transfer::{pay, pay_with_signer};
+    use crate::transfer::{pay_from_escrow, pay_checked};
     use super::*;
 
     pub fn create_comptoir(
@@ -29,7 +49,30 @@ pub mod comptoir {
         comptoir.mint = mint;
 
         comptoir.validate()?;
-        Ok(())
+        emit!(ComptoirCreatedEvent{
+            comptoir: comptoir.key(),
+            authority,
+            mint,
+            fees,
+            fees_destination,
+        });
+
+        let comptoir_key = ctx.accounts.comptoir.key();
+        let seeds = &[
+            PREFIX.as_bytes(),
+            comptoir_key.as_ref(),
+            mint.as_ref(),
+            ESCROW.as_bytes(),
+            &[*ctx.bumps.get("escrow").unwrap()], ];
+        create_escrow(
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.escrow.to_account_info(),
+            &ctx.accounts.mint.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.rent.to_account_info(),
+            &[&seeds[..]],
+        )
     }
 
     pub fn update_comptoir(
@@ -50,6 +93,7 @@ pub mod comptoir {
             comptoir.authority = authority;
         }
         comptoir.validate()?;
+        emit!(comptoir.updated_event(comptoir.key()));
         Ok(())
     }
 
@@ -62,7 +106,24 @@ pub mod comptoir {
         comptoir.mint = mint;
         comptoir.fees_destination = fees_destination;
         comptoir.validate()?;
-        Ok(())
+        emit!(comptoir.updated_event(comptoir.key()));
+
+        let comptoir_key = ctx.accounts.comptoir.key();
+        let seeds = &[
+            PREFIX.as_bytes(),
+            comptoir_key.as_ref(),
+            mint.as_ref(),
+            ESCROW.as_bytes(),
+            &[*ctx.bumps.get("escrow").unwrap()], ];
+        create_escrow(
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.escrow.to_account_info(),
+            &ctx.accounts.mint.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.rent.to_account_info(),
+            &[&seeds[..]],
+        )
     }
 
     pub fn create_collection(
@@ -78,6 +139,15 @@ pub mod comptoir {
         collection.ignore_creator_fee = ignore_fee;
 
         collection.validate()?;
+        emit!(CollectionCreatedEvent{
+            collection: collection.key(),
+            comptoir: collection.comptoir_key,
+            name: collection.name.clone(),
+            symbol: collection.symbol.clone(),
+            required_verifier,
+            fees: fee,
+            ignore_creator_fee: ignore_fee,
+        });
         Ok(())
     }
 
@@ -104,34 +174,227 @@ pub mod comptoir {
         }
 
         collection.validate()?;
+        emit!(collection.updated_event(collection.key()));
         Ok(())
     }
 
-    pub fn create_sell_order(ctx: Context<CreateSellOrder>, price: u64, quantity: u64, destination: Pubkey) -> Result<()> {
-        verify_metadata_and_derivation(
-            ctx.accounts.metadata.as_ref(),
-            &ctx.accounts.seller_nft_token_account.mint.key(),
-            &ctx.accounts.collection,
+    pub fn set_collection_traits_root(ctx: Context<UpdateCollection>, traits_root: Option<[u8; 32]>) -> Result<()> {
+        ctx.accounts.collection.traits_root = traits_root;
+        emit!(ctx.accounts.collection.updated_event(ctx.accounts.collection.key()));
+        Ok(())
+    }
+
+    pub fn set_certified_collection(ctx: Context<UpdateCollection>, certified_collection: Option<Pubkey>) -> Result<()> {
+        ctx.accounts.collection.certified_collection = certified_collection;
+        emit!(ctx.accounts.collection.updated_event(ctx.accounts.collection.key()));
+        Ok(())
+    }
+
+    // When set, the collection only accepts its own registered currencies besides the comptoir mint
+    pub fn set_collection_custom_currencies(ctx: Context<UpdateCollection>, custom_currencies: bool) -> Result<()> {
+        ctx.accounts.collection.custom_currencies = custom_currencies;
+        emit!(ctx.accounts.collection.updated_event(ctx.accounts.collection.key()));
+        Ok(())
+    }
+
+    // Grows a collection created before the collection fields were added, permissionless as nothing is decided here
+    pub fn migrate_collection(ctx: Context<MigrateCollection>) -> Result<()> {
+        let legacy: LegacyCollection = read_legacy(
+            &ctx.accounts.collection.to_account_info(),
+            Collection::discriminator(),
+            LEGACY_COLLECTION_LEN,
         )?;
 
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.seller_nft_token_account.to_account_info(),
-            to: ctx.accounts.vault.to_account_info(),
-            authority: ctx.accounts.payer.to_account_info(),
-        };
-        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
-        token::transfer(cpi_ctx, quantity)?;
+        let collection = legacy.upgrade();
+        write_upgraded(
+            &ctx.accounts.collection.to_account_info(),
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &collection,
+            COLLECTION_LEN,
+        )
+    }
 
-        let sell_order = &mut ctx.accounts.sell_order;
-        sell_order.comptoir = ctx.accounts.comptoir.key();
-        sell_order.price = price;
-        sell_order.quantity = quantity;
-        sell_order.mint = ctx.accounts.seller_nft_token_account.mint;
-        sell_order.authority = ctx.accounts.payer.key();
-        sell_order.destination = destination;
+    pub fn add_currency(ctx: Context<AddCurrency>, fees_destination: Pubkey) -> Result<()> {
+        let currency = &mut ctx.accounts.currency;
+        currency.comptoir = ctx.accounts.comptoir.key();
+        currency.scope = ctx.accounts.comptoir.key();
+        currency.mint = ctx.accounts.mint.key();
+        currency.fees_destination = fees_destination;
+        emit!(currency.updated_event(currency.key()));
+
+        let comptoir_key = ctx.accounts.comptoir.key();
+        let seeds = &[
+            PREFIX.as_bytes(),
+            comptoir_key.as_ref(),
+            ctx.accounts.mint.key.as_ref(),
+            ESCROW.as_bytes(),
+            &[*ctx.bumps.get("escrow").unwrap()], ];
+        create_escrow(
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.escrow.to_account_info(),
+            &ctx.accounts.mint.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.rent.to_account_info(),
+            &[&seeds[..]],
+        )
+    }
+
+    pub fn add_collection_currency(ctx: Context<AddCollectionCurrency>, fees_destination: Pubkey) -> Result<()> {
+        let currency = &mut ctx.accounts.currency;
+        currency.comptoir = ctx.accounts.comptoir.key();
+        currency.scope = ctx.accounts.collection.key();
+        currency.mint = ctx.accounts.mint.key();
+        currency.fees_destination = fees_destination;
+        emit!(currency.updated_event(currency.key()));
+
+        let comptoir_key = ctx.accounts.comptoir.key();
+        let seeds = &[
+            PREFIX.as_bytes(),
+            comptoir_key.as_ref(),
+            ctx.accounts.mint.key.as_ref(),
+            ESCROW.as_bytes(),
+            &[*ctx.bumps.get("escrow").unwrap()], ];
+        create_escrow(
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.escrow.to_account_info(),
+            &ctx.accounts.mint.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.rent.to_account_info(),
+            &[&seeds[..]],
+        )
+    }
+
+    pub fn update_currency(ctx: Context<UpdateCurrency>, fees_destination: Pubkey) -> Result<()> {
+        ctx.accounts.currency.fees_destination = fees_destination;
+        emit!(ctx.accounts.currency.updated_event(ctx.accounts.currency.key()));
+        Ok(())
+    }
+
+    // Orders and offers in a removed currency can still be removed but not filled anymore
+    pub fn remove_currency(ctx: Context<RemoveCurrency>) -> Result<()> {
+        emit!(CurrencyRemovedEvent{
+            currency: ctx.accounts.currency.key(),
+        });
+        Ok(())
+    }
+
+    pub fn create_sell_order(
+        ctx: Context<CreateSellOrder>,
+        price: u64,
+        quantity: u64,
+        destination: Pubkey,
+        price_schedule: Option<PriceSchedule>,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        init_sell_order(
+            &mut ctx.accounts.sell_order,
+            SellOrderListing {
+                comptoir: &ctx.accounts.comptoir,
+                collection: &ctx.accounts.collection,
+                metadata: ctx.accounts.metadata.to_account_info(),
+                seller_nft_token_account: &ctx.accounts.seller_nft_token_account,
+                vault: ctx.accounts.vault.to_account_info(),
+                payer: ctx.accounts.payer.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                remaining_accounts: ctx.remaining_accounts,
+            },
+            SellOrderTerms {
+                price,
+                quantity,
+                destination,
+                price_schedule,
+                expires_at,
+                oracle_price: None,
+            },
+        )
+    }
+
+    // The price is in reference units with the decimals of the order currency, it is converted with the price feed at buy time
+    pub fn create_oracle_sell_order(
+        ctx: Context<CreateSellOrder>,
+        price: u64,
+        quantity: u64,
+        destination: Pubkey,
+        price_schedule: Option<PriceSchedule>,
+        expires_at: Option<i64>,
+        oracle_price: OraclePrice,
+    ) -> Result<()> {
+        oracle_price.validate()?;
+        init_sell_order(
+            &mut ctx.accounts.sell_order,
+            SellOrderListing {
+                comptoir: &ctx.accounts.comptoir,
+                collection: &ctx.accounts.collection,
+                metadata: ctx.accounts.metadata.to_account_info(),
+                seller_nft_token_account: &ctx.accounts.seller_nft_token_account,
+                vault: ctx.accounts.vault.to_account_info(),
+                payer: ctx.accounts.payer.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                remaining_accounts: ctx.remaining_accounts,
+            },
+            SellOrderTerms {
+                price,
+                quantity,
+                destination,
+                price_schedule,
+                expires_at,
+                oracle_price: Some(oracle_price),
+            },
+        )
+    }
+
+    pub fn create_seller_order_counter(ctx: Context<CreateSellerOrderCounter>) -> Result<()> {
+        let counter = &mut ctx.accounts.counter;
+        counter.authority = ctx.accounts.payer.key();
+        counter.next_order_id = 0;
         Ok(())
     }
 
+    // Same as create_sell_order but addressed by an id, so a seller can have many orders at the same price
+    pub fn create_sell_order_with_id<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CreateSellOrderWithId<'info>>,
+        order_id: u64,
+        price: u64,
+        quantity: u64,
+        destination: Pubkey,
+        price_schedule: Option<PriceSchedule>,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        // When the seller order counter is passed first, ids have to be used in sequence
+        if let Some(Ok(mut counter)) = ctx.remaining_accounts.first().map(Account::<'info, SellerOrderCounter>::try_from) {
+            if counter.authority != ctx.accounts.payer.key() || counter.next_order_id != order_id {
+                return Err(error!(ErrorCode::ErrWrongOrderId));
+            }
+            counter.next_order_id = counter.next_order_id.checked_add(1).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+            counter.exit(ctx.program_id)?;
+        }
+
+        init_sell_order(
+            &mut ctx.accounts.sell_order,
+            SellOrderListing {
+                comptoir: &ctx.accounts.comptoir,
+                collection: &ctx.accounts.collection,
+                metadata: ctx.accounts.metadata.to_account_info(),
+                seller_nft_token_account: &ctx.accounts.seller_nft_token_account,
+                vault: ctx.accounts.vault.to_account_info(),
+                payer: ctx.accounts.payer.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                remaining_accounts: ctx.remaining_accounts,
+            },
+            SellOrderTerms {
+                price,
+                quantity,
+                destination,
+                price_schedule,
+                expires_at,
+                oracle_price: None,
+            },
+        )
+    }
+
     pub fn remove_sell_order(ctx: Context<RemoveSellOrder>, quantity_to_unlist: u64) -> Result<()> {
         if ctx.accounts.sell_order.quantity < quantity_to_unlist {
             return Err(error!(ErrorCode::ErrTryingToUnlistMoreThanOwned));
@@ -140,6 +403,7 @@ pub mod comptoir {
         let seeds = &[
             PREFIX.as_bytes(),
             "vault".as_bytes(),
+            ctx.accounts.sell_order.comptoir.as_ref(),
             ctx.accounts.seller_nft_token_account.mint.as_ref(),
             &[*ctx.bumps.get("vault").unwrap()], ];
         let signer = &[&seeds[..]];
@@ -154,10 +418,18 @@ pub mod comptoir {
         token::transfer(cpi_ctx, quantity_to_unlist)?;
 
         let sell_order = &mut ctx.accounts.sell_order;
-        sell_order.quantity = sell_order.quantity.checked_sub(quantity_to_unlist).unwrap();
+        sell_order.quantity = sell_order.quantity.checked_sub(quantity_to_unlist).ok_or(ErrorCode::ErrArithmeticOverflow)?;
 
         if ctx.accounts.sell_order.quantity == 0 {
+            emit!(SellOrderRemovedEvent{
+                sell_order: ctx.accounts.sell_order.key(),
+            });
             ctx.accounts.sell_order.close(ctx.accounts.authority.to_account_info())?;
+        } else {
+            emit!(SellOrderQuantityUpdatedEvent{
+                sell_order: ctx.accounts.sell_order.key(),
+                quantity: ctx.accounts.sell_order.quantity,
+            });
         }
         Ok(())
     }
@@ -172,13 +444,99 @@ pub mod comptoir {
         token::transfer(cpi_ctx, quantity_to_add)?;
 
         let sell_order = &mut ctx.accounts.sell_order;
-        sell_order.quantity = sell_order.quantity.checked_add(quantity_to_add).unwrap();
-
+        sell_order.quantity = sell_order.quantity.checked_add(quantity_to_add).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+        emit!(SellOrderQuantityUpdatedEvent{
+            sell_order: sell_order.key(),
+            quantity: sell_order.quantity,
+        });
         Ok(())
     }
 
-    pub fn buy<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, Buy<'info>>, ask_quantity: u64) -> Result<()> {
+    // Moves the items of an order listed before vaults were per comptoir out of the shared legacy vault.
+    // Orders still in the legacy layout are grown first, just like migrate_sell_order does
+    pub fn migrate_sell_order_vault(ctx: Context<MigrateSellOrderVault>) -> Result<()> {
+        let sell_order_info = ctx.accounts.sell_order.to_account_info();
+        if is_legacy(&sell_order_info, LEGACY_SELL_ORDER_LEN) {
+            let legacy: LegacySellOrder = read_legacy(
+                &sell_order_info,
+                SellOrder::discriminator(),
+                LEGACY_SELL_ORDER_LEN,
+            )?;
+            write_upgraded(
+                &sell_order_info,
+                &ctx.accounts.payer.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                &legacy.upgrade(ctx.accounts.comptoir.mint),
+                SELL_ORDER_LEN,
+            )?;
+        }
+
+        let mut sell_order: Account<SellOrder> = Account::try_from(&sell_order_info)?;
+        if sell_order.comptoir != ctx.accounts.comptoir.key() {
+            return Err(error!(ErrorCode::ErrWrongComptoir));
+        }
+        if sell_order.mint != ctx.accounts.mint.key() {
+            return Err(error!(ErrorCode::ErrWrongMint));
+        }
+        if sell_order.vault_migrated {
+            return Err(error!(ErrorCode::ErrSellOrderVaultAlreadyMigrated));
+        }
+
+        let seeds = &[
+            PREFIX.as_bytes(),
+            "vault".as_bytes(),
+            sell_order.mint.as_ref(),
+            &[*ctx.bumps.get("legacy_vault").unwrap()], ];
+        let signer = &[&seeds[..]];
+        pay_with_signer(
+            ctx.accounts.legacy_vault.to_account_info(),
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.legacy_vault.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            sell_order.quantity,
+            signer,
+        )?;
+
+        sell_order.vault_migrated = true;
+        sell_order.exit(ctx.program_id)
+    }
+
+    // Grows a sell order listed before the order fields were added, its items then still have to leave the legacy vault.
+    // Legacy orders are assumed to be priced in the current comptoir currency
+    pub fn migrate_sell_order(ctx: Context<MigrateSellOrder>) -> Result<()> {
+        let legacy: LegacySellOrder = read_legacy(
+            &ctx.accounts.sell_order.to_account_info(),
+            SellOrder::discriminator(),
+            LEGACY_SELL_ORDER_LEN,
+        )?;
+        if legacy.comptoir != ctx.accounts.comptoir.key() {
+            return Err(error!(ErrorCode::ErrWrongComptoir));
+        }
+
+        let sell_order = legacy.upgrade(ctx.accounts.comptoir.mint);
+        write_upgraded(
+            &ctx.accounts.sell_order.to_account_info(),
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &sell_order,
+            SELL_ORDER_LEN,
+        )
+    }
+
+    // The buyer bounds what the sell orders can charge, whatever their order or price changes
+    pub fn buy<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, Buy<'info>>,
+        ask_quantity: u64,
+        max_total_price: u64,
+        max_unit_price: Option<u64>,
+    ) -> Result<()> {
         let mut total_spent_ed: u64 = 0;
+        let now = Clock::get()?.unix_timestamp;
+        let (payment_mint, fees_destination) = get_payment_terms(&ctx.accounts.comptoir, &ctx.accounts.collection, ctx.remaining_accounts)?;
+        if ctx.accounts.comptoir_dest_account.key() != fees_destination {
+            return Err(error!(ErrorCode::ErrWrongFeesDestination));
+        }
+        let is_native = is_native_mint(&payment_mint);
 
         let metadata = verify_metadata_and_derivation(
             ctx.accounts.metadata.as_ref(),
@@ -191,19 +549,17 @@ pub mod comptoir {
         if !ctx.accounts.collection.ignore_creator_fee {
             if let Some(creators)  = metadata.data.creators {
                 index = creators.len();
-                let creators_distributions = verify_and_get_creators(creators, ctx.remaining_accounts, ctx.accounts.comptoir.mint);
+                let creators_distributions = verify_and_get_creators(creators, ctx.remaining_accounts, payment_mint)?;
                 creators_distributions_option = Some(creators_distributions);
             }
         }
 
-        let mut comptoir_fee = ctx.accounts.comptoir.fees;
-        if let Some(collection_share) = ctx.accounts.collection.fees {
-            comptoir_fee = collection_share;
-        }
+        let comptoir_fee = get_comptoir_fee(&ctx.accounts.comptoir, &ctx.accounts.collection);
 
         let seeds = &[
             PREFIX.as_bytes(),
             "vault".as_bytes(),
+            ctx.accounts.comptoir.to_account_info().key.as_ref(),
             ctx.accounts.buyer_nft_token_account.mint.as_ref(),
             &[*ctx.bumps.get("vault").unwrap()], ];
         let signer = &[&seeds[..]];
@@ -211,23 +567,56 @@ pub mod comptoir {
         let mut remaining_to_buy = ask_quantity;
 
         while index < ctx.remaining_accounts.len() {
-            let sell_order_result= Account::<'info, SellOrder>::try_from(&ctx.remaining_accounts[index]);
+            let sell_order_result= Account::<'info, SellOrder>::try_from(get_remaining_account(ctx.remaining_accounts, index)?);
             if sell_order_result.is_err() {
-                index = index + 2;
+                index += 2;
                 continue
             }
 
-            let mut sell_order = sell_order_result.unwrap();
-            assert_eq!(sell_order.comptoir, ctx.accounts.comptoir.key());
-            assert_eq!(sell_order.mint, ctx.accounts.buyer_nft_token_account.mint.key());
+            let mut sell_order = sell_order_result?;
+            if sell_order.comptoir != ctx.accounts.comptoir.key() {
+                return Err(error!(ErrorCode::ErrWrongComptoir));
+            }
+            if sell_order.mint != ctx.accounts.buyer_nft_token_account.mint.key() {
+                return Err(error!(ErrorCode::ErrWrongMint));
+            }
+            if !sell_order.vault_migrated {
+                return Err(error!(ErrorCode::ErrSellOrderVaultNotMigrated));
+            }
+            if sell_order.payment_mint != payment_mint {
+                return Err(error!(ErrorCode::ErrWrongCurrency));
+            }
+            if is_expired(sell_order.expires_at, now) {
+                return Err(error!(ErrorCode::ErrSellOrderExpired));
+            }
 
-            index = index + 1;
+            index += 1;
 
             let mut to_buy = remaining_to_buy;
             if sell_order.quantity < to_buy {
                 to_buy = sell_order.quantity;
             }
 
+            // Oracle priced orders are followed by their price feed
+            let mut unit_price = sell_order.current_price(now)?;
+            if let Some(oracle_price) = sell_order.oracle_price.as_ref() {
+                let price_feed_account = get_remaining_account(ctx.remaining_accounts, index)?;
+                index += 1;
+                if price_feed_account.key() != oracle_price.price_feed {
+                    return Err(error!(ErrorCode::ErrInvalidPriceFeed));
+                }
+                unit_price = oracle_price.convert(unit_price, &load_price_feed(price_feed_account)?, now)?;
+            }
+            if let Some(max_price) = max_unit_price {
+                if unit_price > max_price {
+                    return Err(error!(ErrorCode::ErrMaxUnitPriceExceeded));
+                }
+            }
+            let total_amount = unit_price.checked_mul(to_buy).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+            if total_spent_ed.checked_add(total_amount).ok_or(ErrorCode::ErrArithmeticOverflow)? > max_total_price {
+                return Err(error!(ErrorCode::ErrMaxTotalPriceExceeded));
+            }
+
             pay_with_signer(
                 ctx.accounts.vault.to_account_info(),
                 ctx.accounts.buyer_nft_token_account.to_account_info(),
@@ -237,40 +626,49 @@ pub mod comptoir {
                 signer,
             )?;
 
-            let seller_token_account = &ctx.remaining_accounts[index];
-            index = index + 1;
-            assert_eq!(seller_token_account.key(), sell_order.destination);
-            let total_amount = sell_order.price.checked_mul(to_buy).unwrap();
-            let mut creators_share: u64 = 0;
-            if !ctx.accounts.collection.ignore_creator_fee {
-                creators_share = calculate_fee(total_amount, metadata.data.seller_fee_basis_points, 10000);
+            let seller_token_account = get_remaining_account(ctx.remaining_accounts, index)?;
+            index += 1;
+            if seller_token_account.key() != sell_order.destination {
+                return Err(error!(ErrorCode::ErrWrongSellerDestination));
             }
-            let comptoir_share = calculate_fee(total_amount, comptoir_fee, 10000);
-            let seller_share = total_amount.checked_sub(creators_share).unwrap().checked_sub(comptoir_share).unwrap();
+            let (seller_share, comptoir_share, creators_share) = split_amount(
+                total_amount,
+                comptoir_fee,
+                &ctx.accounts.collection,
+                metadata.data.seller_fee_basis_points,
+            )?;
 
-            pay(
+            pay_from_wallet(
+                is_native,
                 ctx.accounts.buyer_paying_token_account.to_account_info(),
                 seller_token_account.to_account_info(),
                 ctx.accounts.buyer.to_account_info(),
                 ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
                 seller_share,
             )?;
-            pay(
+            pay_from_wallet(
+                is_native,
                 ctx.accounts.buyer_paying_token_account.to_account_info(),
                 ctx.accounts.comptoir_dest_account.to_account_info(),
                 ctx.accounts.buyer.to_account_info(),
                 ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
                 comptoir_share,
             )?;
 
+            let mut creators_payments = Vec::new();
             if let Some(creators) = creators_distributions_option.as_ref() {
                 for creator in creators {
-                    let creator_share = calculate_fee(creators_share, creator.1 as u16, 100);
-                    pay(
+                    let creator_share = calculate_fee(creators_share, creator.1 as u16, 100)?;
+                    creators_payments.push(CreatorPayment { destination: creator.0.key(), amount: creator_share });
+                    pay_from_wallet(
+                        is_native,
                         ctx.accounts.buyer_paying_token_account.to_account_info(),
                         creator.0.to_account_info(),
                         ctx.accounts.buyer.to_account_info(),
                         ctx.accounts.token_program.to_account_info(),
+                        ctx.accounts.system_program.to_account_info(),
                         creator_share,
                     )?;
                 }
@@ -280,14 +678,20 @@ pub mod comptoir {
                 sell_order: sell_order.key(),
                 quantity: to_buy,
                 buyer: ctx.accounts.buyer.key(),
+                mint: sell_order.mint,
+                price: total_amount,
+                payment_mint,
+                seller_amount: seller_share,
+                comptoir_amount: comptoir_share,
+                creators: creators_payments,
             });
 
-            total_spent_ed = total_spent_ed + total_amount;
+            total_spent_ed += total_amount;
 
-            sell_order.quantity = sell_order.quantity - to_buy;
+            sell_order.quantity -= to_buy;
             sell_order.exit(ctx.program_id)?;
 
-            remaining_to_buy = remaining_to_buy - to_buy;
+            remaining_to_buy -= to_buy;
             if remaining_to_buy == 0 {
                 break;
             }
@@ -300,36 +704,119 @@ pub mod comptoir {
         Ok(())
     }
 
-    pub fn create_buy_offer(ctx: Context<CreateBuyOffer>, price_proposition: u64) -> Result<()> {
+    // The price proposition is a unit price, quantity items of the mint can be bought with the offer
+    pub fn create_buy_offer(
+        ctx: Context<CreateBuyOffer>,
+        price_proposition: u64,
+        quantity: u64,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
         verify_metadata_and_derivation(
             ctx.accounts.metadata.as_ref(),
             &ctx.accounts.nft_mint.key(),
             &ctx.accounts.collection,
         )?;
+        init_buy_offer(
+            &mut ctx.accounts.buy_offer,
+            BuyOfferFunding {
+                comptoir: &ctx.accounts.comptoir,
+                collection: &ctx.accounts.collection,
+                escrow: &ctx.accounts.escrow,
+                buyer_paying_account: ctx.accounts.buyer_paying_account.to_account_info(),
+                payer: ctx.accounts.payer.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                remaining_accounts: ctx.remaining_accounts,
+            },
+            OfferTarget {
+                mint: ctx.accounts.nft_mint.key(),
+                destination: ctx.accounts.buyer_nft_account.key(),
+                trait_hash: None,
+            },
+            price_proposition,
+            quantity,
+            expires_at,
+        )
+    }
 
-        let buy_offer = &mut ctx.accounts.buy_offer;
-        buy_offer.mint = ctx.accounts.nft_mint.key();
-        buy_offer.authority = ctx.accounts.payer.key();
-        buy_offer.proposed_price = price_proposition;
-        buy_offer.comptoir = ctx.accounts.comptoir.key();
-        buy_offer.destination = ctx.accounts.buyer_nft_account.key();
-
-        pay(
-            ctx.accounts.buyer_paying_account.to_account_info(),
-            ctx.accounts.escrow.to_account_info(),
-            ctx.accounts.payer.to_account_info(),
-            ctx.accounts.token_program.to_account_info(),
+    // Any item of the collection can fill the offer
+    pub fn create_collection_buy_offer(
+        ctx: Context<CreateCollectionBuyOffer>,
+        price_proposition: u64,
+        quantity: u64,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        init_buy_offer(
+            &mut ctx.accounts.buy_offer,
+            BuyOfferFunding {
+                comptoir: &ctx.accounts.comptoir,
+                collection: &ctx.accounts.collection,
+                escrow: &ctx.accounts.escrow,
+                buyer_paying_account: ctx.accounts.buyer_paying_account.to_account_info(),
+                payer: ctx.accounts.payer.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                remaining_accounts: ctx.remaining_accounts,
+            },
+            OfferTarget {
+                mint: Pubkey::default(),
+                destination: Pubkey::default(),
+                trait_hash: None,
+            },
             price_proposition,
-        )?;
+            quantity,
+            expires_at,
+        )
+    }
 
-        Ok(())
+    // Any item of the collection proven to have the trait can fill the offer
+    pub fn create_trait_buy_offer(
+        ctx: Context<CreateTraitBuyOffer>,
+        price_proposition: u64,
+        quantity: u64,
+        trait_hash: [u8; 32],
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        if ctx.accounts.collection.traits_root.is_none() {
+            return Err(error!(ErrorCode::ErrCollectionHasNoTraitsRoot));
+        }
+        init_buy_offer(
+            &mut ctx.accounts.buy_offer,
+            BuyOfferFunding {
+                comptoir: &ctx.accounts.comptoir,
+                collection: &ctx.accounts.collection,
+                escrow: &ctx.accounts.escrow,
+                buyer_paying_account: ctx.accounts.buyer_paying_account.to_account_info(),
+                payer: ctx.accounts.payer.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                remaining_accounts: ctx.remaining_accounts,
+            },
+            OfferTarget {
+                mint: Pubkey::default(),
+                destination: Pubkey::default(),
+                trait_hash: Some(trait_hash),
+            },
+            price_proposition,
+            quantity,
+            expires_at,
+        )
     }
 
     pub fn remove_buy_offer(ctx: Context<RemoveBuyOffer>) -> Result<()> {
+        emit!(BuyOfferRemovedEvent{
+            buy_offer: ctx.accounts.buy_offer.key(),
+        });
+
+        // Native SOL funds are held by the buy offer account and go back to the buyer when it is closed
+        if is_native_mint(&ctx.accounts.buy_offer.payment_mint) {
+            return Ok(());
+        }
+
         let seeds = &[
             PREFIX.as_bytes(),
             ctx.accounts.comptoir.to_account_info().key.as_ref(),
-            ctx.accounts.comptoir.mint.as_ref(),
+            ctx.accounts.buy_offer.payment_mint.as_ref(),
             ESCROW.as_bytes(),
             &[*ctx.bumps.get("escrow").unwrap()], ];
 
@@ -339,18 +826,71 @@ pub mod comptoir {
             ctx.accounts.buyer_paying_account.to_account_info(),
             ctx.accounts.escrow.to_account_info(),
             ctx.accounts.token_program.to_account_info(),
-            ctx.accounts.buy_offer.proposed_price,
+            ctx.accounts.buy_offer.locked_amount()?,
             signer,
         )?;
         Ok(())
     }
 
-    pub fn execute_offer<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, ExecuteOffer<'info>>) -> Result<()> {
+    // Grows a buy offer created before the offer fields were added so that it can be removed or executed again.
+    // The buyer picks the collection of the item, legacy offers being assumed funded in the current comptoir currency
+    pub fn migrate_buy_offer(ctx: Context<MigrateBuyOffer>) -> Result<()> {
+        let legacy: LegacyBuyOffer = read_legacy(
+            &ctx.accounts.buy_offer.to_account_info(),
+            BuyOffer::discriminator(),
+            LEGACY_BUY_OFFER_LEN,
+        )?;
+        if legacy.comptoir != ctx.accounts.comptoir.key() {
+            return Err(error!(ErrorCode::ErrWrongComptoir));
+        }
+        if legacy.authority != ctx.accounts.buyer.key() {
+            return Err(error!(ErrorCode::ErrWrongBuyOfferAuthority));
+        }
+        verify_metadata_and_derivation(
+            ctx.accounts.metadata.as_ref(),
+            &legacy.mint,
+            &ctx.accounts.collection,
+        )?;
+
+        let buy_offer = legacy.upgrade(ctx.accounts.collection.key(), ctx.accounts.comptoir.mint);
+        write_upgraded(
+            &ctx.accounts.buy_offer.to_account_info(),
+            &ctx.accounts.buyer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &buy_offer,
+            BUY_OFFER_LEN,
+        )
+    }
+
+    pub fn execute_offer<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, ExecuteOffer<'info>>,
+        quantity: u64,
+        trait_proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        if is_expired(ctx.accounts.buy_offer.expires_at, Clock::get()?.unix_timestamp) {
+            return Err(error!(ErrorCode::ErrBuyOfferExpired));
+        }
+        let (payment_mint, fees_destination) = get_payment_terms(&ctx.accounts.comptoir, &ctx.accounts.collection, ctx.remaining_accounts)?;
+        if ctx.accounts.buy_offer.payment_mint != payment_mint {
+            return Err(error!(ErrorCode::ErrOfferCurrencyChanged));
+        }
+        if ctx.accounts.comptoir_dest_account.key() != fees_destination {
+            return Err(error!(ErrorCode::ErrWrongFeesDestination));
+        }
+
         let metadata = verify_metadata_and_derivation(
             &ctx.accounts.metadata,
             &ctx.accounts.seller_nft_account.mint,
             &ctx.accounts.collection,
         )?;
+        check_offer_fill(
+            &ctx.accounts.buy_offer,
+            &ctx.accounts.collection,
+            &ctx.accounts.seller_nft_account,
+            &ctx.accounts.destination,
+            quantity,
+            &trait_proof,
+        )?;
 
         //Transfer NFT to buyer
         pay(
@@ -358,156 +898,5366 @@ pub mod comptoir {
         ctx.accounts.destination.to_account_info(),
             ctx.accounts.seller.to_account_info(),
             ctx.accounts.token_program.to_account_info(),
-            1
+            quantity,
         )?;
 
         let mut creators_distributions_option: Option<Vec<(&AccountInfo, u8)>> = None;
         if !ctx.accounts.collection.ignore_creator_fee {
             if let Some(creators) = metadata.data.creators {
-                let creators_distributions = verify_and_get_creators(creators, ctx.remaining_accounts, ctx.accounts.comptoir.mint);
+                let creators_distributions = verify_and_get_creators(creators, ctx.remaining_accounts, ctx.accounts.buy_offer.payment_mint)?;
                 creators_distributions_option = Some(creators_distributions);
             }
         }
 
-        let mut comptoir_fee = ctx.accounts.comptoir.fees;
-        msg!("lulz");
-        if let Some(collection_share) = ctx.accounts.collection.fees {
-            msg!("lalla");
-            comptoir_fee = collection_share;
-        }
-        msg!(comptoir_fee.to_string().as_str());
-        let total_amount = ctx.accounts.buy_offer.proposed_price;
-        let mut creators_share = 0;
-        if !ctx.accounts.collection.ignore_creator_fee {
-            creators_share = calculate_fee(total_amount, metadata.data.seller_fee_basis_points, 10000);
-        }
-        let comptoir_share = calculate_fee(total_amount, comptoir_fee, 10000);
-        let seller_share = total_amount.checked_sub(creators_share).unwrap().checked_sub(comptoir_share).unwrap();
+        let comptoir_fee = get_comptoir_fee(&ctx.accounts.comptoir, &ctx.accounts.collection);
+        let total_amount = ctx.accounts.buy_offer.proposed_price.checked_mul(quantity).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+        let (seller_share, comptoir_share, creators_share) = split_amount(
+            total_amount,
+            comptoir_fee,
+            &ctx.accounts.collection,
+            metadata.data.seller_fee_basis_points,
+        )?;
 
         let seeds = &[
             PREFIX.as_bytes(),
             ctx.accounts.comptoir.to_account_info().key.as_ref(),
-            ctx.accounts.comptoir.mint.as_ref(),
+            ctx.accounts.buy_offer.payment_mint.as_ref(),
             ESCROW.as_bytes(),
             &[*ctx.bumps.get("escrow").unwrap()], ];
         let signer: &[&[&[u8]]] = &[&seeds[..]];
+        let is_native = is_native_mint(&ctx.accounts.buy_offer.payment_mint);
 
+        let mut creators_payments = Vec::new();
         if let Some(creators) = creators_distributions_option.as_ref() {
             for creator in creators {
-                let creator_share = calculate_fee(creators_share, creator.1 as u16, 100);
-                pay_with_signer(
+                let creator_share = calculate_fee(creators_share, creator.1 as u16, 100)?;
+                creators_payments.push(CreatorPayment { destination: creator.0.key(), amount: creator_share });
+                pay_from_escrow(
+                    is_native,
+                    ctx.accounts.buy_offer.to_account_info(),
                     ctx.accounts.escrow.to_account_info(),
                     creator.0.to_account_info(),
-                    ctx.accounts.escrow.to_account_info(),
                     ctx.accounts.token_program.to_account_info(),
                     creator_share,
-                    signer
+                    signer,
                 )?;
             }
         }
 
-        msg!(comptoir_share.to_string().as_str());
-
-        pay_with_signer(
+        pay_from_escrow(
+            is_native,
+            ctx.accounts.buy_offer.to_account_info(),
             ctx.accounts.escrow.to_account_info(),
             ctx.accounts.comptoir_dest_account.to_account_info(),
-            ctx.accounts.escrow.to_account_info(),
             ctx.accounts.token_program.to_account_info(),
             comptoir_share,
             signer,
         )?;
 
-        pay_with_signer(
+        pay_from_escrow(
+            is_native,
+            ctx.accounts.buy_offer.to_account_info(),
             ctx.accounts.escrow.to_account_info(),
             ctx.accounts.seller_funds_dest_account.to_account_info(),
-            ctx.accounts.escrow.to_account_info(),
             ctx.accounts.token_program.to_account_info(),
             seller_share,
             signer,
         )?;
 
+        emit!(BuyOfferExecutedEvent{
+            buy_offer: ctx.accounts.buy_offer.key(),
+            seller: ctx.accounts.seller.key(),
+            mint: ctx.accounts.seller_nft_account.mint,
+            quantity,
+            price: total_amount,
+            payment_mint,
+            seller_amount: seller_share,
+            comptoir_amount: comptoir_share,
+            creators: creators_payments,
+        });
+
+        let buy_offer = &mut ctx.accounts.buy_offer;
+        buy_offer.quantity = buy_offer.quantity.checked_sub(quantity).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+
+        if ctx.accounts.buy_offer.quantity == 0 {
+            ctx.accounts.buy_offer.close(ctx.accounts.buyer.to_account_info())?;
+        }
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-#[instruction(price_proposition: u64)]
-pub struct CreateBuyOffer<'info> {
-    #[account(mut)]
-    payer: Signer<'info>,
+    pub fn close_expired_sell_order(ctx: Context<CloseExpiredSellOrder>) -> Result<()> {
+        if !is_expired(ctx.accounts.sell_order.expires_at, Clock::get()?.unix_timestamp) {
+            return Err(error!(ErrorCode::ErrNotExpired));
+        }
 
-    nft_mint: Account<'info, Mint>,
-    /// CHECK: This is not dangerous because check it all the time using the verify_metadata_and_derivation func
-    metadata: UncheckedAccount<'info>,
+        let seeds = &[
+            PREFIX.as_bytes(),
+            "vault".as_bytes(),
+            ctx.accounts.sell_order.comptoir.as_ref(),
+            ctx.accounts.sell_order.mint.as_ref(),
+            &[*ctx.bumps.get("vault").unwrap()], ];
+        let signer = &[&seeds[..]];
+        pay_with_signer(
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.seller_nft_token_account.to_account_info(),
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.sell_order.quantity,
+            signer,
+        )?;
+        emit!(SellOrderRemovedEvent{
+            sell_order: ctx.accounts.sell_order.key(),
+        });
+        Ok(())
+    }
 
-    comptoir: Box<Account<'info, Comptoir>>,
-    #[account(mut, constraint = collection.comptoir_key == comptoir.key())]
-    collection: Box<Account<'info, Collection>>,
-    #[account(
-    mut,
-    seeds = [
-    PREFIX.as_bytes(),
-    comptoir.key().as_ref(),
-    comptoir.mint.as_ref(),
-    ESCROW.as_bytes()
-    ],
-    bump,
-    )]
-    escrow: Box<Account<'info, TokenAccount>>,
+    pub fn close_expired_buy_offer(ctx: Context<CloseExpiredBuyOffer>) -> Result<()> {
+        if !is_expired(ctx.accounts.buy_offer.expires_at, Clock::get()?.unix_timestamp) {
+            return Err(error!(ErrorCode::ErrNotExpired));
+        }
+        emit!(BuyOfferRemovedEvent{
+            buy_offer: ctx.accounts.buy_offer.key(),
+        });
 
-    #[account(mut)]
-    buyer_paying_account: Box<Account<'info, TokenAccount>>,
-    #[account(
-    init_if_needed,
-    payer = payer,
-    associated_token::mint = nft_mint,
-    associated_token::authority = payer,
-    )]
-    buyer_nft_account: Account<'info, TokenAccount>,
+        // Native SOL funds are held by the buy offer account and go back to the buyer when it is closed
+        if is_native_mint(&ctx.accounts.buy_offer.payment_mint) {
+            return Ok(());
+        }
+        let buyer_paying_account = Account::<TokenAccount>::try_from(&ctx.accounts.buyer_paying_account.to_account_info())?;
+        if buyer_paying_account.owner != ctx.accounts.buyer.key() {
+            return Err(error!(ErrorCode::ErrWrongBuyerPayingAccount));
+        }
 
-    #[account(
-    init,
-    seeds = [
-    PREFIX.as_bytes(),
-    comptoir.key().as_ref(),
-    payer.key.as_ref(),
-    nft_mint.key().as_ref(),
-    price_proposition.to_string().as_bytes(),
-    ESCROW.as_bytes(),
-    ],
-    bump,
-    payer = payer,
-    space = 144,
-    )]
-    buy_offer: Account<'info, BuyOffer>,
+        let seeds = &[
+            PREFIX.as_bytes(),
+            ctx.accounts.comptoir.to_account_info().key.as_ref(),
+            ctx.accounts.buy_offer.payment_mint.as_ref(),
+            ESCROW.as_bytes(),
+            &[*ctx.bumps.get("escrow").unwrap()], ];
+        let signer: &[&[&[u8]]] = &[&seeds[..]];
+        pay_with_signer(
+            ctx.accounts.escrow.to_account_info(),
+            ctx.accounts.buyer_paying_account.to_account_info(),
+            ctx.accounts.escrow.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.buy_offer.locked_amount()?,
+            signer,
+        )?;
+        Ok(())
+    }
 
-    system_program: Program<'info, System>,
-    token_program: Program<'info, Token>,
-    associated_token_program: Program<'info, AssociatedToken>,
-    rent: Sysvar<'info, Rent>,
-}
+    // A book per mint and currency, remaining accounts: the currency when not the comptoir one
+    pub fn create_order_book(ctx: Context<CreateOrderBook>, payment_mint: Pubkey) -> Result<()> {
+        verify_metadata_and_derivation(
+            ctx.accounts.metadata.as_ref(),
+            &ctx.accounts.mint.key(),
+            &ctx.accounts.collection,
+        )?;
+        let (accepted_mint, _) = get_payment_terms(&ctx.accounts.comptoir, &ctx.accounts.collection, ctx.remaining_accounts)?;
+        if accepted_mint != payment_mint {
+            return Err(error!(ErrorCode::ErrWrongCurrency));
+        }
 
-#[derive(Accounts)]
-pub struct RemoveBuyOffer<'info> {
-    #[account(mut)]
-    buyer: Signer<'info>,
+        let order_book = &mut ctx.accounts.order_book;
+        order_book.comptoir = ctx.accounts.comptoir.key();
+        order_book.collection = ctx.accounts.collection.key();
+        order_book.mint = ctx.accounts.mint.key();
+        order_book.payment_mint = payment_mint;
+        order_book.next_sequence = 0;
+        order_book.asks = Vec::new();
+        order_book.bids = Vec::new();
+        Ok(())
+    }
+
+    // Only fixed price orders can be booked, their price is part of the book ordering
+    pub fn add_sell_order_to_book(ctx: Context<AddSellOrderToBook>) -> Result<()> {
+        let sell_order = &ctx.accounts.sell_order;
+        let order_book = &mut ctx.accounts.order_book;
+        if sell_order.mint != order_book.mint
+            || sell_order.payment_mint != order_book.payment_mint
+            || !sell_order.vault_migrated
+            || sell_order.price_schedule.is_some()
+            || sell_order.oracle_price.is_some() {
+            return Err(error!(ErrorCode::ErrOrderNotBookable));
+        }
+        order_book.insert_ask(sell_order.key(), sell_order.authority, sell_order.price)
+    }
+
+    pub fn add_buy_offer_to_book(ctx: Context<AddBuyOfferToBook>) -> Result<()> {
+        let buy_offer = &ctx.accounts.buy_offer;
+        let order_book = &mut ctx.accounts.order_book;
+        if buy_offer.mint != order_book.mint
+            || buy_offer.payment_mint != order_book.payment_mint
+            || buy_offer.trait_hash.is_some() {
+            return Err(error!(ErrorCode::ErrOrderNotBookable));
+        }
+        order_book.insert_bid(buy_offer.key(), buy_offer.authority, buy_offer.proposed_price)
+    }
+
+    // Anyone can remove closed, filled or expired orders and the orders of a book whose currency is not accepted anymore,
+    // live ones only by their authority. Remaining accounts: the currency when not the comptoir one, even once removed
+    pub fn remove_from_order_book(ctx: Context<RemoveFromOrderBook>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let book_accepted = get_fees_destination_of(
+            &ctx.accounts.comptoir,
+            &ctx.accounts.collection,
+            &ctx.accounts.order_book.payment_mint,
+            ctx.remaining_accounts,
+            ctx.program_id,
+        )?.is_some();
+        let order = ctx.accounts.order.to_account_info();
+        let mut live_order_authority = None;
+        if let Ok(sell_order) = Account::<SellOrder>::try_from(&order) {
+            if sell_order.quantity > 0 && !is_expired(sell_order.expires_at, now) {
+                live_order_authority = Some(sell_order.authority);
+            }
+        } else if let Ok(buy_offer) = Account::<BuyOffer>::try_from(&order) {
+            if buy_offer.quantity > 0 && !is_expired(buy_offer.expires_at, now) {
+                live_order_authority = Some(buy_offer.authority);
+            }
+        }
+        if let Some(authority) = live_order_authority {
+            if book_accepted && authority != ctx.accounts.authority.key() {
+                return Err(error!(ErrorCode::ErrWrongOrderAccounts));
+            }
+        }
+        ctx.accounts.order_book.remove(&order.key())
+    }
+
+    // Crosses the best ask with the best bid at the price of the order booked first, fees and royalties are the ones of
+    // the collection of the buy offer. Remaining accounts: creators, then the sell order, its funds destination,
+    // the buy offer, its nft destination, the buyer refund account, the buyer and the currency when not the comptoir one
+    pub fn match_orders<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, MatchOrders<'info>>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let fees_destination = get_fees_destination_of(
+            &ctx.accounts.comptoir,
+            &ctx.accounts.collection,
+            &ctx.accounts.order_book.payment_mint,
+            ctx.remaining_accounts,
+            ctx.program_id,
+        )?.ok_or(ErrorCode::ErrOfferCurrencyChanged)?;
+        if ctx.accounts.comptoir_dest_account.key() != fees_destination {
+            return Err(error!(ErrorCode::ErrWrongFeesDestination));
+        }
+        let (ask, bid) = match (ctx.accounts.order_book.asks.first(), ctx.accounts.order_book.bids.first()) {
+            (Some(ask), Some(bid)) if ask.price <= bid.price => (*ask, *bid),
+            _ => return Err(error!(ErrorCode::ErrNothingToMatch)),
+        };
+
+        let metadata = verify_metadata_and_derivation(
+            ctx.accounts.metadata.as_ref(),
+            &ctx.accounts.order_book.mint,
+            &ctx.accounts.collection,
+        )?;
+        let mut index = 0;
+        let mut creators_distributions_option: Option<Vec<(&AccountInfo, u8)>> = None;
+        if !ctx.accounts.collection.ignore_creator_fee {
+            if let Some(creators) = metadata.data.creators {
+                index = creators.len();
+                let creators_distributions = verify_and_get_creators(creators, ctx.remaining_accounts, ctx.accounts.order_book.payment_mint)?;
+                creators_distributions_option = Some(creators_distributions);
+            }
+        }
+
+        let sell_order_account = get_remaining_account(ctx.remaining_accounts, index)?;
+        let seller_funds_dest_account = get_remaining_account(ctx.remaining_accounts, index + 1)?;
+        let buy_offer_account = get_remaining_account(ctx.remaining_accounts, index + 2)?;
+        let buyer_nft_account = get_remaining_account(ctx.remaining_accounts, index + 3)?;
+        let buyer_refund_account = get_remaining_account(ctx.remaining_accounts, index + 4)?;
+        let buyer = get_remaining_account(ctx.remaining_accounts, index + 5)?;
+        if sell_order_account.key() != ask.order || buy_offer_account.key() != bid.order {
+            return Err(error!(ErrorCode::ErrWrongOrderAccounts));
+        }
+
+        // Orders closed, filled or expired since they were booked are dropped instead of matched
+        let sell_order_result = Account::<'info, SellOrder>::try_from(sell_order_account);
+        let sell_order_live = match sell_order_result.as_ref() {
+            Ok(sell_order) => sell_order.quantity > 0 && !is_expired(sell_order.expires_at, now),
+            Err(_) => false,
+        };
+        if !sell_order_live {
+            return ctx.accounts.order_book.remove(&ask.order);
+        }
+        let buy_offer_result = Account::<'info, BuyOffer>::try_from(buy_offer_account);
+        let buy_offer_live = match buy_offer_result.as_ref() {
+            Ok(buy_offer) => buy_offer.quantity > 0 && !is_expired(buy_offer.expires_at, now),
+            Err(_) => false,
+        };
+        if !buy_offer_live {
+            return ctx.accounts.order_book.remove(&bid.order);
+        }
+        let mut sell_order = sell_order_result?;
+        let mut buy_offer = buy_offer_result?;
+
+        let is_native = is_native_mint(&buy_offer.payment_mint);
+        if seller_funds_dest_account.key() != sell_order.destination
+            || buyer_nft_account.key() != buy_offer.destination
+            || buyer.key() != buy_offer.authority
+            || buy_offer.collection != ctx.accounts.collection.key() {
+            return Err(error!(ErrorCode::ErrWrongOrderAccounts));
+        }
+        if is_native {
+            if buyer_refund_account.key() != buy_offer.authority {
+                return Err(error!(ErrorCode::ErrWrongBuyerPayingAccount));
+            }
+        } else {
+            let refund_token_account = Account::<TokenAccount>::try_from(buyer_refund_account)?;
+            if refund_token_account.owner != buy_offer.authority {
+                return Err(error!(ErrorCode::ErrWrongBuyerPayingAccount));
+            }
+        }
+
+        let quantity = std::cmp::min(sell_order.quantity, buy_offer.quantity);
+        let mut price = bid.price;
+        if ask.sequence < bid.sequence {
+            price = ask.price;
+        }
+        let total_amount = price.checked_mul(quantity).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+
+        let vault_seeds = &[
+            PREFIX.as_bytes(),
+            "vault".as_bytes(),
+            ctx.accounts.comptoir.to_account_info().key.as_ref(),
+            ctx.accounts.order_book.mint.as_ref(),
+            &[*ctx.bumps.get("vault").unwrap()], ];
+        let vault_signer = &[&vault_seeds[..]];
+        pay_with_signer(
+            ctx.accounts.vault.to_account_info(),
+            buyer_nft_account.to_account_info(),
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            quantity,
+            vault_signer,
+        )?;
+
+        let comptoir_fee = get_comptoir_fee(&ctx.accounts.comptoir, &ctx.accounts.collection);
+        let (seller_share, comptoir_share, creators_share) = split_amount(
+            total_amount,
+            comptoir_fee,
+            &ctx.accounts.collection,
+            metadata.data.seller_fee_basis_points,
+        )?;
+
+        let escrow_seeds = &[
+            PREFIX.as_bytes(),
+            ctx.accounts.comptoir.to_account_info().key.as_ref(),
+            ctx.accounts.order_book.payment_mint.as_ref(),
+            ESCROW.as_bytes(),
+            &[*ctx.bumps.get("escrow").unwrap()], ];
+        let escrow_signer: &[&[&[u8]]] = &[&escrow_seeds[..]];
+
+        let mut creators_payments = Vec::new();
+        if let Some(creators) = creators_distributions_option.as_ref() {
+            for creator in creators {
+                let creator_share = calculate_fee(creators_share, creator.1 as u16, 100)?;
+                creators_payments.push(CreatorPayment { destination: creator.0.key(), amount: creator_share });
+                pay_from_escrow(
+                    is_native,
+                    buy_offer.to_account_info(),
+                    ctx.accounts.escrow.to_account_info(),
+                    creator.0.to_account_info(),
+                    ctx.accounts.token_program.to_account_info(),
+                    creator_share,
+                    escrow_signer,
+                )?;
+            }
+        }
+        pay_from_escrow(
+            is_native,
+            buy_offer.to_account_info(),
+            ctx.accounts.escrow.to_account_info(),
+            ctx.accounts.comptoir_dest_account.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            comptoir_share,
+            escrow_signer,
+        )?;
+        pay_from_escrow(
+            is_native,
+            buy_offer.to_account_info(),
+            ctx.accounts.escrow.to_account_info(),
+            seller_funds_dest_account.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            seller_share,
+            escrow_signer,
+        )?;
+
+        // The buyer locked its own price, the difference goes back when matched lower
+        let refund = bid.price.checked_sub(price).ok_or(ErrorCode::ErrArithmeticOverflow)?.checked_mul(quantity).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+        if refund > 0 {
+            pay_from_escrow(
+                is_native,
+                buy_offer.to_account_info(),
+                ctx.accounts.escrow.to_account_info(),
+                buyer_refund_account.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                refund,
+                escrow_signer,
+            )?;
+        }
+
+        // A match is both a sale of the sell order and an execution of the buy offer, in that order
+        emit!(BoughtSellOrderEvent{
+            sell_order: sell_order.key(),
+            quantity,
+            buyer: buyer.key(),
+            mint: ctx.accounts.order_book.mint,
+            price: total_amount,
+            payment_mint: ctx.accounts.order_book.payment_mint,
+            seller_amount: seller_share,
+            comptoir_amount: comptoir_share,
+            creators: creators_payments.clone(),
+        });
+        emit!(BuyOfferExecutedEvent{
+            buy_offer: buy_offer.key(),
+            seller: sell_order.authority,
+            mint: ctx.accounts.order_book.mint,
+            quantity,
+            price: total_amount,
+            payment_mint: ctx.accounts.order_book.payment_mint,
+            seller_amount: seller_share,
+            comptoir_amount: comptoir_share,
+            creators: creators_payments,
+        });
+
+        sell_order.quantity = sell_order.quantity.checked_sub(quantity).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+        sell_order.exit(ctx.program_id)?;
+        if sell_order.quantity == 0 {
+            ctx.accounts.order_book.remove(&ask.order)?;
+        }
+
+        buy_offer.quantity = buy_offer.quantity.checked_sub(quantity).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+        if buy_offer.quantity == 0 {
+            ctx.accounts.order_book.remove(&bid.order)?;
+            buy_offer.close(buyer.to_account_info())?;
+        } else {
+            buy_offer.exit(ctx.program_id)?;
+        }
+        Ok(())
+    }
+
+    pub fn create_bundle_order(
+        ctx: Context<CreateBundleOrder>,
+        _bundle_id: u64,
+        price: u64,
+        destination: Pubkey,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        validate_expiration(expires_at)?;
+        let (payment_mint, _) = get_payment_terms_in(&ctx.accounts.comptoir, &ctx.accounts.comptoir.key(), ctx.remaining_accounts)?;
+
+        let bundle_order = &mut ctx.accounts.bundle_order;
+        bundle_order.comptoir = ctx.accounts.comptoir.key();
+        bundle_order.authority = ctx.accounts.payer.key();
+        bundle_order.destination = destination;
+        bundle_order.price = price;
+        bundle_order.payment_mint = payment_mint;
+        bundle_order.expires_at = expires_at;
+        bundle_order.items = Vec::new();
+        emit!(BundleCreatedEvent{
+            bundle_order: bundle_order.key(),
+            comptoir: bundle_order.comptoir,
+            authority: bundle_order.authority,
+            price,
+            payment_mint: bundle_order.payment_mint,
+        });
+        Ok(())
+    }
+
+    pub fn add_bundle_item(ctx: Context<AddBundleItem>) -> Result<()> {
+        verify_metadata_and_derivation(
+            ctx.accounts.metadata.as_ref(),
+            &ctx.accounts.mint.key(),
+            &ctx.accounts.collection,
+        )?;
+
+        let mint = ctx.accounts.mint.key();
+        let collection = ctx.accounts.collection.key();
+        let bundle_order = &mut ctx.accounts.bundle_order;
+        if bundle_order.items.len() >= BUNDLE_CAPACITY {
+            return Err(error!(ErrorCode::ErrBundleFull));
+        }
+        if bundle_order.items.iter().any(|item| item.mint == mint) {
+            return Err(error!(ErrorCode::ErrItemAlreadyInBundle));
+        }
+        bundle_order.items.push(BundleItem {
+            mint,
+            collection,
+        });
+        emit!(BundleItemAddedEvent{
+            bundle_order: bundle_order.key(),
+            mint,
+            collection,
+        });
+
+        pay(
+            ctx.accounts.seller_nft_token_account.to_account_info(),
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.payer.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            1,
+        )?;
+        Ok(())
+    }
+
+    // Remaining accounts: the vault and the seller token account of every item, in bundle order
+    pub fn remove_bundle_order<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, RemoveBundleOrder<'info>>) -> Result<()> {
+        let comptoir_key = ctx.accounts.comptoir.key();
+        for (i, item) in ctx.accounts.bundle_order.items.iter().enumerate() {
+            let vault = get_remaining_account(ctx.remaining_accounts, i * 2)?;
+            let seller_nft_token_account = get_remaining_account(ctx.remaining_accounts, i * 2 + 1)?;
+            let vault_bump = get_vault_bump(vault, &comptoir_key, &item.mint, ctx.program_id)?;
+            let seeds = &[
+                PREFIX.as_bytes(),
+                "vault".as_bytes(),
+                comptoir_key.as_ref(),
+                item.mint.as_ref(),
+                &[vault_bump], ];
+            let signer = &[&seeds[..]];
+            pay_with_signer(
+                vault.to_account_info(),
+                seller_nft_token_account.to_account_info(),
+                vault.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                1,
+                signer,
+            )?;
+        }
+        emit!(BundleRemovedEvent{
+            bundle_order: ctx.accounts.bundle_order.key(),
+        });
+        Ok(())
+    }
+
+    // Each item gets an equal part of the price, royalties and fees follow its own metadata and collection.
+    // Remaining accounts, for every item in bundle order: its collection, metadata, vault,
+    // the buyer token account and the creators accounts, then the currency when not the comptoir one
+    pub fn buy_bundle<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, BuyBundle<'info>>, max_price: u64) -> Result<()> {
+        let bundle_order = &ctx.accounts.bundle_order;
+        if is_expired(bundle_order.expires_at, Clock::get()?.unix_timestamp) {
+            return Err(error!(ErrorCode::ErrSellOrderExpired));
+        }
+        let fees_destination = get_fees_destination_in(
+            &ctx.accounts.comptoir,
+            &ctx.accounts.comptoir.key(),
+            &bundle_order.payment_mint,
+            ctx.remaining_accounts,
+            ctx.program_id,
+        )?.ok_or(ErrorCode::ErrOfferCurrencyChanged)?;
+        if ctx.accounts.comptoir_dest_account.key() != fees_destination {
+            return Err(error!(ErrorCode::ErrWrongFeesDestination));
+        }
+        if bundle_order.price > max_price {
+            return Err(error!(ErrorCode::ErrMaxTotalPriceExceeded));
+        }
+        if bundle_order.items.is_empty() {
+            return Err(error!(ErrorCode::ErrBundleEmpty));
+        }
+        let is_native = is_native_mint(&bundle_order.payment_mint);
+        let comptoir_key = ctx.accounts.comptoir.key();
+
+        let items_count = bundle_order.items.len() as u64;
+        let item_price = bundle_order.price / items_count;
+        let mut seller_total: u64 = 0;
+        let mut comptoir_total: u64 = 0;
+        let mut creators_payments = Vec::new();
+        let mut index = 0;
+        for (i, item) in bundle_order.items.iter().enumerate() {
+            let collection = Account::<'info, Collection>::try_from(get_remaining_account(ctx.remaining_accounts, index)?)?;
+            let metadata_account = get_remaining_account(ctx.remaining_accounts, index + 1)?;
+            let vault = get_remaining_account(ctx.remaining_accounts, index + 2)?;
+            let buyer_nft_token_account = get_remaining_account(ctx.remaining_accounts, index + 3)?;
+            index += 4;
+            if collection.key() != item.collection {
+                return Err(error!(ErrorCode::ErrWrongBundleAccounts));
+            }
+            let metadata = verify_metadata_and_derivation(metadata_account, &item.mint, &collection)?;
+
+            let vault_bump = get_vault_bump(vault, &comptoir_key, &item.mint, ctx.program_id)?;
+            let seeds = &[
+                PREFIX.as_bytes(),
+                "vault".as_bytes(),
+                comptoir_key.as_ref(),
+                item.mint.as_ref(),
+                &[vault_bump], ];
+            let signer = &[&seeds[..]];
+            pay_with_signer(
+                vault.to_account_info(),
+                buyer_nft_token_account.to_account_info(),
+                vault.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                1,
+                signer,
+            )?;
+
+            // The last item takes the remainder of the price split
+            let mut price_share = item_price;
+            if i as u64 == items_count - 1 {
+                price_share = bundle_order.price.checked_sub(item_price.checked_mul(items_count - 1).ok_or(ErrorCode::ErrArithmeticOverflow)?).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+            }
+            let (seller_share, comptoir_share, creators_share) = split_amount(
+                price_share,
+                get_comptoir_fee(&ctx.accounts.comptoir, &collection),
+                &collection,
+                metadata.data.seller_fee_basis_points,
+            )?;
+            seller_total = seller_total.checked_add(seller_share).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+            comptoir_total = comptoir_total.checked_add(comptoir_share).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+
+            if !collection.ignore_creator_fee {
+                if let Some(creators) = metadata.data.creators {
+                    let creators_count = creators.len();
+                    let creators_distributions = verify_and_get_creators(creators, get_remaining_accounts_from(ctx.remaining_accounts, index)?, bundle_order.payment_mint)?;
+                    index += creators_count;
+                    for creator in creators_distributions {
+                        let creator_share = calculate_fee(creators_share, creator.1 as u16, 100)?;
+                        creators_payments.push(CreatorPayment { destination: creator.0.key(), amount: creator_share });
+                        pay_from_wallet(
+                            is_native,
+                            ctx.accounts.buyer_paying_token_account.to_account_info(),
+                            creator.0.to_account_info(),
+                            ctx.accounts.buyer.to_account_info(),
+                            ctx.accounts.token_program.to_account_info(),
+                            ctx.accounts.system_program.to_account_info(),
+                            creator_share,
+                        )?;
+                    }
+                }
+            }
+        }
+
+        pay_from_wallet(
+            is_native,
+            ctx.accounts.buyer_paying_token_account.to_account_info(),
+            ctx.accounts.seller_funds_dest_account.to_account_info(),
+            ctx.accounts.buyer.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            seller_total,
+        )?;
+        pay_from_wallet(
+            is_native,
+            ctx.accounts.buyer_paying_token_account.to_account_info(),
+            ctx.accounts.comptoir_dest_account.to_account_info(),
+            ctx.accounts.buyer.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            comptoir_total,
+        )?;
+
+        emit!(BundleBoughtEvent{
+            bundle_order: bundle_order.key(),
+            buyer: ctx.accounts.buyer.key(),
+            price: bundle_order.price,
+            payment_mint: bundle_order.payment_mint,
+            seller_amount: seller_total,
+            comptoir_amount: comptoir_total,
+            creators: creators_payments,
+        });
+        Ok(())
+    }
+
+    pub fn create_swap_offer(
+        ctx: Context<CreateSwapOffer>,
+        _swap_id: u64,
+        requested: Vec<SwapAsk>,
+        token_amount: u64,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        validate_expiration(expires_at)?;
+        if requested.is_empty() || requested.len() > SWAP_CAPACITY {
+            return Err(error!(ErrorCode::ErrWrongSwapRequest));
+        }
+        let (payment_mint, _) = get_payment_terms_in(&ctx.accounts.comptoir, &ctx.accounts.comptoir.key(), ctx.remaining_accounts)?;
+        if ctx.accounts.escrow.mint != payment_mint {
+            return Err(error!(ErrorCode::ErrWrongCurrency));
+        }
+        record_swap_offer(
+            &mut ctx.accounts.swap_offer,
+            ctx.accounts.comptoir.key(),
+            ctx.accounts.payer.key(),
+            payment_mint,
+            token_amount,
+            expires_at,
+            requested,
+        );
+
+        if token_amount > 0 {
+            // Native SOL swaps lock their funds in the swap offer account itself
+            let is_native = is_native_mint(&payment_mint);
+            let mut escrow = ctx.accounts.escrow.to_account_info();
+            if is_native {
+                escrow = ctx.accounts.swap_offer.to_account_info();
+            }
+            pay_from_wallet(
+                is_native,
+                ctx.accounts.payer_paying_account.to_account_info(),
+                escrow,
+                ctx.accounts.payer.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                token_amount,
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn add_swap_item(ctx: Context<AddSwapItem>) -> Result<()> {
+        let swap_offer = &mut ctx.accounts.swap_offer;
+        if swap_offer.offered_mints.len() >= SWAP_CAPACITY {
+            return Err(error!(ErrorCode::ErrSwapFull));
+        }
+        if swap_offer.offered_mints.contains(&ctx.accounts.mint.key()) {
+            return Err(error!(ErrorCode::ErrItemAlreadyInSwap));
+        }
+        swap_offer.offered_mints.push(ctx.accounts.mint.key());
+        emit!(SwapItemAddedEvent{
+            swap_offer: swap_offer.key(),
+            mint: ctx.accounts.mint.key(),
+        });
+
+        pay(
+            ctx.accounts.payer_nft_token_account.to_account_info(),
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.payer.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            1,
+        )?;
+        Ok(())
+    }
+
+    // Remaining accounts: the vault and the authority token account of every offered item
+    pub fn remove_swap_offer<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, RemoveSwapOffer<'info>>) -> Result<()> {
+        let comptoir_key = ctx.accounts.comptoir.key();
+        return_swap_items(
+            &ctx.accounts.swap_offer,
+            ctx.remaining_accounts,
+            &ctx.accounts.token_program.to_account_info(),
+            ctx.program_id,
+        )?;
+
+        // Native SOL funds are held by the swap offer account and go back to the authority when it is closed
+        if ctx.accounts.swap_offer.token_amount == 0 || is_native_mint(&ctx.accounts.swap_offer.payment_mint) {
+            return Ok(());
+        }
+        let seeds = &[
+            PREFIX.as_bytes(),
+            comptoir_key.as_ref(),
+            ctx.accounts.swap_offer.payment_mint.as_ref(),
+            ESCROW.as_bytes(),
+            &[*ctx.bumps.get("escrow").unwrap()], ];
+        let signer: &[&[&[u8]]] = &[&seeds[..]];
+        pay_with_signer(
+            ctx.accounts.escrow.to_account_info(),
+            ctx.accounts.authority_paying_account.to_account_info(),
+            ctx.accounts.escrow.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.swap_offer.token_amount,
+            signer,
+        )?;
+        Ok(())
+    }
+
+    // Remaining accounts: the vault and the counterparty token account of every offered item, then for every
+    // requested item the counterparty token account and the authority token account, followed by the
+    // metadata and the collection when a collection member is requested, and the currency when not the comptoir one
+    pub fn accept_swap_offer<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, AcceptSwapOffer<'info>>) -> Result<()> {
+        let swap_offer = &ctx.accounts.swap_offer;
+        if is_expired(swap_offer.expires_at, Clock::get()?.unix_timestamp) {
+            return Err(error!(ErrorCode::ErrSwapOfferExpired));
+        }
+        if swap_offer.offered_mints.is_empty() && swap_offer.token_amount == 0 {
+            return Err(error!(ErrorCode::ErrSwapEmpty));
+        }
+        let comptoir_key = ctx.accounts.comptoir.key();
+
+        let received_mints = exchange_swap_items(
+            swap_offer,
+            &ctx.accounts.counterparty.to_account_info(),
+            ctx.remaining_accounts,
+            &ctx.accounts.token_program.to_account_info(),
+            ctx.program_id,
+        )?;
+
+        let mut comptoir_share = 0;
+        if swap_offer.token_amount > 0 {
+            let fees_destination = get_fees_destination_in(
+                &ctx.accounts.comptoir,
+                &comptoir_key,
+                &swap_offer.payment_mint,
+                ctx.remaining_accounts,
+                ctx.program_id,
+            )?.ok_or(ErrorCode::ErrOfferCurrencyChanged)?;
+            if ctx.accounts.comptoir_dest_account.key() != fees_destination {
+                return Err(error!(ErrorCode::ErrWrongFeesDestination));
+            }
+            let is_native = is_native_mint(&swap_offer.payment_mint);
+            comptoir_share = calculate_fee(swap_offer.token_amount, ctx.accounts.comptoir.fees, 10000)?;
+            let counterparty_share = swap_offer.token_amount.checked_sub(comptoir_share).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+
+            let seeds = &[
+                PREFIX.as_bytes(),
+                comptoir_key.as_ref(),
+                swap_offer.payment_mint.as_ref(),
+                ESCROW.as_bytes(),
+                &[*ctx.bumps.get("escrow").unwrap()], ];
+            let signer: &[&[&[u8]]] = &[&seeds[..]];
+            pay_from_escrow(
+                is_native,
+                ctx.accounts.swap_offer.to_account_info(),
+                ctx.accounts.escrow.to_account_info(),
+                ctx.accounts.comptoir_dest_account.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                comptoir_share,
+                signer,
+            )?;
+            pay_from_escrow(
+                is_native,
+                ctx.accounts.swap_offer.to_account_info(),
+                ctx.accounts.escrow.to_account_info(),
+                ctx.accounts.counterparty_funds_account.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                counterparty_share,
+                signer,
+            )?;
+        }
+
+        emit!(SwapOfferAcceptedEvent{
+            swap_offer: swap_offer.key(),
+            counterparty: ctx.accounts.counterparty.key(),
+            offered_mints: swap_offer.offered_mints.clone(),
+            received_mints,
+            token_amount: swap_offer.token_amount,
+            comptoir_amount: comptoir_share,
+        });
+        Ok(())
+    }
+
+    pub fn create_auction(
+        ctx: Context<CreateAuction>,
+        reserve_price: u64,
+        min_bid_increment: u64,
+        start_time: i64,
+        end_time: i64,
+        quantity: u64,
+        destination: Pubkey,
+    ) -> Result<()> {
+        verify_metadata_and_derivation(
+            ctx.accounts.metadata.as_ref(),
+            &ctx.accounts.seller_nft_token_account.mint.key(),
+            &ctx.accounts.collection,
+        )?;
+
+        if start_time >= end_time || end_time <= Clock::get()?.unix_timestamp {
+            return Err(error!(ErrorCode::ErrInvalidAuctionTimes));
+        }
+        if min_bid_increment == 0 {
+            return Err(error!(ErrorCode::ErrMinBidIncrementShouldBePositive));
+        }
+        if quantity == 0 {
+            return Err(error!(ErrorCode::ErrWrongAuctionQuantity));
+        }
+
+        pay(
+            ctx.accounts.seller_nft_token_account.to_account_info(),
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.payer.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            quantity,
+        )?;
+
+        let auction = &mut ctx.accounts.auction;
+        auction.comptoir = ctx.accounts.comptoir.key();
+        auction.collection = ctx.accounts.collection.key();
+        auction.mint = ctx.accounts.seller_nft_token_account.mint;
+        auction.payment_mint = ctx.accounts.comptoir.mint;
+        auction.quantity = quantity;
+        auction.authority = ctx.accounts.payer.key();
+        auction.destination = destination;
+        auction.seller_nft_token_account = ctx.accounts.seller_nft_token_account.key();
+        auction.reserve_price = reserve_price;
+        auction.min_bid_increment = min_bid_increment;
+        auction.start_time = start_time;
+        auction.end_time = end_time;
+        auction.highest_bid = 0;
+        emit!(AuctionCreatedEvent{
+            auction: auction.key(),
+            comptoir: auction.comptoir,
+            collection: auction.collection,
+            mint: auction.mint,
+            authority: auction.authority,
+            quantity,
+            reserve_price,
+            payment_mint: auction.payment_mint,
+            start_time,
+            end_time,
+        });
+        Ok(())
+    }
+
+    pub fn place_bid<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, PlaceBid<'info>>, amount: u64) -> Result<()> {
+        let to_pay = get_bid_top_up(&ctx.accounts.auction, &ctx.accounts.bid, amount)?;
+        pay(
+            ctx.accounts.bidder_paying_account.to_account_info(),
+            ctx.accounts.escrow.to_account_info(),
+            ctx.accounts.bidder.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            to_pay,
+        )?;
+        record_highest_bid(
+            &mut ctx.accounts.auction,
+            &mut ctx.accounts.bid,
+            ctx.accounts.bidder.key(),
+            amount,
+            ctx.remaining_accounts,
+            ctx.program_id,
+        )
+    }
+
+    pub fn claim_bid_refund(ctx: Context<ClaimBidRefund>) -> Result<()> {
+        let seeds = &[
+            PREFIX.as_bytes(),
+            ctx.accounts.bid.comptoir.as_ref(),
+            ctx.accounts.bid.payment_mint.as_ref(),
+            ESCROW.as_bytes(),
+            &[*ctx.bumps.get("escrow").unwrap()], ];
+        let signer: &[&[&[u8]]] = &[&seeds[..]];
+        pay_with_signer(
+            ctx.accounts.escrow.to_account_info(),
+            ctx.accounts.bidder_refund_account.to_account_info(),
+            ctx.accounts.escrow.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.bid.amount,
+            signer,
+        )?;
+
+        emit!(AuctionBidRefundedEvent{
+            auction: ctx.accounts.bid.auction,
+            bid: ctx.accounts.bid.key(),
+            bidder: ctx.accounts.bid.bidder,
+            amount: ctx.accounts.bid.amount,
+        });
+        Ok(())
+    }
+
+    pub fn cancel_auction(ctx: Context<CancelAuction>) -> Result<()> {
+        if ctx.accounts.auction.highest_bid > 0 {
+            return Err(error!(ErrorCode::ErrAuctionHasBids));
+        }
+
+        let seeds = &[
+            PREFIX.as_bytes(),
+            "vault".as_bytes(),
+            ctx.accounts.auction.comptoir.as_ref(),
+            ctx.accounts.auction.mint.as_ref(),
+            &[*ctx.bumps.get("vault").unwrap()], ];
+        let signer = &[&seeds[..]];
+        pay_with_signer(
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.seller_nft_token_account.to_account_info(),
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.auction.quantity,
+            signer,
+        )?;
+
+        emit!(AuctionCancelledEvent{
+            auction: ctx.accounts.auction.key(),
+        });
+        Ok(())
+    }
+
+    pub fn settle_auction<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, SettleAuction<'info>>) -> Result<()> {
+        if Clock::get()?.unix_timestamp < ctx.accounts.auction.end_time {
+            return Err(error!(ErrorCode::ErrAuctionNotEnded));
+        }
+
+        let vault_seeds = &[
+            PREFIX.as_bytes(),
+            "vault".as_bytes(),
+            ctx.accounts.auction.comptoir.as_ref(),
+            ctx.accounts.auction.mint.as_ref(),
+            &[*ctx.bumps.get("vault").unwrap()], ];
+        let vault_signer = &[&vault_seeds[..]];
+
+        // Nobody met the reserve price, the items go back to the seller
+        if ctx.accounts.auction.highest_bid == 0 {
+            pay_with_signer(
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.seller_nft_token_account.to_account_info(),
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.auction.quantity,
+                vault_signer,
+            )?;
+            emit!(AuctionUnsoldEvent{
+                auction: ctx.accounts.auction.key(),
+                refunded_bid: None,
+            });
+            return Ok(());
+        }
+
+        if ctx.accounts.winner.key() != ctx.accounts.auction.highest_bidder
+            || ctx.accounts.winning_bid.key() != ctx.accounts.auction.highest_bid_account {
+            return Err(error!(ErrorCode::ErrWrongWinnerAccount));
+        }
+
+        // The bids are escrowed in the auction currency whatever the comptoir mint became since. When that currency
+        // is no longer accepted there is nobody to pay the fees to: the winning bid is made refundable and the items
+        // go back to the seller
+        let fees_destination = get_fees_destination_of(
+            &ctx.accounts.comptoir,
+            &ctx.accounts.collection,
+            &ctx.accounts.auction.payment_mint,
+            ctx.remaining_accounts,
+            ctx.program_id,
+        )?;
+        let fees_destination = match fees_destination {
+            Some(fees_destination) => fees_destination,
+            None => {
+                let mut winning_bid = Account::<'info, Bid>::try_from(&ctx.accounts.winning_bid)?;
+                winning_bid.outbid = true;
+                winning_bid.exit(ctx.program_id)?;
+                pay_with_signer(
+                    ctx.accounts.vault.to_account_info(),
+                    ctx.accounts.seller_nft_token_account.to_account_info(),
+                    ctx.accounts.vault.to_account_info(),
+                    ctx.accounts.token_program.to_account_info(),
+                    ctx.accounts.auction.quantity,
+                    vault_signer,
+                )?;
+                emit!(AuctionUnsoldEvent{
+                    auction: ctx.accounts.auction.key(),
+                    refunded_bid: Some(ctx.accounts.winning_bid.key()),
+                });
+                return Ok(());
+            }
+        };
+        if ctx.accounts.comptoir_dest_account.key() != fees_destination {
+            return Err(error!(ErrorCode::ErrWrongFeesDestination));
+        }
+
+        // The items go to the winner associated token account, created if they closed it
+        let ata_seeds: &[&[u8]] = &[
+            ctx.accounts.winner.key.as_ref(),
+            spl_token::ID.as_ref(),
+            ctx.accounts.auction.mint.as_ref(),
+        ];
+        let (winner_ata, _) = Pubkey::find_program_address(ata_seeds, &ctx.accounts.associated_token_program.key());
+        if ctx.accounts.winner_nft_account.key() != winner_ata {
+            return Err(error!(ErrorCode::ErrWrongWinnerAccount));
+        }
+        if ctx.accounts.winner_nft_account.data_is_empty() {
+            anchor_spl::associated_token::create(CpiContext::new(
+                ctx.accounts.associated_token_program.to_account_info(),
+                anchor_spl::associated_token::Create {
+                    payer: ctx.accounts.payer.to_account_info(),
+                    associated_token: ctx.accounts.winner_nft_account.to_account_info(),
+                    authority: ctx.accounts.winner.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                },
+            ))?;
+        }
+
+        let metadata = verify_metadata_and_derivation(
+            ctx.accounts.metadata.as_ref(),
+            &ctx.accounts.auction.mint,
+            &ctx.accounts.collection,
+        )?;
+
+        let mut creators_distributions_option: Option<Vec<(&AccountInfo, u8)>> = None;
+        if !ctx.accounts.collection.ignore_creator_fee {
+            if let Some(creators) = metadata.data.creators {
+                let creators_distributions = verify_and_get_creators(creators, ctx.remaining_accounts, ctx.accounts.auction.payment_mint)?;
+                creators_distributions_option = Some(creators_distributions);
+            }
+        }
+
+        let comptoir_fee = get_comptoir_fee(&ctx.accounts.comptoir, &ctx.accounts.collection);
+        let (seller_share, comptoir_share, creators_share) = split_amount(
+            ctx.accounts.auction.highest_bid,
+            comptoir_fee,
+            &ctx.accounts.collection,
+            metadata.data.seller_fee_basis_points,
+        )?;
+
+        let seeds = &[
+            PREFIX.as_bytes(),
+            ctx.accounts.comptoir.to_account_info().key.as_ref(),
+            ctx.accounts.auction.payment_mint.as_ref(),
+            ESCROW.as_bytes(),
+            &[*ctx.bumps.get("escrow").unwrap()], ];
+        let signer: &[&[&[u8]]] = &[&seeds[..]];
+
+        if let Some(creators) = creators_distributions_option.as_ref() {
+            for creator in creators {
+                let creator_share = calculate_fee(creators_share, creator.1 as u16, 100)?;
+                pay_with_signer(
+                    ctx.accounts.escrow.to_account_info(),
+                    creator.0.to_account_info(),
+                    ctx.accounts.escrow.to_account_info(),
+                    ctx.accounts.token_program.to_account_info(),
+                    creator_share,
+                    signer,
+                )?;
+            }
+        }
+
+        pay_with_signer(
+            ctx.accounts.escrow.to_account_info(),
+            ctx.accounts.comptoir_dest_account.to_account_info(),
+            ctx.accounts.escrow.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            comptoir_share,
+            signer,
+        )?;
+
+        pay_with_signer(
+            ctx.accounts.escrow.to_account_info(),
+            ctx.accounts.destination.to_account_info(),
+            ctx.accounts.escrow.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            seller_share,
+            signer,
+        )?;
+
+        pay_with_signer(
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.winner_nft_account.to_account_info(),
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.auction.quantity,
+            vault_signer,
+        )?;
+
+        // The winning bid is spent, its rent goes back to the winner
+        let winning_bid = Account::<'info, Bid>::try_from(&ctx.accounts.winning_bid)?;
+        winning_bid.close(ctx.accounts.winner.to_account_info())?;
+
+        emit!(AuctionSettledEvent{
+            auction: ctx.accounts.auction.key(),
+            winner: ctx.accounts.auction.highest_bidder,
+            price: ctx.accounts.auction.highest_bid,
+            quantity: ctx.accounts.auction.quantity,
+        });
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_compressed_sell_order<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CreateCompressedSellOrder<'info>>,
+        asset_id: Pubkey,
+        nonce: u64,
+        index: u32,
+        root: [u8; 32],
+        metadata: CompressedMetadata,
+        price: u64,
+        destination: Pubkey,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        if asset_id != get_asset_id(&ctx.accounts.merkle_tree.key(), nonce) {
+            return Err(error!(ErrorCode::ErrWrongAssetId));
+        }
+        if !ctx.accounts.collection.is_compressed_part_of_collection(&metadata) {
+            return Err(error!(ErrorCode::ErrNftNotPartOfCollection));
+        }
+        validate_expiration(expires_at)?;
+        let (payment_mint, _) = get_payment_terms(&ctx.accounts.comptoir, &ctx.accounts.collection, ctx.remaining_accounts)?;
+
+        let data_hash = metadata.data_hash();
+        let creator_hash = metadata.creator_hash();
+
+        // Remaining accounts are the proof nodes, then the currency when not the comptoir one
+        transfer_compressed(
+            ctx.accounts.bubblegum_program.to_account_info(),
+            TransferCompressed {
+                tree_authority: ctx.accounts.tree_authority.to_account_info(),
+                leaf_owner: ctx.accounts.payer.to_account_info(),
+                leaf_delegate: ctx.accounts.leaf_delegate.to_account_info(),
+                new_leaf_owner: ctx.accounts.vault.to_account_info(),
+                merkle_tree: ctx.accounts.merkle_tree.to_account_info(),
+                log_wrapper: ctx.accounts.log_wrapper.to_account_info(),
+                compression_program: ctx.accounts.compression_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+            },
+            get_proof_accounts(ctx.remaining_accounts, 0, &payment_mint, &ctx.accounts.comptoir)?,
+            Leaf { root, data_hash, creator_hash, nonce, index },
+            &[],
+        )?;
+
+        let sell_order = &mut ctx.accounts.sell_order;
+        sell_order.comptoir = ctx.accounts.comptoir.key();
+        sell_order.collection = ctx.accounts.collection.key();
+        sell_order.merkle_tree = ctx.accounts.merkle_tree.key();
+        sell_order.asset_id = asset_id;
+        sell_order.nonce = nonce;
+        sell_order.index = index;
+        sell_order.price = price;
+        sell_order.authority = ctx.accounts.payer.key();
+        sell_order.destination = destination;
+        sell_order.expires_at = expires_at;
+        sell_order.data_hash = data_hash;
+        sell_order.creator_hash = creator_hash;
+        sell_order.payment_mint = payment_mint;
+        emit!(SellOrderCreatedEvent{
+            sell_order: sell_order.key(),
+            comptoir: sell_order.comptoir,
+            collection: sell_order.collection,
+            mint: asset_id,
+            authority: sell_order.authority,
+            price,
+            quantity: 1,
+            payment_mint,
+        });
+        Ok(())
+    }
+
+    pub fn remove_compressed_sell_order<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, RemoveCompressedSellOrder<'info>>,
+        root: [u8; 32],
+    ) -> Result<()> {
+        let sell_order = &ctx.accounts.sell_order;
+        let seeds = &[
+            PREFIX.as_bytes(),
+            "vault".as_bytes(),
+            sell_order.comptoir.as_ref(),
+            sell_order.asset_id.as_ref(),
+            &[*ctx.bumps.get("vault").unwrap()], ];
+        let signer = &[&seeds[..]];
+
+        transfer_compressed(
+            ctx.accounts.bubblegum_program.to_account_info(),
+            TransferCompressed {
+                tree_authority: ctx.accounts.tree_authority.to_account_info(),
+                leaf_owner: ctx.accounts.vault.to_account_info(),
+                leaf_delegate: ctx.accounts.vault.to_account_info(),
+                new_leaf_owner: ctx.accounts.authority.to_account_info(),
+                merkle_tree: ctx.accounts.merkle_tree.to_account_info(),
+                log_wrapper: ctx.accounts.log_wrapper.to_account_info(),
+                compression_program: ctx.accounts.compression_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+            },
+            ctx.remaining_accounts,
+            Leaf {
+                root,
+                data_hash: sell_order.data_hash,
+                creator_hash: sell_order.creator_hash,
+                nonce: sell_order.nonce,
+                index: sell_order.index,
+            },
+            signer,
+        )?;
+
+        emit!(SellOrderRemovedEvent{
+            sell_order: sell_order.key(),
+        });
+        Ok(())
+    }
+
+    pub fn buy_compressed<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, BuyCompressed<'info>>,
+        root: [u8; 32],
+        metadata: CompressedMetadata,
+        max_price: u64,
+    ) -> Result<()> {
+        let sell_order = &ctx.accounts.sell_order;
+        let fees_destination = get_fees_destination_of(
+            &ctx.accounts.comptoir,
+            &ctx.accounts.collection,
+            &sell_order.payment_mint,
+            ctx.remaining_accounts,
+            ctx.program_id,
+        )?.ok_or(ErrorCode::ErrCurrencyNotAccepted)?;
+        if ctx.accounts.comptoir_dest_account.key() != fees_destination {
+            return Err(error!(ErrorCode::ErrWrongFeesDestination));
+        }
+        let is_native = is_native_mint(&sell_order.payment_mint);
+        if is_expired(sell_order.expires_at, Clock::get()?.unix_timestamp) {
+            return Err(error!(ErrorCode::ErrSellOrderExpired));
+        }
+        if sell_order.price > max_price {
+            return Err(error!(ErrorCode::ErrMaxTotalPriceExceeded));
+        }
+        if metadata.data_hash() != sell_order.data_hash || metadata.creator_hash() != sell_order.creator_hash {
+            return Err(error!(ErrorCode::ErrCompressedMetadataMismatch));
+        }
+
+        // Remaining accounts are the creators followed by the proof nodes, then the currency when not the comptoir one
+        let mut proof_index = 0;
+        let mut creators_distributions: Vec<(&AccountInfo, u8)> = Vec::new();
+        if !ctx.accounts.collection.ignore_creator_fee {
+            proof_index = metadata.creators.len();
+            creators_distributions = verify_and_get_creators(metadata.metaplex_creators(), ctx.remaining_accounts, sell_order.payment_mint)?;
+        }
+
+        let comptoir_fee = get_comptoir_fee(&ctx.accounts.comptoir, &ctx.accounts.collection);
+        let (seller_share, comptoir_share, creators_share) = split_amount(
+            sell_order.price,
+            comptoir_fee,
+            &ctx.accounts.collection,
+            metadata.seller_fee_basis_points,
+        )?;
+
+        pay_from_wallet(
+            is_native,
+            ctx.accounts.buyer_paying_token_account.to_account_info(),
+            ctx.accounts.seller_funds_dest_account.to_account_info(),
+            ctx.accounts.buyer.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            seller_share,
+        )?;
+        pay_from_wallet(
+            is_native,
+            ctx.accounts.buyer_paying_token_account.to_account_info(),
+            ctx.accounts.comptoir_dest_account.to_account_info(),
+            ctx.accounts.buyer.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            comptoir_share,
+        )?;
+        let mut creators_payments = Vec::new();
+        for creator in creators_distributions.iter() {
+            let creator_share = calculate_fee(creators_share, creator.1 as u16, 100)?;
+            creators_payments.push(CreatorPayment { destination: creator.0.key(), amount: creator_share });
+            pay_from_wallet(
+                is_native,
+                ctx.accounts.buyer_paying_token_account.to_account_info(),
+                creator.0.to_account_info(),
+                ctx.accounts.buyer.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                creator_share,
+            )?;
+        }
+
+        let seeds = &[
+            PREFIX.as_bytes(),
+            "vault".as_bytes(),
+            sell_order.comptoir.as_ref(),
+            sell_order.asset_id.as_ref(),
+            &[*ctx.bumps.get("vault").unwrap()], ];
+        let signer = &[&seeds[..]];
+
+        transfer_compressed(
+            ctx.accounts.bubblegum_program.to_account_info(),
+            TransferCompressed {
+                tree_authority: ctx.accounts.tree_authority.to_account_info(),
+                leaf_owner: ctx.accounts.vault.to_account_info(),
+                leaf_delegate: ctx.accounts.vault.to_account_info(),
+                new_leaf_owner: ctx.accounts.buyer.to_account_info(),
+                merkle_tree: ctx.accounts.merkle_tree.to_account_info(),
+                log_wrapper: ctx.accounts.log_wrapper.to_account_info(),
+                compression_program: ctx.accounts.compression_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+            },
+            get_proof_accounts(ctx.remaining_accounts, proof_index, &sell_order.payment_mint, &ctx.accounts.comptoir)?,
+            Leaf {
+                root,
+                data_hash: sell_order.data_hash,
+                creator_hash: sell_order.creator_hash,
+                nonce: sell_order.nonce,
+                index: sell_order.index,
+            },
+            signer,
+        )?;
+
+        emit!(BoughtSellOrderEvent{
+            sell_order: sell_order.key(),
+            quantity: 1,
+            buyer: ctx.accounts.buyer.key(),
+            mint: sell_order.asset_id,
+            price: sell_order.price,
+            payment_mint: sell_order.payment_mint,
+            seller_amount: seller_share,
+            comptoir_amount: comptoir_share,
+            creators: creators_payments,
+        });
+        Ok(())
+    }
+
+    pub fn create_compressed_buy_offer(
+        ctx: Context<CreateCompressedBuyOffer>,
+        asset_id: Pubkey,
+        price_proposition: u64,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        // Compressed items are received by the buyer wallet directly
+        init_buy_offer(
+            &mut ctx.accounts.buy_offer,
+            BuyOfferFunding {
+                comptoir: &ctx.accounts.comptoir,
+                collection: &ctx.accounts.collection,
+                escrow: &ctx.accounts.escrow,
+                buyer_paying_account: ctx.accounts.buyer_paying_account.to_account_info(),
+                payer: ctx.accounts.payer.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                remaining_accounts: ctx.remaining_accounts,
+            },
+            OfferTarget {
+                mint: asset_id,
+                destination: ctx.accounts.payer.key(),
+                trait_hash: None,
+            },
+            price_proposition,
+            1,
+            expires_at,
+        )
+    }
+
+    pub fn execute_compressed_offer<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, ExecuteCompressedOffer<'info>>,
+        nonce: u64,
+        index: u32,
+        root: [u8; 32],
+        metadata: CompressedMetadata,
+    ) -> Result<()> {
+        if is_expired(ctx.accounts.buy_offer.expires_at, Clock::get()?.unix_timestamp) {
+            return Err(error!(ErrorCode::ErrBuyOfferExpired));
+        }
+        let fees_destination = get_fees_destination_of(
+            &ctx.accounts.comptoir,
+            &ctx.accounts.collection,
+            &ctx.accounts.buy_offer.payment_mint,
+            ctx.remaining_accounts,
+            ctx.program_id,
+        )?.ok_or(ErrorCode::ErrOfferCurrencyChanged)?;
+        if ctx.accounts.comptoir_dest_account.key() != fees_destination {
+            return Err(error!(ErrorCode::ErrWrongFeesDestination));
+        }
+        if get_asset_id(&ctx.accounts.merkle_tree.key(), nonce) != ctx.accounts.buy_offer.mint {
+            return Err(error!(ErrorCode::ErrItemNotTargetedByOffer));
+        }
+        if !ctx.accounts.collection.is_compressed_part_of_collection(&metadata) {
+            return Err(error!(ErrorCode::ErrNftNotPartOfCollection));
+        }
+
+        // Remaining accounts are the creators followed by the proof nodes, then the currency when not the comptoir one
+        let mut proof_index = 0;
+        let mut creators_distributions: Vec<(&AccountInfo, u8)> = Vec::new();
+        if !ctx.accounts.collection.ignore_creator_fee {
+            proof_index = metadata.creators.len();
+            creators_distributions = verify_and_get_creators(metadata.metaplex_creators(), ctx.remaining_accounts, ctx.accounts.buy_offer.payment_mint)?;
+        }
+
+        //Transfer the item to buyer, bubblegum rejects the transfer if the metadata does not match the leaf
+        transfer_compressed(
+            ctx.accounts.bubblegum_program.to_account_info(),
+            TransferCompressed {
+                tree_authority: ctx.accounts.tree_authority.to_account_info(),
+                leaf_owner: ctx.accounts.seller.to_account_info(),
+                leaf_delegate: ctx.accounts.leaf_delegate.to_account_info(),
+                new_leaf_owner: ctx.accounts.buyer.to_account_info(),
+                merkle_tree: ctx.accounts.merkle_tree.to_account_info(),
+                log_wrapper: ctx.accounts.log_wrapper.to_account_info(),
+                compression_program: ctx.accounts.compression_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+            },
+            get_proof_accounts(ctx.remaining_accounts, proof_index, &ctx.accounts.buy_offer.payment_mint, &ctx.accounts.comptoir)?,
+            Leaf {
+                root,
+                data_hash: metadata.data_hash(),
+                creator_hash: metadata.creator_hash(),
+                nonce,
+                index,
+            },
+            &[],
+        )?;
+
+        let comptoir_fee = get_comptoir_fee(&ctx.accounts.comptoir, &ctx.accounts.collection);
+        let (seller_share, comptoir_share, creators_share) = split_amount(
+            ctx.accounts.buy_offer.proposed_price,
+            comptoir_fee,
+            &ctx.accounts.collection,
+            metadata.seller_fee_basis_points,
+        )?;
+
+        let seeds = &[
+            PREFIX.as_bytes(),
+            ctx.accounts.comptoir.to_account_info().key.as_ref(),
+            ctx.accounts.buy_offer.payment_mint.as_ref(),
+            ESCROW.as_bytes(),
+            &[*ctx.bumps.get("escrow").unwrap()], ];
+        let signer: &[&[&[u8]]] = &[&seeds[..]];
+        let is_native = is_native_mint(&ctx.accounts.buy_offer.payment_mint);
+
+        let mut creators_payments = Vec::new();
+        for creator in creators_distributions.iter() {
+            let creator_share = calculate_fee(creators_share, creator.1 as u16, 100)?;
+            creators_payments.push(CreatorPayment { destination: creator.0.key(), amount: creator_share });
+            pay_from_escrow(
+                is_native,
+                ctx.accounts.buy_offer.to_account_info(),
+                ctx.accounts.escrow.to_account_info(),
+                creator.0.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                creator_share,
+                signer,
+            )?;
+        }
+
+        pay_from_escrow(
+            is_native,
+            ctx.accounts.buy_offer.to_account_info(),
+            ctx.accounts.escrow.to_account_info(),
+            ctx.accounts.comptoir_dest_account.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            comptoir_share,
+            signer,
+        )?;
+
+        pay_from_escrow(
+            is_native,
+            ctx.accounts.buy_offer.to_account_info(),
+            ctx.accounts.escrow.to_account_info(),
+            ctx.accounts.seller_funds_dest_account.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            seller_share,
+            signer,
+        )?;
+
+        emit!(BuyOfferExecutedEvent{
+            buy_offer: ctx.accounts.buy_offer.key(),
+            seller: ctx.accounts.seller.key(),
+            mint: ctx.accounts.buy_offer.mint,
+            quantity: 1,
+            price: ctx.accounts.buy_offer.proposed_price,
+            payment_mint: ctx.accounts.buy_offer.payment_mint,
+            seller_amount: seller_share,
+            comptoir_amount: comptoir_share,
+            creators: creators_payments,
+        });
+        Ok(())
+    }
+
+    // Programmable NFTs stay frozen in their owner account, token metadata moves them into an associated account of the
+    // vault authority under their rule set. Remaining accounts: the currency when not the comptoir one
+    pub fn create_pnft_sell_order(
+        ctx: Context<CreatePnftSellOrder>,
+        price: u64,
+        destination: Pubkey,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        let (_, rule_set) = verify_programmable_metadata_and_derivation(
+            ctx.accounts.metadata.as_ref(),
+            &ctx.accounts.mint.key(),
+            &ctx.accounts.collection,
+        )?;
+        validate_expiration(expires_at)?;
+        let (payment_mint, _) = get_payment_terms(&ctx.accounts.comptoir, &ctx.accounts.collection, ctx.remaining_accounts)?;
+
+        TransferProgrammable {
+            token: ctx.accounts.seller_nft_token_account.to_account_info(),
+            token_owner: ctx.accounts.payer.to_account_info(),
+            destination: ctx.accounts.vault.to_account_info(),
+            destination_owner: ctx.accounts.vault_authority.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            edition: ctx.accounts.edition.to_account_info(),
+            owner_token_record: ctx.accounts.seller_token_record.to_account_info(),
+            destination_token_record: ctx.accounts.vault_token_record.to_account_info(),
+            authority: ctx.accounts.payer.to_account_info(),
+            payer: ctx.accounts.payer.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+            authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+            authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+            token_metadata_program: ctx.accounts.token_metadata_program.to_account_info(),
+        }.transfer(rule_set, 1, &[])?;
+
+        let sell_order = &mut ctx.accounts.sell_order;
+        sell_order.comptoir = ctx.accounts.comptoir.key();
+        sell_order.price = price;
+        sell_order.quantity = 1;
+        sell_order.mint = ctx.accounts.mint.key();
+        sell_order.authority = ctx.accounts.payer.key();
+        sell_order.destination = destination;
+        sell_order.price_schedule = None;
+        sell_order.expires_at = expires_at;
+        sell_order.vault_migrated = true;
+        sell_order.payment_mint = payment_mint;
+        sell_order.oracle_price = None;
+        emit!(SellOrderCreatedEvent{
+            sell_order: sell_order.key(),
+            comptoir: sell_order.comptoir,
+            collection: ctx.accounts.collection.key(),
+            mint: sell_order.mint,
+            authority: sell_order.authority,
+            price,
+            quantity: 1,
+            payment_mint,
+        });
+        Ok(())
+    }
+
+    pub fn remove_pnft_sell_order(ctx: Context<RemovePnftSellOrder>) -> Result<()> {
+        let (_, rule_set) = load_programmable_metadata_and_derivation(
+            ctx.accounts.metadata.as_ref(),
+            &ctx.accounts.sell_order.mint,
+        )?;
+
+        let seeds = &[
+            PREFIX.as_bytes(),
+            "vault".as_bytes(),
+            ctx.accounts.sell_order.comptoir.as_ref(),
+            ctx.accounts.sell_order.mint.as_ref(),
+            &[*ctx.bumps.get("vault_authority").unwrap()], ];
+        let signer = &[&seeds[..]];
+        TransferProgrammable {
+            token: ctx.accounts.vault.to_account_info(),
+            token_owner: ctx.accounts.vault_authority.to_account_info(),
+            destination: ctx.accounts.seller_nft_token_account.to_account_info(),
+            destination_owner: ctx.accounts.authority.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            edition: ctx.accounts.edition.to_account_info(),
+            owner_token_record: ctx.accounts.vault_token_record.to_account_info(),
+            destination_token_record: ctx.accounts.seller_token_record.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+            payer: ctx.accounts.authority.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+            authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+            authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+            token_metadata_program: ctx.accounts.token_metadata_program.to_account_info(),
+        }.transfer(rule_set, ctx.accounts.sell_order.quantity, signer)?;
+
+        emit!(SellOrderRemovedEvent{
+            sell_order: ctx.accounts.sell_order.key(),
+        });
+        Ok(())
+    }
+
+    // Remaining accounts: the creators accounts, then the currency when not the comptoir one
+    pub fn buy_pnft<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, BuyPnft<'info>>, max_price: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let (payment_mint, fees_destination) = get_payment_terms(&ctx.accounts.comptoir, &ctx.accounts.collection, ctx.remaining_accounts)?;
+        if ctx.accounts.comptoir_dest_account.key() != fees_destination {
+            return Err(error!(ErrorCode::ErrWrongFeesDestination));
+        }
+        if ctx.accounts.sell_order.payment_mint != payment_mint {
+            return Err(error!(ErrorCode::ErrWrongCurrency));
+        }
+        if is_expired(ctx.accounts.sell_order.expires_at, now) {
+            return Err(error!(ErrorCode::ErrSellOrderExpired));
+        }
+        let price = ctx.accounts.sell_order.current_price(now)?;
+        if price > max_price {
+            return Err(error!(ErrorCode::ErrMaxTotalPriceExceeded));
+        }
+        let is_native = is_native_mint(&payment_mint);
+
+        let (metadata, rule_set) = verify_programmable_metadata_and_derivation(
+            ctx.accounts.metadata.as_ref(),
+            &ctx.accounts.sell_order.mint,
+            &ctx.accounts.collection,
+        )?;
+
+        let mut creators_distributions_option: Option<Vec<(&AccountInfo, u8)>> = None;
+        if !ctx.accounts.collection.ignore_creator_fee {
+            if let Some(creators) = metadata.data.creators {
+                let creators_distributions = verify_and_get_creators(creators, ctx.remaining_accounts, payment_mint)?;
+                creators_distributions_option = Some(creators_distributions);
+            }
+        }
+
+        let comptoir_fee = get_comptoir_fee(&ctx.accounts.comptoir, &ctx.accounts.collection);
+        let (seller_share, comptoir_share, creators_share) = split_amount(
+            price,
+            comptoir_fee,
+            &ctx.accounts.collection,
+            metadata.data.seller_fee_basis_points,
+        )?;
+
+        let seeds = &[
+            PREFIX.as_bytes(),
+            "vault".as_bytes(),
+            ctx.accounts.sell_order.comptoir.as_ref(),
+            ctx.accounts.sell_order.mint.as_ref(),
+            &[*ctx.bumps.get("vault_authority").unwrap()], ];
+        let signer = &[&seeds[..]];
+        TransferProgrammable {
+            token: ctx.accounts.vault.to_account_info(),
+            token_owner: ctx.accounts.vault_authority.to_account_info(),
+            destination: ctx.accounts.buyer_nft_token_account.to_account_info(),
+            destination_owner: ctx.accounts.buyer.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            edition: ctx.accounts.edition.to_account_info(),
+            owner_token_record: ctx.accounts.vault_token_record.to_account_info(),
+            destination_token_record: ctx.accounts.buyer_token_record.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+            payer: ctx.accounts.buyer.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+            authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+            authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+            token_metadata_program: ctx.accounts.token_metadata_program.to_account_info(),
+        }.transfer(rule_set, 1, signer)?;
+
+        pay_from_wallet(
+            is_native,
+            ctx.accounts.buyer_paying_token_account.to_account_info(),
+            ctx.accounts.seller_funds_dest_account.to_account_info(),
+            ctx.accounts.buyer.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            seller_share,
+        )?;
+        pay_from_wallet(
+            is_native,
+            ctx.accounts.buyer_paying_token_account.to_account_info(),
+            ctx.accounts.comptoir_dest_account.to_account_info(),
+            ctx.accounts.buyer.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            comptoir_share,
+        )?;
+
+        let mut creators_payments = Vec::new();
+        if let Some(creators) = creators_distributions_option.as_ref() {
+            for creator in creators {
+                let creator_share = calculate_fee(creators_share, creator.1 as u16, 100)?;
+                creators_payments.push(CreatorPayment { destination: creator.0.key(), amount: creator_share });
+                pay_from_wallet(
+                    is_native,
+                    ctx.accounts.buyer_paying_token_account.to_account_info(),
+                    creator.0.to_account_info(),
+                    ctx.accounts.buyer.to_account_info(),
+                    ctx.accounts.token_program.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                    creator_share,
+                )?;
+            }
+        }
+
+        emit!(BoughtSellOrderEvent{
+            sell_order: ctx.accounts.sell_order.key(),
+            quantity: 1,
+            buyer: ctx.accounts.buyer.key(),
+            mint: ctx.accounts.sell_order.mint,
+            price,
+            payment_mint,
+            seller_amount: seller_share,
+            comptoir_amount: comptoir_share,
+            creators: creators_payments,
+        });
+        Ok(())
+    }
+
+    // Remaining accounts: the creators accounts, then the currency when not the comptoir one
+    pub fn execute_pnft_offer<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, ExecutePnftOffer<'info>>,
+        trait_proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        if is_expired(ctx.accounts.buy_offer.expires_at, Clock::get()?.unix_timestamp) {
+            return Err(error!(ErrorCode::ErrBuyOfferExpired));
+        }
+        let (payment_mint, fees_destination) = get_payment_terms(&ctx.accounts.comptoir, &ctx.accounts.collection, ctx.remaining_accounts)?;
+        if ctx.accounts.buy_offer.payment_mint != payment_mint {
+            return Err(error!(ErrorCode::ErrOfferCurrencyChanged));
+        }
+        if ctx.accounts.comptoir_dest_account.key() != fees_destination {
+            return Err(error!(ErrorCode::ErrWrongFeesDestination));
+        }
+
+        let (metadata, rule_set) = verify_programmable_metadata_and_derivation(
+            ctx.accounts.metadata.as_ref(),
+            &ctx.accounts.mint.key(),
+            &ctx.accounts.collection,
+        )?;
+
+        // Token metadata only moves programmable NFTs to the associated account of the buyer, whatever the offer destination
+        let buy_offer = &ctx.accounts.buy_offer;
+        if !buy_offer.is_collection_offer() && ctx.accounts.mint.key() != buy_offer.mint {
+            return Err(error!(ErrorCode::ErrItemNotTargetedByOffer));
+        }
+        if let Some(trait_hash) = buy_offer.trait_hash.as_ref() {
+            let traits_root = ctx.accounts.collection.traits_root.ok_or(error!(ErrorCode::ErrCollectionHasNoTraitsRoot))?;
+            let leaf = merkle::trait_leaf(&ctx.accounts.mint.key(), trait_hash);
+            if !merkle::verify_proof(&trait_proof, traits_root, leaf) {
+                return Err(error!(ErrorCode::ErrInvalidTraitProof));
+            }
+        }
+
+        TransferProgrammable {
+            token: ctx.accounts.seller_nft_account.to_account_info(),
+            token_owner: ctx.accounts.seller.to_account_info(),
+            destination: ctx.accounts.destination.to_account_info(),
+            destination_owner: ctx.accounts.buyer.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            metadata: ctx.accounts.metadata.to_account_info(),
+            edition: ctx.accounts.edition.to_account_info(),
+            owner_token_record: ctx.accounts.seller_token_record.to_account_info(),
+            destination_token_record: ctx.accounts.destination_token_record.to_account_info(),
+            authority: ctx.accounts.seller.to_account_info(),
+            payer: ctx.accounts.seller.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+            authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+            authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+            token_metadata_program: ctx.accounts.token_metadata_program.to_account_info(),
+        }.transfer(rule_set, 1, &[])?;
+
+        let mut creators_distributions_option: Option<Vec<(&AccountInfo, u8)>> = None;
+        if !ctx.accounts.collection.ignore_creator_fee {
+            if let Some(creators) = metadata.data.creators {
+                let creators_distributions = verify_and_get_creators(creators, ctx.remaining_accounts, payment_mint)?;
+                creators_distributions_option = Some(creators_distributions);
+            }
+        }
+
+        let comptoir_fee = get_comptoir_fee(&ctx.accounts.comptoir, &ctx.accounts.collection);
+        let total_amount = ctx.accounts.buy_offer.proposed_price;
+        let (seller_share, comptoir_share, creators_share) = split_amount(
+            total_amount,
+            comptoir_fee,
+            &ctx.accounts.collection,
+            metadata.data.seller_fee_basis_points,
+        )?;
+
+        let seeds = &[
+            PREFIX.as_bytes(),
+            ctx.accounts.comptoir.to_account_info().key.as_ref(),
+            ctx.accounts.buy_offer.payment_mint.as_ref(),
+            ESCROW.as_bytes(),
+            &[*ctx.bumps.get("escrow").unwrap()], ];
+        let signer: &[&[&[u8]]] = &[&seeds[..]];
+        let is_native = is_native_mint(&payment_mint);
+
+        let mut creators_payments = Vec::new();
+        if let Some(creators) = creators_distributions_option.as_ref() {
+            for creator in creators {
+                let creator_share = calculate_fee(creators_share, creator.1 as u16, 100)?;
+                creators_payments.push(CreatorPayment { destination: creator.0.key(), amount: creator_share });
+                pay_from_escrow(
+                    is_native,
+                    ctx.accounts.buy_offer.to_account_info(),
+                    ctx.accounts.escrow.to_account_info(),
+                    creator.0.to_account_info(),
+                    ctx.accounts.token_program.to_account_info(),
+                    creator_share,
+                    signer,
+                )?;
+            }
+        }
+
+        pay_from_escrow(
+            is_native,
+            ctx.accounts.buy_offer.to_account_info(),
+            ctx.accounts.escrow.to_account_info(),
+            ctx.accounts.comptoir_dest_account.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            comptoir_share,
+            signer,
+        )?;
+
+        pay_from_escrow(
+            is_native,
+            ctx.accounts.buy_offer.to_account_info(),
+            ctx.accounts.escrow.to_account_info(),
+            ctx.accounts.seller_funds_dest_account.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            seller_share,
+            signer,
+        )?;
+
+        emit!(BuyOfferExecutedEvent{
+            buy_offer: ctx.accounts.buy_offer.key(),
+            seller: ctx.accounts.seller.key(),
+            mint: ctx.accounts.mint.key(),
+            quantity: 1,
+            price: total_amount,
+            payment_mint,
+            seller_amount: seller_share,
+            comptoir_amount: comptoir_share,
+            creators: creators_payments,
+        });
+
+        let buy_offer = &mut ctx.accounts.buy_offer;
+        buy_offer.quantity = buy_offer.quantity.checked_sub(1).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+
+        if ctx.accounts.buy_offer.quantity == 0 {
+            ctx.accounts.buy_offer.close(ctx.accounts.buyer.to_account_info())?;
+        }
+        Ok(())
+    }
+
+    // Registers a Token-2022 currency without creating its escrow, so it can only price sell orders. add_currency
+    // registers Token-2022 currencies with their escrow
+    pub fn add_token22_currency(ctx: Context<AddToken22Currency>, fees_destination: Pubkey) -> Result<()> {
+        load_mint_of(&ctx.accounts.mint, &ctx.accounts.token_program)?;
+        let currency = &mut ctx.accounts.currency;
+        currency.comptoir = ctx.accounts.comptoir.key();
+        currency.scope = ctx.accounts.comptoir.key();
+        currency.mint = ctx.accounts.mint.key();
+        currency.fees_destination = fees_destination;
+        emit!(currency.updated_event(currency.key()));
+        Ok(())
+    }
+
+    // Token-2022 items are kept in an associated account of the vault authority, the order quantity being what the
+    // vault received after the mint transfer fee. Remaining accounts: the item transfer hook accounts when it has one,
+    // then the currency when not the comptoir one
+    pub fn create_token22_sell_order<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CreateToken22SellOrder<'info>>,
+        price: u64,
+        quantity: u64,
+        destination: Pubkey,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        verify_metadata_and_derivation(
+            ctx.accounts.metadata.as_ref(),
+            &ctx.accounts.mint.key(),
+            &ctx.accounts.collection,
+        )?;
+        validate_expiration(expires_at)?;
+        let mint = load_mint_of(&ctx.accounts.mint, &ctx.accounts.token_program)?;
+        let (hook_accounts, remaining_accounts) = split_hook_accounts(&ctx.accounts.mint.key(), &mint, ctx.remaining_accounts)?;
+        let (payment_mint, _) = get_payment_terms(&ctx.accounts.comptoir, &ctx.accounts.collection, remaining_accounts)?;
+
+        create_associated_account(
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.vault_authority.to_account_info(),
+            &ctx.accounts.mint.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.associated_token_program.to_account_info(),
+        )?;
+        transfer_checked(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.seller_nft_token_account.to_account_info(),
+            &ctx.accounts.mint.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.payer.to_account_info(),
+            quantity,
+            mint.decimals,
+            hook_accounts,
+            &[],
+        )?;
+        let listed_quantity = mint.received(quantity, Clock::get()?.epoch)?;
+
+        let sell_order = &mut ctx.accounts.sell_order;
+        sell_order.comptoir = ctx.accounts.comptoir.key();
+        sell_order.price = price;
+        sell_order.quantity = listed_quantity;
+        sell_order.mint = ctx.accounts.mint.key();
+        sell_order.authority = ctx.accounts.payer.key();
+        sell_order.destination = destination;
+        sell_order.price_schedule = None;
+        sell_order.expires_at = expires_at;
+        sell_order.vault_migrated = true;
+        sell_order.payment_mint = payment_mint;
+        sell_order.oracle_price = None;
+        emit!(SellOrderCreatedEvent{
+            sell_order: sell_order.key(),
+            comptoir: sell_order.comptoir,
+            collection: ctx.accounts.collection.key(),
+            mint: sell_order.mint,
+            authority: sell_order.authority,
+            price,
+            quantity: listed_quantity,
+            payment_mint,
+        });
+        Ok(())
+    }
+
+    // Remaining accounts: the item transfer hook accounts when it has one
+    pub fn remove_token22_sell_order<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, RemoveToken22SellOrder<'info>>,
+        quantity_to_unlist: u64,
+    ) -> Result<()> {
+        if ctx.accounts.sell_order.quantity < quantity_to_unlist {
+            return Err(error!(ErrorCode::ErrTryingToUnlistMoreThanOwned));
+        }
+        let mint = load_mint_of(&ctx.accounts.mint, &ctx.accounts.token_program)?;
+        let (hook_accounts, _) = split_hook_accounts(&ctx.accounts.mint.key(), &mint, ctx.remaining_accounts)?;
+
+        let seeds = &[
+            PREFIX.as_bytes(),
+            "vault".as_bytes(),
+            ctx.accounts.sell_order.comptoir.as_ref(),
+            ctx.accounts.sell_order.mint.as_ref(),
+            &[*ctx.bumps.get("vault_authority").unwrap()], ];
+        let signer = &[&seeds[..]];
+        transfer_checked(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.mint.to_account_info(),
+            &ctx.accounts.seller_nft_token_account.to_account_info(),
+            &ctx.accounts.vault_authority.to_account_info(),
+            quantity_to_unlist,
+            mint.decimals,
+            hook_accounts,
+            signer,
+        )?;
+
+        let sell_order = &mut ctx.accounts.sell_order;
+        sell_order.quantity = sell_order.quantity.checked_sub(quantity_to_unlist).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+
+        if ctx.accounts.sell_order.quantity == 0 {
+            emit!(SellOrderRemovedEvent{
+                sell_order: ctx.accounts.sell_order.key(),
+            });
+            ctx.accounts.sell_order.close(ctx.accounts.authority.to_account_info())?;
+        } else {
+            emit!(SellOrderQuantityUpdatedEvent{
+                sell_order: ctx.accounts.sell_order.key(),
+                quantity: ctx.accounts.sell_order.quantity,
+            });
+        }
+        Ok(())
+    }
+
+    // The currency token program is the owner of the currency mint, the recipients bear its transfer fee and the
+    // event reports what they received. Remaining accounts: the item transfer hook accounts, then the currency ones,
+    // when they have a hook, the creators accounts, then the currency when not the comptoir one
+    pub fn buy_token22<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, BuyToken22<'info>>,
+        quantity: u64,
+        max_total_price: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let mint = load_mint_of(&ctx.accounts.mint, &ctx.accounts.token_program)?;
+        let currency = load_mint_of(&ctx.accounts.payment_mint, &ctx.accounts.payment_token_program)?;
+        let (item_hook_accounts, remaining_accounts) = split_hook_accounts(&ctx.accounts.mint.key(), &mint, ctx.remaining_accounts)?;
+        let (currency_hook_accounts, remaining_accounts) = split_hook_accounts(&ctx.accounts.payment_mint.key(), &currency, remaining_accounts)?;
+        let (payment_mint, fees_destination) = get_payment_terms(&ctx.accounts.comptoir, &ctx.accounts.collection, remaining_accounts)?;
+        if ctx.accounts.comptoir_dest_account.key() != fees_destination {
+            return Err(error!(ErrorCode::ErrWrongFeesDestination));
+        }
+        if ctx.accounts.sell_order.payment_mint != payment_mint {
+            return Err(error!(ErrorCode::ErrWrongCurrency));
+        }
+        if is_expired(ctx.accounts.sell_order.expires_at, clock.unix_timestamp) {
+            return Err(error!(ErrorCode::ErrSellOrderExpired));
+        }
+        if quantity == 0 || quantity > ctx.accounts.sell_order.quantity {
+            return Err(error!(ErrorCode::ErrCouldNotBuyEnoughItem));
+        }
+        let total_amount = ctx.accounts.sell_order.current_price(clock.unix_timestamp)?
+            .checked_mul(quantity)
+            .ok_or(ErrorCode::ErrArithmeticOverflow)?;
+        if total_amount > max_total_price {
+            return Err(error!(ErrorCode::ErrMaxTotalPriceExceeded));
+        }
+        let is_native = is_native_mint(&payment_mint);
+
+        let metadata = verify_metadata_and_derivation(
+            ctx.accounts.metadata.as_ref(),
+            &ctx.accounts.mint.key(),
+            &ctx.accounts.collection,
+        )?;
+
+        let mut creators_distributions_option: Option<Vec<(&AccountInfo, u8)>> = None;
+        if !ctx.accounts.collection.ignore_creator_fee {
+            if let Some(creators) = metadata.data.creators {
+                let creators_distributions = verify_and_get_token_creators(
+                    creators,
+                    remaining_accounts,
+                    payment_mint,
+                    &ctx.accounts.payment_token_program.key(),
+                )?;
+                creators_distributions_option = Some(creators_distributions);
+            }
+        }
+
+        let comptoir_fee = get_comptoir_fee(&ctx.accounts.comptoir, &ctx.accounts.collection);
+        let (seller_share, comptoir_share, creators_share) = split_amount(
+            total_amount,
+            comptoir_fee,
+            &ctx.accounts.collection,
+            metadata.data.seller_fee_basis_points,
+        )?;
+
+        let seeds = &[
+            PREFIX.as_bytes(),
+            "vault".as_bytes(),
+            ctx.accounts.sell_order.comptoir.as_ref(),
+            ctx.accounts.sell_order.mint.as_ref(),
+            &[*ctx.bumps.get("vault_authority").unwrap()], ];
+        let signer = &[&seeds[..]];
+        transfer_checked(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.mint.to_account_info(),
+            &ctx.accounts.buyer_nft_token_account.to_account_info(),
+            &ctx.accounts.vault_authority.to_account_info(),
+            quantity,
+            mint.decimals,
+            item_hook_accounts,
+            signer,
+        )?;
+
+        pay_checked(
+            is_native,
+            ctx.accounts.payment_token_program.to_account_info(),
+            ctx.accounts.buyer_paying_token_account.to_account_info(),
+            ctx.accounts.payment_mint.to_account_info(),
+            ctx.accounts.seller_funds_dest_account.to_account_info(),
+            ctx.accounts.buyer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            seller_share,
+            currency.decimals,
+            currency_hook_accounts,
+        )?;
+        pay_checked(
+            is_native,
+            ctx.accounts.payment_token_program.to_account_info(),
+            ctx.accounts.buyer_paying_token_account.to_account_info(),
+            ctx.accounts.payment_mint.to_account_info(),
+            ctx.accounts.comptoir_dest_account.to_account_info(),
+            ctx.accounts.buyer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            comptoir_share,
+            currency.decimals,
+            currency_hook_accounts,
+        )?;
+
+        let mut creators_payments = Vec::new();
+        if let Some(creators) = creators_distributions_option.as_ref() {
+            for creator in creators {
+                let creator_share = calculate_fee(creators_share, creator.1 as u16, 100)?;
+                pay_checked(
+                    is_native,
+                    ctx.accounts.payment_token_program.to_account_info(),
+                    ctx.accounts.buyer_paying_token_account.to_account_info(),
+                    ctx.accounts.payment_mint.to_account_info(),
+                    creator.0.to_account_info(),
+                    ctx.accounts.buyer.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                    creator_share,
+                    currency.decimals,
+                    currency_hook_accounts,
+                )?;
+                creators_payments.push(CreatorPayment {
+                    destination: creator.0.key(),
+                    amount: currency.received(creator_share, clock.epoch)?,
+                });
+            }
+        }
+
+        emit!(BoughtSellOrderEvent{
+            sell_order: ctx.accounts.sell_order.key(),
+            quantity,
+            buyer: ctx.accounts.buyer.key(),
+            mint: ctx.accounts.sell_order.mint,
+            price: total_amount,
+            payment_mint,
+            seller_amount: currency.received(seller_share, clock.epoch)?,
+            comptoir_amount: currency.received(comptoir_share, clock.epoch)?,
+            creators: creators_payments,
+        });
+
+        let sell_order = &mut ctx.accounts.sell_order;
+        sell_order.quantity = sell_order.quantity
+            .checked_sub(quantity)
+            .ok_or(ErrorCode::ErrArithmeticOverflow)?;
+        Ok(())
+    }
+
+    // Buy offers funded in a Token-2022 currency of the comptoir, its escrow holding the whole amount
+    pub fn create_token22_buy_offer<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CreateToken22BuyOffer<'info>>,
+        price_proposition: u64,
+        quantity: u64,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        verify_metadata_and_derivation(
+            ctx.accounts.metadata.as_ref(),
+            &ctx.accounts.nft_mint.key(),
+            &ctx.accounts.collection,
+        )?;
+        init_token22_buy_offer(
+            &mut ctx.accounts.buy_offer,
+            Token22OfferFunding {
+                comptoir: &ctx.accounts.comptoir,
+                collection: &ctx.accounts.collection,
+                escrow: ctx.accounts.escrow.to_account_info(),
+                buyer_paying_account: ctx.accounts.buyer_paying_account.to_account_info(),
+                payer: ctx.accounts.payer.to_account_info(),
+                payment_mint: ctx.accounts.payment_mint.to_account_info(),
+                payment_token_program: ctx.accounts.payment_token_program.to_account_info(),
+                remaining_accounts: ctx.remaining_accounts,
+            },
+            OfferTarget {
+                mint: ctx.accounts.nft_mint.key(),
+                destination: ctx.accounts.buyer_nft_account.key(),
+                trait_hash: None,
+            },
+            price_proposition,
+            quantity,
+            expires_at,
+        )
+    }
+
+    pub fn create_token22_collection_buy_offer<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CreateToken22CollectionBuyOffer<'info>>,
+        price_proposition: u64,
+        quantity: u64,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        init_token22_buy_offer(
+            &mut ctx.accounts.buy_offer,
+            Token22OfferFunding {
+                comptoir: &ctx.accounts.comptoir,
+                collection: &ctx.accounts.collection,
+                escrow: ctx.accounts.escrow.to_account_info(),
+                buyer_paying_account: ctx.accounts.buyer_paying_account.to_account_info(),
+                payer: ctx.accounts.payer.to_account_info(),
+                payment_mint: ctx.accounts.payment_mint.to_account_info(),
+                payment_token_program: ctx.accounts.payment_token_program.to_account_info(),
+                remaining_accounts: ctx.remaining_accounts,
+            },
+            OfferTarget {
+                mint: Pubkey::default(),
+                destination: Pubkey::default(),
+                trait_hash: None,
+            },
+            price_proposition,
+            quantity,
+            expires_at,
+        )
+    }
+
+    pub fn create_token22_trait_buy_offer<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CreateToken22TraitBuyOffer<'info>>,
+        price_proposition: u64,
+        quantity: u64,
+        trait_hash: [u8; 32],
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        if ctx.accounts.collection.traits_root.is_none() {
+            return Err(error!(ErrorCode::ErrCollectionHasNoTraitsRoot));
+        }
+        init_token22_buy_offer(
+            &mut ctx.accounts.buy_offer,
+            Token22OfferFunding {
+                comptoir: &ctx.accounts.comptoir,
+                collection: &ctx.accounts.collection,
+                escrow: ctx.accounts.escrow.to_account_info(),
+                buyer_paying_account: ctx.accounts.buyer_paying_account.to_account_info(),
+                payer: ctx.accounts.payer.to_account_info(),
+                payment_mint: ctx.accounts.payment_mint.to_account_info(),
+                payment_token_program: ctx.accounts.payment_token_program.to_account_info(),
+                remaining_accounts: ctx.remaining_accounts,
+            },
+            OfferTarget {
+                mint: Pubkey::default(),
+                destination: Pubkey::default(),
+                trait_hash: Some(trait_hash),
+            },
+            price_proposition,
+            quantity,
+            expires_at,
+        )
+    }
+
+    // Permissionless, the funds go back to the buyer associated account under Token-2022 and the buyer bears the
+    // transfer fee. Remaining accounts: the currency transfer hook accounts when it has one
+    pub fn close_expired_token22_buy_offer<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CloseExpiredToken22BuyOffer<'info>>,
+    ) -> Result<()> {
+        if !is_expired(ctx.accounts.buy_offer.expires_at, Clock::get()?.unix_timestamp) {
+            return Err(error!(ErrorCode::ErrNotExpired));
+        }
+        let ata_seeds: &[&[u8]] = &[
+            ctx.accounts.buyer.key.as_ref(),
+            ctx.accounts.payment_token_program.key.as_ref(),
+            ctx.accounts.buy_offer.payment_mint.as_ref(),
+        ];
+        let (buyer_ata, _) = Pubkey::find_program_address(ata_seeds, &Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM).unwrap());
+        if ctx.accounts.buyer_paying_account.key() != buyer_ata {
+            return Err(error!(ErrorCode::ErrWrongBuyerPayingAccount));
+        }
+        emit!(BuyOfferRemovedEvent{
+            buy_offer: ctx.accounts.buy_offer.key(),
+        });
+
+        let (currency, _) = load_token22_currency(
+            &ctx.accounts.payment_mint.to_account_info(),
+            &ctx.accounts.payment_token_program.to_account_info(),
+            ctx.remaining_accounts,
+        )?;
+        let seeds = &[
+            PREFIX.as_bytes(),
+            ctx.accounts.comptoir.to_account_info().key.as_ref(),
+            ctx.accounts.buy_offer.payment_mint.as_ref(),
+            ESCROW.as_bytes(),
+            &[*ctx.bumps.get("escrow").unwrap()], ];
+        let signer: &[&[&[u8]]] = &[&seeds[..]];
+        currency.transfer(
+            &ctx.accounts.escrow.to_account_info(),
+            &ctx.accounts.buyer_paying_account.to_account_info(),
+            &ctx.accounts.escrow.to_account_info(),
+            ctx.accounts.buy_offer.locked_amount()?,
+            signer,
+        )?;
+        Ok(())
+    }
+
+    // The buyer bears the transfer fee of the refund. Remaining accounts: the currency transfer hook accounts when it
+    // has one
+    pub fn remove_token22_buy_offer<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, RemoveToken22BuyOffer<'info>>,
+    ) -> Result<()> {
+        emit!(BuyOfferRemovedEvent{
+            buy_offer: ctx.accounts.buy_offer.key(),
+        });
+
+        let (currency, _) = load_token22_currency(
+            &ctx.accounts.payment_mint.to_account_info(),
+            &ctx.accounts.payment_token_program.to_account_info(),
+            ctx.remaining_accounts,
+        )?;
+        let seeds = &[
+            PREFIX.as_bytes(),
+            ctx.accounts.comptoir.to_account_info().key.as_ref(),
+            ctx.accounts.buy_offer.payment_mint.as_ref(),
+            ESCROW.as_bytes(),
+            &[*ctx.bumps.get("escrow").unwrap()], ];
+        let signer: &[&[&[u8]]] = &[&seeds[..]];
+        currency.transfer(
+            &ctx.accounts.escrow.to_account_info(),
+            &ctx.accounts.buyer_paying_account.to_account_info(),
+            &ctx.accounts.escrow.to_account_info(),
+            ctx.accounts.buy_offer.locked_amount()?,
+            signer,
+        )?;
+        Ok(())
+    }
+
+    // The recipients bear the transfer fee of the currency and the event reports what they received. Remaining
+    // accounts: the currency transfer hook accounts when it has one, the creators accounts, then the currency when
+    // not the comptoir one
+    pub fn execute_token22_offer<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, ExecuteToken22Offer<'info>>,
+        quantity: u64,
+        trait_proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        if is_expired(ctx.accounts.buy_offer.expires_at, Clock::get()?.unix_timestamp) {
+            return Err(error!(ErrorCode::ErrBuyOfferExpired));
+        }
+        let (currency, remaining_accounts) = load_token22_currency(
+            &ctx.accounts.payment_mint.to_account_info(),
+            &ctx.accounts.payment_token_program.to_account_info(),
+            ctx.remaining_accounts,
+        )?;
+        let fees_destination = get_fees_destination_of(
+            &ctx.accounts.comptoir,
+            &ctx.accounts.collection,
+            &ctx.accounts.buy_offer.payment_mint,
+            remaining_accounts,
+            ctx.program_id,
+        )?.ok_or(ErrorCode::ErrOfferCurrencyChanged)?;
+        if ctx.accounts.comptoir_dest_account.key() != fees_destination {
+            return Err(error!(ErrorCode::ErrWrongFeesDestination));
+        }
+
+        let metadata = verify_metadata_and_derivation(
+            &ctx.accounts.metadata,
+            &ctx.accounts.seller_nft_account.mint,
+            &ctx.accounts.collection,
+        )?;
+        check_offer_fill(
+            &ctx.accounts.buy_offer,
+            &ctx.accounts.collection,
+            &ctx.accounts.seller_nft_account,
+            &ctx.accounts.destination,
+            quantity,
+            &trait_proof,
+        )?;
+
+        pay(
+            ctx.accounts.seller_nft_account.to_account_info(),
+            ctx.accounts.destination.to_account_info(),
+            ctx.accounts.seller.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            quantity,
+        )?;
+
+        let mut creators_distributions_option: Option<Vec<(&AccountInfo, u8)>> = None;
+        if !ctx.accounts.collection.ignore_creator_fee {
+            if let Some(creators) = metadata.data.creators {
+                let creators_distributions = verify_and_get_token_creators(
+                    creators,
+                    remaining_accounts,
+                    ctx.accounts.buy_offer.payment_mint,
+                    &ctx.accounts.payment_token_program.key(),
+                )?;
+                creators_distributions_option = Some(creators_distributions);
+            }
+        }
+
+        let comptoir_fee = get_comptoir_fee(&ctx.accounts.comptoir, &ctx.accounts.collection);
+        let total_amount = ctx.accounts.buy_offer.proposed_price.checked_mul(quantity).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+        let (seller_share, comptoir_share, creators_share) = split_amount(
+            total_amount,
+            comptoir_fee,
+            &ctx.accounts.collection,
+            metadata.data.seller_fee_basis_points,
+        )?;
+
+        let seeds = &[
+            PREFIX.as_bytes(),
+            ctx.accounts.comptoir.to_account_info().key.as_ref(),
+            ctx.accounts.buy_offer.payment_mint.as_ref(),
+            ESCROW.as_bytes(),
+            &[*ctx.bumps.get("escrow").unwrap()], ];
+        let signer: &[&[&[u8]]] = &[&seeds[..]];
+        let escrow = ctx.accounts.escrow.to_account_info();
+
+        let mut creators_payments = Vec::new();
+        if let Some(creators) = creators_distributions_option.as_ref() {
+            for creator in creators {
+                let creator_share = calculate_fee(creators_share, creator.1 as u16, 100)?;
+                let received = currency.transfer(&escrow, creator.0, &escrow, creator_share, signer)?;
+                creators_payments.push(CreatorPayment { destination: creator.0.key(), amount: received });
+            }
+        }
+        let comptoir_amount = currency.transfer(
+            &escrow,
+            &ctx.accounts.comptoir_dest_account.to_account_info(),
+            &escrow,
+            comptoir_share,
+            signer,
+        )?;
+        let seller_amount = currency.transfer(
+            &escrow,
+            &ctx.accounts.seller_funds_dest_account.to_account_info(),
+            &escrow,
+            seller_share,
+            signer,
+        )?;
+
+        emit!(BuyOfferExecutedEvent{
+            buy_offer: ctx.accounts.buy_offer.key(),
+            seller: ctx.accounts.seller.key(),
+            mint: ctx.accounts.seller_nft_account.mint,
+            quantity,
+            price: total_amount,
+            payment_mint: ctx.accounts.buy_offer.payment_mint,
+            seller_amount,
+            comptoir_amount,
+            creators: creators_payments,
+        });
+
+        let buy_offer = &mut ctx.accounts.buy_offer;
+        buy_offer.quantity = buy_offer.quantity.checked_sub(quantity).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+
+        if ctx.accounts.buy_offer.quantity == 0 {
+            ctx.accounts.buy_offer.close(ctx.accounts.buyer.to_account_info())?;
+        }
+        Ok(())
+    }
+
+    // Bids of an auction in a Token-2022 currency. The bidder pays the transfer fee so the escrow holds the whole bid.
+    // Remaining accounts: the currency transfer hook accounts when it has one, then the outbid bid account
+    pub fn place_token22_bid<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, PlaceToken22Bid<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        let (currency, remaining_accounts) = load_token22_currency(
+            &ctx.accounts.payment_mint.to_account_info(),
+            &ctx.accounts.payment_token_program.to_account_info(),
+            ctx.remaining_accounts,
+        )?;
+        let to_pay = get_bid_top_up(&ctx.accounts.auction, &ctx.accounts.bid, amount)?;
+        currency.transfer(
+            &ctx.accounts.bidder_paying_account.to_account_info(),
+            &ctx.accounts.escrow.to_account_info(),
+            &ctx.accounts.bidder.to_account_info(),
+            currency.info.pre_fee_amount(to_pay, Clock::get()?.epoch)?,
+            &[],
+        )?;
+        record_highest_bid(
+            &mut ctx.accounts.auction,
+            &mut ctx.accounts.bid,
+            ctx.accounts.bidder.key(),
+            amount,
+            remaining_accounts,
+            ctx.program_id,
+        )
+    }
+
+    // The bidder bears the transfer fee of the refund. Remaining accounts: the currency transfer hook accounts when
+    // it has one
+    pub fn claim_token22_bid_refund<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, ClaimToken22BidRefund<'info>>,
+    ) -> Result<()> {
+        let (currency, _) = load_token22_currency(
+            &ctx.accounts.payment_mint.to_account_info(),
+            &ctx.accounts.payment_token_program.to_account_info(),
+            ctx.remaining_accounts,
+        )?;
+        let seeds = &[
+            PREFIX.as_bytes(),
+            ctx.accounts.bid.comptoir.as_ref(),
+            ctx.accounts.bid.payment_mint.as_ref(),
+            ESCROW.as_bytes(),
+            &[*ctx.bumps.get("escrow").unwrap()], ];
+        let signer: &[&[&[u8]]] = &[&seeds[..]];
+        let escrow = ctx.accounts.escrow.to_account_info();
+        let amount = currency.transfer(
+            &escrow,
+            &ctx.accounts.bidder_refund_account.to_account_info(),
+            &escrow,
+            ctx.accounts.bid.amount,
+            signer,
+        )?;
+
+        emit!(AuctionBidRefundedEvent{
+            auction: ctx.accounts.bid.auction,
+            bid: ctx.accounts.bid.key(),
+            bidder: ctx.accounts.bid.bidder,
+            amount,
+        });
+        Ok(())
+    }
+
+    // The recipients bear the transfer fee of the currency. Remaining accounts: the currency transfer hook accounts
+    // when it has one, the creators accounts, then the currency when not the comptoir one
+    pub fn settle_token22_auction<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, SettleToken22Auction<'info>>,
+    ) -> Result<()> {
+        if Clock::get()?.unix_timestamp < ctx.accounts.auction.end_time {
+            return Err(error!(ErrorCode::ErrAuctionNotEnded));
+        }
+        let (currency, remaining_accounts) = load_token22_currency(
+            &ctx.accounts.payment_mint.to_account_info(),
+            &ctx.accounts.payment_token_program.to_account_info(),
+            ctx.remaining_accounts,
+        )?;
+
+        let vault_seeds = &[
+            PREFIX.as_bytes(),
+            "vault".as_bytes(),
+            ctx.accounts.auction.comptoir.as_ref(),
+            ctx.accounts.auction.mint.as_ref(),
+            &[*ctx.bumps.get("vault").unwrap()], ];
+        let vault_signer = &[&vault_seeds[..]];
+
+        if ctx.accounts.auction.highest_bid == 0 {
+            pay_with_signer(
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.seller_nft_token_account.to_account_info(),
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.auction.quantity,
+                vault_signer,
+            )?;
+            emit!(AuctionUnsoldEvent{
+                auction: ctx.accounts.auction.key(),
+                refunded_bid: None,
+            });
+            return Ok(());
+        }
+
+        if ctx.accounts.winner.key() != ctx.accounts.auction.highest_bidder
+            || ctx.accounts.winning_bid.key() != ctx.accounts.auction.highest_bid_account {
+            return Err(error!(ErrorCode::ErrWrongWinnerAccount));
+        }
+
+        let fees_destination = get_fees_destination_of(
+            &ctx.accounts.comptoir,
+            &ctx.accounts.collection,
+            &ctx.accounts.auction.payment_mint,
+            remaining_accounts,
+            ctx.program_id,
+        )?;
+        let fees_destination = match fees_destination {
+            Some(fees_destination) => fees_destination,
+            None => {
+                let mut winning_bid = Account::<'info, Bid>::try_from(&ctx.accounts.winning_bid)?;
+                winning_bid.outbid = true;
+                winning_bid.exit(ctx.program_id)?;
+                pay_with_signer(
+                    ctx.accounts.vault.to_account_info(),
+                    ctx.accounts.seller_nft_token_account.to_account_info(),
+                    ctx.accounts.vault.to_account_info(),
+                    ctx.accounts.token_program.to_account_info(),
+                    ctx.accounts.auction.quantity,
+                    vault_signer,
+                )?;
+                emit!(AuctionUnsoldEvent{
+                    auction: ctx.accounts.auction.key(),
+                    refunded_bid: Some(ctx.accounts.winning_bid.key()),
+                });
+                return Ok(());
+            }
+        };
+        if ctx.accounts.comptoir_dest_account.key() != fees_destination {
+            return Err(error!(ErrorCode::ErrWrongFeesDestination));
+        }
+
+        let ata_seeds: &[&[u8]] = &[
+            ctx.accounts.winner.key.as_ref(),
+            spl_token::ID.as_ref(),
+            ctx.accounts.auction.mint.as_ref(),
+        ];
+        let (winner_ata, _) = Pubkey::find_program_address(ata_seeds, &ctx.accounts.associated_token_program.key());
+        if ctx.accounts.winner_nft_account.key() != winner_ata {
+            return Err(error!(ErrorCode::ErrWrongWinnerAccount));
+        }
+        if ctx.accounts.winner_nft_account.data_is_empty() {
+            anchor_spl::associated_token::create(CpiContext::new(
+                ctx.accounts.associated_token_program.to_account_info(),
+                anchor_spl::associated_token::Create {
+                    payer: ctx.accounts.payer.to_account_info(),
+                    associated_token: ctx.accounts.winner_nft_account.to_account_info(),
+                    authority: ctx.accounts.winner.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                },
+            ))?;
+        }
+
+        let metadata = verify_metadata_and_derivation(
+            ctx.accounts.metadata.as_ref(),
+            &ctx.accounts.auction.mint,
+            &ctx.accounts.collection,
+        )?;
+
+        let mut creators_distributions_option: Option<Vec<(&AccountInfo, u8)>> = None;
+        if !ctx.accounts.collection.ignore_creator_fee {
+            if let Some(creators) = metadata.data.creators {
+                let creators_distributions = verify_and_get_token_creators(
+                    creators,
+                    remaining_accounts,
+                    ctx.accounts.auction.payment_mint,
+                    &ctx.accounts.payment_token_program.key(),
+                )?;
+                creators_distributions_option = Some(creators_distributions);
+            }
+        }
+
+        let comptoir_fee = get_comptoir_fee(&ctx.accounts.comptoir, &ctx.accounts.collection);
+        let (seller_share, comptoir_share, creators_share) = split_amount(
+            ctx.accounts.auction.highest_bid,
+            comptoir_fee,
+            &ctx.accounts.collection,
+            metadata.data.seller_fee_basis_points,
+        )?;
+
+        let seeds = &[
+            PREFIX.as_bytes(),
+            ctx.accounts.comptoir.to_account_info().key.as_ref(),
+            ctx.accounts.auction.payment_mint.as_ref(),
+            ESCROW.as_bytes(),
+            &[*ctx.bumps.get("escrow").unwrap()], ];
+        let signer: &[&[&[u8]]] = &[&seeds[..]];
+        let escrow = ctx.accounts.escrow.to_account_info();
+
+        if let Some(creators) = creators_distributions_option.as_ref() {
+            for creator in creators {
+                let creator_share = calculate_fee(creators_share, creator.1 as u16, 100)?;
+                currency.transfer(&escrow, creator.0, &escrow, creator_share, signer)?;
+            }
+        }
+        currency.transfer(
+            &escrow,
+            &ctx.accounts.comptoir_dest_account.to_account_info(),
+            &escrow,
+            comptoir_share,
+            signer,
+        )?;
+        currency.transfer(
+            &escrow,
+            &ctx.accounts.destination.to_account_info(),
+            &escrow,
+            seller_share,
+            signer,
+        )?;
+
+        pay_with_signer(
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.winner_nft_account.to_account_info(),
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.auction.quantity,
+            vault_signer,
+        )?;
+
+        let winning_bid = Account::<'info, Bid>::try_from(&ctx.accounts.winning_bid)?;
+        winning_bid.close(ctx.accounts.winner.to_account_info())?;
+
+        emit!(AuctionSettledEvent{
+            auction: ctx.accounts.auction.key(),
+            winner: ctx.accounts.auction.highest_bidder,
+            price: ctx.accounts.auction.highest_bid,
+            quantity: ctx.accounts.auction.quantity,
+        });
+        Ok(())
+    }
+
+    // Bundles priced in a Token-2022 currency, the recipients bearing its transfer fee. Remaining accounts: the
+    // currency transfer hook accounts when it has one, the accounts of each item as for buy_bundle, then the currency
+    // when not the comptoir one
+    pub fn buy_token22_bundle<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, BuyToken22Bundle<'info>>,
+        max_price: u64,
+    ) -> Result<()> {
+        let bundle_order = &ctx.accounts.bundle_order;
+        if is_expired(bundle_order.expires_at, Clock::get()?.unix_timestamp) {
+            return Err(error!(ErrorCode::ErrSellOrderExpired));
+        }
+        let (currency, remaining_accounts) = load_token22_currency(
+            &ctx.accounts.payment_mint.to_account_info(),
+            &ctx.accounts.payment_token_program.to_account_info(),
+            ctx.remaining_accounts,
+        )?;
+        let fees_destination = get_fees_destination_in(
+            &ctx.accounts.comptoir,
+            &ctx.accounts.comptoir.key(),
+            &bundle_order.payment_mint,
+            remaining_accounts,
+            ctx.program_id,
+        )?.ok_or(ErrorCode::ErrOfferCurrencyChanged)?;
+        if ctx.accounts.comptoir_dest_account.key() != fees_destination {
+            return Err(error!(ErrorCode::ErrWrongFeesDestination));
+        }
+        if bundle_order.price > max_price {
+            return Err(error!(ErrorCode::ErrMaxTotalPriceExceeded));
+        }
+        if bundle_order.items.is_empty() {
+            return Err(error!(ErrorCode::ErrBundleEmpty));
+        }
+        let comptoir_key = ctx.accounts.comptoir.key();
+        let buyer_paying_account = ctx.accounts.buyer_paying_account.to_account_info();
+        let buyer = ctx.accounts.buyer.to_account_info();
+
+        let items_count = bundle_order.items.len() as u64;
+        let item_price = bundle_order.price / items_count;
+        let mut seller_total: u64 = 0;
+        let mut comptoir_total: u64 = 0;
+        let mut creators_payments = Vec::new();
+        let mut index = 0;
+        for (i, item) in bundle_order.items.iter().enumerate() {
+            let collection = Account::<'info, Collection>::try_from(get_remaining_account(remaining_accounts, index)?)?;
+            let metadata_account = get_remaining_account(remaining_accounts, index + 1)?;
+            let vault = get_remaining_account(remaining_accounts, index + 2)?;
+            let buyer_nft_token_account = get_remaining_account(remaining_accounts, index + 3)?;
+            index += 4;
+            if collection.key() != item.collection {
+                return Err(error!(ErrorCode::ErrWrongBundleAccounts));
+            }
+            let metadata = verify_metadata_and_derivation(metadata_account, &item.mint, &collection)?;
+
+            let vault_bump = get_vault_bump(vault, &comptoir_key, &item.mint, ctx.program_id)?;
+            let seeds = &[
+                PREFIX.as_bytes(),
+                "vault".as_bytes(),
+                comptoir_key.as_ref(),
+                item.mint.as_ref(),
+                &[vault_bump], ];
+            let signer = &[&seeds[..]];
+            pay_with_signer(
+                vault.to_account_info(),
+                buyer_nft_token_account.to_account_info(),
+                vault.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                1,
+                signer,
+            )?;
+
+            let mut price_share = item_price;
+            if i as u64 == items_count - 1 {
+                price_share = bundle_order.price.checked_sub(item_price.checked_mul(items_count - 1).ok_or(ErrorCode::ErrArithmeticOverflow)?).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+            }
+            let (seller_share, comptoir_share, creators_share) = split_amount(
+                price_share,
+                get_comptoir_fee(&ctx.accounts.comptoir, &collection),
+                &collection,
+                metadata.data.seller_fee_basis_points,
+            )?;
+            seller_total = seller_total.checked_add(seller_share).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+            comptoir_total = comptoir_total.checked_add(comptoir_share).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+
+            if !collection.ignore_creator_fee {
+                if let Some(creators) = metadata.data.creators {
+                    let creators_count = creators.len();
+                    let creators_distributions = verify_and_get_token_creators(
+                        creators,
+                        get_remaining_accounts_from(remaining_accounts, index)?,
+                        bundle_order.payment_mint,
+                        &ctx.accounts.payment_token_program.key(),
+                    )?;
+                    index += creators_count;
+                    for creator in creators_distributions {
+                        let creator_share = calculate_fee(creators_share, creator.1 as u16, 100)?;
+                        let received = currency.transfer(&buyer_paying_account, creator.0, &buyer, creator_share, &[])?;
+                        creators_payments.push(CreatorPayment { destination: creator.0.key(), amount: received });
+                    }
+                }
+            }
+        }
+
+        let seller_amount = currency.transfer(
+            &buyer_paying_account,
+            &ctx.accounts.seller_funds_dest_account.to_account_info(),
+            &buyer,
+            seller_total,
+            &[],
+        )?;
+        let comptoir_amount = currency.transfer(
+            &buyer_paying_account,
+            &ctx.accounts.comptoir_dest_account.to_account_info(),
+            &buyer,
+            comptoir_total,
+            &[],
+        )?;
+
+        emit!(BundleBoughtEvent{
+            bundle_order: bundle_order.key(),
+            buyer: ctx.accounts.buyer.key(),
+            price: bundle_order.price,
+            payment_mint: bundle_order.payment_mint,
+            seller_amount,
+            comptoir_amount,
+            creators: creators_payments,
+        });
+        Ok(())
+    }
+
+    // Swaps sending tokens of a Token-2022 currency, the authority paying the transfer fee so the escrow holds the
+    // whole amount. Remaining accounts: the currency transfer hook accounts when it has one, then the currency when
+    // not the comptoir one
+    pub fn create_token22_swap_offer<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CreateToken22SwapOffer<'info>>,
+        _swap_id: u64,
+        requested: Vec<SwapAsk>,
+        token_amount: u64,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        validate_expiration(expires_at)?;
+        if requested.is_empty() || requested.len() > SWAP_CAPACITY {
+            return Err(error!(ErrorCode::ErrWrongSwapRequest));
+        }
+        let (currency, remaining_accounts) = load_token22_currency(
+            &ctx.accounts.payment_mint.to_account_info(),
+            &ctx.accounts.payment_token_program.to_account_info(),
+            ctx.remaining_accounts,
+        )?;
+        let (payment_mint, _) = get_payment_terms_in(&ctx.accounts.comptoir, &ctx.accounts.comptoir.key(), remaining_accounts)?;
+        if ctx.accounts.payment_mint.key() != payment_mint {
+            return Err(error!(ErrorCode::ErrWrongCurrency));
+        }
+        record_swap_offer(
+            &mut ctx.accounts.swap_offer,
+            ctx.accounts.comptoir.key(),
+            ctx.accounts.payer.key(),
+            payment_mint,
+            token_amount,
+            expires_at,
+            requested,
+        );
+
+        if token_amount > 0 {
+            currency.transfer(
+                &ctx.accounts.payer_paying_account.to_account_info(),
+                &ctx.accounts.escrow.to_account_info(),
+                &ctx.accounts.payer.to_account_info(),
+                currency.info.pre_fee_amount(token_amount, Clock::get()?.epoch)?,
+                &[],
+            )?;
+        }
+        Ok(())
+    }
+
+    // The authority bears the transfer fee of the refund. Remaining accounts: the currency transfer hook accounts
+    // when it has one, then the accounts of remove_swap_offer
+    pub fn remove_token22_swap_offer<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, RemoveToken22SwapOffer<'info>>,
+    ) -> Result<()> {
+        let (currency, remaining_accounts) = load_token22_currency(
+            &ctx.accounts.payment_mint.to_account_info(),
+            &ctx.accounts.payment_token_program.to_account_info(),
+            ctx.remaining_accounts,
+        )?;
+        return_swap_items(
+            &ctx.accounts.swap_offer,
+            remaining_accounts,
+            &ctx.accounts.token_program.to_account_info(),
+            ctx.program_id,
+        )?;
+
+        if ctx.accounts.swap_offer.token_amount == 0 {
+            return Ok(());
+        }
+        let seeds = &[
+            PREFIX.as_bytes(),
+            ctx.accounts.swap_offer.comptoir.as_ref(),
+            ctx.accounts.swap_offer.payment_mint.as_ref(),
+            ESCROW.as_bytes(),
+            &[*ctx.bumps.get("escrow").unwrap()], ];
+        let signer: &[&[&[u8]]] = &[&seeds[..]];
+        let escrow = ctx.accounts.escrow.to_account_info();
+        currency.transfer(
+            &escrow,
+            &ctx.accounts.authority_paying_account.to_account_info(),
+            &escrow,
+            ctx.accounts.swap_offer.token_amount,
+            signer,
+        )?;
+        Ok(())
+    }
+
+    // The counterparty and the comptoir bear the transfer fee of the currency, the event reports what the comptoir
+    // received. Remaining accounts: the currency transfer hook accounts when it has one, then the accounts of
+    // accept_swap_offer
+    pub fn accept_token22_swap_offer<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, AcceptToken22SwapOffer<'info>>,
+    ) -> Result<()> {
+        let swap_offer = &ctx.accounts.swap_offer;
+        if is_expired(swap_offer.expires_at, Clock::get()?.unix_timestamp) {
+            return Err(error!(ErrorCode::ErrSwapOfferExpired));
+        }
+        if swap_offer.offered_mints.is_empty() && swap_offer.token_amount == 0 {
+            return Err(error!(ErrorCode::ErrSwapEmpty));
+        }
+        let (currency, remaining_accounts) = load_token22_currency(
+            &ctx.accounts.payment_mint.to_account_info(),
+            &ctx.accounts.payment_token_program.to_account_info(),
+            ctx.remaining_accounts,
+        )?;
+        let received_mints = exchange_swap_items(
+            swap_offer,
+            &ctx.accounts.counterparty.to_account_info(),
+            remaining_accounts,
+            &ctx.accounts.token_program.to_account_info(),
+            ctx.program_id,
+        )?;
+
+        let mut comptoir_amount = 0;
+        if swap_offer.token_amount > 0 {
+            let fees_destination = get_fees_destination_in(
+                &ctx.accounts.comptoir,
+                &ctx.accounts.comptoir.key(),
+                &swap_offer.payment_mint,
+                remaining_accounts,
+                ctx.program_id,
+            )?.ok_or(ErrorCode::ErrOfferCurrencyChanged)?;
+            if ctx.accounts.comptoir_dest_account.key() != fees_destination {
+                return Err(error!(ErrorCode::ErrWrongFeesDestination));
+            }
+            let comptoir_share = calculate_fee(swap_offer.token_amount, ctx.accounts.comptoir.fees, 10000)?;
+            let counterparty_share = swap_offer.token_amount.checked_sub(comptoir_share).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+
+            let seeds = &[
+                PREFIX.as_bytes(),
+                swap_offer.comptoir.as_ref(),
+                swap_offer.payment_mint.as_ref(),
+                ESCROW.as_bytes(),
+                &[*ctx.bumps.get("escrow").unwrap()], ];
+            let signer: &[&[&[u8]]] = &[&seeds[..]];
+            let escrow = ctx.accounts.escrow.to_account_info();
+            comptoir_amount = currency.transfer(
+                &escrow,
+                &ctx.accounts.comptoir_dest_account.to_account_info(),
+                &escrow,
+                comptoir_share,
+                signer,
+            )?;
+            currency.transfer(
+                &escrow,
+                &ctx.accounts.counterparty_funds_account.to_account_info(),
+                &escrow,
+                counterparty_share,
+                signer,
+            )?;
+        }
+
+        emit!(SwapOfferAcceptedEvent{
+            swap_offer: swap_offer.key(),
+            counterparty: ctx.accounts.counterparty.key(),
+            offered_mints: swap_offer.offered_mints.clone(),
+            received_mints,
+            token_amount: swap_offer.token_amount,
+            comptoir_amount,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(price_proposition: u64)]
+pub struct CreateBuyOffer<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    nft_mint: Account<'info, Mint>,
+    /// CHECK: This is not dangerous because check it all the time using the verify_metadata_and_derivation func
+    metadata: UncheckedAccount<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(mut, constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    escrow.mint.as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Token account checked by the token program, or the paying wallet itself for native SOL comptoirs
+    #[account(mut)]
+    buyer_paying_account: UncheckedAccount<'info>,
+    #[account(
+    init_if_needed,
+    payer = payer,
+    associated_token::mint = nft_mint,
+    associated_token::authority = payer,
+    )]
+    buyer_nft_account: Account<'info, TokenAccount>,
+
+    #[account(
+    init,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    payer.key.as_ref(),
+    nft_mint.key().as_ref(),
+    price_proposition.to_string().as_bytes(),
+    ESCROW.as_bytes(),
+    ],
+    bump,
+    payer = payer,
+    space = BUY_OFFER_LEN,
+    )]
+    buy_offer: Account<'info, BuyOffer>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(price_proposition: u64)]
+pub struct CreateCollectionBuyOffer<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    escrow.mint.as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Token account checked by the token program, or the paying wallet itself for native SOL comptoirs
+    #[account(mut)]
+    buyer_paying_account: UncheckedAccount<'info>,
+
+    #[account(
+    init,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    payer.key.as_ref(),
+    collection.key().as_ref(),
+    price_proposition.to_string().as_bytes(),
+    ESCROW.as_bytes(),
+    ],
+    bump,
+    payer = payer,
+    space = BUY_OFFER_LEN,
+    )]
+    buy_offer: Account<'info, BuyOffer>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(price_proposition: u64, quantity: u64, trait_hash: [u8; 32])]
+pub struct CreateTraitBuyOffer<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    escrow.mint.as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Token account checked by the token program, or the paying wallet itself for native SOL comptoirs
+    #[account(mut)]
+    buyer_paying_account: UncheckedAccount<'info>,
+
+    #[account(
+    init,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    payer.key.as_ref(),
+    collection.key().as_ref(),
+    trait_hash.as_ref(),
+    price_proposition.to_string().as_bytes(),
+    ESCROW.as_bytes(),
+    ],
+    bump,
+    payer = payer,
+    space = BUY_OFFER_LEN,
+    )]
+    buy_offer: Account<'info, BuyOffer>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveBuyOffer<'info> {
+    #[account(mut)]
+    buyer: Signer<'info>,
+
+    /// CHECK: Token account checked by the token program, unused for native SOL offers
+    #[account(mut)]
+    buyer_paying_account: UncheckedAccount<'info>,
+
+    comptoir: Account<'info, Comptoir>,
+
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    buy_offer.payment_mint.as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: Account<'info, TokenAccount>,
+
+    #[account(
+    mut,
+    close = buyer,
+    has_one = comptoir,
+    constraint = buy_offer.authority == buyer.key(),
+    )]
+    buy_offer: Account<'info, BuyOffer>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+    rent: Sysvar<'info, Rent>,
+}
+
+// Permissionless, the funds go back to the buyer and the rent to the buy offer creator
+#[derive(Accounts)]
+pub struct CloseExpiredBuyOffer<'info> {
+    #[account(mut)]
+    buyer: SystemAccount<'info>,
+
+    /// CHECK: Token account owned by the buyer, checked in the instruction, unused for native SOL offers
+    #[account(mut)]
+    buyer_paying_account: UncheckedAccount<'info>,
+
+    comptoir: Account<'info, Comptoir>,
+
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    buy_offer.payment_mint.as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: Account<'info, TokenAccount>,
+
+    #[account(
+    mut,
+    close = buyer,
+    has_one = comptoir,
+    constraint = buy_offer.authority == buyer.key(),
+    )]
+    buy_offer: Account<'info, BuyOffer>,
+
+    token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateBuyOffer<'info> {
+    #[account(mut)]
+    buyer: Signer<'info>,
+
+    /// CHECK: This is not dangerous because check it all the time using the verify_metadata_and_derivation func
+    metadata: UncheckedAccount<'info>,
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+    /// CHECK: Legacy layout, its owner, size and discriminator are checked by read_legacy
+    #[account(mut)]
+    buy_offer: UncheckedAccount<'info>,
+
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteOffer<'info> {
+    seller: Signer<'info>,
+
+    #[account(mut)]
+    buyer: SystemAccount<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(mut, constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+
+    /// CHECK: Checked against the fees destination of the offer currency in the instruction
+    #[account(mut)]
+    comptoir_dest_account: UncheckedAccount<'info>,
+
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    buy_offer.payment_mint.as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Token account checked by the token program, or a wallet for native SOL comptoirs
+    #[account(mut)]
+    seller_funds_dest_account: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    destination: Account<'info, TokenAccount>,
+    #[account(mut)]
+    seller_nft_account: Account<'info, TokenAccount>,
+
+
+    /// CHECK: This is not dangerous because check it all the time using the verify_metadata_and_derivation func
+    metadata: UncheckedAccount<'info>,
+
+    #[account(
+    mut,
+    constraint = buy_offer.authority == buyer.key(),
+    has_one = comptoir,
+    has_one = collection,
+    )]
+    buy_offer: Account<'info, BuyOffer>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(payment_mint: Pubkey)]
+pub struct CreateOrderBook<'info> {
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(has_one = authority)]
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+
+    mint: Account<'info, Mint>,
+    /// CHECK: This is not dangerous because check it all the time using the verify_metadata_and_derivation func
+    metadata: UncheckedAccount<'info>,
+
+    #[account(
+    init,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    mint.key().as_ref(),
+    payment_mint.as_ref(),
+    ORDER_BOOK.as_bytes(),
+    ],
+    bump,
+    payer = authority,
+    space = 5272,
+    )]
+    order_book: Box<Account<'info, OrderBook>>,
+
+    system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AddSellOrderToBook<'info> {
+    authority: Signer<'info>,
+
+    #[account(mut)]
+    order_book: Box<Account<'info, OrderBook>>,
+
+    #[account(
+    constraint = sell_order.authority == authority.key(),
+    constraint = sell_order.comptoir == order_book.comptoir,
+    )]
+    sell_order: Account<'info, SellOrder>,
+}
+
+#[derive(Accounts)]
+pub struct AddBuyOfferToBook<'info> {
+    authority: Signer<'info>,
+
+    #[account(mut)]
+    order_book: Box<Account<'info, OrderBook>>,
+
+    #[account(
+    constraint = buy_offer.authority == authority.key(),
+    constraint = buy_offer.comptoir == order_book.comptoir,
+    )]
+    buy_offer: Account<'info, BuyOffer>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveFromOrderBook<'info> {
+    authority: Signer<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    collection: Box<Account<'info, Collection>>,
+    #[account(mut, has_one = comptoir, has_one = collection)]
+    order_book: Box<Account<'info, OrderBook>>,
+
+    /// CHECK: Sell order or buy offer of the book, possibly closed, checked in the instruction
+    order: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MatchOrders<'info> {
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+
+    #[account(mut, has_one = comptoir)]
+    order_book: Box<Account<'info, OrderBook>>,
+
+    /// CHECK: Token account checked by the token program, or a wallet for native SOL comptoirs
+    #[account(mut)]
+    comptoir_dest_account: UncheckedAccount<'info>,
+
+    /// CHECK: This is not dangerous because check it all the time using the verify_metadata_and_derivation func
+    metadata: UncheckedAccount<'info>,
+
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    "vault".as_bytes(),
+    comptoir.key().as_ref(),
+    order_book.mint.as_ref(),
+    ],
+    bump,
+    )]
+    vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    order_book.payment_mint.as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: Box<Account<'info, TokenAccount>>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(bundle_id: u64)]
+pub struct CreateBundleOrder<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+
+    #[account(
+    init,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    payer.key.as_ref(),
+    BUNDLE.as_bytes(),
+    bundle_id.to_le_bytes().as_ref(),
+    ],
+    bump,
+    payer = payer,
+    space = 669,
+    )]
+    bundle_order: Box<Account<'info, BundleOrder>>,
+
+    system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AddBundleItem<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+    #[account(mut)]
+    seller_nft_token_account: Box<Account<'info, TokenAccount>>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+
+    #[account(
+    mut,
+    has_one = comptoir,
+    constraint = bundle_order.authority == payer.key(),
+    )]
+    bundle_order: Box<Account<'info, BundleOrder>>,
+
+    #[account(constraint = mint.key() == seller_nft_token_account.mint)]
+    mint: Account<'info, Mint>,
+    /// CHECK: This is not dangerous because check it all the time using the verify_metadata_and_derivation func
+    metadata: UncheckedAccount<'info>,
+
+    #[account(
+    init_if_needed,
+    token::mint = mint,
+    token::authority = vault,
+    seeds = [
+    PREFIX.as_bytes(),
+    "vault".as_bytes(),
+    comptoir.key().as_ref(),
+    mint.key().as_ref(),
+    ],
+    bump,
+    payer = payer,
+    )]
+    vault: Box<Account<'info, TokenAccount>>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveBundleOrder<'info> {
+    #[account(mut)]
+    authority: Signer<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+
+    #[account(
+    mut,
+    close = authority,
+    has_one = comptoir,
+    has_one = authority,
+    )]
+    bundle_order: Box<Account<'info, BundleOrder>>,
+
+    token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct BuyBundle<'info> {
+    #[account(mut)]
+    buyer: Signer<'info>,
+    /// CHECK: Token account checked by the token program, or the paying wallet itself for native SOL comptoirs
+    #[account(mut)]
+    buyer_paying_token_account: UncheckedAccount<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    /// CHECK: Token account checked by the token program, or a wallet for native SOL comptoirs
+    #[account(mut)]
+    comptoir_dest_account: UncheckedAccount<'info>,
+
+    /// CHECK: Token account checked by the token program, or a wallet for native SOL comptoirs
+    #[account(mut, constraint = seller_funds_dest_account.key() == bundle_order.destination)]
+    seller_funds_dest_account: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    authority: SystemAccount<'info>,
+    #[account(
+    mut,
+    close = authority,
+    has_one = comptoir,
+    has_one = authority,
+    )]
+    bundle_order: Box<Account<'info, BundleOrder>>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(swap_id: u64)]
+pub struct CreateSwapOffer<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+    /// CHECK: Token account checked by the token program, or the paying wallet itself for native SOL comptoirs
+    #[account(mut)]
+    payer_paying_account: UncheckedAccount<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    escrow.mint.as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+    init,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    payer.key.as_ref(),
+    SWAP.as_bytes(),
+    swap_id.to_le_bytes().as_ref(),
+    ],
+    bump,
+    payer = payer,
+    space = 389,
+    )]
+    swap_offer: Box<Account<'info, SwapOffer>>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AddSwapItem<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+    #[account(mut)]
+    payer_nft_token_account: Box<Account<'info, TokenAccount>>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+
+    #[account(
+    mut,
+    has_one = comptoir,
+    constraint = swap_offer.authority == payer.key(),
+    )]
+    swap_offer: Box<Account<'info, SwapOffer>>,
+
+    #[account(constraint = mint.key() == payer_nft_token_account.mint)]
+    mint: Account<'info, Mint>,
+
+    #[account(
+    init_if_needed,
+    token::mint = mint,
+    token::authority = vault,
+    seeds = [
+    PREFIX.as_bytes(),
+    "vault".as_bytes(),
+    comptoir.key().as_ref(),
+    mint.key().as_ref(),
+    ],
+    bump,
+    payer = payer,
+    )]
+    vault: Box<Account<'info, TokenAccount>>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveSwapOffer<'info> {
+    #[account(mut)]
+    authority: Signer<'info>,
+    /// CHECK: Token account checked by the token program, unused for native SOL swaps
+    #[account(mut)]
+    authority_paying_account: UncheckedAccount<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    swap_offer.payment_mint.as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+    mut,
+    close = authority,
+    has_one = comptoir,
+    has_one = authority,
+    )]
+    swap_offer: Box<Account<'info, SwapOffer>>,
+
+    token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptSwapOffer<'info> {
+    #[account(mut)]
+    counterparty: Signer<'info>,
+    /// CHECK: Token account checked by the token program, or a wallet for native SOL comptoirs
+    #[account(mut)]
+    counterparty_funds_account: UncheckedAccount<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    /// CHECK: Token account checked by the token program, or a wallet for native SOL comptoirs
+    #[account(mut)]
+    comptoir_dest_account: UncheckedAccount<'info>,
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    swap_offer.payment_mint.as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    authority: SystemAccount<'info>,
+    #[account(
+    mut,
+    close = authority,
+    has_one = comptoir,
+    has_one = authority,
+    )]
+    swap_offer: Box<Account<'info, SwapOffer>>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(comptoir_mint: Pubkey)]
+pub struct CreateComptoir<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+    #[account(
+    init,
+    seeds = [
+        PREFIX.as_bytes(),
+        payer.key.as_ref()
+    ],
+    bump,
+    payer = payer,
+    space = 112,
+    )]
+    comptoir: Account<'info, Comptoir>,
+
+    /// CHECK: Loaded with the load_mint_of func when creating the escrow
+    #[account(address = comptoir_mint)]
+    mint: UncheckedAccount<'info>,
+
+    /// CHECK: Created for the token program of the mint with the create_escrow func
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    comptoir_mint.as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: UncheckedAccount<'info>,
+
+
+    system_program: Program<'info, System>,
+    /// CHECK: Owner of the mint, the token program or Token-2022, checked in the load_mint_of func
+    token_program: UncheckedAccount<'info>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateComptoir<'info> {
+    authority: Signer<'info>,
+    #[account(mut, has_one = authority)]
+    comptoir: Account<'info, Comptoir>,
+}
+
+#[derive(Accounts)]
+#[instruction(new_comptoir_mint: Pubkey)]
+pub struct UpdateComptoirMint<'info> {
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(mut, has_one = authority)]
+    comptoir: Account<'info, Comptoir>,
+
+    /// CHECK: Loaded with the load_mint_of func when creating the escrow
+    #[account(constraint = new_comptoir_mint == mint.key())]
+    mint: UncheckedAccount<'info>,
+
+    /// CHECK: Created for the token program of the mint with the create_escrow func, unless it already exists
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    new_comptoir_mint.as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: UncheckedAccount<'info>,
+
+    system_program: Program<'info, System>,
+    /// CHECK: Owner of the mint, the token program or Token-2022, checked in the load_mint_of func
+    token_program: UncheckedAccount<'info>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct CreateCollection<'info> {
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(mut, has_one = authority)]
+    comptoir: Account<'info, Comptoir>,
+    #[account(
+    init,
+    seeds = [
+    PREFIX.as_bytes(),
+    name.as_bytes(),
+    comptoir.key().as_ref(),
+    ],
+    bump,
+    payer = authority,
+    space = COLLECTION_LEN,
+    )]
+    collection: Account<'info, Collection>,
+
+    system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCollection<'info> {
+    authority: Signer<'info>,
+    #[account(has_one = authority)]
+    comptoir: Account<'info, Comptoir>,
+
+    #[account(mut, constraint = collection.comptoir_key == comptoir.key())]
+    collection: Account<'info, Collection>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateCollection<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    /// CHECK: Legacy layout, its owner, size and discriminator are checked by read_legacy
+    #[account(mut)]
+    collection: UncheckedAccount<'info>,
+
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddCurrency<'info> {
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(has_one = authority)]
+    comptoir: Account<'info, Comptoir>,
+
+    /// CHECK: Loaded with the load_mint_of func when creating the escrow
+    mint: UncheckedAccount<'info>,
+
+    #[account(
+    init,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    mint.key().as_ref(),
+    CURRENCY.as_bytes(),
+    ],
+    bump,
+    payer = authority,
+    space = 136,
+    )]
+    currency: Account<'info, Currency>,
+
+    /// CHECK: Created for the token program of the mint with the create_escrow func, unless it already exists
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    mint.key().as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: UncheckedAccount<'info>,
+
+    system_program: Program<'info, System>,
+    /// CHECK: Owner of the mint, the token program or Token-2022, checked in the load_mint_of func
+    token_program: UncheckedAccount<'info>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AddCollectionCurrency<'info> {
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(has_one = authority)]
+    comptoir: Account<'info, Comptoir>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Account<'info, Collection>,
+
+    /// CHECK: Loaded with the load_mint_of func when creating the escrow
+    mint: UncheckedAccount<'info>,
+
+    #[account(
+    init,
+    seeds = [
+    PREFIX.as_bytes(),
+    collection.key().as_ref(),
+    mint.key().as_ref(),
+    CURRENCY.as_bytes(),
+    ],
+    bump,
+    payer = authority,
+    space = 136,
+    )]
+    currency: Account<'info, Currency>,
+
+    /// CHECK: Created for the token program of the mint with the create_escrow func, unless it already exists
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    mint.key().as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: UncheckedAccount<'info>,
+
+    system_program: Program<'info, System>,
+    /// CHECK: Owner of the mint, the token program or Token-2022, checked in the load_mint_of func
+    token_program: UncheckedAccount<'info>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCurrency<'info> {
+    authority: Signer<'info>,
+    #[account(has_one = authority)]
+    comptoir: Account<'info, Comptoir>,
+
+    #[account(mut, has_one = comptoir)]
+    currency: Account<'info, Currency>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveCurrency<'info> {
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(has_one = authority)]
+    comptoir: Account<'info, Comptoir>,
+
+    #[account(mut, close = authority, has_one = comptoir)]
+    currency: Account<'info, Currency>,
+}
+
+#[derive(Accounts)]
+#[instruction(price: u64)]
+pub struct CreateSellOrder<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+    #[account(mut)]
+    seller_nft_token_account: Box<Account<'info, TokenAccount>>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+
+    #[account(constraint = mint.key() == seller_nft_token_account.mint)]
+    mint: Account<'info, Mint>,
+    /// CHECK: This is not dangerous because check it all the time using the verify_metadata_and_derivation func
+    metadata: UncheckedAccount<'info>,
+
+    #[account(
+    init_if_needed,
+    token::mint = mint,
+    token::authority = vault,
+    seeds = [
+    PREFIX.as_bytes(),
+    "vault".as_bytes(),
+    comptoir.key().as_ref(),
+    seller_nft_token_account.mint.as_ref(),
+    ],
+    bump,
+    payer = payer,
+    )]
+    vault: Account<'info, TokenAccount>,
+
+    #[account(
+    init,
+    seeds = [
+    PREFIX.as_bytes(),
+    seller_nft_token_account.key().as_ref(),
+    price.to_string().as_bytes(),
+    ],
+    bump,
+    payer = payer,
+    space = SELL_ORDER_LEN,
+    )]
+    sell_order: Account<'info, SellOrder>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CreateSellerOrderCounter<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    #[account(
+    init,
+    seeds = [
+    PREFIX.as_bytes(),
+    payer.key.as_ref(),
+    ORDER_COUNTER.as_bytes(),
+    ],
+    bump,
+    payer = payer,
+    space = 48,
+    )]
+    counter: Account<'info, SellerOrderCounter>,
+
+    system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(order_id: u64)]
+pub struct CreateSellOrderWithId<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+    #[account(mut)]
+    seller_nft_token_account: Box<Account<'info, TokenAccount>>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+
+    #[account(constraint = mint.key() == seller_nft_token_account.mint)]
+    mint: Account<'info, Mint>,
+    /// CHECK: This is not dangerous because check it all the time using the verify_metadata_and_derivation func
+    metadata: UncheckedAccount<'info>,
+
+    #[account(
+    init_if_needed,
+    token::mint = mint,
+    token::authority = vault,
+    seeds = [
+    PREFIX.as_bytes(),
+    "vault".as_bytes(),
+    comptoir.key().as_ref(),
+    seller_nft_token_account.mint.as_ref(),
+    ],
+    bump,
+    payer = payer,
+    )]
+    vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+    init,
+    seeds = [
+    PREFIX.as_bytes(),
+    seller_nft_token_account.key().as_ref(),
+    ORDER_ID.as_bytes(),
+    order_id.to_le_bytes().as_ref(),
+    ],
+    bump,
+    payer = payer,
+    space = SELL_ORDER_LEN,
+    )]
+    sell_order: Box<Account<'info, SellOrder>>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveSellOrder<'info> {
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(mut, constraint = authority.key() == seller_nft_token_account.owner)]
+    seller_nft_token_account: Account<'info, TokenAccount>,
+    #[account(
+    mut,
+    has_one = authority,
+    constraint = seller_nft_token_account.mint == sell_order.mint,
+    constraint = sell_order.vault_migrated @ ErrorCode::ErrSellOrderVaultNotMigrated,
+    )]
+    sell_order: Account<'info, SellOrder>,
+
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    "vault".as_bytes(),
+    sell_order.comptoir.as_ref(),
+    seller_nft_token_account.mint.as_ref(),
+    ],
+    bump,
+    )]
+    vault: Account<'info, TokenAccount>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct SellOrderAddQuantity<'info> {
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(mut, constraint = authority.key() == seller_nft_token_account.owner)]
+    seller_nft_token_account: Account<'info, TokenAccount>,
+    #[account(
+    mut,
+    has_one = authority,
+    constraint = seller_nft_token_account.mint == sell_order.mint,
+    constraint = sell_order.vault_migrated @ ErrorCode::ErrSellOrderVaultNotMigrated,
+    )]
+    sell_order: Account<'info, SellOrder>,
+
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    "vault".as_bytes(),
+    sell_order.comptoir.as_ref(),
+    seller_nft_token_account.mint.as_ref(),
+    ],
+    bump,
+    )]
+    vault: Account<'info, TokenAccount>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+    rent: Sysvar<'info, Rent>,
+}
+
+// Permissionless, the items go back to the seller and the rent to the sell order creator
+#[derive(Accounts)]
+pub struct CloseExpiredSellOrder<'info> {
+    #[account(mut)]
+    authority: SystemAccount<'info>,
+    #[account(
+    mut,
+    constraint = authority.key() == seller_nft_token_account.owner,
+    constraint = seller_nft_token_account.mint == sell_order.mint,
+    )]
+    seller_nft_token_account: Account<'info, TokenAccount>,
+    #[account(
+    mut,
+    close = authority,
+    has_one = authority,
+    constraint = sell_order.vault_migrated @ ErrorCode::ErrSellOrderVaultNotMigrated,
+    )]
+    sell_order: Account<'info, SellOrder>,
+
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    "vault".as_bytes(),
+    sell_order.comptoir.as_ref(),
+    sell_order.mint.as_ref(),
+    ],
+    bump,
+    )]
+    vault: Account<'info, TokenAccount>,
+
+    token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateSellOrder<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    /// CHECK: Legacy layout, its owner, size and discriminator are checked by read_legacy
+    #[account(mut)]
+    sell_order: UncheckedAccount<'info>,
+
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateSellOrderVault<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    /// CHECK: Current or legacy layout, legacy orders are upgraded then checked in the instruction
+    #[account(mut)]
+    sell_order: UncheckedAccount<'info>,
+    mint: Account<'info, Mint>,
+
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    "vault".as_bytes(),
+    mint.key().as_ref(),
+    ],
+    bump,
+    )]
+    legacy_vault: Box<Account<'info, TokenAccount>>,
+    #[account(
+    init_if_needed,
+    token::mint = mint,
+    token::authority = vault,
+    seeds = [
+    PREFIX.as_bytes(),
+    "vault".as_bytes(),
+    comptoir.key().as_ref(),
+    mint.key().as_ref(),
+    ],
+    bump,
+    payer = payer,
+    )]
+    vault: Box<Account<'info, TokenAccount>>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct Buy<'info> {
+    #[account(mut)]
+    buyer: Signer<'info>,
+    #[account(mut)]
+    buyer_nft_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Token account checked by the token program, or the paying wallet itself for native SOL comptoirs
+    #[account(mut)]
+    buyer_paying_token_account: UncheckedAccount<'info>,
+
+    comptoir: Account<'info, Comptoir>,
+    /// CHECK: Checked against the fees destination of the sell orders currency in the instruction
+    #[account(mut)]
+    comptoir_dest_account: UncheckedAccount<'info>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Account<'info, Collection>,
+
+    /// CHECK: This is not dangerous because check it all the time using the verify_metadata_and_derivation func
+    metadata: UncheckedAccount<'info>,
+
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    "vault".as_bytes(),
+    comptoir.key().as_ref(),
+    buyer_nft_token_account.mint.as_ref()
+    ],
+    bump,
+    )]
+    vault: Box<Account<'info, TokenAccount>>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CreateAuction<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+    #[account(mut)]
+    seller_nft_token_account: Box<Account<'info, TokenAccount>>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+
+    #[account(constraint = mint.key() == seller_nft_token_account.mint)]
+    mint: Account<'info, Mint>,
+    /// CHECK: This is not dangerous because check it all the time using the verify_metadata_and_derivation func
+    metadata: UncheckedAccount<'info>,
+
+    #[account(
+    init_if_needed,
+    token::mint = mint,
+    token::authority = vault,
+    seeds = [
+    PREFIX.as_bytes(),
+    "vault".as_bytes(),
+    comptoir.key().as_ref(),
+    seller_nft_token_account.mint.as_ref(),
+    ],
+    bump,
+    payer = payer,
+    )]
+    vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+    init,
+    seeds = [
+    PREFIX.as_bytes(),
+    seller_nft_token_account.key().as_ref(),
+    AUCTION.as_bytes(),
+    ],
+    bump,
+    payer = payer,
+    space = 344,
+    )]
+    auction: Box<Account<'info, Auction>>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceBid<'info> {
+    #[account(mut)]
+    bidder: Signer<'info>,
+    #[account(mut)]
+    bidder_paying_account: Box<Account<'info, TokenAccount>>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    auction.payment_mint.as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, has_one = comptoir)]
+    auction: Box<Account<'info, Auction>>,
+
+    #[account(
+    init_if_needed,
+    seeds = [
+    PREFIX.as_bytes(),
+    auction.key().as_ref(),
+    bidder.key().as_ref(),
+    BID.as_bytes(),
+    ],
+    bump,
+    payer = bidder,
+    space = 145,
+    )]
+    bid: Box<Account<'info, Bid>>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimBidRefund<'info> {
+    #[account(mut)]
+    bidder: Signer<'info>,
+    #[account(mut)]
+    bidder_refund_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    bid.comptoir.as_ref(),
+    bid.payment_mint.as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+    mut,
+    close = bidder,
+    has_one = bidder,
+    constraint = bid.outbid @ ErrorCode::ErrBidNotOutbid,
+    )]
+    bid: Box<Account<'info, Bid>>,
+
+    token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAuction<'info> {
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(mut)]
+    seller_nft_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+    mut,
+    close = authority,
+    has_one = authority,
+    has_one = seller_nft_token_account,
+    )]
+    auction: Box<Account<'info, Auction>>,
+
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    "vault".as_bytes(),
+    auction.comptoir.as_ref(),
+    auction.mint.as_ref(),
+    ],
+    bump,
+    )]
+    vault: Box<Account<'info, TokenAccount>>,
+
+    token_program: Program<'info, Token>,
+}
+
+// When the auction received no bid, winner, winner_nft_account and winning_bid are not used and can be any account.
+// Remaining accounts are the creators, then the Currency account of the auction when it is not in the comptoir mint
+#[derive(Accounts)]
+pub struct SettleAuction<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+    #[account(mut)]
+    authority: SystemAccount<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+
+    /// CHECK: Checked against the fees destination of the auction currency in the instruction
+    #[account(mut)]
+    comptoir_dest_account: UncheckedAccount<'info>,
+
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    auction.payment_mint.as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    destination: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    seller_nft_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(constraint = mint.key() == auction.mint)]
+    mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    winner: SystemAccount<'info>,
+    /// CHECK: Checked to be the winner associated token account, created when missing
+    #[account(mut)]
+    winner_nft_account: UncheckedAccount<'info>,
+    /// CHECK: Checked against the auction highest bid account in the instruction
+    #[account(mut)]
+    winning_bid: UncheckedAccount<'info>,
+
+    /// CHECK: This is not dangerous because check it all the time using the verify_metadata_and_derivation func
+    metadata: UncheckedAccount<'info>,
+
+    #[account(
+    mut,
+    close = authority,
+    has_one = authority,
+    has_one = comptoir,
+    has_one = collection,
+    has_one = destination,
+    has_one = seller_nft_token_account,
+    )]
+    auction: Box<Account<'info, Auction>>,
+
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    "vault".as_bytes(),
+    auction.comptoir.as_ref(),
+    auction.mint.as_ref(),
+    ],
+    bump,
+    )]
+    vault: Box<Account<'info, TokenAccount>>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    rent: Sysvar<'info, Rent>,
+}
+
+// The leaf owner of a listed compressed item is the vault PDA, it holds no data
+#[derive(Accounts)]
+#[instruction(asset_id: Pubkey)]
+pub struct CreateCompressedSellOrder<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+
+    /// CHECK: Only used as the new leaf owner
+    #[account(
+    seeds = [
+    PREFIX.as_bytes(),
+    "vault".as_bytes(),
+    comptoir.key().as_ref(),
+    asset_id.as_ref(),
+    ],
+    bump,
+    )]
+    vault: UncheckedAccount<'info>,
+
+    #[account(
+    init,
+    seeds = [
+    PREFIX.as_bytes(),
+    asset_id.as_ref(),
+    COMPRESSED.as_bytes(),
+    ],
+    bump,
+    payer = payer,
+    space = 325,
+    )]
+    sell_order: Box<Account<'info, CompressedSellOrder>>,
+
+    /// CHECK: Checked by bubblegum
+    tree_authority: UncheckedAccount<'info>,
+    /// CHECK: Checked by bubblegum
+    leaf_delegate: UncheckedAccount<'info>,
+    /// CHECK: Checked by bubblegum
+    #[account(mut)]
+    merkle_tree: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(SPL_NOOP_PROGRAM).unwrap())]
+    log_wrapper: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(SPL_ACCOUNT_COMPRESSION_PROGRAM).unwrap())]
+    compression_program: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(BUBBLEGUM_PROGRAM).unwrap())]
+    bubblegum_program: UncheckedAccount<'info>,
+
+    system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveCompressedSellOrder<'info> {
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(mut, close = authority, has_one = authority, has_one = merkle_tree)]
+    sell_order: Box<Account<'info, CompressedSellOrder>>,
+
+    /// CHECK: Only used as the leaf owner
+    #[account(
+    seeds = [
+    PREFIX.as_bytes(),
+    "vault".as_bytes(),
+    sell_order.comptoir.as_ref(),
+    sell_order.asset_id.as_ref(),
+    ],
+    bump,
+    )]
+    vault: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by bubblegum
+    tree_authority: UncheckedAccount<'info>,
+    /// CHECK: Checked by bubblegum
+    #[account(mut)]
+    merkle_tree: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(SPL_NOOP_PROGRAM).unwrap())]
+    log_wrapper: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(SPL_ACCOUNT_COMPRESSION_PROGRAM).unwrap())]
+    compression_program: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(BUBBLEGUM_PROGRAM).unwrap())]
+    bubblegum_program: UncheckedAccount<'info>,
+
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BuyCompressed<'info> {
+    #[account(mut)]
+    buyer: Signer<'info>,
+    /// CHECK: Token account checked by the token program, or the paying wallet itself for native SOL comptoirs
+    #[account(mut)]
+    buyer_paying_token_account: UncheckedAccount<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    /// CHECK: Token account checked by the token program, or a wallet for native SOL comptoirs
+    #[account(mut)]
+    comptoir_dest_account: UncheckedAccount<'info>,
+    collection: Box<Account<'info, Collection>>,
+
+    #[account(mut)]
+    authority: SystemAccount<'info>,
+    /// CHECK: Token account checked by the token program, or a wallet for native SOL comptoirs
+    #[account(mut, constraint = seller_funds_dest_account.key() == sell_order.destination)]
+    seller_funds_dest_account: UncheckedAccount<'info>,
+    #[account(
+    mut,
+    close = authority,
+    has_one = authority,
+    has_one = comptoir,
+    has_one = collection,
+    has_one = merkle_tree,
+    )]
+    sell_order: Box<Account<'info, CompressedSellOrder>>,
+
+    /// CHECK: Only used as the leaf owner
+    #[account(
+    seeds = [
+    PREFIX.as_bytes(),
+    "vault".as_bytes(),
+    sell_order.comptoir.as_ref(),
+    sell_order.asset_id.as_ref(),
+    ],
+    bump,
+    )]
+    vault: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by bubblegum
+    tree_authority: UncheckedAccount<'info>,
+    /// CHECK: Checked by bubblegum
+    #[account(mut)]
+    merkle_tree: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(SPL_NOOP_PROGRAM).unwrap())]
+    log_wrapper: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(SPL_ACCOUNT_COMPRESSION_PROGRAM).unwrap())]
+    compression_program: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(BUBBLEGUM_PROGRAM).unwrap())]
+    bubblegum_program: UncheckedAccount<'info>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(asset_id: Pubkey, price_proposition: u64)]
+pub struct CreateCompressedBuyOffer<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    escrow.mint.as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Token account checked by the token program, or the paying wallet itself for native SOL comptoirs
+    #[account(mut)]
+    buyer_paying_account: UncheckedAccount<'info>,
+
+    #[account(
+    init,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    payer.key.as_ref(),
+    asset_id.as_ref(),
+    price_proposition.to_string().as_bytes(),
+    ESCROW.as_bytes(),
+    ],
+    bump,
+    payer = payer,
+    space = BUY_OFFER_LEN,
+    )]
+    buy_offer: Account<'info, BuyOffer>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteCompressedOffer<'info> {
+    seller: Signer<'info>,
+
+    #[account(mut)]
+    buyer: SystemAccount<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+
+    /// CHECK: Token account checked by the token program, or a wallet for native SOL comptoirs
+    #[account(mut)]
+    comptoir_dest_account: UncheckedAccount<'info>,
+
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    buy_offer.payment_mint.as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Token account checked by the token program, or a wallet for native SOL comptoirs
+    #[account(mut)]
+    seller_funds_dest_account: UncheckedAccount<'info>,
+
+    #[account(
+    mut,
+    close = buyer,
+    constraint = buy_offer.authority == buyer.key(),
+    has_one = comptoir,
+    has_one = collection,
+    )]
+    buy_offer: Box<Account<'info, BuyOffer>>,
+
+    /// CHECK: Checked by bubblegum
+    tree_authority: UncheckedAccount<'info>,
+    /// CHECK: Checked by bubblegum
+    leaf_delegate: UncheckedAccount<'info>,
+    /// CHECK: Checked by bubblegum
+    #[account(mut)]
+    merkle_tree: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(SPL_NOOP_PROGRAM).unwrap())]
+    log_wrapper: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(SPL_ACCOUNT_COMPRESSION_PROGRAM).unwrap())]
+    compression_program: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(BUBBLEGUM_PROGRAM).unwrap())]
+    bubblegum_program: UncheckedAccount<'info>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(price: u64)]
+pub struct CreatePnftSellOrder<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+    #[account(mut, constraint = seller_nft_token_account.owner == payer.key())]
+    seller_nft_token_account: Box<Account<'info, TokenAccount>>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+
+    #[account(constraint = mint.key() == seller_nft_token_account.mint)]
+    mint: Box<Account<'info, Mint>>,
+    /// CHECK: This is not dangerous because check it all the time using the verify_programmable_metadata_and_derivation func
+    #[account(mut)]
+    metadata: UncheckedAccount<'info>,
+    /// CHECK: Checked by token metadata
+    edition: UncheckedAccount<'info>,
+    /// CHECK: Checked by token metadata
+    #[account(mut)]
+    seller_token_record: UncheckedAccount<'info>,
+
+    /// CHECK: Only signs for the vault
+    #[account(
+    seeds = [
+    PREFIX.as_bytes(),
+    "vault".as_bytes(),
+    comptoir.key().as_ref(),
+    mint.key().as_ref(),
+    ],
+    bump,
+    )]
+    vault_authority: UncheckedAccount<'info>,
+    /// CHECK: Associated token account of the vault authority, created by token metadata
+    #[account(mut)]
+    vault: UncheckedAccount<'info>,
+    /// CHECK: Checked by token metadata
+    #[account(mut)]
+    vault_token_record: UncheckedAccount<'info>,
+
+    #[account(
+    init,
+    seeds = [
+    PREFIX.as_bytes(),
+    seller_nft_token_account.key().as_ref(),
+    price.to_string().as_bytes(),
+    ],
+    bump,
+    payer = payer,
+    space = SELL_ORDER_LEN,
+    )]
+    sell_order: Box<Account<'info, SellOrder>>,
+
+    /// CHECK: Address checked
+    #[account(address = mpl_token_metadata::id())]
+    token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: Checked by token metadata
+    authorization_rules_program: UncheckedAccount<'info>,
+    /// CHECK: Checked against the rule set of the item in the instruction
+    authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = sysvar::instructions::ID)]
+    sysvar_instructions: UncheckedAccount<'info>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct RemovePnftSellOrder<'info> {
+    #[account(mut)]
+    authority: Signer<'info>,
+    /// CHECK: Associated token account of the authority, created by token metadata
+    #[account(mut)]
+    seller_nft_token_account: UncheckedAccount<'info>,
+    #[account(
+    mut,
+    has_one = authority,
+    has_one = mint,
+    close = authority,
+    )]
+    sell_order: Box<Account<'info, SellOrder>>,
+
+    mint: Box<Account<'info, Mint>>,
+    /// CHECK: This is not dangerous because check it all the time using the load_programmable_metadata_and_derivation func
+    #[account(mut)]
+    metadata: UncheckedAccount<'info>,
+    /// CHECK: Checked by token metadata
+    edition: UncheckedAccount<'info>,
+    /// CHECK: Checked by token metadata
+    #[account(mut)]
+    seller_token_record: UncheckedAccount<'info>,
+
+    /// CHECK: Only signs for the vault
+    #[account(
+    seeds = [
+    PREFIX.as_bytes(),
+    "vault".as_bytes(),
+    sell_order.comptoir.as_ref(),
+    mint.key().as_ref(),
+    ],
+    bump,
+    )]
+    vault_authority: UncheckedAccount<'info>,
+    /// CHECK: Checked by token metadata against the vault authority
+    #[account(mut)]
+    vault: UncheckedAccount<'info>,
+    /// CHECK: Checked by token metadata
+    #[account(mut)]
+    vault_token_record: UncheckedAccount<'info>,
+
+    /// CHECK: Address checked
+    #[account(address = mpl_token_metadata::id())]
+    token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: Checked by token metadata
+    authorization_rules_program: UncheckedAccount<'info>,
+    /// CHECK: Checked against the rule set of the item in the instruction
+    authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = sysvar::instructions::ID)]
+    sysvar_instructions: UncheckedAccount<'info>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct BuyPnft<'info> {
+    #[account(mut)]
+    buyer: Signer<'info>,
+    /// CHECK: Associated token account of the buyer, created by token metadata
+    #[account(mut)]
+    buyer_nft_token_account: UncheckedAccount<'info>,
+    /// CHECK: Token account checked by the token program, or the paying wallet itself for native SOL comptoirs
+    #[account(mut)]
+    buyer_paying_token_account: UncheckedAccount<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    /// CHECK: Checked against the fees destination of the sell order currency in the instruction
+    #[account(mut)]
+    comptoir_dest_account: UncheckedAccount<'info>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+
+    #[account(mut)]
+    authority: SystemAccount<'info>,
+    /// CHECK: Token account checked by the token program, or a wallet for native SOL comptoirs
+    #[account(mut, constraint = seller_funds_dest_account.key() == sell_order.destination @ ErrorCode::ErrWrongSellerDestination)]
+    seller_funds_dest_account: UncheckedAccount<'info>,
+    #[account(
+    mut,
+    has_one = comptoir,
+    has_one = authority,
+    has_one = mint,
+    close = authority,
+    )]
+    sell_order: Box<Account<'info, SellOrder>>,
+
+    mint: Box<Account<'info, Mint>>,
+    /// CHECK: This is not dangerous because check it all the time using the verify_programmable_metadata_and_derivation func
+    #[account(mut)]
+    metadata: UncheckedAccount<'info>,
+    /// CHECK: Checked by token metadata
+    edition: UncheckedAccount<'info>,
+    /// CHECK: Checked by token metadata
+    #[account(mut)]
+    buyer_token_record: UncheckedAccount<'info>,
+
+    /// CHECK: Only signs for the vault
+    #[account(
+    seeds = [
+    PREFIX.as_bytes(),
+    "vault".as_bytes(),
+    comptoir.key().as_ref(),
+    mint.key().as_ref(),
+    ],
+    bump,
+    )]
+    vault_authority: UncheckedAccount<'info>,
+    /// CHECK: Checked by token metadata against the vault authority
+    #[account(mut)]
+    vault: UncheckedAccount<'info>,
+    /// CHECK: Checked by token metadata
+    #[account(mut)]
+    vault_token_record: UncheckedAccount<'info>,
+
+    /// CHECK: Address checked
+    #[account(address = mpl_token_metadata::id())]
+    token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: Checked by token metadata
+    authorization_rules_program: UncheckedAccount<'info>,
+    /// CHECK: Checked against the rule set of the item in the instruction
+    authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = sysvar::instructions::ID)]
+    sysvar_instructions: UncheckedAccount<'info>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct ExecutePnftOffer<'info> {
+    #[account(mut)]
+    seller: Signer<'info>,
+
+    #[account(mut)]
+    buyer: SystemAccount<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+
+    /// CHECK: Checked against the fees destination of the offer currency in the instruction
+    #[account(mut)]
+    comptoir_dest_account: UncheckedAccount<'info>,
+
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    buy_offer.payment_mint.as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Token account checked by the token program, or a wallet for native SOL comptoirs
+    #[account(mut)]
+    seller_funds_dest_account: UncheckedAccount<'info>,
+
+    /// CHECK: Associated token account of the buyer, created by token metadata
+    #[account(mut)]
+    destination: UncheckedAccount<'info>,
+    /// CHECK: Checked by token metadata against the seller
+    #[account(mut)]
+    seller_nft_account: UncheckedAccount<'info>,
+
+    mint: Box<Account<'info, Mint>>,
+    /// CHECK: This is not dangerous because check it all the time using the verify_programmable_metadata_and_derivation func
+    #[account(mut)]
+    metadata: UncheckedAccount<'info>,
+    /// CHECK: Checked by token metadata
+    edition: UncheckedAccount<'info>,
+    /// CHECK: Checked by token metadata
+    #[account(mut)]
+    seller_token_record: UncheckedAccount<'info>,
+    /// CHECK: Checked by token metadata
+    #[account(mut)]
+    destination_token_record: UncheckedAccount<'info>,
+
+    #[account(
+    mut,
+    constraint = buy_offer.authority == buyer.key(),
+    has_one = comptoir,
+    has_one = collection,
+    )]
+    buy_offer: Box<Account<'info, BuyOffer>>,
+
+    /// CHECK: Address checked
+    #[account(address = mpl_token_metadata::id())]
+    token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: Checked by token metadata
+    authorization_rules_program: UncheckedAccount<'info>,
+    /// CHECK: Checked against the rule set of the item in the instruction
+    authorization_rules: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = sysvar::instructions::ID)]
+    sysvar_instructions: UncheckedAccount<'info>,
+
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct AddToken22Currency<'info> {
+    #[account(mut)]
+    authority: Signer<'info>,
+    #[account(has_one = authority)]
+    comptoir: Account<'info, Comptoir>,
+
+    /// CHECK: Loaded with the load_mint_of func
+    mint: UncheckedAccount<'info>,
+
+    #[account(
+    init,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    mint.key().as_ref(),
+    CURRENCY.as_bytes(),
+    ],
+    bump,
+    payer = authority,
+    space = 136,
+    )]
+    currency: Account<'info, Currency>,
+
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(TOKEN_2022_PROGRAM).unwrap())]
+    token_program: UncheckedAccount<'info>,
+    system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(price: u64)]
+pub struct CreateToken22SellOrder<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+    /// CHECK: Checked by the token program
+    #[account(mut)]
+    seller_nft_token_account: UncheckedAccount<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+
+    /// CHECK: Loaded with the load_mint_of func
+    mint: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because check it all the time using the verify_metadata_and_derivation func
+    metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Only signs for the vault
+    #[account(
+    seeds = [
+    PREFIX.as_bytes(),
+    "vault".as_bytes(),
+    comptoir.key().as_ref(),
+    mint.key().as_ref(),
+    ],
+    bump,
+    )]
+    vault_authority: UncheckedAccount<'info>,
+    /// CHECK: Associated token account of the vault authority, checked by the associated token program
+    #[account(mut)]
+    vault: UncheckedAccount<'info>,
+
+    #[account(
+    init,
+    seeds = [
+    PREFIX.as_bytes(),
+    seller_nft_token_account.key().as_ref(),
+    price.to_string().as_bytes(),
+    ],
+    bump,
+    payer = payer,
+    space = SELL_ORDER_LEN,
+    )]
+    sell_order: Box<Account<'info, SellOrder>>,
+
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(TOKEN_2022_PROGRAM).unwrap())]
+    token_program: UncheckedAccount<'info>,
+    /// CHECK: Checked in the create_associated_account func
+    associated_token_program: UncheckedAccount<'info>,
+    system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveToken22SellOrder<'info> {
+    #[account(mut)]
+    authority: Signer<'info>,
+    /// CHECK: Checked by the token program
+    #[account(mut)]
+    seller_nft_token_account: UncheckedAccount<'info>,
+    #[account(
+    mut,
+    has_one = authority,
+    has_one = mint,
+    )]
+    sell_order: Box<Account<'info, SellOrder>>,
+
+    /// CHECK: Loaded with the load_mint_of func
+    mint: UncheckedAccount<'info>,
+    /// CHECK: Only signs for the vault
+    #[account(
+    seeds = [
+    PREFIX.as_bytes(),
+    "vault".as_bytes(),
+    sell_order.comptoir.as_ref(),
+    mint.key().as_ref(),
+    ],
+    bump,
+    )]
+    vault_authority: UncheckedAccount<'info>,
+    /// CHECK: Checked by the token program against the vault authority
+    #[account(mut)]
+    vault: UncheckedAccount<'info>,
+
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(TOKEN_2022_PROGRAM).unwrap())]
+    token_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BuyToken22<'info> {
+    #[account(mut)]
+    buyer: Signer<'info>,
+    /// CHECK: Checked by the token program
+    #[account(mut)]
+    buyer_nft_token_account: UncheckedAccount<'info>,
+    /// CHECK: Token account checked by the token program, or the paying wallet itself for native SOL comptoirs
+    #[account(mut)]
+    buyer_paying_token_account: UncheckedAccount<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    /// CHECK: Checked against the fees destination of the sell order currency in the instruction
+    #[account(mut)]
+    comptoir_dest_account: UncheckedAccount<'info>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+
+    /// CHECK: Token account checked by the token program, or a wallet for native SOL comptoirs
+    #[account(mut, constraint = seller_funds_dest_account.key() == sell_order.destination @ ErrorCode::ErrWrongSellerDestination)]
+    seller_funds_dest_account: UncheckedAccount<'info>,
+    #[account(
+    mut,
+    has_one = comptoir,
+    has_one = mint,
+    )]
+    sell_order: Box<Account<'info, SellOrder>>,
+
+    /// CHECK: Loaded with the load_mint_of func
+    mint: UncheckedAccount<'info>,
+    /// CHECK: This is not dangerous because check it all the time using the verify_metadata_and_derivation func
+    metadata: UncheckedAccount<'info>,
+    /// CHECK: Only signs for the vault
+    #[account(
+    seeds = [
+    PREFIX.as_bytes(),
+    "vault".as_bytes(),
+    comptoir.key().as_ref(),
+    mint.key().as_ref(),
+    ],
+    bump,
+    )]
+    vault_authority: UncheckedAccount<'info>,
+    /// CHECK: Checked by the token program against the vault authority
+    #[account(mut)]
+    vault: UncheckedAccount<'info>,
+
+    /// CHECK: Loaded with the load_mint_of func
+    #[account(constraint = payment_mint.key() == sell_order.payment_mint @ ErrorCode::ErrWrongCurrency)]
+    payment_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(TOKEN_2022_PROGRAM).unwrap())]
+    token_program: UncheckedAccount<'info>,
+    /// CHECK: Owner of the currency mint, checked in the load_mint_of func
+    payment_token_program: UncheckedAccount<'info>,
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(price_proposition: u64)]
+pub struct CreateToken22BuyOffer<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    nft_mint: Account<'info, Mint>,
+    /// CHECK: This is not dangerous because check it all the time using the verify_metadata_and_derivation func
+    metadata: UncheckedAccount<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+    /// CHECK: Token-2022 escrow of the currency, checked by the token program
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    payment_mint.key().as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: UncheckedAccount<'info>,
 
+    /// CHECK: Checked by the token program
     #[account(mut)]
-    buyer_paying_account: Account<'info, TokenAccount>,
+    buyer_paying_account: UncheckedAccount<'info>,
+    #[account(
+    init_if_needed,
+    payer = payer,
+    associated_token::mint = nft_mint,
+    associated_token::authority = payer,
+    )]
+    buyer_nft_account: Box<Account<'info, TokenAccount>>,
 
-    comptoir: Account<'info, Comptoir>,
+    #[account(
+    init,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    payer.key.as_ref(),
+    nft_mint.key().as_ref(),
+    price_proposition.to_string().as_bytes(),
+    ESCROW.as_bytes(),
+    ],
+    bump,
+    payer = payer,
+    space = BUY_OFFER_LEN,
+    )]
+    buy_offer: Box<Account<'info, BuyOffer>>,
+
+    /// CHECK: Loaded with the load_mint_of func, checked against the accepted currency in the instruction
+    payment_mint: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(TOKEN_2022_PROGRAM).unwrap())]
+    payment_token_program: UncheckedAccount<'info>,
+    system_program: Program<'info, System>,
+    token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(price_proposition: u64)]
+pub struct CreateToken22CollectionBuyOffer<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
 
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+    /// CHECK: Token-2022 escrow of the currency, checked by the token program
     #[account(
     mut,
     seeds = [
     PREFIX.as_bytes(),
     comptoir.key().as_ref(),
-    comptoir.mint.as_ref(),
+    payment_mint.key().as_ref(),
     ESCROW.as_bytes()
     ],
     bump,
     )]
-    escrow: Account<'info, TokenAccount>,
+    escrow: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by the token program
+    #[account(mut)]
+    buyer_paying_account: UncheckedAccount<'info>,
+
+    #[account(
+    init,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    payer.key.as_ref(),
+    collection.key().as_ref(),
+    price_proposition.to_string().as_bytes(),
+    ESCROW.as_bytes(),
+    ],
+    bump,
+    payer = payer,
+    space = BUY_OFFER_LEN,
+    )]
+    buy_offer: Box<Account<'info, BuyOffer>>,
+
+    /// CHECK: Loaded with the load_mint_of func, checked against the accepted currency in the instruction
+    payment_mint: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(TOKEN_2022_PROGRAM).unwrap())]
+    payment_token_program: UncheckedAccount<'info>,
+    system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(price_proposition: u64, quantity: u64, trait_hash: [u8; 32])]
+pub struct CreateToken22TraitBuyOffer<'info> {
+    #[account(mut)]
+    payer: Signer<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+    /// CHECK: Token-2022 escrow of the currency, checked by the token program
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    payment_mint.key().as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: UncheckedAccount<'info>,
+
+    /// CHECK: Checked by the token program
+    #[account(mut)]
+    buyer_paying_account: UncheckedAccount<'info>,
+
+    #[account(
+    init,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    payer.key.as_ref(),
+    collection.key().as_ref(),
+    trait_hash.as_ref(),
+    price_proposition.to_string().as_bytes(),
+    ESCROW.as_bytes(),
+    ],
+    bump,
+    payer = payer,
+    space = BUY_OFFER_LEN,
+    )]
+    buy_offer: Box<Account<'info, BuyOffer>>,
+
+    /// CHECK: Loaded with the load_mint_of func, checked against the accepted currency in the instruction
+    payment_mint: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(TOKEN_2022_PROGRAM).unwrap())]
+    payment_token_program: UncheckedAccount<'info>,
+    system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CloseExpiredToken22BuyOffer<'info> {
+    #[account(mut)]
+    buyer: SystemAccount<'info>,
+
+    /// CHECK: Checked to be the buyer associated account in the instruction
+    #[account(mut)]
+    buyer_paying_account: UncheckedAccount<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+
+    /// CHECK: Token-2022 escrow of the offer currency, checked by the token program
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    buy_offer.payment_mint.as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: UncheckedAccount<'info>,
 
     #[account(
     mut,
@@ -515,354 +6265,824 @@ pub struct RemoveBuyOffer<'info> {
     has_one = comptoir,
     constraint = buy_offer.authority == buyer.key(),
     )]
-    buy_offer: Account<'info, BuyOffer>,
+    buy_offer: Box<Account<'info, BuyOffer>>,
+
+    /// CHECK: Loaded with the load_mint_of func
+    #[account(constraint = payment_mint.key() == buy_offer.payment_mint @ ErrorCode::ErrWrongCurrency)]
+    payment_mint: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(TOKEN_2022_PROGRAM).unwrap())]
+    payment_token_program: UncheckedAccount<'info>,
+}
 
-    system_program: Program<'info, System>,
-    token_program: Program<'info, Token>,
-    rent: Sysvar<'info, Rent>,
+#[derive(Accounts)]
+pub struct RemoveToken22BuyOffer<'info> {
+    #[account(mut)]
+    buyer: Signer<'info>,
+
+    /// CHECK: Checked by the token program
+    #[account(mut)]
+    buyer_paying_account: UncheckedAccount<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+
+    /// CHECK: Token-2022 escrow of the offer currency, checked by the token program
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    buy_offer.payment_mint.as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: UncheckedAccount<'info>,
+
+    #[account(
+    mut,
+    close = buyer,
+    has_one = comptoir,
+    constraint = buy_offer.authority == buyer.key(),
+    )]
+    buy_offer: Box<Account<'info, BuyOffer>>,
+
+    /// CHECK: Loaded with the load_mint_of func
+    #[account(constraint = payment_mint.key() == buy_offer.payment_mint @ ErrorCode::ErrWrongCurrency)]
+    payment_mint: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(TOKEN_2022_PROGRAM).unwrap())]
+    payment_token_program: UncheckedAccount<'info>,
 }
 
 #[derive(Accounts)]
-pub struct ExecuteOffer<'info> {
+pub struct ExecuteToken22Offer<'info> {
     seller: Signer<'info>,
 
     #[account(mut)]
     buyer: SystemAccount<'info>,
 
     comptoir: Box<Account<'info, Comptoir>>,
-    #[account(mut, constraint = collection.comptoir_key == comptoir.key())]
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
     collection: Box<Account<'info, Collection>>,
 
-    #[account(mut, constraint = comptoir_dest_account.key() == comptoir.fees_destination)]
-    comptoir_dest_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Checked against the fees destination of the offer currency in the instruction
+    #[account(mut)]
+    comptoir_dest_account: UncheckedAccount<'info>,
 
+    /// CHECK: Token-2022 escrow of the offer currency, checked by the token program
     #[account(
     mut,
     seeds = [
     PREFIX.as_bytes(),
     comptoir.key().as_ref(),
-    comptoir.mint.as_ref(),
+    buy_offer.payment_mint.as_ref(),
     ESCROW.as_bytes()
     ],
     bump,
     )]
-    escrow: Box<Account<'info, TokenAccount>>,
+    escrow: UncheckedAccount<'info>,
 
+    /// CHECK: Checked by the token program
     #[account(mut)]
-    seller_funds_dest_account: Box<Account<'info, TokenAccount>>,
+    seller_funds_dest_account: UncheckedAccount<'info>,
 
     #[account(mut)]
-    destination: Account<'info, TokenAccount>,
+    destination: Box<Account<'info, TokenAccount>>,
     #[account(mut)]
-    seller_nft_account: Account<'info, TokenAccount>,
-
+    seller_nft_account: Box<Account<'info, TokenAccount>>,
 
     /// CHECK: This is not dangerous because check it all the time using the verify_metadata_and_derivation func
     metadata: UncheckedAccount<'info>,
 
     #[account(
     mut,
-    close = buyer,
     constraint = buy_offer.authority == buyer.key(),
-    constraint = seller_nft_account.mint.key() == buy_offer.mint,
-    has_one = destination,
     has_one = comptoir,
+    has_one = collection,
     )]
-    buy_offer: Account<'info, BuyOffer>,
-
-    system_program: Program<'info, System>,
+    buy_offer: Box<Account<'info, BuyOffer>>,
+
+    /// CHECK: Loaded with the load_mint_of func
+    #[account(constraint = payment_mint.key() == buy_offer.payment_mint @ ErrorCode::ErrWrongCurrency)]
+    payment_mint: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(TOKEN_2022_PROGRAM).unwrap())]
+    payment_token_program: UncheckedAccount<'info>,
     token_program: Program<'info, Token>,
-    rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-#[instruction(comptoir_mint: Pubkey)]
-pub struct CreateComptoir<'info> {
+pub struct PlaceToken22Bid<'info> {
     #[account(mut)]
-    payer: Signer<'info>,
+    bidder: Signer<'info>,
+    /// CHECK: Checked by the token program
+    #[account(mut)]
+    bidder_paying_account: UncheckedAccount<'info>,
+
+    comptoir: Box<Account<'info, Comptoir>>,
+    /// CHECK: Token-2022 escrow of the auction currency, checked by the token program
     #[account(
-    init,
+    mut,
     seeds = [
-        PREFIX.as_bytes(),
-        payer.key.as_ref()
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    auction.payment_mint.as_ref(),
+    ESCROW.as_bytes()
     ],
     bump,
-    payer = payer,
-    space = 112,
     )]
-    comptoir: Account<'info, Comptoir>,
+    escrow: UncheckedAccount<'info>,
 
-    mint: Account<'info, Mint>,
+    #[account(mut, has_one = comptoir)]
+    auction: Box<Account<'info, Auction>>,
 
     #[account(
-    init,
-    token::mint = mint,
-    token::authority = escrow,
+    init_if_needed,
     seeds = [
     PREFIX.as_bytes(),
-    comptoir.key().as_ref(),
-    comptoir_mint.as_ref(),
-    ESCROW.as_bytes()
+    auction.key().as_ref(),
+    bidder.key().as_ref(),
+    BID.as_bytes(),
     ],
     bump,
-    payer = payer,
+    payer = bidder,
+    space = 145,
     )]
-    escrow: Account<'info, TokenAccount>,
-
-
+    bid: Box<Account<'info, Bid>>,
+
+    /// CHECK: Loaded with the load_mint_of func
+    #[account(constraint = payment_mint.key() == auction.payment_mint @ ErrorCode::ErrWrongCurrency)]
+    payment_mint: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(TOKEN_2022_PROGRAM).unwrap())]
+    payment_token_program: UncheckedAccount<'info>,
     system_program: Program<'info, System>,
-    token_program: Program<'info, Token>,
     rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateComptoir<'info> {
-    authority: Signer<'info>,
-    #[account(mut, has_one = authority)]
-    comptoir: Account<'info, Comptoir>,
+pub struct ClaimToken22BidRefund<'info> {
+    #[account(mut)]
+    bidder: Signer<'info>,
+    /// CHECK: Checked by the token program
+    #[account(mut)]
+    bidder_refund_account: UncheckedAccount<'info>,
+
+    /// CHECK: Token-2022 escrow of the bid currency, checked by the token program
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    bid.comptoir.as_ref(),
+    bid.payment_mint.as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: UncheckedAccount<'info>,
+
+    #[account(
+    mut,
+    close = bidder,
+    has_one = bidder,
+    constraint = bid.outbid @ ErrorCode::ErrBidNotOutbid,
+    )]
+    bid: Box<Account<'info, Bid>>,
+
+    /// CHECK: Loaded with the load_mint_of func
+    #[account(constraint = payment_mint.key() == bid.payment_mint @ ErrorCode::ErrWrongCurrency)]
+    payment_mint: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(TOKEN_2022_PROGRAM).unwrap())]
+    payment_token_program: UncheckedAccount<'info>,
 }
 
+// When the auction received no bid, winner, winner_nft_account and winning_bid are not used and can be any account
 #[derive(Accounts)]
-#[instruction(new_comptoir_mint: Pubkey)]
-pub struct UpdateComptoirMint<'info> {
+pub struct SettleToken22Auction<'info> {
     #[account(mut)]
-    authority: Signer<'info>,
-    #[account(mut, has_one = authority)]
-    comptoir: Account<'info, Comptoir>,
+    payer: Signer<'info>,
+    #[account(mut)]
+    authority: SystemAccount<'info>,
 
-    #[account(constraint = new_comptoir_mint == mint.key())]
-    mint: Account<'info, Mint>,
+    comptoir: Box<Account<'info, Comptoir>>,
+    #[account(constraint = collection.comptoir_key == comptoir.key())]
+    collection: Box<Account<'info, Collection>>,
+
+    /// CHECK: Checked against the fees destination of the auction currency in the instruction
+    #[account(mut)]
+    comptoir_dest_account: UncheckedAccount<'info>,
+
+    /// CHECK: Token-2022 escrow of the auction currency, checked by the token program
+    #[account(
+    mut,
+    seeds = [
+    PREFIX.as_bytes(),
+    comptoir.key().as_ref(),
+    auction.payment_mint.as_ref(),
+    ESCROW.as_bytes()
+    ],
+    bump,
+    )]
+    escrow: UncheckedAccount<'info>,
+
+    /// CHECK: Checked against the auction destination, then by the token program
+    #[account(mut)]
+    destination: UncheckedAccount<'info>,
+    #[account(mut)]
+    seller_nft_token_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(constraint = mint.key() == auction.mint)]
+    mint: Box<Account<'info, Mint>>,
+    #[account(mut)]
+    winner: SystemAccount<'info>,
+    /// CHECK: Checked to be the winner associated token account, created when missing
+    #[account(mut)]
+    winner_nft_account: UncheckedAccount<'info>,
+    /// CHECK: Checked against the auction highest bid account in the instruction
+    #[account(mut)]
+    winning_bid: UncheckedAccount<'info>,
+
+    /// CHECK: This is not dangerous because check it all the time using the verify_metadata_and_derivation func
+    metadata: UncheckedAccount<'info>,
+
+    #[account(
+    mut,
+    close = authority,
+    has_one = authority,
+    has_one = comptoir,
+    has_one = collection,
+    has_one = destination,
+    has_one = seller_nft_token_account,
+    )]
+    auction: Box<Account<'info, Auction>>,
 
     #[account(
-    init_if_needed,
-    token::mint = mint,
-    token::authority = escrow,
+    mut,
     seeds = [
     PREFIX.as_bytes(),
-    comptoir.key().as_ref(),
-    new_comptoir_mint.as_ref(),
-    ESCROW.as_bytes()
+    "vault".as_bytes(),
+    auction.comptoir.as_ref(),
+    auction.mint.as_ref(),
     ],
     bump,
-    payer = authority,
     )]
-    escrow: Account<'info, TokenAccount>,
+    vault: Box<Account<'info, TokenAccount>>,
 
+    /// CHECK: Loaded with the load_mint_of func
+    #[account(constraint = payment_mint.key() == auction.payment_mint @ ErrorCode::ErrWrongCurrency)]
+    payment_mint: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(TOKEN_2022_PROGRAM).unwrap())]
+    payment_token_program: UncheckedAccount<'info>,
     system_program: Program<'info, System>,
     token_program: Program<'info, Token>,
+    associated_token_program: Program<'info, AssociatedToken>,
     rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-#[instruction(name: String)]
-pub struct CreateCollection<'info> {
+pub struct BuyToken22Bundle<'info> {
     #[account(mut)]
-    authority: Signer<'info>,
-    #[account(mut, has_one = authority)]
-    comptoir: Account<'info, Comptoir>,
-    #[account(
-    init,
-    seeds = [
-    PREFIX.as_bytes(),
-    name.as_bytes(),
-    comptoir.key().as_ref(),
-    ],
-    bump,
-    payer = authority,
-    space = 110,
-    )]
-    collection: Account<'info, Collection>,
+    buyer: Signer<'info>,
+    /// CHECK: Checked by the token program
+    #[account(mut)]
+    buyer_paying_account: UncheckedAccount<'info>,
 
-    system_program: Program<'info, System>,
-    rent: Sysvar<'info, Rent>,
-}
+    comptoir: Box<Account<'info, Comptoir>>,
+    /// CHECK: Checked against the fees destination of the bundle currency in the instruction
+    #[account(mut)]
+    comptoir_dest_account: UncheckedAccount<'info>,
 
-#[derive(Accounts)]
-pub struct UpdateCollection<'info> {
-    authority: Signer<'info>,
-    #[account(has_one = authority)]
-    comptoir: Account<'info, Comptoir>,
+    /// CHECK: Checked by the token program
+    #[account(mut, constraint = seller_funds_dest_account.key() == bundle_order.destination)]
+    seller_funds_dest_account: UncheckedAccount<'info>,
 
-    #[account(mut, constraint = collection.comptoir_key == comptoir.key())]
-    collection: Account<'info, Collection>,
+    #[account(mut)]
+    authority: SystemAccount<'info>,
+    #[account(
+    mut,
+    close = authority,
+    has_one = comptoir,
+    has_one = authority,
+    )]
+    bundle_order: Box<Account<'info, BundleOrder>>,
+
+    /// CHECK: Loaded with the load_mint_of func
+    #[account(constraint = payment_mint.key() == bundle_order.payment_mint @ ErrorCode::ErrWrongCurrency)]
+    payment_mint: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(TOKEN_2022_PROGRAM).unwrap())]
+    payment_token_program: UncheckedAccount<'info>,
+    token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-#[instruction(price: u64)]
-pub struct CreateSellOrder<'info> {
+#[instruction(swap_id: u64)]
+pub struct CreateToken22SwapOffer<'info> {
     #[account(mut)]
     payer: Signer<'info>,
+    /// CHECK: Checked by the token program
     #[account(mut)]
-    seller_nft_token_account: Box<Account<'info, TokenAccount>>,
+    payer_paying_account: UncheckedAccount<'info>,
 
     comptoir: Box<Account<'info, Comptoir>>,
-    #[account(constraint = collection.comptoir_key == comptoir.key())]
-    collection: Box<Account<'info, Collection>>,
-
-    #[account(constraint = mint.key() == seller_nft_token_account.mint)]
-    mint: Account<'info, Mint>,
-    /// CHECK: This is not dangerous because check it all the time using the verify_metadata_and_derivation func
-    metadata: UncheckedAccount<'info>,
-
+    /// CHECK: Token-2022 escrow of the currency, checked by the token program
     #[account(
-    init_if_needed,
-    token::mint = mint,
-    token::authority = vault,
+    mut,
     seeds = [
     PREFIX.as_bytes(),
-    "vault".as_bytes(),
-    seller_nft_token_account.mint.as_ref(),
+    comptoir.key().as_ref(),
+    payment_mint.key().as_ref(),
+    ESCROW.as_bytes()
     ],
     bump,
-    payer = payer,
     )]
-    vault: Account<'info, TokenAccount>,
+    escrow: UncheckedAccount<'info>,
 
     #[account(
     init,
     seeds = [
     PREFIX.as_bytes(),
-    seller_nft_token_account.key().as_ref(),
-    price.to_string().as_bytes(),
+    comptoir.key().as_ref(),
+    payer.key.as_ref(),
+    SWAP.as_bytes(),
+    swap_id.to_le_bytes().as_ref(),
     ],
     bump,
     payer = payer,
-    space = 152,
+    space = 389,
     )]
-    sell_order: Account<'info, SellOrder>,
+    swap_offer: Box<Account<'info, SwapOffer>>,
 
+    /// CHECK: Loaded with the load_mint_of func, checked against the accepted currency in the instruction
+    payment_mint: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(TOKEN_2022_PROGRAM).unwrap())]
+    payment_token_program: UncheckedAccount<'info>,
     system_program: Program<'info, System>,
-    token_program: Program<'info, Token>,
     rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct RemoveSellOrder<'info> {
+pub struct RemoveToken22SwapOffer<'info> {
     #[account(mut)]
     authority: Signer<'info>,
-    #[account(mut, constraint = authority.key() == seller_nft_token_account.owner)]
-    seller_nft_token_account: Account<'info, TokenAccount>,
-    #[account(mut, has_one = authority, constraint = seller_nft_token_account.mint == sell_order.mint)]
-    sell_order: Account<'info, SellOrder>,
+    /// CHECK: Checked by the token program
+    #[account(mut)]
+    authority_paying_account: UncheckedAccount<'info>,
 
+    comptoir: Box<Account<'info, Comptoir>>,
+    /// CHECK: Token-2022 escrow of the swap currency, checked by the token program
     #[account(
     mut,
     seeds = [
     PREFIX.as_bytes(),
-    "vault".as_bytes(),
-    seller_nft_token_account.mint.as_ref(),
+    comptoir.key().as_ref(),
+    swap_offer.payment_mint.as_ref(),
+    ESCROW.as_bytes()
     ],
     bump,
     )]
-    vault: Account<'info, TokenAccount>,
+    escrow: UncheckedAccount<'info>,
 
-    system_program: Program<'info, System>,
+    #[account(
+    mut,
+    close = authority,
+    has_one = comptoir,
+    has_one = authority,
+    )]
+    swap_offer: Box<Account<'info, SwapOffer>>,
+
+    /// CHECK: Loaded with the load_mint_of func
+    #[account(constraint = payment_mint.key() == swap_offer.payment_mint @ ErrorCode::ErrWrongCurrency)]
+    payment_mint: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(TOKEN_2022_PROGRAM).unwrap())]
+    payment_token_program: UncheckedAccount<'info>,
     token_program: Program<'info, Token>,
-    rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct SellOrderAddQuantity<'info> {
+pub struct AcceptToken22SwapOffer<'info> {
     #[account(mut)]
-    authority: Signer<'info>,
-    #[account(mut, constraint = authority.key() == seller_nft_token_account.owner)]
-    seller_nft_token_account: Account<'info, TokenAccount>,
-    #[account(mut, has_one = authority, constraint = seller_nft_token_account.mint == sell_order.mint)]
-    sell_order: Account<'info, SellOrder>,
+    counterparty: Signer<'info>,
+    /// CHECK: Checked by the token program
+    #[account(mut)]
+    counterparty_funds_account: UncheckedAccount<'info>,
 
+    comptoir: Box<Account<'info, Comptoir>>,
+    /// CHECK: Checked against the fees destination of the swap currency in the instruction
+    #[account(mut)]
+    comptoir_dest_account: UncheckedAccount<'info>,
+    /// CHECK: Token-2022 escrow of the swap currency, checked by the token program
     #[account(
     mut,
     seeds = [
     PREFIX.as_bytes(),
-    "vault".as_bytes(),
-    seller_nft_token_account.mint.as_ref(),
+    comptoir.key().as_ref(),
+    swap_offer.payment_mint.as_ref(),
+    ESCROW.as_bytes()
     ],
     bump,
     )]
-    vault: Account<'info, TokenAccount>,
+    escrow: UncheckedAccount<'info>,
 
-    system_program: Program<'info, System>,
+    #[account(mut)]
+    authority: SystemAccount<'info>,
+    #[account(
+    mut,
+    close = authority,
+    has_one = comptoir,
+    has_one = authority,
+    )]
+    swap_offer: Box<Account<'info, SwapOffer>>,
+
+    /// CHECK: Loaded with the load_mint_of func
+    #[account(constraint = payment_mint.key() == swap_offer.payment_mint @ ErrorCode::ErrWrongCurrency)]
+    payment_mint: UncheckedAccount<'info>,
+    /// CHECK: Address checked
+    #[account(address = Pubkey::from_str(TOKEN_2022_PROGRAM).unwrap())]
+    payment_token_program: UncheckedAccount<'info>,
     token_program: Program<'info, Token>,
-    rent: Sysvar<'info, Rent>,
 }
 
-#[derive(Accounts)]
-pub struct Buy<'info> {
-    buyer: Signer<'info>,
-    #[account(mut)]
-    buyer_nft_token_account: Box<Account<'info, TokenAccount>>,
-    #[account(mut)]
-    buyer_paying_token_account: Account<'info, TokenAccount>,
+#[account]
+pub struct Comptoir {
+    pub fees: u16,
+    pub fees_destination: Pubkey,
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+}
 
-    comptoir: Account<'info, Comptoir>,
-    #[account(mut, constraint = comptoir_dest_account.key() == comptoir.fees_destination)]
-    comptoir_dest_account: Account<'info, TokenAccount>,
-    #[account(constraint = collection.comptoir_key == comptoir.key())]
-    collection: Account<'info, Collection>,
+#[account]
+pub struct SellOrder {
+    pub comptoir: Pubkey,
+    pub price: u64,
+    pub quantity: u64,
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+    pub destination: Pubkey,
+    pub price_schedule: Option<PriceSchedule>, // When set, price is the starting price of a dutch auction
+    pub expires_at: Option<i64>,
+    pub vault_migrated: bool, // Orders listed before vaults were per comptoir keep their items in the legacy shared vault until migrated
+    pub payment_mint: Pubkey, // Currency the order is priced in
+    pub oracle_price: Option<OraclePrice>, // When set, price is in a reference unit converted at buy time
+}
 
-    /// CHECK: This is not dangerous because check it all the time using the verify_metadata_and_derivation func
-    metadata: UncheckedAccount<'info>,
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq)]
+pub enum PriceDecay {
+    Linear,
+    Stepped { step_duration: i64 },
+}
 
-    #[account(
-    mut,
-    seeds = [
-    PREFIX.as_bytes(),
-    "vault".as_bytes(),
-    buyer_nft_token_account.mint.as_ref()
-    ],
-    bump,
-    )]
-    vault: Box<Account<'info, TokenAccount>>,
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct PriceSchedule {
+    pub end_price: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub decay: PriceDecay,
+}
 
-    system_program: Program<'info, System>,
-    token_program: Program<'info, Token>,
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct OraclePrice {
+    pub price_feed: Pubkey,
+    pub max_staleness: i64, // In seconds
+    pub max_confidence_bps: u16, // Maximum confidence interval relative to the price
 }
 
 #[account]
-pub struct Comptoir {
-    fees: u16,
-    fees_destination: Pubkey,
+pub struct SellerOrderCounter {
     authority: Pubkey,
+    next_order_id: u64,
+}
+
+#[account]
+pub struct Collection {
+    pub comptoir_key: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub required_verifier: Pubkey,
+    pub fees: Option<u16>, //Takes priority over comptoir fees
+    pub ignore_creator_fee: bool,
+    pub traits_root: Option<[u8; 32]>, // Merkle root of the (mint, trait) pairs of the collection
+    pub certified_collection: Option<Pubkey>, // When set, items are matched on their verified metaplex collection instead of symbol and verifier
+    pub custom_currencies: bool, // When set, currencies registered for the comptoir are replaced by the ones registered for the collection
+}
+
+#[account]
+pub struct Currency {
+    comptoir: Pubkey,
+    scope: Pubkey, // The comptoir, or the collection for collection currencies
     mint: Pubkey,
+    fees_destination: Pubkey,
 }
 
 #[account]
-pub struct SellOrder {
+pub struct BuyOffer {
+    pub comptoir: Pubkey,
+    pub mint: Pubkey,
+    pub proposed_price: u64,
+    pub authority: Pubkey,
+    pub destination: Pubkey,
+    pub expires_at: Option<i64>,
+    pub collection: Pubkey,
+    pub quantity: u64, // Items left to buy, each one at proposed_price
+    pub trait_hash: Option<[u8; 32]>, // Trait offers only accept items proven to have this trait
+    pub payment_mint: Pubkey, // Currency the offer was funded in, its escrow stays reachable after a comptoir mint update
+}
+
+#[account]
+pub struct CompressedSellOrder {
     comptoir: Pubkey,
+    collection: Pubkey,
+    merkle_tree: Pubkey,
+    asset_id: Pubkey,
+    nonce: u64,
+    index: u32,
     price: u64,
-    quantity: u64,
+    authority: Pubkey,
+    destination: Pubkey,
+    expires_at: Option<i64>,
+    data_hash: [u8; 32], // Leaf hashes at listing time, the vault cannot be given the metadata back on removal
+    creator_hash: [u8; 32],
+    payment_mint: Pubkey,
+}
+
+#[account]
+pub struct OrderBook {
+    comptoir: Pubkey,
+    collection: Pubkey,
     mint: Pubkey,
+    payment_mint: Pubkey,
+    next_sequence: u64,
+    asks: Vec<BookEntry>, // Lowest price first, then oldest first
+    bids: Vec<BookEntry>, // Highest price first, then oldest first
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct BookEntry {
+    pub order: Pubkey,
+    pub owner: Pubkey,
+    pub price: u64,
+    pub sequence: u64,
+}
+
+// Each owner holds a few entries per side at most, so that one wallet cannot fill the book by itself
+impl OrderBook {
+    pub fn insert_ask(&mut self, order: Pubkey, owner: Pubkey, price: u64) -> Result<()> {
+        let entry = self.new_entry(order, owner, price)?;
+        if self.asks.iter().filter(|e| e.owner == owner).count() >= ORDER_BOOK_OWNER_CAPACITY {
+            return Err(error!(ErrorCode::ErrOrderBookOwnerLimit));
+        }
+        let position = self.asks.iter().position(|e| e.price > price).unwrap_or(self.asks.len());
+        self.asks.insert(position, entry);
+        Ok(())
+    }
+
+    pub fn insert_bid(&mut self, order: Pubkey, owner: Pubkey, price: u64) -> Result<()> {
+        let entry = self.new_entry(order, owner, price)?;
+        if self.bids.iter().filter(|e| e.owner == owner).count() >= ORDER_BOOK_OWNER_CAPACITY {
+            return Err(error!(ErrorCode::ErrOrderBookOwnerLimit));
+        }
+        let position = self.bids.iter().position(|e| e.price < price).unwrap_or(self.bids.len());
+        self.bids.insert(position, entry);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, order: &Pubkey) -> Result<()> {
+        let asks_len = self.asks.len();
+        let bids_len = self.bids.len();
+        self.asks.retain(|e| e.order != *order);
+        self.bids.retain(|e| e.order != *order);
+        if self.asks.len() == asks_len && self.bids.len() == bids_len {
+            return Err(error!(ErrorCode::ErrOrderNotInBook));
+        }
+        Ok(())
+    }
+
+    fn new_entry(&mut self, order: Pubkey, owner: Pubkey, price: u64) -> Result<BookEntry> {
+        if self.asks.iter().chain(self.bids.iter()).any(|e| e.order == order) {
+            return Err(error!(ErrorCode::ErrOrderAlreadyInBook));
+        }
+        if self.asks.len() >= ORDER_BOOK_CAPACITY || self.bids.len() >= ORDER_BOOK_CAPACITY {
+            return Err(error!(ErrorCode::ErrOrderBookFull));
+        }
+        let entry = BookEntry { order, owner, price, sequence: self.next_sequence };
+        self.next_sequence = self.next_sequence.checked_add(1).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+        Ok(entry)
+    }
+}
+
+#[account]
+pub struct BundleOrder {
+    comptoir: Pubkey,
     authority: Pubkey,
     destination: Pubkey,
+    price: u64, // For the whole bundle
+    payment_mint: Pubkey,
+    expires_at: Option<i64>,
+    items: Vec<BundleItem>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct BundleItem {
+    pub mint: Pubkey,
+    pub collection: Pubkey,
 }
 
 #[account]
-pub struct Collection {
-    comptoir_key: Pubkey,
-    name: String,
-    symbol: String,
-    required_verifier: Pubkey,
-    fees: Option<u16>, //Takes priority over comptoir fees
-    ignore_creator_fee: bool,
+pub struct SwapOffer {
+    comptoir: Pubkey,
+    authority: Pubkey,
+    payment_mint: Pubkey,
+    token_amount: u64, // Sent along the offered items, the comptoir fees are taken on it
+    expires_at: Option<i64>,
+    offered_mints: Vec<Pubkey>,
+    requested: Vec<SwapAsk>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq)]
+pub enum SwapAsk {
+    Mint { mint: Pubkey },
+    Collection { collection: Pubkey }, // Any verified item of the collection
+}
+
+impl BuyOffer {
+    // Collection offers have no mint and accept any item of the collection
+    pub fn is_collection_offer(&self) -> bool {
+        self.mint == Pubkey::default()
+    }
+
+    pub fn locked_amount(&self) -> Result<u64> {
+        let amount = self.proposed_price.checked_mul(self.quantity).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+        Ok(amount)
+    }
 }
 
 #[account]
-pub struct BuyOffer {
+pub struct Auction {
     comptoir: Pubkey,
+    collection: Pubkey,
     mint: Pubkey,
-    proposed_price: u64,
+    payment_mint: Pubkey, // Currency the bids are locked in, its escrow stays reachable after a comptoir mint update
+    quantity: u64,
     authority: Pubkey,
     destination: Pubkey,
+    seller_nft_token_account: Pubkey,
+    reserve_price: u64,
+    min_bid_increment: u64,
+    start_time: i64,
+    end_time: i64,
+    highest_bid: u64,
+    highest_bidder: Pubkey,
+    highest_bid_account: Pubkey,
+}
+
+#[account]
+pub struct Bid {
+    auction: Pubkey,
+    comptoir: Pubkey,
+    bidder: Pubkey,
+    payment_mint: Pubkey,
+    amount: u64,
+    outbid: bool, // Outbid bids are claimed back by their bidder
+}
+
+impl Auction {
+    pub fn minimum_bid(&self) -> Result<u64> {
+        if self.highest_bid == 0 {
+            return Ok(std::cmp::max(self.reserve_price, 1));
+        }
+        let minimum_bid = self.highest_bid.checked_add(self.min_bid_increment).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+        Ok(minimum_bid)
+    }
+}
+
+impl SellOrder {
+    pub fn current_price(&self, now: i64) -> Result<u64> {
+        if let Some(schedule) = self.price_schedule.as_ref() {
+            return schedule.price_at(self.price, now);
+        }
+        Ok(self.price)
+    }
+}
+
+impl PriceSchedule {
+    pub fn price_at(&self, start_price: u64, now: i64) -> Result<u64> {
+        if now <= self.start_time {
+            return Ok(start_price);
+        }
+        if now >= self.end_time {
+            return Ok(self.end_price);
+        }
+
+        let duration = self.end_time.checked_sub(self.start_time).ok_or(ErrorCode::ErrArithmeticOverflow)? as u128;
+        let mut elapsed = now.checked_sub(self.start_time).ok_or(ErrorCode::ErrArithmeticOverflow)? as u128;
+        if let PriceDecay::Stepped { step_duration } = self.decay {
+            elapsed = elapsed - elapsed % step_duration as u128;
+        }
+
+        let total_decay = start_price.checked_sub(self.end_price).ok_or(ErrorCode::ErrArithmeticOverflow)? as u128;
+        let decay = total_decay.checked_mul(elapsed).ok_or(ErrorCode::ErrArithmeticOverflow)? / duration;
+        let price = start_price.checked_sub(decay as u64).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+        Ok(price)
+    }
+
+    pub fn validate(&self, start_price: u64) -> Result<()> {
+        if self.end_price > start_price {
+            return Err(error!(ErrorCode::ErrEndPriceHigherThanStartPrice));
+        }
+        // Also keeps the schedule duration within an i64, price_at relies on it
+        if self.start_time >= self.end_time || self.end_time.checked_sub(self.start_time).is_none() {
+            return Err(error!(ErrorCode::ErrInvalidPriceScheduleTimes));
+        }
+        if let PriceDecay::Stepped { step_duration } = self.decay {
+            if step_duration <= 0 {
+                return Err(error!(ErrorCode::ErrInvalidPriceScheduleTimes));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl OraclePrice {
+    pub fn convert(&self, reference_price: u64, price_feed: &PriceFeed, now: i64) -> Result<u64> {
+        if now.checked_sub(price_feed.publish_time).ok_or(ErrorCode::ErrArithmeticOverflow)? > self.max_staleness {
+            return Err(error!(ErrorCode::ErrStalePrice));
+        }
+        if price_feed.price <= 0 {
+            return Err(error!(ErrorCode::ErrInvalidPriceFeed));
+        }
+        let feed_price = price_feed.price as u128;
+        if (price_feed.conf as u128) * 10000 > feed_price * self.max_confidence_bps as u128 {
+            return Err(error!(ErrorCode::ErrPriceConfidenceTooWide));
+        }
+
+        let amount = if price_feed.expo < 0 {
+            let scale = 10u128.checked_pow(price_feed.expo.unsigned_abs()).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+            (reference_price as u128).checked_mul(scale).ok_or(ErrorCode::ErrArithmeticOverflow)? / feed_price
+        } else {
+            let scale = 10u128.checked_pow(price_feed.expo as u32).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+            (reference_price as u128) / feed_price.checked_mul(scale).ok_or(ErrorCode::ErrArithmeticOverflow)?
+        };
+        if amount > u64::MAX as u128 {
+            return Err(error!(ErrorCode::ErrInvalidPriceFeed));
+        }
+        Ok(amount as u64)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.max_staleness <= 0 || self.max_confidence_bps > 10000 {
+            return Err(error!(ErrorCode::ErrInvalidOraclePrice));
+        }
+        Ok(())
+    }
 }
 
 impl Collection {
+    pub fn updated_event(&self, collection: Pubkey) -> CollectionUpdatedEvent {
+        CollectionUpdatedEvent {
+            collection,
+            symbol: self.symbol.clone(),
+            required_verifier: self.required_verifier,
+            fees: self.fees,
+            ignore_creator_fee: self.ignore_creator_fee,
+            traits_root: self.traits_root,
+            certified_collection: self.certified_collection,
+            custom_currencies: self.custom_currencies,
+        }
+    }
+
     pub fn is_part_of_collection(&self, metadata: &Metadata) -> bool {
-        return if let Some(creators) = metadata.data.creators.as_ref() {
+        if let Some(certified_collection) = self.certified_collection {
+            return if let Some(metadata_collection) = metadata.collection.as_ref() {
+                metadata_collection.verified && metadata_collection.key == certified_collection
+            } else {
+                false
+            };
+        }
+
+        if let Some(creators) = metadata.data.creators.as_ref() {
             metadata.data.symbol.starts_with(&self.symbol.to_string())
                 && creators.iter().any(|c| c.address == self.required_verifier && c.verified)
         } else {
             false
-        };
+        }
+    }
+
+    pub fn is_compressed_part_of_collection(&self, metadata: &CompressedMetadata) -> bool {
+        if let Some(certified_collection) = self.certified_collection {
+            return if let Some(metadata_collection) = metadata.collection.as_ref() {
+                metadata_collection.verified && metadata_collection.key == certified_collection
+            } else {
+                false
+            };
+        }
+
+        metadata.symbol.starts_with(&self.symbol.to_string())
+            && metadata.creators.iter().any(|c| c.address == self.required_verifier && c.verified)
     }
 
     pub fn validate(&self) -> Result<()> {
@@ -875,7 +7095,29 @@ impl Collection {
     }
 }
 
+impl Currency {
+    pub fn updated_event(&self, currency: Pubkey) -> CurrencyUpdatedEvent {
+        CurrencyUpdatedEvent {
+            currency,
+            comptoir: self.comptoir,
+            scope: self.scope,
+            mint: self.mint,
+            fees_destination: self.fees_destination,
+        }
+    }
+}
+
 impl Comptoir {
+    pub fn updated_event(&self, comptoir: Pubkey) -> ComptoirUpdatedEvent {
+        ComptoirUpdatedEvent {
+            comptoir,
+            authority: self.authority,
+            mint: self.mint,
+            fees: self.fees,
+            fees_destination: self.fees_destination,
+        }
+    }
+
     pub fn validate(&self) -> Result<()> {
         if self.fees > 10000 {
             return Err(error!(ErrorCode::ErrFeeShouldLowerOrEqualThan10000));
@@ -889,11 +7131,11 @@ fn verify_metadata_and_derivation(unverified_metadata: &AccountInfo, nft_mint: &
         return Err(error!(ErrorCode::NotInitialized));
     };
     assert_derivation(
-        &metaplex_token_metadata::id(),
+        &mpl_token_metadata::id(),
         unverified_metadata,
         &[
             METAPLEX_PREFIX.as_bytes(),
-            metaplex_token_metadata::id().as_ref(),
+            mpl_token_metadata::id().as_ref(),
             nft_mint.as_ref(),
         ],
     )?;
@@ -901,55 +7143,938 @@ fn verify_metadata_and_derivation(unverified_metadata: &AccountInfo, nft_mint: &
     if !collection.is_part_of_collection(&metadata) {
         return Err(error!(ErrorCode::ErrNftNotPartOfCollection));
     }
-    return Ok(metadata);
+    Ok(metadata)
+}
+
+// Programmable NFTs are not loaded by the metadata crate, only the fields the program reads are kept
+fn load_programmable_metadata_and_derivation(unverified_metadata: &AccountInfo, nft_mint: &Pubkey) -> Result<(Metadata, Option<Pubkey>)> {
+    if unverified_metadata.data_is_empty() {
+        return Err(error!(ErrorCode::NotInitialized));
+    };
+    assert_derivation(
+        &mpl_token_metadata::id(),
+        unverified_metadata,
+        &[
+            METAPLEX_PREFIX.as_bytes(),
+            mpl_token_metadata::id().as_ref(),
+            nft_mint.as_ref(),
+        ],
+    )?;
+    let data = unverified_metadata.try_borrow_data()?;
+    load_programmable_metadata(&data)
+}
+
+fn verify_programmable_metadata_and_derivation(unverified_metadata: &AccountInfo, nft_mint: &Pubkey, collection: &Collection) -> Result<(Metadata, Option<Pubkey>)> {
+    let (metadata, rule_set) = load_programmable_metadata_and_derivation(unverified_metadata, nft_mint)?;
+    if !collection.is_part_of_collection(&metadata) {
+        return Err(error!(ErrorCode::ErrNftNotPartOfCollection));
+    }
+    Ok((metadata, rule_set))
 }
 
 pub mod constant {
     pub const ASSOCIATED_TOKEN_PROGRAM: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
     pub const TOKEN_PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+    pub const TOKEN_2022_PROGRAM: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
     pub const PREFIX: &str = "COMPTOIR";
     pub const ESCROW: &str = "ESCROW";
+    pub const AUCTION: &str = "AUCTION";
+    pub const BID: &str = "BID";
+    pub const COMPRESSED: &str = "COMPRESSED";
+    pub const ORDER_ID: &str = "ORDER_ID";
+    pub const ORDER_COUNTER: &str = "ORDER_COUNTER";
+    pub const CURRENCY: &str = "CURRENCY";
+    pub const PYTH_PROGRAM: &str = "FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH";
+    pub const ORDER_BOOK: &str = "ORDER_BOOK";
+    pub const ORDER_BOOK_CAPACITY: usize = 32;
+    pub const ORDER_BOOK_OWNER_CAPACITY: usize = 4;
+    pub const BUNDLE: &str = "BUNDLE";
+    pub const BUNDLE_CAPACITY: usize = 8;
+    pub const SWAP: &str = "SWAP";
+    pub const SWAP_CAPACITY: usize = 4;
+    pub const BUBBLEGUM_PROGRAM: &str = "BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY";
+    pub const SPL_ACCOUNT_COMPRESSION_PROGRAM: &str = "cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK";
+    pub const SPL_NOOP_PROGRAM: &str = "noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV";
+}
+
+
+struct SellOrderListing<'a, 'info, 'remaining> {
+    comptoir: &'a Account<'info, Comptoir>,
+    collection: &'a Account<'info, Collection>,
+    metadata: AccountInfo<'info>,
+    seller_nft_token_account: &'a Account<'info, TokenAccount>,
+    vault: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    remaining_accounts: &'a [AccountInfo<'remaining>],
+}
+
+struct SellOrderTerms {
+    price: u64,
+    quantity: u64,
+    destination: Pubkey,
+    price_schedule: Option<PriceSchedule>,
+    expires_at: Option<i64>,
+    oracle_price: Option<OraclePrice>,
+}
+
+// Shared by every way of listing, the items are locked in the vault and the order is recorded in the accepted currency
+fn init_sell_order<'info>(
+    sell_order: &mut Account<'info, SellOrder>,
+    listing: SellOrderListing<'_, 'info, '_>,
+    terms: SellOrderTerms,
+) -> Result<()> {
+    verify_metadata_and_derivation(
+        &listing.metadata,
+        &listing.seller_nft_token_account.mint,
+        listing.collection,
+    )?;
+    if let Some(schedule) = terms.price_schedule.as_ref() {
+        schedule.validate(terms.price)?;
+    }
+    validate_expiration(terms.expires_at)?;
+    let (payment_mint, _) = get_payment_terms(listing.comptoir, listing.collection, listing.remaining_accounts)?;
+
+    let cpi_accounts = Transfer {
+        from: listing.seller_nft_token_account.to_account_info(),
+        to: listing.vault,
+        authority: listing.payer.clone(),
+    };
+    let cpi_ctx = CpiContext::new(listing.token_program, cpi_accounts);
+    token::transfer(cpi_ctx, terms.quantity)?;
+
+    sell_order.comptoir = listing.comptoir.key();
+    sell_order.price = terms.price;
+    sell_order.quantity = terms.quantity;
+    sell_order.mint = listing.seller_nft_token_account.mint;
+    sell_order.authority = listing.payer.key();
+    sell_order.destination = terms.destination;
+    sell_order.price_schedule = terms.price_schedule;
+    sell_order.expires_at = terms.expires_at;
+    sell_order.vault_migrated = true;
+    sell_order.payment_mint = payment_mint;
+    sell_order.oracle_price = terms.oracle_price;
+    emit!(SellOrderCreatedEvent{
+        sell_order: sell_order.key(),
+        comptoir: sell_order.comptoir,
+        collection: listing.collection.key(),
+        mint: sell_order.mint,
+        authority: sell_order.authority,
+        price: terms.price,
+        quantity: terms.quantity,
+        payment_mint,
+    });
+    Ok(())
+}
+
+// What a buy offer buys: items of a single mint sent to the destination, or any item of the collection having the
+// trait when there is one
+struct OfferTarget {
+    mint: Pubkey,
+    destination: Pubkey,
+    trait_hash: Option<[u8; 32]>,
+}
+
+struct BuyOfferFunding<'a, 'info, 'remaining> {
+    comptoir: &'a Account<'info, Comptoir>,
+    collection: &'a Account<'info, Collection>,
+    escrow: &'a Account<'info, TokenAccount>,
+    buyer_paying_account: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    remaining_accounts: &'a [AccountInfo<'remaining>],
+}
+
+// Shared by every kind of buy offer, the offer is recorded in the accepted currency and funded with its whole amount
+fn init_buy_offer<'info>(
+    buy_offer: &mut Account<'info, BuyOffer>,
+    funding: BuyOfferFunding<'_, 'info, '_>,
+    target: OfferTarget,
+    price_proposition: u64,
+    quantity: u64,
+    expires_at: Option<i64>,
+) -> Result<()> {
+    validate_expiration(expires_at)?;
+    let (payment_mint, _) = get_payment_terms(funding.comptoir, funding.collection, funding.remaining_accounts)?;
+    if funding.escrow.mint != payment_mint {
+        return Err(error!(ErrorCode::ErrWrongCurrency));
+    }
+    record_buy_offer(
+        buy_offer,
+        funding.comptoir.key(),
+        funding.collection.key(),
+        funding.payer.key(),
+        target,
+        OfferTerms { price_proposition, quantity, expires_at, payment_mint },
+    );
+
+    // Native SOL offers lock their funds in the buy offer account itself
+    let is_native = is_native_mint(&payment_mint);
+    let mut escrow = funding.escrow.to_account_info();
+    if is_native {
+        escrow = buy_offer.to_account_info();
+    }
+    pay_from_wallet(
+        is_native,
+        funding.buyer_paying_account,
+        escrow,
+        funding.payer,
+        funding.token_program,
+        funding.system_program,
+        price_proposition.checked_mul(quantity).ok_or(ErrorCode::ErrArithmeticOverflow)?,
+    )
+}
+
+struct OfferTerms {
+    price_proposition: u64,
+    quantity: u64,
+    expires_at: Option<i64>,
+    payment_mint: Pubkey,
+}
+
+fn record_buy_offer(
+    buy_offer: &mut Account<BuyOffer>,
+    comptoir: Pubkey,
+    collection: Pubkey,
+    authority: Pubkey,
+    target: OfferTarget,
+    terms: OfferTerms,
+) {
+    buy_offer.mint = target.mint;
+    buy_offer.authority = authority;
+    buy_offer.proposed_price = terms.price_proposition;
+    buy_offer.comptoir = comptoir;
+    buy_offer.destination = target.destination;
+    buy_offer.expires_at = terms.expires_at;
+    buy_offer.collection = collection;
+    buy_offer.quantity = terms.quantity;
+    buy_offer.trait_hash = target.trait_hash;
+    buy_offer.payment_mint = terms.payment_mint;
+    emit!(BuyOfferCreatedEvent{
+        buy_offer: buy_offer.key(),
+        comptoir,
+        mint: buy_offer.mint,
+        collection,
+        authority,
+        price: terms.price_proposition,
+        quantity: terms.quantity,
+        payment_mint: terms.payment_mint,
+    });
+}
+
+struct Token22OfferFunding<'a, 'info> {
+    comptoir: &'a Account<'info, Comptoir>,
+    collection: &'a Account<'info, Collection>,
+    escrow: AccountInfo<'info>,
+    buyer_paying_account: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    payment_mint: AccountInfo<'info>,
+    payment_token_program: AccountInfo<'info>,
+    remaining_accounts: &'a [AccountInfo<'info>],
+}
+
+// Token-2022 offers are funded with what the escrow has to receive for holding the whole amount, the buyer bearing
+// the transfer fee. Remaining accounts: the currency transfer hook accounts when it has one, then the currency when
+// not the comptoir one
+fn init_token22_buy_offer<'info>(
+    buy_offer: &mut Account<'info, BuyOffer>,
+    funding: Token22OfferFunding<'_, 'info>,
+    target: OfferTarget,
+    price_proposition: u64,
+    quantity: u64,
+    expires_at: Option<i64>,
+) -> Result<()> {
+    validate_expiration(expires_at)?;
+    let (currency, remaining_accounts) = load_token22_currency(
+        &funding.payment_mint,
+        &funding.payment_token_program,
+        funding.remaining_accounts,
+    )?;
+    let (payment_mint, _) = get_payment_terms(funding.comptoir, funding.collection, remaining_accounts)?;
+    if funding.payment_mint.key() != payment_mint {
+        return Err(error!(ErrorCode::ErrWrongCurrency));
+    }
+    record_buy_offer(
+        buy_offer,
+        funding.comptoir.key(),
+        funding.collection.key(),
+        funding.payer.key(),
+        target,
+        OfferTerms { price_proposition, quantity, expires_at, payment_mint },
+    );
+
+    let locked_amount = price_proposition.checked_mul(quantity).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+    currency.transfer(
+        &funding.buyer_paying_account,
+        &funding.escrow,
+        &funding.payer,
+        currency.info.pre_fee_amount(locked_amount, Clock::get()?.epoch)?,
+        &[],
+    )?;
+    Ok(())
 }
 
+// Checks shared by every way of filling a buy offer with the items of a token account: the quantity, the items being
+// targeted by the offer, where they are sent and the trait proof
+fn check_offer_fill(
+    buy_offer: &BuyOffer,
+    collection: &Collection,
+    seller_nft_account: &TokenAccount,
+    destination: &Account<TokenAccount>,
+    quantity: u64,
+    trait_proof: &[[u8; 32]],
+) -> Result<()> {
+    if quantity == 0 || quantity > buy_offer.quantity {
+        return Err(error!(ErrorCode::ErrWrongOfferQuantity));
+    }
+    if buy_offer.is_collection_offer() {
+        if destination.owner != buy_offer.authority || destination.mint != seller_nft_account.mint {
+            return Err(error!(ErrorCode::ErrWrongOfferDestination));
+        }
+    } else {
+        if seller_nft_account.mint != buy_offer.mint {
+            return Err(error!(ErrorCode::ErrItemNotTargetedByOffer));
+        }
+        if destination.key() != buy_offer.destination {
+            return Err(error!(ErrorCode::ErrWrongOfferDestination));
+        }
+    }
+    if let Some(trait_hash) = buy_offer.trait_hash.as_ref() {
+        let traits_root = collection.traits_root.ok_or(error!(ErrorCode::ErrCollectionHasNoTraitsRoot))?;
+        let leaf = merkle::trait_leaf(&seller_nft_account.mint, trait_hash);
+        if !merkle::verify_proof(trait_proof, traits_root, leaf) {
+            return Err(error!(ErrorCode::ErrInvalidTraitProof));
+        }
+    }
+    Ok(())
+}
 
-fn calculate_fee(amount: u64, fee_share: u16, basis: u64) -> u64 {
+pub fn calculate_fee(amount: u64, fee_share: u16, basis: u64) -> Result<u64> {
     let fee = amount
         .checked_mul(fee_share as u64)
-        .unwrap()
+        .ok_or(ErrorCode::ErrArithmeticOverflow)?
         .checked_div(basis)
-        .unwrap();
+        .ok_or(ErrorCode::ErrArithmeticOverflow)?;
+
+    Ok(fee)
+}
+
+// What the bidder has to send to the escrow for the bid: the whole amount, or a top up of their previous bid
+fn get_bid_top_up(auction: &Auction, bid: &Bid, amount: u64) -> Result<u64> {
+    let now = Clock::get()?.unix_timestamp;
+    if now < auction.start_time {
+        return Err(error!(ErrorCode::ErrAuctionNotStarted));
+    }
+    if now >= auction.end_time {
+        return Err(error!(ErrorCode::ErrAuctionEnded));
+    }
+    if amount < auction.minimum_bid()? {
+        return Err(error!(ErrorCode::ErrBidTooLow));
+    }
+
+    // A bidder bidding again only tops up what their bid account already holds in escrow
+    let is_new_bid = bid.auction == Pubkey::default();
+    if is_new_bid {
+        return Ok(amount);
+    }
+    let to_pay = amount.checked_sub(bid.amount).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+    Ok(to_pay)
+}
+
+// The bid we just outbid, first remaining account, becomes refundable and its bidder claims it back. Raising our own
+// bid outbids nobody
+fn record_highest_bid<'info>(
+    auction: &mut Account<'info, Auction>,
+    bid: &mut Account<'info, Bid>,
+    bidder: Pubkey,
+    amount: u64,
+    remaining_accounts: &[AccountInfo<'info>],
+    program_id: &Pubkey,
+) -> Result<()> {
+    if auction.highest_bid > 0 && auction.highest_bid_account != bid.key() {
+        let previous_bid_account = get_remaining_account(remaining_accounts, 0)?;
+        if previous_bid_account.key() != auction.highest_bid_account {
+            return Err(error!(ErrorCode::ErrWrongPreviousBidderAccount));
+        }
+        let mut previous_bid = Account::<'info, Bid>::try_from(previous_bid_account)?;
+        previous_bid.outbid = true;
+        previous_bid.exit(program_id)?;
+    }
+
+    bid.auction = auction.key();
+    bid.comptoir = auction.comptoir;
+    bid.bidder = bidder;
+    bid.payment_mint = auction.payment_mint;
+    bid.amount = amount;
+    bid.outbid = false;
+
+    auction.highest_bid = amount;
+    auction.highest_bidder = bidder;
+    auction.highest_bid_account = bid.key();
+
+    emit!(AuctionBidEvent{
+        auction: auction.key(),
+        bidder,
+        amount,
+    });
+    Ok(())
+}
+
+// Records a new swap offer, its items are added afterwards
+fn record_swap_offer(
+    swap_offer: &mut Account<SwapOffer>,
+    comptoir: Pubkey,
+    authority: Pubkey,
+    payment_mint: Pubkey,
+    token_amount: u64,
+    expires_at: Option<i64>,
+    requested: Vec<SwapAsk>,
+) {
+    swap_offer.comptoir = comptoir;
+    swap_offer.authority = authority;
+    swap_offer.payment_mint = payment_mint;
+    swap_offer.token_amount = token_amount;
+    swap_offer.expires_at = expires_at;
+    swap_offer.offered_mints = Vec::new();
+    swap_offer.requested = requested;
+    emit!(SwapOfferCreatedEvent{
+        swap_offer: swap_offer.key(),
+        comptoir,
+        authority,
+        token_amount,
+        payment_mint,
+        requested: swap_offer.requested.clone(),
+    });
+}
+
+// Sends the offered items back to the authority. Remaining accounts: the vault and the authority token account of
+// every offered item
+fn return_swap_items<'info>(
+    swap_offer: &Account<'info, SwapOffer>,
+    remaining_accounts: &[AccountInfo<'info>],
+    token_program: &AccountInfo<'info>,
+    program_id: &Pubkey,
+) -> Result<()> {
+    for (i, mint) in swap_offer.offered_mints.iter().enumerate() {
+        let vault = get_remaining_account(remaining_accounts, i * 2)?;
+        let authority_nft_token_account = get_remaining_account(remaining_accounts, i * 2 + 1)?;
+        let vault_bump = get_vault_bump(vault, &swap_offer.comptoir, mint, program_id)?;
+        let seeds = &[
+            PREFIX.as_bytes(),
+            "vault".as_bytes(),
+            swap_offer.comptoir.as_ref(),
+            mint.as_ref(),
+            &[vault_bump], ];
+        let signer = &[&seeds[..]];
+        pay_with_signer(
+            vault.to_account_info(),
+            authority_nft_token_account.to_account_info(),
+            vault.to_account_info(),
+            token_program.to_account_info(),
+            1,
+            signer,
+        )?;
+    }
+
+    emit!(SwapOfferRemovedEvent{
+        swap_offer: swap_offer.key(),
+    });
+    Ok(())
+}
+
+// Sends the offered items to the counterparty and the requested ones to the authority, returning the mints the
+// authority received. Remaining accounts are laid out as for accept_swap_offer
+fn exchange_swap_items<'info>(
+    swap_offer: &Account<'info, SwapOffer>,
+    counterparty: &AccountInfo<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    token_program: &AccountInfo<'info>,
+    program_id: &Pubkey,
+) -> Result<Vec<Pubkey>> {
+    let comptoir_key = swap_offer.comptoir;
+    let mut index = 0;
+    for mint in swap_offer.offered_mints.iter() {
+        let vault = get_remaining_account(remaining_accounts, index)?;
+        let counterparty_nft_token_account = get_remaining_account(remaining_accounts, index + 1)?;
+        index += 2;
+        let vault_bump = get_vault_bump(vault, &comptoir_key, mint, program_id)?;
+        let seeds = &[
+            PREFIX.as_bytes(),
+            "vault".as_bytes(),
+            comptoir_key.as_ref(),
+            mint.as_ref(),
+            &[vault_bump], ];
+        let signer = &[&seeds[..]];
+        pay_with_signer(
+            vault.to_account_info(),
+            counterparty_nft_token_account.to_account_info(),
+            vault.to_account_info(),
+            token_program.to_account_info(),
+            1,
+            signer,
+        )?;
+    }
+
+    let mut received_mints = Vec::new();
+    for ask in swap_offer.requested.iter() {
+        let counterparty_nft_token_account = Account::<'info, TokenAccount>::try_from(get_remaining_account(remaining_accounts, index)?)?;
+        let authority_nft_token_account = Account::<'info, TokenAccount>::try_from(get_remaining_account(remaining_accounts, index + 1)?)?;
+        index += 2;
+        let mint = counterparty_nft_token_account.mint;
+        match ask {
+            SwapAsk::Mint { mint: requested_mint } => {
+                if mint != *requested_mint {
+                    return Err(error!(ErrorCode::ErrWrongSwapItem));
+                }
+            }
+            SwapAsk::Collection { collection: requested_collection } => {
+                let metadata = get_remaining_account(remaining_accounts, index)?;
+                let collection = Account::<'info, Collection>::try_from(get_remaining_account(remaining_accounts, index + 1)?)?;
+                index += 2;
+                if collection.key() != *requested_collection || collection.comptoir_key != comptoir_key {
+                    return Err(error!(ErrorCode::ErrWrongSwapItem));
+                }
+                verify_metadata_and_derivation(metadata, &mint, &collection)?;
+            }
+        }
+        if authority_nft_token_account.owner != swap_offer.authority || authority_nft_token_account.mint != mint {
+            return Err(error!(ErrorCode::ErrWrongSwapItem));
+        }
+        pay(
+            counterparty_nft_token_account.to_account_info(),
+            authority_nft_token_account.to_account_info(),
+            counterparty.to_account_info(),
+            token_program.to_account_info(),
+            1,
+        )?;
+        received_mints.push(mint);
+    }
+    Ok(received_mints)
+}
+
+fn is_expired(expires_at: Option<i64>, now: i64) -> bool {
+    if let Some(expiration) = expires_at {
+        return now >= expiration;
+    }
+    false
+}
+
+fn validate_expiration(expires_at: Option<i64>) -> Result<()> {
+    if is_expired(expires_at, Clock::get()?.unix_timestamp) {
+        return Err(error!(ErrorCode::ErrExpirationInThePast));
+    }
+    Ok(())
+}
+
+fn is_native_mint(mint: &Pubkey) -> bool {
+    *mint == spl_token::native_mint::id()
+}
+
+// The comptoir mint is accepted everywhere, other currencies pass their Currency account as last remaining account
+fn get_payment_terms<'a, 'info>(
+    comptoir: &Account<'a, Comptoir>,
+    collection: &Account<'a, Collection>,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<(Pubkey, Pubkey)> {
+    get_payment_terms_in(comptoir, &get_currency_scope(comptoir, collection), remaining_accounts)
+}
+
+// Currencies are registered for the comptoir, or for the collection when it has its own
+fn get_currency_scope(comptoir: &Account<Comptoir>, collection: &Account<Collection>) -> Pubkey {
+    if collection.custom_currencies {
+        return collection.key();
+    }
+    comptoir.key()
+}
+
+// Orders spanning several collections, such as bundles and swaps, are paid in the currencies of the comptoir
+fn get_payment_terms_in<'a, 'info>(
+    comptoir: &Account<'a, Comptoir>,
+    scope: &Pubkey,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<(Pubkey, Pubkey)> {
+    if let Some(Ok(currency)) = remaining_accounts.last().map(Account::<'info, Currency>::try_from) {
+        if currency.comptoir != comptoir.key() || currency.scope != *scope {
+            return Err(error!(ErrorCode::ErrCurrencyNotAccepted));
+        }
+        return Ok((currency.mint, currency.fees_destination));
+    }
+    Ok((comptoir.mint, comptoir.fees_destination))
+}
+
+// Fees destination of a currency an order was already priced in, None when the comptoir stopped accepting it.
+// Currencies other than the comptoir mint pass their Currency account as last remaining account, even once removed,
+// so that nobody can make an accepted currency look removed by leaving it out
+fn get_fees_destination_of<'a, 'info>(
+    comptoir: &Account<'a, Comptoir>,
+    collection: &Account<'a, Collection>,
+    payment_mint: &Pubkey,
+    remaining_accounts: &[AccountInfo<'info>],
+    program_id: &Pubkey,
+) -> Result<Option<Pubkey>> {
+    let scope = get_currency_scope(comptoir, collection);
+    get_fees_destination_in(comptoir, &scope, payment_mint, remaining_accounts, program_id)
+}
+
+fn get_fees_destination_in<'a, 'info>(
+    comptoir: &Account<'a, Comptoir>,
+    scope: &Pubkey,
+    payment_mint: &Pubkey,
+    remaining_accounts: &[AccountInfo<'info>],
+    program_id: &Pubkey,
+) -> Result<Option<Pubkey>> {
+    if *payment_mint == comptoir.mint {
+        return Ok(Some(comptoir.fees_destination));
+    }
+    let (currency_key, _) = Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), scope.as_ref(), payment_mint.as_ref(), CURRENCY.as_bytes()],
+        program_id,
+    );
+    let currency_account = remaining_accounts.last().ok_or(ErrorCode::ErrMissingRemainingAccounts)?;
+    if currency_account.key() != currency_key {
+        return Err(error!(ErrorCode::ErrCurrencyNotAccepted));
+    }
+    if currency_account.data_is_empty() {
+        return Ok(None);
+    }
+    let currency = Account::<'info, Currency>::try_from(currency_account)?;
+    Ok(Some(currency.fees_destination))
+}
+
+fn get_vault_bump(vault: &AccountInfo, comptoir: &Pubkey, mint: &Pubkey, program_id: &Pubkey) -> Result<u8> {
+    let (vault_key, vault_bump) = Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), "vault".as_bytes(), comptoir.as_ref(), mint.as_ref()],
+        program_id,
+    );
+    if vault.key() != vault_key {
+        return Err(error!(ErrorCode::ErrWrongVault));
+    }
+    Ok(vault_bump)
+}
+
+pub fn get_comptoir_fee(comptoir: &Comptoir, collection: &Collection) -> u16 {
+    if let Some(collection_share) = collection.fees {
+        return collection_share;
+    }
+    comptoir.fees
+}
+
+// Returns the (seller, comptoir, creators) shares of a sale
+pub fn split_amount(total_amount: u64, comptoir_fee: u16, collection: &Collection, seller_fee_basis_points: u16) -> Result<(u64, u64, u64)> {
+    let mut creators_share: u64 = 0;
+    if !collection.ignore_creator_fee {
+        creators_share = calculate_fee(total_amount, seller_fee_basis_points, 10000)?;
+    }
+    let comptoir_share = calculate_fee(total_amount, comptoir_fee, 10000)?;
+    let seller_share = total_amount.checked_sub(creators_share).ok_or(ErrorCode::ErrArithmeticOverflow)?.checked_sub(comptoir_share).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+
+    Ok((seller_share, comptoir_share, creators_share))
+}
 
-    return fee;
+fn verify_and_get_creators<'c, 'info>(creators: Vec<Creator>, remaining_accounts: &'c [AccountInfo<'info>], comptoir_mint: Pubkey) -> Result<Vec<(&'c AccountInfo<'info>, u8)>> {
+    verify_and_get_token_creators(creators, remaining_accounts, comptoir_mint, &spl_token::ID)
 }
 
-fn verify_and_get_creators<'a, 'b, 'c, 'info>(creators: Vec<Creator>, remaining_accounts: &'c [AccountInfo<'info>], comptoir_mint: Pubkey) -> Vec<(&'c AccountInfo<'info>, u8)> {
+// Creators are paid on their associated account, which depends on the token program of the currency
+fn verify_and_get_token_creators<'c, 'info>(
+    creators: Vec<Creator>,
+    remaining_accounts: &'c [AccountInfo<'info>],
+    comptoir_mint: Pubkey,
+    token_program: &Pubkey,
+) -> Result<Vec<(&'c AccountInfo<'info>, u8)>> {
     let is_native = comptoir_mint == spl_token::native_mint::id();
     let mut creators_distributions = Vec::new();
-    for i in 0..creators.len() {
-        let remaining_account_creator = &remaining_accounts[i];
+    for (i, creator) in creators.iter().enumerate() {
+        let remaining_account_creator = get_remaining_account(remaining_accounts, i)?;
         if is_native {
-            assert_eq!(remaining_account_creator.key(), creators[i].address);
-            creators_distributions.push((remaining_account_creator, creators[i].share));
+            if remaining_account_creator.key() != creator.address {
+                return Err(error!(ErrorCode::ErrWrongCreatorAccount));
+            }
+            creators_distributions.push((remaining_account_creator, creator.share));
         } else {
             let ata_seeds: &[&[u8]] = &[
-                creators[i].address.as_ref(),
-                spl_token::ID.as_ref(),
+                creator.address.as_ref(),
+                token_program.as_ref(),
                 comptoir_mint.as_ref(),
             ];
             let atp = Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM).unwrap();
-            let creator_associated_token_addr = Pubkey::find_program_address(&ata_seeds, &atp);
-            assert_eq!(remaining_account_creator.key(), creator_associated_token_addr.0);
-            creators_distributions.push((remaining_account_creator, creators[i].share));
+            let creator_associated_token_addr = Pubkey::find_program_address(ata_seeds, &atp);
+            if remaining_account_creator.key() != creator_associated_token_addr.0 {
+                return Err(error!(ErrorCode::ErrWrongCreatorAccount));
+            }
+            creators_distributions.push((remaining_account_creator, creator.share));
         }
     }
-    return creators_distributions;
+    Ok(creators_distributions)
+}
+
+fn get_remaining_account<'c, 'info>(remaining_accounts: &'c [AccountInfo<'info>], index: usize) -> Result<&'c AccountInfo<'info>> {
+    let account = remaining_accounts.get(index).ok_or(ErrorCode::ErrMissingRemainingAccounts)?;
+    Ok(account)
+}
+
+// Proof nodes of a compressed leaf from the start index, the currency account follows them when not the comptoir one
+fn get_proof_accounts<'c, 'info>(
+    remaining_accounts: &'c [AccountInfo<'info>],
+    start: usize,
+    payment_mint: &Pubkey,
+    comptoir: &Comptoir,
+) -> Result<&'c [AccountInfo<'info>]> {
+    let mut end = remaining_accounts.len();
+    if *payment_mint != comptoir.mint {
+        end = end.checked_sub(1).ok_or(ErrorCode::ErrMissingRemainingAccounts)?;
+    }
+    let accounts = remaining_accounts.get(start..end).ok_or(ErrorCode::ErrMissingRemainingAccounts)?;
+    Ok(accounts)
+}
+
+fn get_remaining_accounts_from<'c, 'info>(remaining_accounts: &'c [AccountInfo<'info>], start: usize) -> Result<&'c [AccountInfo<'info>]> {
+    let accounts = remaining_accounts.get(start..).ok_or(ErrorCode::ErrMissingRemainingAccounts)?;
+    Ok(accounts)
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CreatorPayment {
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ComptoirCreatedEvent {
+    pub comptoir: Pubkey,
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub fees: u16,
+    pub fees_destination: Pubkey,
+}
+
+#[event]
+pub struct ComptoirUpdatedEvent {
+    pub comptoir: Pubkey,
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub fees: u16,
+    pub fees_destination: Pubkey,
+}
+
+#[event]
+pub struct CollectionCreatedEvent {
+    pub collection: Pubkey,
+    pub comptoir: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub required_verifier: Pubkey,
+    pub fees: Option<u16>,
+    pub ignore_creator_fee: bool,
+}
+
+#[event]
+pub struct CollectionUpdatedEvent {
+    pub collection: Pubkey,
+    pub symbol: String,
+    pub required_verifier: Pubkey,
+    pub fees: Option<u16>,
+    pub ignore_creator_fee: bool,
+    pub traits_root: Option<[u8; 32]>,
+    pub certified_collection: Option<Pubkey>,
+    pub custom_currencies: bool,
+}
+
+#[event]
+pub struct SellOrderCreatedEvent {
+    pub sell_order: Pubkey,
+    pub comptoir: Pubkey,
+    pub collection: Pubkey,
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+    pub price: u64,
+    pub quantity: u64,
+    pub payment_mint: Pubkey,
+}
+
+#[event]
+pub struct SellOrderQuantityUpdatedEvent {
+    pub sell_order: Pubkey,
+    pub quantity: u64,
+}
+
+#[event]
+pub struct SellOrderRemovedEvent {
+    pub sell_order: Pubkey,
 }
 
+// The price is the total paid by the buyer, split between the seller, the comptoir and the creators
 #[event]
 pub struct BoughtSellOrderEvent {
     pub sell_order: Pubkey,
     pub quantity: u64,
     pub buyer: Pubkey,
+    pub mint: Pubkey,
+    pub price: u64,
+    pub payment_mint: Pubkey,
+    pub seller_amount: u64,
+    pub comptoir_amount: u64,
+    pub creators: Vec<CreatorPayment>,
+}
+
+// Collection offers have no mint
+#[event]
+pub struct BuyOfferCreatedEvent {
+    pub buy_offer: Pubkey,
+    pub comptoir: Pubkey,
+    pub mint: Pubkey,
+    pub collection: Pubkey,
+    pub authority: Pubkey,
+    pub price: u64,
+    pub quantity: u64,
+    pub payment_mint: Pubkey,
+}
+
+#[event]
+pub struct BuyOfferRemovedEvent {
+    pub buy_offer: Pubkey,
+}
+
+#[event]
+pub struct BuyOfferExecutedEvent {
+    pub buy_offer: Pubkey,
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    pub quantity: u64,
+    pub price: u64,
+    pub payment_mint: Pubkey,
+    pub seller_amount: u64,
+    pub comptoir_amount: u64,
+    pub creators: Vec<CreatorPayment>,
+}
+
+// Sent when the currency is added too
+#[event]
+pub struct CurrencyUpdatedEvent {
+    pub currency: Pubkey,
+    pub comptoir: Pubkey,
+    pub scope: Pubkey,
+    pub mint: Pubkey,
+    pub fees_destination: Pubkey,
+}
+
+#[event]
+pub struct CurrencyRemovedEvent {
+    pub currency: Pubkey,
+}
+
+#[event]
+pub struct BundleCreatedEvent {
+    pub bundle_order: Pubkey,
+    pub comptoir: Pubkey,
+    pub authority: Pubkey,
+    pub price: u64,
+    pub payment_mint: Pubkey,
+}
+
+#[event]
+pub struct BundleItemAddedEvent {
+    pub bundle_order: Pubkey,
+    pub mint: Pubkey,
+    pub collection: Pubkey,
+}
+
+#[event]
+pub struct BundleRemovedEvent {
+    pub bundle_order: Pubkey,
+}
+
+// The amounts add up the shares of every item of the bundle
+#[event]
+pub struct BundleBoughtEvent {
+    pub bundle_order: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub payment_mint: Pubkey,
+    pub seller_amount: u64,
+    pub comptoir_amount: u64,
+    pub creators: Vec<CreatorPayment>,
+}
+
+#[event]
+pub struct SwapOfferCreatedEvent {
+    pub swap_offer: Pubkey,
+    pub comptoir: Pubkey,
+    pub authority: Pubkey,
+    pub token_amount: u64,
+    pub payment_mint: Pubkey,
+    pub requested: Vec<SwapAsk>,
+}
+
+#[event]
+pub struct SwapItemAddedEvent {
+    pub swap_offer: Pubkey,
+    pub mint: Pubkey,
+}
+
+#[event]
+pub struct SwapOfferRemovedEvent {
+    pub swap_offer: Pubkey,
+}
+
+#[event]
+pub struct SwapOfferAcceptedEvent {
+    pub swap_offer: Pubkey,
+    pub counterparty: Pubkey,
+    pub offered_mints: Vec<Pubkey>,
+    pub received_mints: Vec<Pubkey>,
+    pub token_amount: u64,
+    pub comptoir_amount: u64,
+}
+
+#[event]
+pub struct AuctionCreatedEvent {
+    pub auction: Pubkey,
+    pub comptoir: Pubkey,
+    pub collection: Pubkey,
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+    pub quantity: u64,
+    pub reserve_price: u64,
+    pub payment_mint: Pubkey,
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+#[event]
+pub struct AuctionCancelledEvent {
+    pub auction: Pubkey,
+}
+
+// Auctions settled without a sale, refunded_bid is the winning bid made refundable when its currency was no longer accepted
+#[event]
+pub struct AuctionUnsoldEvent {
+    pub auction: Pubkey,
+    pub refunded_bid: Option<Pubkey>,
+}
+
+#[event]
+pub struct AuctionBidEvent {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct AuctionBidRefundedEvent {
+    pub auction: Pubkey,
+    pub bid: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct AuctionSettledEvent {
+    pub auction: Pubkey,
+    pub winner: Pubkey,
+    pub price: u64,
+    pub quantity: u64,
 }
 
 #[error_code]
@@ -967,5 +8092,147 @@ pub enum ErrorCode {
     #[msg("Derived key invalid")]
     DerivedKeyInvalid,
     #[msg("AccountNotInitialized")]
-    NotInitialized
+    NotInitialized,
+    #[msg("Auction end time should be after its start time and in the future")]
+    ErrInvalidAuctionTimes,
+    #[msg("Minimum bid increment should be > 0")]
+    ErrMinBidIncrementShouldBePositive,
+    #[msg("Auction has not started yet")]
+    ErrAuctionNotStarted,
+    #[msg("Auction has ended")]
+    ErrAuctionEnded,
+    #[msg("Auction has not ended yet")]
+    ErrAuctionNotEnded,
+    #[msg("Bid is lower than the minimum bid")]
+    ErrBidTooLow,
+    #[msg("Auction already has bids")]
+    ErrAuctionHasBids,
+    #[msg("Previous bidder account does not match the auction")]
+    ErrWrongPreviousBidderAccount,
+    #[msg("Winner account does not match the auction")]
+    ErrWrongWinnerAccount,
+    #[msg("Dutch auction end price should be <= start price")]
+    ErrEndPriceHigherThanStartPrice,
+    #[msg("Price schedule end time should be after its start time and steps should be > 0")]
+    ErrInvalidPriceScheduleTimes,
+    #[msg("Expiration should be in the future")]
+    ErrExpirationInThePast,
+    #[msg("Sell order has expired")]
+    ErrSellOrderExpired,
+    #[msg("Buy offer has expired")]
+    ErrBuyOfferExpired,
+    #[msg("Not expired yet")]
+    ErrNotExpired,
+    #[msg("Item is not targeted by the offer")]
+    ErrItemNotTargetedByOffer,
+    #[msg("Destination does not match the offer")]
+    ErrWrongOfferDestination,
+    #[msg("Collection has no traits root")]
+    ErrCollectionHasNoTraitsRoot,
+    #[msg("Trait proof is invalid")]
+    ErrInvalidTraitProof,
+    #[msg("Asset id does not match the merkle tree and nonce")]
+    ErrWrongAssetId,
+    #[msg("Metadata does not match the listed item")]
+    ErrCompressedMetadataMismatch,
+    #[msg("Total price is above the buyer maximum")]
+    ErrMaxTotalPriceExceeded,
+    #[msg("Unit price is above the buyer maximum")]
+    ErrMaxUnitPriceExceeded,
+    #[msg("Sell order items are still in the legacy vault")]
+    ErrSellOrderVaultNotMigrated,
+    #[msg("Sell order vault is already migrated")]
+    ErrSellOrderVaultAlreadyMigrated,
+    #[msg("Order id does not match the seller order counter")]
+    ErrWrongOrderId,
+    #[msg("Offer currency is not accepted anymore, it can only be removed")]
+    ErrOfferCurrencyChanged,
+    #[msg("Paying account is not owned by the buyer")]
+    ErrWrongBuyerPayingAccount,
+    #[msg("Currency is not accepted by the collection")]
+    ErrCurrencyNotAccepted,
+    #[msg("Order or escrow is in another currency")]
+    ErrWrongCurrency,
+    #[msg("Fees destination does not match the currency")]
+    ErrWrongFeesDestination,
+    #[msg("Price feed is invalid or does not match the sell order")]
+    ErrInvalidPriceFeed,
+    #[msg("Price feed is stale")]
+    ErrStalePrice,
+    #[msg("Price feed confidence interval is too wide")]
+    ErrPriceConfidenceTooWide,
+    #[msg("Oracle max staleness should be > 0 and max confidence <= 10000")]
+    ErrInvalidOraclePrice,
+    #[msg("Quantity should be > 0 and <= the quantity left on the offer")]
+    ErrWrongOfferQuantity,
+    #[msg("Only fixed price orders of the book mint and currency can be booked")]
+    ErrOrderNotBookable,
+    #[msg("Order is already in the book")]
+    ErrOrderAlreadyInBook,
+    #[msg("Order is not in the book")]
+    ErrOrderNotInBook,
+    #[msg("Order book is full")]
+    ErrOrderBookFull,
+    #[msg("Best ask is above best bid")]
+    ErrNothingToMatch,
+    #[msg("Accounts do not match the book orders")]
+    ErrWrongOrderAccounts,
+    #[msg("Bundle is full")]
+    ErrBundleFull,
+    #[msg("Bundle has no items")]
+    ErrBundleEmpty,
+    #[msg("Item is already in the bundle")]
+    ErrItemAlreadyInBundle,
+    #[msg("Accounts do not match the bundle items")]
+    ErrWrongBundleAccounts,
+    #[msg("Wrong vault account")]
+    ErrWrongVault,
+    #[msg("Swap offers must request between one and four items")]
+    ErrWrongSwapRequest,
+    #[msg("Swap offer is full")]
+    ErrSwapFull,
+    #[msg("Item is already in the swap offer")]
+    ErrItemAlreadyInSwap,
+    #[msg("Swap offer has nothing to give")]
+    ErrSwapEmpty,
+    #[msg("Swap offer has expired")]
+    ErrSwapOfferExpired,
+    #[msg("Item does not match the swap request")]
+    ErrWrongSwapItem,
+    #[msg("Account does not belong to this comptoir")]
+    ErrWrongComptoir,
+    #[msg("Wrong mint")]
+    ErrWrongMint,
+    #[msg("Wrong seller destination account")]
+    ErrWrongSellerDestination,
+    #[msg("Creator account does not match the metadata creators")]
+    ErrWrongCreatorAccount,
+    #[msg("Arithmetic overflow")]
+    ErrArithmeticOverflow,
+    #[msg("Missing remaining accounts")]
+    ErrMissingRemainingAccounts,
+    #[msg("Auction quantity should be positive")]
+    ErrWrongAuctionQuantity,
+    #[msg("Bid is still the highest bid")]
+    ErrBidNotOutbid,
+    #[msg("The comptoir no longer accepts the auction currency")]
+    ErrAuctionCurrencyChanged,
+    #[msg("Account is not in the legacy layout")]
+    ErrWrongLegacyAccount,
+    #[msg("Signer is not the buy offer authority")]
+    ErrWrongBuyOfferAuthority,
+    #[msg("Item is not a programmable NFT")]
+    ErrNotProgrammable,
+    #[msg("Wrong token metadata or authorization rules account")]
+    ErrWrongProgrammableAccounts,
+    #[msg("Mint is not owned by the expected token program")]
+    ErrWrongTokenProgram,
+    #[msg("Mints with a transfer hook are not supported")]
+    ErrTransferHookNotSupported,
+    #[msg("Owner already has the maximum number of orders on this side of the book")]
+    ErrOrderBookOwnerLimit,
+    #[msg("Wrong transfer hook program, validation or extra accounts")]
+    ErrWrongTransferHookAccounts,
+    #[msg("Escrow is not a token account of the currency owning itself")]
+    ErrWrongEscrow,
 }
@@ -0,0 +1,25 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::keccak;
+
+// Leaves and nodes are prefixed differently so that a node can never be passed off as a leaf
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+// A collection traits tree holds one leaf per (mint, trait) pair, the trait hash being computed off chain
+// (e.g. keccak256("rarity:Legendary"))
+pub fn trait_leaf(mint: &Pubkey, trait_hash: &[u8; 32]) -> [u8; 32] {
+    keccak::hashv(&[LEAF_PREFIX, mint.as_ref(), trait_hash]).0
+}
+
+// Pairs are hashed sorted so proofs do not need to carry the position of each node
+pub fn verify_proof(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    let mut computed_hash = leaf;
+    for proof_element in proof.iter() {
+        if computed_hash <= *proof_element {
+            computed_hash = keccak::hashv(&[NODE_PREFIX, &computed_hash, proof_element]).0;
+        } else {
+            computed_hash = keccak::hashv(&[NODE_PREFIX, proof_element, &computed_hash]).0;
+        }
+    }
+    computed_hash == root
+}
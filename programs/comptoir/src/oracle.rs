@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+use std::convert::TryInto;
+use std::str::FromStr;
+use crate::constant::PYTH_PROGRAM;
+use crate::ErrorCode;
+
+const PYTH_MAGIC: u32 = 0xa1b2c3d4;
+const PYTH_VERSION: u32 = 2;
+const PYTH_PRICE_ACCOUNT_TYPE: u32 = 3;
+const PYTH_STATUS_TRADING: u32 = 1;
+const PYTH_PRICE_ACCOUNT_MIN_LEN: usize = 240;
+
+// Aggregate price of a pyth price account, one whole token is worth price * 10^expo reference units
+pub struct PriceFeed {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_i32(data: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_i64(data: &[u8], offset: usize) -> i64 {
+    i64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+// Reads the pyth v2 price account layout, only trading prices are usable
+pub fn load_price_feed(price_feed_account: &AccountInfo) -> Result<PriceFeed> {
+    if *price_feed_account.owner != Pubkey::from_str(PYTH_PROGRAM).unwrap() {
+        return Err(error!(ErrorCode::ErrInvalidPriceFeed));
+    }
+    let data = price_feed_account.try_borrow_data()?;
+    if data.len() < PYTH_PRICE_ACCOUNT_MIN_LEN
+        || read_u32(&data, 0) != PYTH_MAGIC
+        || read_u32(&data, 4) != PYTH_VERSION
+        || read_u32(&data, 8) != PYTH_PRICE_ACCOUNT_TYPE
+        || read_u32(&data, 224) != PYTH_STATUS_TRADING {
+        return Err(error!(ErrorCode::ErrInvalidPriceFeed));
+    }
+
+    Ok(PriceFeed {
+        expo: read_i32(&data, 20),
+        publish_time: read_i64(&data, 96),
+        price: read_i64(&data, 208),
+        conf: read_u64(&data, 216),
+    })
+}
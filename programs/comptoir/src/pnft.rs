@@ -0,0 +1,163 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::Key as _;
+use mpl_token_metadata::state::{Collection, Creator, Data, Key, Metadata, Uses};
+use crate::ErrorCode;
+
+// Programmable NFTs are newer than the metadata crate the program is built against, their standard and
+// config are read by hand
+const PROGRAMMABLE_NON_FUNGIBLE: u8 = 4;
+// Transfer instruction of token metadata, taking TransferArgs::V1
+const TRANSFER_INSTRUCTION: u8 = 49;
+const TRANSFER_ARGS_V1: u8 = 0;
+
+#[derive(AnchorDeserialize)]
+enum CollectionDetails {
+    V1 { _size: u64 },
+}
+
+#[derive(AnchorDeserialize)]
+enum ProgrammableConfig {
+    V1 { rule_set: Option<Pubkey> },
+}
+
+#[derive(AnchorDeserialize)]
+struct ProgrammableMetadata {
+    key: u8,
+    update_authority: Pubkey,
+    mint: Pubkey,
+    name: String,
+    symbol: String,
+    uri: String,
+    seller_fee_basis_points: u16,
+    creators: Option<Vec<Creator>>,
+    primary_sale_happened: bool,
+    is_mutable: bool,
+    edition_nonce: Option<u8>,
+    token_standard: Option<u8>,
+    collection: Option<Collection>,
+    uses: Option<Uses>,
+    _collection_details: Option<CollectionDetails>,
+    programmable_config: Option<ProgrammableConfig>,
+}
+
+// Returns the metadata as the rest of the program knows it, with the rule set the transfers are checked against
+pub fn load_programmable_metadata(data: &[u8]) -> Result<(Metadata, Option<Pubkey>)> {
+    let parsed = ProgrammableMetadata::deserialize(&mut &data[..]).map_err(|_| error!(ErrorCode::ErrNotProgrammable))?;
+    if parsed.key != Key::MetadataV1 as u8 || parsed.token_standard != Some(PROGRAMMABLE_NON_FUNGIBLE) {
+        return Err(error!(ErrorCode::ErrNotProgrammable));
+    }
+    let rule_set = match parsed.programmable_config {
+        Some(ProgrammableConfig::V1 { rule_set }) => rule_set,
+        None => None,
+    };
+    let metadata = Metadata {
+        key: Key::MetadataV1,
+        update_authority: parsed.update_authority,
+        mint: parsed.mint,
+        data: Data {
+            name: parsed.name,
+            symbol: parsed.symbol,
+            uri: parsed.uri,
+            seller_fee_basis_points: parsed.seller_fee_basis_points,
+            creators: parsed.creators,
+        },
+        primary_sale_happened: parsed.primary_sale_happened,
+        is_mutable: parsed.is_mutable,
+        edition_nonce: parsed.edition_nonce,
+        token_standard: None,
+        collection: parsed.collection,
+        uses: parsed.uses,
+    };
+    Ok((metadata, rule_set))
+}
+
+// Accounts of the token metadata Transfer instruction, absent optional accounts being the token metadata program
+pub struct TransferProgrammable<'info> {
+    pub token: AccountInfo<'info>,
+    pub token_owner: AccountInfo<'info>,
+    pub destination: AccountInfo<'info>,
+    pub destination_owner: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    pub metadata: AccountInfo<'info>,
+    pub edition: AccountInfo<'info>,
+    pub owner_token_record: AccountInfo<'info>,
+    pub destination_token_record: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+    pub payer: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub sysvar_instructions: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub associated_token_program: AccountInfo<'info>,
+    pub authorization_rules_program: AccountInfo<'info>,
+    pub authorization_rules: AccountInfo<'info>,
+    pub token_metadata_program: AccountInfo<'info>,
+}
+
+impl<'info> TransferProgrammable<'info> {
+    fn instruction(&self, amount: u64) -> Instruction {
+        let mut data = vec![TRANSFER_INSTRUCTION, TRANSFER_ARGS_V1];
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.push(0); // No authorization data
+        Instruction {
+            program_id: mpl_token_metadata::id(),
+            accounts: vec![
+                AccountMeta::new(self.token.key(), false),
+                AccountMeta::new_readonly(self.token_owner.key(), false),
+                AccountMeta::new(self.destination.key(), false),
+                AccountMeta::new_readonly(self.destination_owner.key(), false),
+                AccountMeta::new_readonly(self.mint.key(), false),
+                AccountMeta::new(self.metadata.key(), false),
+                AccountMeta::new_readonly(self.edition.key(), false),
+                AccountMeta::new(self.owner_token_record.key(), false),
+                AccountMeta::new(self.destination_token_record.key(), false),
+                AccountMeta::new_readonly(self.authority.key(), true),
+                AccountMeta::new(self.payer.key(), true),
+                AccountMeta::new_readonly(self.system_program.key(), false),
+                AccountMeta::new_readonly(self.sysvar_instructions.key(), false),
+                AccountMeta::new_readonly(self.token_program.key(), false),
+                AccountMeta::new_readonly(self.associated_token_program.key(), false),
+                AccountMeta::new_readonly(self.authorization_rules_program.key(), false),
+                AccountMeta::new_readonly(self.authorization_rules.key(), false),
+            ],
+            data,
+        }
+    }
+
+    // The rule set of the item must be the one passed, when it has none the rules accounts are placeholders
+    pub fn transfer(&self, rule_set: Option<Pubkey>, amount: u64, signer: &[&[&[u8]]]) -> Result<()> {
+        if self.token_metadata_program.key() != mpl_token_metadata::id() {
+            return Err(error!(ErrorCode::ErrWrongProgrammableAccounts));
+        }
+        let expected_rules = rule_set.unwrap_or_else(mpl_token_metadata::id);
+        if self.authorization_rules.key() != expected_rules {
+            return Err(error!(ErrorCode::ErrWrongProgrammableAccounts));
+        }
+        invoke_signed(
+            &self.instruction(amount),
+            &[
+                self.token.clone(),
+                self.token_owner.clone(),
+                self.destination.clone(),
+                self.destination_owner.clone(),
+                self.mint.clone(),
+                self.metadata.clone(),
+                self.edition.clone(),
+                self.owner_token_record.clone(),
+                self.destination_token_record.clone(),
+                self.authority.clone(),
+                self.payer.clone(),
+                self.system_program.clone(),
+                self.sysvar_instructions.clone(),
+                self.token_program.clone(),
+                self.associated_token_program.clone(),
+                self.authorization_rules_program.clone(),
+                self.authorization_rules.clone(),
+                self.token_metadata_program.clone(),
+            ],
+            signer,
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,428 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::system_program;
+use std::convert::TryInto;
+use std::str::FromStr;
+use crate::constant::{ASSOCIATED_TOKEN_PROGRAM, TOKEN_2022_PROGRAM};
+use crate::ErrorCode;
+
+// Token-2022 is newer than the token crate the program is built against, its mints and instructions are handled by hand
+const MINT_LEN: usize = 82;
+const ACCOUNT_LEN: usize = 165;
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const EXTENSION_TRANSFER_FEE_CONFIG: u16 = 1;
+const EXTENSION_NON_TRANSFERABLE: u16 = 9;
+const EXTENSION_TRANSFER_HOOK: u16 = 14;
+// Token accounts of a mint carry the account side of these extensions: the withheld fee, the non transferable flag
+// and the hook transferring flag
+const TRANSFER_FEE_AMOUNT_LEN: usize = 4 + 8;
+const NON_TRANSFERABLE_ACCOUNT_LEN: usize = 4;
+const TRANSFER_HOOK_ACCOUNT_LEN: usize = 4 + 1;
+const TRANSFER_CHECKED_INSTRUCTION: u8 = 12;
+const INITIALIZE_ACCOUNT2_INSTRUCTION: u8 = 16;
+const CREATE_IDEMPOTENT_INSTRUCTION: u8 = 1;
+const EXTRA_ACCOUNT_METAS_SEED: &str = "extra-account-metas";
+
+pub fn token_2022_program() -> Pubkey {
+    Pubkey::from_str(TOKEN_2022_PROGRAM).unwrap()
+}
+
+#[derive(Clone, Copy)]
+pub struct TransferFee {
+    pub epoch: u64,
+    pub maximum_fee: u64,
+    pub transfer_fee_basis_points: u16,
+}
+
+impl TransferFee {
+    // Same rounding as the token program, the fee is rounded up and capped
+    pub fn calculate(&self, amount: u64) -> Result<u64> {
+        if self.transfer_fee_basis_points == 0 || amount == 0 {
+            return Ok(0);
+        }
+        let fee = (amount as u128)
+            .checked_mul(self.transfer_fee_basis_points as u128)
+            .and_then(|n| n.checked_add(9_999))
+            .ok_or(ErrorCode::ErrArithmeticOverflow)?
+            / 10_000;
+        Ok(std::cmp::min(fee as u64, self.maximum_fee))
+    }
+
+    // Amount to send for the recipient to receive post_fee_amount, the inverse of calculate
+    pub fn pre_fee_amount(&self, post_fee_amount: u64) -> Result<u64> {
+        if self.transfer_fee_basis_points == 0 || post_fee_amount == 0 {
+            return Ok(post_fee_amount);
+        }
+        let maximum = post_fee_amount.checked_add(self.maximum_fee).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+        if self.transfer_fee_basis_points >= 10_000 {
+            return Ok(maximum);
+        }
+        let denominator = 10_000 - self.transfer_fee_basis_points as u128;
+        let pre_fee_amount = (post_fee_amount as u128)
+            .checked_mul(10_000)
+            .and_then(|n| n.checked_add(denominator - 1))
+            .ok_or(ErrorCode::ErrArithmeticOverflow)?
+            / denominator;
+        Ok(std::cmp::min(pre_fee_amount, maximum as u128) as u64)
+    }
+}
+
+pub struct MintInfo {
+    pub decimals: u8,
+    pub transfer_hook: Option<Pubkey>,
+    pub account_len: usize,
+    older_transfer_fee: Option<TransferFee>,
+    newer_transfer_fee: Option<TransferFee>,
+}
+
+impl MintInfo {
+    fn current_transfer_fee(&self, epoch: u64) -> Option<TransferFee> {
+        match (self.older_transfer_fee, self.newer_transfer_fee) {
+            (Some(older), Some(newer)) => Some(if epoch >= newer.epoch { newer } else { older }),
+            _ => None,
+        }
+    }
+
+    pub fn transfer_fee(&self, amount: u64, epoch: u64) -> Result<u64> {
+        match self.current_transfer_fee(epoch) {
+            Some(transfer_fee) => transfer_fee.calculate(amount),
+            None => Ok(0),
+        }
+    }
+
+    // What the recipient of a transfer of amount receives
+    pub fn received(&self, amount: u64, epoch: u64) -> Result<u64> {
+        let received = amount
+            .checked_sub(self.transfer_fee(amount, epoch)?)
+            .ok_or(ErrorCode::ErrArithmeticOverflow)?;
+        Ok(received)
+    }
+
+    // What to send for the recipient to receive amount, escrows are funded this way so that they hold what they owe
+    pub fn pre_fee_amount(&self, amount: u64, epoch: u64) -> Result<u64> {
+        match self.current_transfer_fee(epoch) {
+            Some(transfer_fee) => transfer_fee.pre_fee_amount(amount),
+            None => Ok(amount),
+        }
+    }
+}
+
+fn read_transfer_fee(data: &[u8]) -> TransferFee {
+    TransferFee {
+        epoch: u64::from_le_bytes(data[0..8].try_into().unwrap()),
+        maximum_fee: u64::from_le_bytes(data[8..16].try_into().unwrap()),
+        transfer_fee_basis_points: u16::from_le_bytes(data[16..18].try_into().unwrap()),
+    }
+}
+
+// Reads the decimals, transfer fees and transfer hook of a mint of either token program, and the size of its token
+// accounts
+pub fn load_mint(data: &[u8]) -> Result<MintInfo> {
+    if data.len() < MINT_LEN || data[45] != 1 {
+        return Err(error!(ErrorCode::ErrWrongTokenProgram));
+    }
+    let mut mint = MintInfo {
+        decimals: data[44],
+        transfer_hook: None,
+        account_len: ACCOUNT_LEN,
+        older_transfer_fee: None,
+        newer_transfer_fee: None,
+    };
+    if data.len() <= ACCOUNT_LEN {
+        return Ok(mint);
+    }
+    if data[ACCOUNT_LEN] != ACCOUNT_TYPE_MINT {
+        return Err(error!(ErrorCode::ErrWrongTokenProgram));
+    }
+    let mut account_extensions_len = 0;
+    let mut offset = ACCOUNT_LEN + 1;
+    while offset + 4 <= data.len() {
+        let extension_type = u16::from_le_bytes([data[offset], data[offset + 1]]);
+        let length = u16::from_le_bytes([data[offset + 2], data[offset + 3]]) as usize;
+        let value = data.get(offset + 4..offset + 4 + length).ok_or(ErrorCode::ErrWrongTokenProgram)?;
+        match extension_type {
+            0 => break,
+            EXTENSION_TRANSFER_FEE_CONFIG if length >= 108 => {
+                // Authorities and withheld amount come first, then the older and newer fees
+                mint.older_transfer_fee = Some(read_transfer_fee(&value[72..90]));
+                mint.newer_transfer_fee = Some(read_transfer_fee(&value[90..108]));
+                account_extensions_len += TRANSFER_FEE_AMOUNT_LEN;
+            }
+            EXTENSION_NON_TRANSFERABLE => {
+                account_extensions_len += NON_TRANSFERABLE_ACCOUNT_LEN;
+            }
+            EXTENSION_TRANSFER_HOOK if length >= 64 => {
+                // The authority comes first, then the hook program, unset when all zeros
+                let program_id = Pubkey::new(&value[32..64]);
+                if program_id != Pubkey::default() {
+                    mint.transfer_hook = Some(program_id);
+                }
+                account_extensions_len += TRANSFER_HOOK_ACCOUNT_LEN;
+            }
+            _ => {}
+        }
+        offset = offset + 4 + length;
+    }
+    if account_extensions_len > 0 {
+        mint.account_len = ACCOUNT_LEN + 1 + account_extensions_len;
+    }
+    Ok(mint)
+}
+
+// The token program of a mint is its owner, only the token program and Token-2022 are accepted
+pub fn load_mint_of(mint: &AccountInfo, token_program: &AccountInfo) -> Result<MintInfo> {
+    if mint.owner != token_program.key || (token_program.key() != spl_token::ID && token_program.key() != token_2022_program()) {
+        return Err(error!(ErrorCode::ErrWrongTokenProgram));
+    }
+    load_mint(&mint.try_borrow_data()?)
+}
+
+// The transfer hook of a mint is run by the token program with accounts passed first in the remaining accounts: the
+// hook program, its validation account and the extra accounts listed by the validation account. Returns them and the
+// remaining accounts following them
+pub fn split_hook_accounts<'c, 'info>(
+    mint: &Pubkey,
+    mint_info: &MintInfo,
+    remaining_accounts: &'c [AccountInfo<'info>],
+) -> Result<(&'c [AccountInfo<'info>], &'c [AccountInfo<'info>])> {
+    let hook_program = match mint_info.transfer_hook {
+        Some(hook_program) => hook_program,
+        None => return Ok((&[], remaining_accounts)),
+    };
+    let (validation_key, _) = Pubkey::find_program_address(
+        &[EXTRA_ACCOUNT_METAS_SEED.as_bytes(), mint.as_ref()],
+        &hook_program,
+    );
+    let (program, validation) = match (remaining_accounts.first(), remaining_accounts.get(1)) {
+        (Some(program), Some(validation)) => (program, validation),
+        _ => return Err(error!(ErrorCode::ErrWrongTransferHookAccounts)),
+    };
+    if program.key() != hook_program || validation.key() != validation_key {
+        return Err(error!(ErrorCode::ErrWrongTransferHookAccounts));
+    }
+    // The validation account holds a single TLV entry, its value starts with the number of extra accounts
+    let extra_accounts_count = validation.try_borrow_data()?
+        .get(12..16)
+        .map(|count| u32::from_le_bytes(count.try_into().unwrap()) as usize)
+        .ok_or(ErrorCode::ErrWrongTransferHookAccounts)?;
+    let hook_accounts_count = extra_accounts_count.checked_add(2).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+    if remaining_accounts.len() < hook_accounts_count {
+        return Err(error!(ErrorCode::ErrWrongTransferHookAccounts));
+    }
+    Ok(remaining_accounts.split_at(hook_accounts_count))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_checked<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    amount: u64,
+    decimals: u8,
+    hook_accounts: &[AccountInfo<'info>],
+    signer: &[&[&[u8]]],
+) -> Result<()> {
+    let mut data = vec![TRANSFER_CHECKED_INSTRUCTION];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(decimals);
+    let mut accounts = vec![
+        AccountMeta::new(from.key(), false),
+        AccountMeta::new_readonly(mint.key(), false),
+        AccountMeta::new(to.key(), false),
+        AccountMeta::new_readonly(authority.key(), true),
+    ];
+    let mut account_infos = vec![from.clone(), mint.clone(), to.clone(), authority.clone()];
+    for hook_account in hook_accounts {
+        if hook_account.is_writable {
+            accounts.push(AccountMeta::new(hook_account.key(), false));
+        } else {
+            accounts.push(AccountMeta::new_readonly(hook_account.key(), false));
+        }
+        account_infos.push(hook_account.clone());
+    }
+    account_infos.push(token_program.clone());
+    let instruction = Instruction {
+        program_id: token_program.key(),
+        accounts,
+        data,
+    };
+    invoke_signed(&instruction, &account_infos, signer)?;
+    Ok(())
+}
+
+// A currency moved with transfer_checked, along with the accounts of its transfer hook
+pub struct Token22Currency<'a, 'info> {
+    pub mint: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub info: MintInfo,
+    pub hook_accounts: &'a [AccountInfo<'info>],
+}
+
+impl<'a, 'info> Token22Currency<'a, 'info> {
+    // Returns what the recipient received once the transfer fee withheld
+    pub fn transfer(
+        &self,
+        from: &AccountInfo<'info>,
+        to: &AccountInfo<'info>,
+        authority: &AccountInfo<'info>,
+        amount: u64,
+        signer: &[&[&[u8]]],
+    ) -> Result<u64> {
+        transfer_checked(
+            &self.token_program,
+            from,
+            &self.mint,
+            to,
+            authority,
+            amount,
+            self.info.decimals,
+            self.hook_accounts,
+            signer,
+        )?;
+        self.info.received(amount, Clock::get()?.epoch)
+    }
+}
+
+// Loads a currency mint and takes the accounts of its transfer hook from the start of the remaining accounts, which
+// are returned without them
+pub fn load_token22_currency<'a, 'info>(
+    mint: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    remaining_accounts: &'a [AccountInfo<'info>],
+) -> Result<(Token22Currency<'a, 'info>, &'a [AccountInfo<'info>])> {
+    let info = load_mint_of(mint, token_program)?;
+    let (hook_accounts, remaining_accounts) = split_hook_accounts(&mint.key(), &info, remaining_accounts)?;
+    let currency = Token22Currency {
+        mint: mint.clone(),
+        token_program: token_program.clone(),
+        info,
+        hook_accounts,
+    };
+    Ok((currency, remaining_accounts))
+}
+
+// Escrows are token accounts at a program address, owning themselves. Anchor only creates them for the token program,
+// here they are created for the token program of the mint and sized after its extensions. An escrow created before
+// is only checked
+pub fn create_escrow<'info>(
+    payer: &AccountInfo<'info>,
+    escrow: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    rent: &AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+) -> Result<()> {
+    let mint_info = load_mint_of(mint, token_program)?;
+    if escrow.owner == token_program.key {
+        let data = escrow.try_borrow_data()?;
+        if data.len() < ACCOUNT_LEN || data[0..32] != mint.key().to_bytes() || data[32..64] != escrow.key().to_bytes() {
+            return Err(error!(ErrorCode::ErrWrongEscrow));
+        }
+        return Ok(());
+    }
+    if escrow.owner != &system_program::ID {
+        return Err(error!(ErrorCode::ErrWrongEscrow));
+    }
+
+    // Someone may have sent lamports to the escrow address already, the account is then funded, allocated and
+    // assigned rather than created
+    let required_lamports = Rent::get()?.minimum_balance(mint_info.account_len);
+    if escrow.lamports() == 0 {
+        let cpi_accounts = system_program::CreateAccount {
+            from: payer.clone(),
+            to: escrow.clone(),
+        };
+        system_program::create_account(
+            CpiContext::new_with_signer(system_program.clone(), cpi_accounts, signer),
+            required_lamports,
+            mint_info.account_len as u64,
+            token_program.key,
+        )?;
+    } else {
+        let missing_lamports = required_lamports.saturating_sub(escrow.lamports());
+        if missing_lamports > 0 {
+            let cpi_accounts = system_program::Transfer {
+                from: payer.clone(),
+                to: escrow.clone(),
+            };
+            system_program::transfer(CpiContext::new(system_program.clone(), cpi_accounts), missing_lamports)?;
+        }
+        let cpi_accounts = system_program::Allocate {
+            account_to_allocate: escrow.clone(),
+        };
+        system_program::allocate(
+            CpiContext::new_with_signer(system_program.clone(), cpi_accounts, signer),
+            mint_info.account_len as u64,
+        )?;
+        let cpi_accounts = system_program::Assign {
+            account_to_assign: escrow.clone(),
+        };
+        system_program::assign(
+            CpiContext::new_with_signer(system_program.clone(), cpi_accounts, signer),
+            token_program.key,
+        )?;
+    }
+
+    let mut data = vec![INITIALIZE_ACCOUNT2_INSTRUCTION];
+    data.extend_from_slice(escrow.key.as_ref());
+    let instruction = Instruction {
+        program_id: token_program.key(),
+        accounts: vec![
+            AccountMeta::new(escrow.key(), false),
+            AccountMeta::new_readonly(mint.key(), false),
+            AccountMeta::new_readonly(rent.key(), false),
+        ],
+        data,
+    };
+    invoke_signed(
+        &instruction,
+        &[escrow.clone(), mint.clone(), rent.clone(), token_program.clone()],
+        &[],
+    )?;
+    Ok(())
+}
+
+// Token-2022 accounts are sized after the mint extensions, the associated token program takes care of it
+pub fn create_associated_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    owner: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    associated_token_program: &AccountInfo<'info>,
+) -> Result<()> {
+    if associated_token_program.key() != Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM).unwrap() {
+        return Err(error!(ErrorCode::ErrWrongTokenProgram));
+    }
+    let instruction = Instruction {
+        program_id: associated_token_program.key(),
+        accounts: vec![
+            AccountMeta::new(payer.key(), true),
+            AccountMeta::new(account.key(), false),
+            AccountMeta::new_readonly(owner.key(), false),
+            AccountMeta::new_readonly(mint.key(), false),
+            AccountMeta::new_readonly(system_program.key(), false),
+            AccountMeta::new_readonly(token_program.key(), false),
+        ],
+        data: vec![CREATE_IDEMPOTENT_INSTRUCTION],
+    };
+    invoke_signed(
+        &instruction,
+        &[
+            payer.clone(),
+            account.clone(),
+            owner.clone(),
+            mint.clone(),
+            system_program.clone(),
+            token_program.clone(),
+            associated_token_program.clone(),
+        ],
+        &[],
+    )?;
+    Ok(())
+}
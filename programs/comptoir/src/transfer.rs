@@ -1,6 +1,9 @@
 use anchor_lang::prelude::{AccountInfo, CpiContext, Result};
+use anchor_lang::system_program;
 use anchor_spl::token;
 use anchor_spl::token::Transfer;
+use crate::ErrorCode;
+use crate::token22::transfer_checked;
 
 
 pub fn pay<'info>(
@@ -34,4 +37,81 @@ pub fn pay_with_signer<'info>(
     };
     let cpi_ctx = CpiContext::new_with_signer(token_program, cpi_accounts, signer);
     token::transfer(cpi_ctx, amount)
-}
\ No newline at end of file
+}
+
+pub fn pay_native<'info>(
+    payer: AccountInfo<'info>,
+    dest: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let cpi_accounts = system_program::Transfer {
+        from: payer,
+        to: dest,
+    };
+    let cpi_ctx = CpiContext::new(system_program, cpi_accounts);
+    system_program::transfer(cpi_ctx, amount)
+}
+
+// The payer has to be owned by the program, lamports are moved without a CPI
+pub fn pay_native_from_program_account<'info>(
+    payer: &AccountInfo<'info>,
+    dest: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    **payer.try_borrow_mut_lamports()? = payer.lamports().checked_sub(amount).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+    **dest.try_borrow_mut_lamports()? = dest.lamports().checked_add(amount).ok_or(ErrorCode::ErrArithmeticOverflow)?;
+    Ok(())
+}
+
+// Native SOL comptoirs pay wallets with system transfers instead of going through token accounts
+pub fn pay_from_wallet<'info>(
+    is_native: bool,
+    payer_token_account: AccountInfo<'info>,
+    dest: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    if is_native {
+        return pay_native(payer, dest, system_program, amount);
+    }
+    pay(payer_token_account, dest, payer, token_program, amount)
+}
+
+// Native SOL buy offers hold their funds in the buy offer account rather than in the comptoir escrow
+pub fn pay_from_escrow<'info>(
+    is_native: bool,
+    buy_offer: AccountInfo<'info>,
+    escrow: AccountInfo<'info>,
+    dest: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    amount: u64,
+    signer: &[&[&[u8]]]
+) -> Result<()> {
+    if is_native {
+        return pay_native_from_program_account(&buy_offer, &dest, amount);
+    }
+    pay_with_signer(escrow.clone(), dest, escrow, token_program, amount, signer)
+}
+
+// Token-2022 currencies need the mint and its decimals to be transferred, native SOL still goes wallet to wallet
+#[allow(clippy::too_many_arguments)]
+pub fn pay_checked<'info>(
+    is_native: bool,
+    token_program: AccountInfo<'info>,
+    payer_token_account: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    dest: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    amount: u64,
+    decimals: u8,
+    hook_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    if is_native {
+        return pay_native(payer, dest, system_program, amount);
+    }
+    transfer_checked(&token_program, &payer_token_account, &mint, &dest, &payer, amount, decimals, hook_accounts, &[])
+}
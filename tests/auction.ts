@@ -0,0 +1,437 @@
+import * as anchor from '@project-serum/anchor';
+import {Program, web3} from '@project-serum/anchor';
+import {Comptoir} from '../target/types/comptoir';
+import * as splToken from '@solana/spl-token';
+import {PublicKey} from "@solana/web3.js";
+import {ASSOCIATED_TOKEN_PROGRAM_ID, Token, TOKEN_PROGRAM_ID} from "@solana/spl-token";
+import assert from "assert";
+import {nft_data, nft_json_url} from "./data";
+import {createMint} from "./utils/utils";
+
+let provider = anchor.getProvider()
+anchor.setProvider(provider);
+
+const program = anchor.workspace.Comptoir as Program<Comptoir>;
+
+const sleep = (ms: number) => new Promise(resolve => setTimeout(resolve, ms));
+
+describe('english auction', () => {
+    let admin: web3.Keypair;
+    let adminTokenAccount: splToken.AccountInfo;
+    let creator: web3.Keypair;
+    let creatorTokenAccount: splToken.AccountInfo;
+    let seller: web3.Keypair;
+    let sellerTokenAccount: splToken.AccountInfo;
+    let firstBidder: web3.Keypair;
+    let firstBidderTokenAccount: splToken.AccountInfo;
+    let secondBidder: web3.Keypair;
+    let secondBidderTokenAccount: splToken.AccountInfo;
+    let secondBidderNftAccount: PublicKey;
+    let comptoirPDA: PublicKey;
+    let comptoirMint: splToken.Token;
+    let fee = 200;
+    let collectionName = "AURY"
+    let collectionPDA: PublicKey;
+    let nftMint: splToken.Token;
+    let metadataPDA: PublicKey;
+    let sellerNftAssociatedTokenAccount: PublicKey;
+    let programNftVaultPDA: PublicKey;
+    let auctionPDA: PublicKey;
+    let firstBidPDA: PublicKey;
+    let secondBidPDA: PublicKey;
+    let escrowPDA: PublicKey;
+    let endTime: number;
+
+    it('Prepare tests variables', async () => {
+        admin = anchor.web3.Keypair.generate()
+        creator = anchor.web3.Keypair.generate()
+        seller = anchor.web3.Keypair.generate()
+        firstBidder = anchor.web3.Keypair.generate()
+        secondBidder = anchor.web3.Keypair.generate()
+        for (let keypair of [admin, creator, seller, firstBidder, secondBidder]) {
+            let fromAirdropSignature = await provider.connection.requestAirdrop(
+                keypair.publicKey,
+                anchor.web3.LAMPORTS_PER_SOL,
+            );
+            await provider.connection.confirmTransaction(fromAirdropSignature);
+        }
+
+        [comptoirPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                admin.publicKey.toBuffer()
+            ],
+            program.programId,
+        )
+
+        comptoirMint = await splToken.Token.createMint(
+            provider.connection,
+            admin,
+            admin.publicKey,
+            null,
+            6,
+            splToken.TOKEN_PROGRAM_ID,
+        );
+
+        [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                comptoirMint.publicKey.toBuffer(),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+
+        adminTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(admin.publicKey);
+        creatorTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(creator.publicKey);
+        sellerTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(seller.publicKey);
+        firstBidderTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(firstBidder.publicKey);
+        secondBidderTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(secondBidder.publicKey);
+        await comptoirMint.mintTo(firstBidderTokenAccount.address, admin, [], 1300)
+        await comptoirMint.mintTo(secondBidderTokenAccount.address, admin, [], 1400)
+
+        [collectionPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                Buffer.from(collectionName),
+                comptoirPDA.toBuffer(),
+            ],
+            program.programId,
+        );
+
+        const data = nft_data(creator.publicKey);
+        const lamports = await Token.getMinBalanceRentForExemptMint(
+            provider.connection
+        );
+        const [mint, metadataAddr, tx] = await createMint(
+            creator.publicKey,
+            seller.publicKey,
+            lamports,
+            data,
+            nft_json_url
+        );
+        await provider.sendAndConfirm(tx, [mint, creator]);
+
+        metadataPDA = metadataAddr
+        nftMint = new Token(provider.connection, mint.publicKey, TOKEN_PROGRAM_ID, admin)
+
+        sellerNftAssociatedTokenAccount = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            nftMint.publicKey,
+            seller.publicKey
+        );
+        // Settling creates the winner associated token account
+        secondBidderNftAccount = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            nftMint.publicKey,
+            secondBidder.publicKey
+        );
+
+        [programNftVaultPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from('COMPTOIR'), Buffer.from("vault"), comptoirPDA.toBuffer(), nftMint.publicKey.toBuffer()],
+            program.programId,
+        );
+        [auctionPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                sellerNftAssociatedTokenAccount.toBuffer(),
+                Buffer.from("AUCTION"),
+            ],
+            program.programId,
+        );
+        [firstBidPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                auctionPDA.toBuffer(),
+                firstBidder.publicKey.toBuffer(),
+                Buffer.from("BID"),
+            ],
+            program.programId,
+        );
+        [secondBidPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                auctionPDA.toBuffer(),
+                secondBidder.publicKey.toBuffer(),
+                Buffer.from("BID"),
+            ],
+            program.programId,
+        );
+
+        await program.methods.createComptoir(comptoirMint.publicKey, fee, adminTokenAccount.address, admin.publicKey)
+            .accounts({
+                payer: admin.publicKey,
+                comptoir: comptoirPDA,
+                mint: comptoirMint.publicKey,
+                escrow: escrowPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc();
+
+        await program.methods.createCollection(collectionName, collectionName, creator.publicKey, null, false).accounts(
+            {
+                authority: admin.publicKey,
+                comptoir: comptoirPDA,
+                collection: collectionPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc()
+    });
+
+    it('fail: auction of no item', async () => {
+        let startTime = Math.floor(Date.now() / 1000) - 1;
+        await assert.rejects(
+            program.methods.createAuction(
+                new anchor.BN(500),
+                new anchor.BN(100),
+                new anchor.BN(startTime),
+                new anchor.BN(startTime + 8),
+                new anchor.BN(0),
+                sellerTokenAccount.address,
+            ).accounts({
+                payer: seller.publicKey,
+                sellerNftTokenAccount: sellerNftAssociatedTokenAccount,
+                comptoir: comptoirPDA,
+                collection: collectionPDA,
+                mint: nftMint.publicKey,
+                metadata: metadataPDA,
+                vault: programNftVaultPDA,
+                auction: auctionPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([seller]).rpc(),
+            (err: any) => err.error.errorCode.code === "ErrWrongAuctionQuantity"
+        );
+    });
+
+    it('create auction', async () => {
+        let startTime = Math.floor(Date.now() / 1000) - 1;
+        endTime = startTime + 15;
+
+        await program.methods.createAuction(
+            new anchor.BN(500),
+            new anchor.BN(100),
+            new anchor.BN(startTime),
+            new anchor.BN(endTime),
+            new anchor.BN(1),
+            sellerTokenAccount.address,
+        ).accounts({
+            payer: seller.publicKey,
+            sellerNftTokenAccount: sellerNftAssociatedTokenAccount,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            mint: nftMint.publicKey,
+            metadata: metadataPDA,
+            vault: programNftVaultPDA,
+            auction: auctionPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([seller]).rpc()
+
+        let auction = await program.account.auction.fetch(auctionPDA)
+        assert.equal(auction.reservePrice.toNumber(), 500);
+        assert.equal(auction.minBidIncrement.toNumber(), 100);
+        assert.equal(auction.highestBid.toNumber(), 0);
+        assert.equal(auction.authority.toString(), seller.publicKey.toString());
+        assert.equal(auction.paymentMint.toString(), comptoirMint.publicKey.toString());
+        let vault = await nftMint.getAccountInfo(programNftVaultPDA)
+        assert.equal(vault.amount.toNumber(), 1);
+    });
+
+    const placeBid = (bidder: web3.Keypair, bidderTokenAccount: PublicKey, bid: PublicKey, amount: number, previousBid?: PublicKey) =>
+        program.methods.placeBid(new anchor.BN(amount)).accounts({
+            bidder: bidder.publicKey,
+            bidderPayingAccount: bidderTokenAccount,
+            comptoir: comptoirPDA,
+            escrow: escrowPDA,
+            auction: auctionPDA,
+            bid: bid,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).remainingAccounts(previousBid ? [
+            {pubkey: previousBid, isWritable: true, isSigner: false},
+        ] : []).signers([bidder]).rpc()
+
+    const claimBidRefund = (bidder: web3.Keypair, bidderTokenAccount: PublicKey, bid: PublicKey) =>
+        program.methods.claimBidRefund().accounts({
+            bidder: bidder.publicKey,
+            bidderRefundAccount: bidderTokenAccount,
+            escrow: escrowPDA,
+            bid: bid,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        }).signers([bidder]).rpc()
+
+    it('fail: bid under the reserve price', async () => {
+        await assert.rejects(placeBid(firstBidder, firstBidderTokenAccount.address, firstBidPDA, 400));
+    });
+
+    it('place bid', async () => {
+        await placeBid(firstBidder, firstBidderTokenAccount.address, firstBidPDA, 1000)
+
+        let auction = await program.account.auction.fetch(auctionPDA)
+        assert.equal(auction.highestBid.toNumber(), 1000);
+        assert.equal(auction.highestBidder.toString(), firstBidder.publicKey.toString());
+        assert.equal(auction.highestBidAccount.toString(), firstBidPDA.toString());
+        let bid = await program.account.bid.fetch(firstBidPDA)
+        assert.equal(bid.amount.toNumber(), 1000);
+        assert.equal(bid.outbid, false);
+        let escrow = await comptoirMint.getAccountInfo(escrowPDA)
+        assert.equal(escrow.amount.toNumber(), 1000);
+    });
+
+    it('fail: claim back the highest bid', async () => {
+        await assert.rejects(
+            claimBidRefund(firstBidder, firstBidderTokenAccount.address, firstBidPDA),
+            (err: any) => err.error.errorCode.code === "ErrBidNotOutbid"
+        );
+    });
+
+    it('fail: outbid with less than the minimum increment', async () => {
+        await assert.rejects(placeBid(secondBidder, secondBidderTokenAccount.address, secondBidPDA, 1050, firstBidPDA));
+    });
+
+    it('outbid makes the previous bid refundable', async () => {
+        await placeBid(secondBidder, secondBidderTokenAccount.address, secondBidPDA, 1100, firstBidPDA)
+
+        let auction = await program.account.auction.fetch(auctionPDA)
+        assert.equal(auction.highestBid.toNumber(), 1100);
+        assert.equal(auction.highestBidder.toString(), secondBidder.publicKey.toString());
+        let escrow = await comptoirMint.getAccountInfo(escrowPDA)
+        assert.equal(escrow.amount.toNumber(), 2100);
+        let previousBid = await program.account.bid.fetch(firstBidPDA)
+        assert.equal(previousBid.outbid, true);
+
+        await claimBidRefund(firstBidder, firstBidderTokenAccount.address, firstBidPDA)
+        let refunded = await comptoirMint.getAccountInfo(firstBidderTokenAccount.address)
+        assert.equal(refunded.amount.toNumber(), 1300);
+        escrow = await comptoirMint.getAccountInfo(escrowPDA)
+        assert.equal(escrow.amount.toNumber(), 1100);
+        assert.equal(await provider.connection.getAccountInfo(firstBidPDA), null);
+    });
+
+    it('highest bidder raises their own bid', async () => {
+        await placeBid(secondBidder, secondBidderTokenAccount.address, secondBidPDA, 1200)
+
+        let auction = await program.account.auction.fetch(auctionPDA)
+        assert.equal(auction.highestBid.toNumber(), 1200);
+        assert.equal(auction.highestBidAccount.toString(), secondBidPDA.toString());
+        let bid = await program.account.bid.fetch(secondBidPDA)
+        assert.equal(bid.amount.toNumber(), 1200);
+        assert.equal(bid.outbid, false);
+        // Only the difference is taken
+        let escrow = await comptoirMint.getAccountInfo(escrowPDA)
+        assert.equal(escrow.amount.toNumber(), 1200);
+        let bidder = await comptoirMint.getAccountInfo(secondBidderTokenAccount.address)
+        assert.equal(bidder.amount.toNumber(), 200);
+    });
+
+    it('outbid bidder bids again without claiming their refund', async () => {
+        await placeBid(firstBidder, firstBidderTokenAccount.address, firstBidPDA, 1300, secondBidPDA)
+        let outbid = await program.account.bid.fetch(secondBidPDA)
+        assert.equal(outbid.outbid, true);
+
+        await placeBid(secondBidder, secondBidderTokenAccount.address, secondBidPDA, 1400, firstBidPDA)
+
+        let auction = await program.account.auction.fetch(auctionPDA)
+        assert.equal(auction.highestBid.toNumber(), 1400);
+        assert.equal(auction.highestBidder.toString(), secondBidder.publicKey.toString());
+        let bid = await program.account.bid.fetch(secondBidPDA)
+        assert.equal(bid.amount.toNumber(), 1400);
+        assert.equal(bid.outbid, false);
+        let previousBid = await program.account.bid.fetch(firstBidPDA)
+        assert.equal(previousBid.outbid, true);
+        let escrow = await comptoirMint.getAccountInfo(escrowPDA)
+        assert.equal(escrow.amount.toNumber(), 2700);
+        let bidder = await comptoirMint.getAccountInfo(secondBidderTokenAccount.address)
+        assert.equal(bidder.amount.toNumber(), 0);
+    });
+
+    it('fail: settle before the end', async () => {
+        await assert.rejects(
+            program.methods.settleAuction().accounts({
+                payer: provider.wallet.publicKey,
+                authority: seller.publicKey,
+                comptoir: comptoirPDA,
+                collection: collectionPDA,
+                comptoirDestAccount: adminTokenAccount.address,
+                escrow: escrowPDA,
+                destination: sellerTokenAccount.address,
+                sellerNftTokenAccount: sellerNftAssociatedTokenAccount,
+                mint: nftMint.publicKey,
+                winner: secondBidder.publicKey,
+                winnerNftAccount: secondBidderNftAccount,
+                winningBid: secondBidPDA,
+                metadata: metadataPDA,
+                auction: auctionPDA,
+                vault: programNftVaultPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).remainingAccounts([
+                {pubkey: creatorTokenAccount.address, isWritable: true, isSigner: false},
+            ]).rpc()
+        );
+    });
+
+    it('settle auction', async () => {
+        await sleep((endTime + 2) * 1000 - Date.now());
+
+        await program.methods.settleAuction().accounts({
+            payer: provider.wallet.publicKey,
+            authority: seller.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            comptoirDestAccount: adminTokenAccount.address,
+            escrow: escrowPDA,
+            destination: sellerTokenAccount.address,
+            sellerNftTokenAccount: sellerNftAssociatedTokenAccount,
+            mint: nftMint.publicKey,
+            winner: secondBidder.publicKey,
+            winnerNftAccount: secondBidderNftAccount,
+            winningBid: secondBidPDA,
+            metadata: metadataPDA,
+            auction: auctionPDA,
+            vault: programNftVaultPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).remainingAccounts([
+            {pubkey: creatorTokenAccount.address, isWritable: true, isSigner: false},
+        ]).rpc()
+
+        let winnerNftAccount = await nftMint.getAccountInfo(secondBidderNftAccount)
+        assert.equal(winnerNftAccount.amount.toNumber(), 1);
+
+        let updatedAdminTokenAccount = await comptoirMint.getAccountInfo(adminTokenAccount.address)
+        assert.equal(updatedAdminTokenAccount.amount.toNumber(), 28);
+
+        let updatedCreatorTokenAccount = await comptoirMint.getAccountInfo(creatorTokenAccount.address)
+        assert.equal(updatedCreatorTokenAccount.amount.toNumber(), 140);
+
+        let updatedSellerTokenAccount = await comptoirMint.getAccountInfo(sellerTokenAccount.address)
+        assert.equal(updatedSellerTokenAccount.amount.toNumber(), 1232);
+
+        // Only the outbid bid is left in escrow
+        let escrow = await comptoirMint.getAccountInfo(escrowPDA)
+        assert.equal(escrow.amount.toNumber(), 1300);
+
+        let closedAuction = await provider.connection.getAccountInfo(auctionPDA);
+        assert.equal(closedAuction, null);
+        assert.equal(await provider.connection.getAccountInfo(secondBidPDA), null);
+
+        await claimBidRefund(firstBidder, firstBidderTokenAccount.address, firstBidPDA)
+        let refunded = await comptoirMint.getAccountInfo(firstBidderTokenAccount.address)
+        assert.equal(refunded.amount.toNumber(), 1300);
+        escrow = await comptoirMint.getAccountInfo(escrowPDA)
+        assert.equal(escrow.amount.toNumber(), 0);
+    });
+});
@@ -0,0 +1,331 @@
+import * as anchor from '@project-serum/anchor';
+import {Program, web3} from '@project-serum/anchor';
+import {Comptoir} from '../target/types/comptoir';
+import * as splToken from '@solana/spl-token';
+import {PublicKey} from "@solana/web3.js";
+import {ASSOCIATED_TOKEN_PROGRAM_ID, Token, TOKEN_PROGRAM_ID} from "@solana/spl-token";
+import assert from "assert";
+import {nft_data, nft_json_url} from "./data";
+import {createMint} from "./utils/utils";
+
+let provider = anchor.getProvider()
+anchor.setProvider(provider);
+
+const program = anchor.workspace.Comptoir as Program<Comptoir>;
+
+describe('bundle orders', () => {
+    let admin: web3.Keypair;
+    let adminTokenAccount: splToken.AccountInfo;
+    let creator: web3.Keypair;
+    let creatorTokenAccount: splToken.AccountInfo;
+    let seller: web3.Keypair;
+    let sellerTokenAccount: splToken.AccountInfo;
+    let buyer: web3.Keypair;
+    let buyerTokenAccount: splToken.AccountInfo;
+    let comptoirPDA: PublicKey;
+    let comptoirMint: splToken.Token;
+    let collectionName = "AURY"
+    let collectionPDA: PublicKey;
+    let escrowPDA: PublicKey;
+    let bundleOrderPDA: PublicKey;
+    let items: {
+        mint: splToken.Token,
+        metadata: PublicKey,
+        sellerAccount: PublicKey,
+        buyerAccount: PublicKey,
+        vault: PublicKey,
+    }[] = [];
+    let price = 1000;
+
+    const getBundleOrderPDA = async (bundleId: anchor.BN): Promise<PublicKey> => {
+        return (await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                seller.publicKey.toBuffer(),
+                Buffer.from("BUNDLE"),
+                bundleId.toArrayLike(Buffer, "le", 8),
+            ],
+            program.programId,
+        ))[0];
+    }
+
+    const createBundle = async (bundleId: anchor.BN) => {
+        let bundleOrder = await getBundleOrderPDA(bundleId);
+        await program.methods.createBundleOrder(bundleId, new anchor.BN(price), sellerTokenAccount.address, null).accounts({
+            payer: seller.publicKey,
+            comptoir: comptoirPDA,
+            bundleOrder: bundleOrder,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([seller]).rpc()
+        for (let item of items) {
+            await program.methods.addBundleItem().accounts({
+                payer: seller.publicKey,
+                sellerNftTokenAccount: item.sellerAccount,
+                comptoir: comptoirPDA,
+                collection: collectionPDA,
+                bundleOrder: bundleOrder,
+                mint: item.mint.publicKey,
+                metadata: item.metadata,
+                vault: item.vault,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([seller]).rpc()
+        }
+        return bundleOrder
+    }
+
+    it('Prepare tests variables', async () => {
+        admin = anchor.web3.Keypair.generate()
+        creator = anchor.web3.Keypair.generate()
+        seller = anchor.web3.Keypair.generate()
+        buyer = anchor.web3.Keypair.generate()
+        for (let keypair of [admin, creator, seller, buyer]) {
+            let fromAirdropSignature = await provider.connection.requestAirdrop(
+                keypair.publicKey,
+                anchor.web3.LAMPORTS_PER_SOL,
+            );
+            await provider.connection.confirmTransaction(fromAirdropSignature);
+        }
+
+        [comptoirPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                admin.publicKey.toBuffer()
+            ],
+            program.programId,
+        )
+
+        comptoirMint = await splToken.Token.createMint(
+            provider.connection,
+            admin,
+            admin.publicKey,
+            null,
+            6,
+            splToken.TOKEN_PROGRAM_ID,
+        );
+
+        [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                comptoirMint.publicKey.toBuffer(),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+
+        adminTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(admin.publicKey);
+        creatorTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(creator.publicKey);
+        sellerTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(seller.publicKey);
+        buyerTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(buyer.publicKey);
+        await comptoirMint.mintTo(buyerTokenAccount.address, admin, [], 10_000);
+
+        [collectionPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                Buffer.from(collectionName),
+                comptoirPDA.toBuffer(),
+            ],
+            program.programId,
+        );
+
+        const lamports = await Token.getMinBalanceRentForExemptMint(
+            provider.connection
+        );
+        for (let i = 0; i < 2; i++) {
+            const [mint, metadataAddr, tx] = await createMint(
+                creator.publicKey,
+                seller.publicKey,
+                lamports,
+                nft_data(creator.publicKey),
+                nft_json_url
+            );
+            await provider.sendAndConfirm(tx, [mint, creator]);
+
+            let nftMint = new Token(provider.connection, mint.publicKey, TOKEN_PROGRAM_ID, admin)
+            let [vault] = await anchor.web3.PublicKey.findProgramAddress(
+                [Buffer.from('COMPTOIR'), Buffer.from("vault"), comptoirPDA.toBuffer(), nftMint.publicKey.toBuffer()],
+                program.programId,
+            );
+            items.push({
+                mint: nftMint,
+                metadata: metadataAddr,
+                sellerAccount: await Token.getAssociatedTokenAddress(
+                    ASSOCIATED_TOKEN_PROGRAM_ID,
+                    TOKEN_PROGRAM_ID,
+                    nftMint.publicKey,
+                    seller.publicKey
+                ),
+                buyerAccount: (await nftMint.getOrCreateAssociatedAccountInfo(buyer.publicKey)).address,
+                vault: vault,
+            })
+        }
+
+        await program.methods.createComptoir(comptoirMint.publicKey, 200, adminTokenAccount.address, admin.publicKey)
+            .accounts({
+                payer: admin.publicKey,
+                comptoir: comptoirPDA,
+                mint: comptoirMint.publicKey,
+                escrow: escrowPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc();
+
+        await program.methods.createCollection(collectionName, collectionName, creator.publicKey, null, false).accounts(
+            {
+                authority: admin.publicKey,
+                comptoir: comptoirPDA,
+                collection: collectionPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc()
+    });
+
+    it('remove bundle returns every item', async () => {
+        let bundleOrder = await createBundle(new anchor.BN(0));
+        let storedBundle = await program.account.bundleOrder.fetch(bundleOrder)
+        assert.equal(storedBundle.items.length, 2);
+
+        let remainingAccounts = [];
+        for (let item of items) {
+            remainingAccounts.push({pubkey: item.vault, isWritable: true, isSigner: false})
+            remainingAccounts.push({pubkey: item.sellerAccount, isWritable: true, isSigner: false})
+        }
+        await program.methods.removeBundleOrder().accounts({
+            authority: seller.publicKey,
+            comptoir: comptoirPDA,
+            bundleOrder: bundleOrder,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        }).remainingAccounts(remainingAccounts).signers([seller]).rpc()
+
+        for (let item of items) {
+            let sellerNftAccount = await item.mint.getAccountInfo(item.sellerAccount)
+            assert.equal(sellerNftAccount.amount.toNumber(), 1);
+        }
+    });
+
+    it('buy bundle splits royalties per item', async () => {
+        let bundleOrder = await createBundle(new anchor.BN(1));
+
+        let remainingAccounts = [];
+        for (let item of items) {
+            remainingAccounts.push({pubkey: collectionPDA, isWritable: false, isSigner: false})
+            remainingAccounts.push({pubkey: item.metadata, isWritable: false, isSigner: false})
+            remainingAccounts.push({pubkey: item.vault, isWritable: true, isSigner: false})
+            remainingAccounts.push({pubkey: item.buyerAccount, isWritable: true, isSigner: false})
+            remainingAccounts.push({pubkey: creatorTokenAccount.address, isWritable: true, isSigner: false})
+        }
+
+        await assert.rejects(program.methods.buyBundle(new anchor.BN(price - 1)).accounts({
+            buyer: buyer.publicKey,
+            buyerPayingTokenAccount: buyerTokenAccount.address,
+            comptoir: comptoirPDA,
+            comptoirDestAccount: adminTokenAccount.address,
+            sellerFundsDestAccount: sellerTokenAccount.address,
+            authority: seller.publicKey,
+            bundleOrder: bundleOrder,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        }).remainingAccounts(remainingAccounts).signers([buyer]).rpc());
+
+        let signature = await program.methods.buyBundle(new anchor.BN(price)).accounts({
+            buyer: buyer.publicKey,
+            buyerPayingTokenAccount: buyerTokenAccount.address,
+            comptoir: comptoirPDA,
+            comptoirDestAccount: adminTokenAccount.address,
+            sellerFundsDestAccount: sellerTokenAccount.address,
+            authority: seller.publicKey,
+            bundleOrder: bundleOrder,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        }).remainingAccounts(remainingAccounts).signers([buyer]).rpc()
+
+        for (let item of items) {
+            let buyerNftAccount = await item.mint.getAccountInfo(item.buyerAccount)
+            assert.equal(buyerNftAccount.amount.toNumber(), 1);
+        }
+        let updatedSellerTokenAccount = await comptoirMint.getAccountInfo(sellerTokenAccount.address)
+        assert.equal(updatedSellerTokenAccount.amount.toNumber(), 880);
+        let updatedAdminTokenAccount = await comptoirMint.getAccountInfo(adminTokenAccount.address)
+        assert.equal(updatedAdminTokenAccount.amount.toNumber(), 20);
+        let updatedCreatorTokenAccount = await comptoirMint.getAccountInfo(creatorTokenAccount.address)
+        assert.equal(updatedCreatorTokenAccount.amount.toNumber(), 100);
+        assert.equal(await provider.connection.getAccountInfo(bundleOrder), null);
+
+        // The sale event adds up the shares of both items
+        let transaction = await provider.connection.getTransaction(signature, {commitment: "confirmed"});
+        let events = [];
+        new anchor.EventParser(program.programId, program.coder).parseLogs(transaction.meta.logMessages, (event) => events.push(event));
+        let boughtEvent = events.find((event) => event.name === "BundleBoughtEvent");
+        assert.equal(boughtEvent.data.bundleOrder.toString(), bundleOrder.toString());
+        assert.equal(boughtEvent.data.price.toNumber(), price);
+        assert.equal(boughtEvent.data.sellerAmount.toNumber(), 880);
+        assert.equal(boughtEvent.data.comptoirAmount.toNumber(), 20);
+        assert.deepEqual(boughtEvent.data.creators.map((creator) => creator.amount.toNumber()), [50, 50]);
+    });
+    it('price a bundle in a registered currency', async () => {
+        const currencyMint = await splToken.Token.createMint(
+            provider.connection,
+            admin,
+            admin.publicKey,
+            null,
+            6,
+            splToken.TOKEN_PROGRAM_ID,
+        );
+        const adminCurrencyAccount = await currencyMint.getOrCreateAssociatedAccountInfo(admin.publicKey);
+        const sellerCurrencyAccount = await currencyMint.getOrCreateAssociatedAccountInfo(seller.publicKey);
+        const [currencyPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from("COMPTOIR"), comptoirPDA.toBuffer(), currencyMint.publicKey.toBuffer(), Buffer.from("CURRENCY")],
+            program.programId,
+        );
+        const [currencyEscrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from("COMPTOIR"), comptoirPDA.toBuffer(), currencyMint.publicKey.toBuffer(), Buffer.from("ESCROW")],
+            program.programId,
+        );
+        await program.methods.addCurrency(adminCurrencyAccount.address).accounts({
+            authority: admin.publicKey,
+            comptoir: comptoirPDA,
+            mint: currencyMint.publicKey,
+            currency: currencyPDA,
+            escrow: currencyEscrowPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([admin]).rpc()
+
+        const bundleOrder = await getBundleOrderPDA(new anchor.BN(2));
+        await program.methods.createBundleOrder(new anchor.BN(2), new anchor.BN(price), sellerCurrencyAccount.address, null).accounts({
+            payer: seller.publicKey,
+            comptoir: comptoirPDA,
+            bundleOrder: bundleOrder,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).remainingAccounts([
+            {pubkey: currencyPDA, isWritable: false, isSigner: false},
+        ]).signers([seller]).rpc()
+        let storedBundle = await program.account.bundleOrder.fetch(bundleOrder)
+        assert.equal(storedBundle.paymentMint.toString(), currencyMint.publicKey.toString());
+
+        // Fees of the bundle go to the destination of its currency
+        await assert.rejects(
+            program.methods.buyBundle(new anchor.BN(price)).accounts({
+                buyer: buyer.publicKey,
+                buyerPayingTokenAccount: buyerTokenAccount.address,
+                comptoir: comptoirPDA,
+                comptoirDestAccount: adminTokenAccount.address,
+                sellerFundsDestAccount: sellerCurrencyAccount.address,
+                authority: seller.publicKey,
+                bundleOrder: bundleOrder,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+            }).remainingAccounts([
+                {pubkey: currencyPDA, isWritable: false, isSigner: false},
+            ]).signers([buyer]).rpc(),
+            (err: any) => err.error.errorCode.code === "ErrWrongFeesDestination"
+        );
+    });
+});
@@ -0,0 +1,277 @@
+import * as anchor from '@project-serum/anchor';
+import {Program, web3} from '@project-serum/anchor';
+import {Comptoir} from '../target/types/comptoir';
+import * as splToken from '@solana/spl-token';
+import {PublicKey} from "@solana/web3.js";
+import {Token, TOKEN_PROGRAM_ID} from "@solana/spl-token";
+import assert from "assert";
+import {nft_data, nft_json_url} from "./data";
+import {createMint} from "./utils/utils";
+
+let provider = anchor.getProvider()
+anchor.setProvider(provider);
+
+const program = anchor.workspace.Comptoir as Program<Comptoir>;
+
+describe('collection buy offers', () => {
+    let admin: web3.Keypair;
+    let adminTokenAccount: splToken.AccountInfo;
+    let creator: web3.Keypair;
+    let creatorTokenAccount: splToken.AccountInfo;
+    let seller: web3.Keypair;
+    let sellerTokenAccount: splToken.AccountInfo;
+    let buyer: web3.Keypair;
+    let buyerTokenAccount: splToken.AccountInfo;
+    let comptoirPDA: PublicKey;
+    let comptoirMint: splToken.Token;
+    let collectionName = "AURY"
+    let collectionPDA: PublicKey;
+    let nftMints: splToken.Token[] = [];
+    let metadataPDAs: PublicKey[] = [];
+    let escrowPDA: PublicKey;
+    let buyOfferPDA: PublicKey;
+
+    const executeOffer = async (index: number) => {
+        let sellerNftAccount = await nftMints[index].getOrCreateAssociatedAccountInfo(seller.publicKey)
+        let buyerNftAccount = await nftMints[index].getOrCreateAssociatedAccountInfo(buyer.publicKey)
+
+        await program.methods.executeOffer(new anchor.BN(1), []).accounts({
+            seller: seller.publicKey,
+            buyer: buyer.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            comptoirDestAccount: adminTokenAccount.address,
+            escrow: escrowPDA,
+            sellerFundsDestAccount: sellerTokenAccount.address,
+            destination: buyerNftAccount.address,
+            sellerNftAccount: sellerNftAccount.address,
+            metadata: metadataPDAs[index],
+            buyOffer: buyOfferPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).remainingAccounts([
+            {pubkey: creatorTokenAccount.address, isWritable: true, isSigner: false},
+        ]).signers([seller]).rpc()
+    }
+
+    it('Prepare tests variables', async () => {
+        admin = anchor.web3.Keypair.generate()
+        creator = anchor.web3.Keypair.generate()
+        seller = anchor.web3.Keypair.generate()
+        buyer = anchor.web3.Keypair.generate()
+        for (let keypair of [admin, creator, seller, buyer]) {
+            let fromAirdropSignature = await provider.connection.requestAirdrop(
+                keypair.publicKey,
+                anchor.web3.LAMPORTS_PER_SOL,
+            );
+            await provider.connection.confirmTransaction(fromAirdropSignature);
+        }
+
+        [comptoirPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                admin.publicKey.toBuffer()
+            ],
+            program.programId,
+        )
+
+        comptoirMint = await splToken.Token.createMint(
+            provider.connection,
+            admin,
+            admin.publicKey,
+            null,
+            6,
+            splToken.TOKEN_PROGRAM_ID,
+        );
+
+        [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                comptoirMint.publicKey.toBuffer(),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+
+        adminTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(admin.publicKey);
+        creatorTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(creator.publicKey);
+        sellerTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(seller.publicKey);
+        buyerTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(buyer.publicKey);
+        await comptoirMint.mintTo(buyerTokenAccount.address, admin, [], 2000)
+
+        [collectionPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                Buffer.from(collectionName),
+                comptoirPDA.toBuffer(),
+            ],
+            program.programId,
+        );
+
+        const data = nft_data(creator.publicKey);
+        const lamports = await Token.getMinBalanceRentForExemptMint(
+            provider.connection
+        );
+        for (let i = 0; i < 2; i++) {
+            const [mint, metadataAddr, tx] = await createMint(
+                creator.publicKey,
+                seller.publicKey,
+                lamports,
+                data,
+                nft_json_url
+            );
+            await provider.sendAndConfirm(tx, [mint, creator]);
+            nftMints.push(new Token(provider.connection, mint.publicKey, TOKEN_PROGRAM_ID, admin));
+            metadataPDAs.push(metadataAddr);
+        }
+
+        [buyOfferPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                buyer.publicKey.toBuffer(),
+                collectionPDA.toBuffer(),
+                Buffer.from("1000"),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+
+        await program.methods.createComptoir(comptoirMint.publicKey, 200, adminTokenAccount.address, admin.publicKey)
+            .accounts({
+                payer: admin.publicKey,
+                comptoir: comptoirPDA,
+                mint: comptoirMint.publicKey,
+                escrow: escrowPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc();
+
+        await program.methods.createCollection(collectionName, collectionName, creator.publicKey, null, false).accounts(
+            {
+                authority: admin.publicKey,
+                comptoir: comptoirPDA,
+                collection: collectionPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc()
+    });
+
+    it('create collection offer', async () => {
+        await program.methods.createCollectionBuyOffer(new anchor.BN(1000), new anchor.BN(2), null).accounts({
+            payer: buyer.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            escrow: escrowPDA,
+            buyerPayingAccount: buyerTokenAccount.address,
+            buyOffer: buyOfferPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([buyer]).rpc()
+
+        let buyOffer = await program.account.buyOffer.fetch(buyOfferPDA)
+        assert.equal(buyOffer.mint.toString(), PublicKey.default.toString());
+        assert.equal(buyOffer.collection.toString(), collectionPDA.toString());
+        assert.equal(buyOffer.quantity.toNumber(), 2);
+
+        let escrow = await comptoirMint.getAccountInfo(escrowPDA)
+        assert.equal(escrow.amount.toNumber(), 2000);
+    });
+
+    it('any item of the collection fills the offer', async () => {
+        await executeOffer(0)
+
+        let buyOffer = await program.account.buyOffer.fetch(buyOfferPDA)
+        assert.equal(buyOffer.quantity.toNumber(), 1);
+        let buyerNftAccount = await nftMints[0].getOrCreateAssociatedAccountInfo(buyer.publicKey)
+        assert.equal(buyerNftAccount.amount.toNumber(), 1);
+
+        await executeOffer(1)
+
+        let closedBuyOffer = await provider.connection.getAccountInfo(buyOfferPDA);
+        assert.equal(closedBuyOffer, null);
+        buyerNftAccount = await nftMints[1].getOrCreateAssociatedAccountInfo(buyer.publicKey)
+        assert.equal(buyerNftAccount.amount.toNumber(), 1);
+
+        let escrow = await comptoirMint.getAccountInfo(escrowPDA)
+        assert.equal(escrow.amount.toNumber(), 0);
+        let updatedSellerTokenAccount = await comptoirMint.getAccountInfo(sellerTokenAccount.address)
+        assert.equal(updatedSellerTokenAccount.amount.toNumber(), 1760);
+        let updatedCreatorTokenAccount = await comptoirMint.getAccountInfo(creatorTokenAccount.address)
+        assert.equal(updatedCreatorTokenAccount.amount.toNumber(), 200);
+        let updatedAdminTokenAccount = await comptoirMint.getAccountInfo(adminTokenAccount.address)
+        assert.equal(updatedAdminTokenAccount.amount.toNumber(), 40);
+    });
+
+    it('offer funded before a currency change can still be removed', async () => {
+        await comptoirMint.mintTo(buyerTokenAccount.address, admin, [], 500)
+        let [offerPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                buyer.publicKey.toBuffer(),
+                collectionPDA.toBuffer(),
+                Buffer.from("500"),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+        await program.methods.createCollectionBuyOffer(new anchor.BN(500), new anchor.BN(1), null).accounts({
+            payer: buyer.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            escrow: escrowPDA,
+            buyerPayingAccount: buyerTokenAccount.address,
+            buyOffer: offerPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([buyer]).rpc()
+
+        let newComptoirMint = await splToken.Token.createMint(
+            provider.connection,
+            admin,
+            admin.publicKey,
+            null,
+            6,
+            splToken.TOKEN_PROGRAM_ID,
+        );
+        let [newEscrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                newComptoirMint.publicKey.toBuffer(),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+        let newAdminTokenAccount = await newComptoirMint.getOrCreateAssociatedAccountInfo(admin.publicKey);
+        await program.methods.updateComptoirMint(newComptoirMint.publicKey, newAdminTokenAccount.address).accounts({
+            authority: admin.publicKey,
+            comptoir: comptoirPDA,
+            mint: newComptoirMint.publicKey,
+            escrow: newEscrowPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([admin]).rpc()
+
+        await program.methods.removeBuyOffer().accounts({
+            buyer: buyer.publicKey,
+            buyerPayingAccount: buyerTokenAccount.address,
+            comptoir: comptoirPDA,
+            escrow: escrowPDA,
+            buyOffer: offerPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([buyer]).rpc()
+
+        let updatedBuyerTokenAccount = await comptoirMint.getAccountInfo(buyerTokenAccount.address)
+        assert.equal(updatedBuyerTokenAccount.amount.toNumber(), 500);
+    });
+});
@@ -0,0 +1,547 @@
+import * as anchor from '@project-serum/anchor';
+import {Program, web3} from '@project-serum/anchor';
+import {Comptoir} from '../target/types/comptoir';
+import * as splToken from '@solana/spl-token';
+import {AccountMeta, PublicKey, TransactionInstruction} from "@solana/web3.js";
+import {TOKEN_PROGRAM_ID} from "@solana/spl-token";
+import assert from "assert";
+import {createHash} from "crypto";
+import {keccak_256} from "js-sha3";
+
+let provider = anchor.getProvider()
+anchor.setProvider(provider);
+
+const program = anchor.workspace.Comptoir as Program<Comptoir>;
+
+const BUBBLEGUM_PROGRAM_ID = new PublicKey("BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY");
+const COMPRESSION_PROGRAM_ID = new PublicKey("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+const NOOP_PROGRAM_ID = new PublicKey("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+
+// Depth 3 and a buffer of 8 changes, without canopy so the whole proof is passed
+const MAX_DEPTH = 3;
+const MAX_BUFFER_SIZE = 8;
+const TREE_SIZE = 56 + 24 + MAX_BUFFER_SIZE * (32 + 32 * MAX_DEPTH + 8) + (32 * MAX_DEPTH + 32 + 8);
+
+const keccak = (...data: Buffer[]): Buffer => Buffer.from(keccak_256.arrayBuffer(Buffer.concat(data)));
+
+const u64 = (n: number): Buffer => new anchor.BN(n).toArrayLike(Buffer, "le", 8);
+
+const bubblegumInstruction = (name: string, keys: AccountMeta[], args: Buffer): TransactionInstruction =>
+    new TransactionInstruction({
+        programId: BUBBLEGUM_PROGRAM_ID,
+        keys: keys,
+        data: Buffer.concat([createHash("sha256").update(`global:${name}`).digest().slice(0, 8), args]),
+    });
+
+// Mirror of the tree kept by the compression program, leaves being updated as the items move
+class LocalTree {
+    leaves: Buffer[] = Array.from({length: 1 << MAX_DEPTH}, () => Buffer.alloc(32));
+
+    root(): Buffer {
+        let level = this.leaves;
+        while (level.length > 1) {
+            let next: Buffer[] = [];
+            for (let i = 0; i < level.length; i += 2) {
+                next.push(keccak(level[i], level[i + 1]));
+            }
+            level = next;
+        }
+        return level[0];
+    }
+
+    proof(index: number): AccountMeta[] {
+        let nodes: AccountMeta[] = [];
+        let level = this.leaves;
+        while (level.length > 1) {
+            nodes.push({pubkey: new PublicKey(level[index ^ 1]), isWritable: false, isSigner: false});
+            let next: Buffer[] = [];
+            for (let i = 0; i < level.length; i += 2) {
+                next.push(keccak(level[i], level[i + 1]));
+            }
+            level = next;
+            index >>= 1;
+        }
+        return nodes;
+    }
+}
+
+describe('compressed NFTs', () => {
+    let admin: web3.Keypair;
+    let adminTokenAccount: splToken.AccountInfo;
+    let creator: web3.Keypair;
+    let creatorTokenAccount: splToken.AccountInfo;
+    let seller: web3.Keypair;
+    let sellerTokenAccount: splToken.AccountInfo;
+    let buyer: web3.Keypair;
+    let buyerTokenAccount: splToken.AccountInfo;
+    let comptoirPDA: PublicKey;
+    let comptoirMint: splToken.Token;
+    let escrowPDA: PublicKey;
+    let collectionPDA: PublicKey;
+    let merkleTree: web3.Keypair;
+    let treeAuthority: PublicKey;
+    let tree = new LocalTree();
+    let metadata: any[] = [];
+    let dataHashes: Buffer[] = [];
+    let creatorHashes: Buffer[] = [];
+    let assetIds: PublicKey[] = [];
+
+    const getVault = async (assetId: PublicKey): Promise<PublicKey> => {
+        return (await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from('COMPTOIR'), Buffer.from("vault"), comptoirPDA.toBuffer(), assetId.toBuffer()],
+            program.programId,
+        ))[0];
+    }
+
+    const getSellOrder = async (assetId: PublicKey): Promise<PublicKey> => {
+        return (await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from('COMPTOIR'), assetId.toBuffer(), Buffer.from("COMPRESSED")],
+            program.programId,
+        ))[0];
+    }
+
+    const setLeaf = (nonce: number, owner: PublicKey, delegate: PublicKey) => {
+        tree.leaves[nonce] = keccak(
+            Buffer.from([1]),
+            assetIds[nonce].toBuffer(),
+            owner.toBuffer(),
+            delegate.toBuffer(),
+            u64(nonce),
+            dataHashes[nonce],
+            creatorHashes[nonce],
+        );
+    }
+
+    const mint = async (nonce: number, owner: PublicKey) => {
+        metadata[nonce] = {
+            name: `Aurorian #${nonce}`,
+            symbol: "AURY",
+            uri: "https://aurory.io",
+            sellerFeeBasisPoints: 500,
+            primarySaleHappened: false,
+            isMutable: true,
+            editionNonce: null,
+            tokenStandard: 0,
+            collection: null,
+            uses: null,
+            tokenProgramVersion: 0,
+            creators: [{address: creator.publicKey, verified: true, share: 100}],
+        };
+        const encoded = program.coder.types.encode("CompressedMetadata", metadata[nonce]);
+        await provider.sendAndConfirm(new anchor.web3.Transaction().add(bubblegumInstruction("mint_v1", [
+            {pubkey: treeAuthority, isWritable: true, isSigner: false},
+            {pubkey: owner, isWritable: false, isSigner: false},
+            {pubkey: owner, isWritable: false, isSigner: false},
+            {pubkey: merkleTree.publicKey, isWritable: true, isSigner: false},
+            {pubkey: creator.publicKey, isWritable: true, isSigner: true},
+            {pubkey: creator.publicKey, isWritable: false, isSigner: true},
+            {pubkey: NOOP_PROGRAM_ID, isWritable: false, isSigner: false},
+            {pubkey: COMPRESSION_PROGRAM_ID, isWritable: false, isSigner: false},
+            {pubkey: anchor.web3.SystemProgram.programId, isWritable: false, isSigner: false},
+        ], encoded)), [creator]);
+
+        dataHashes[nonce] = keccak(keccak(encoded), Buffer.from([500 & 0xff, 500 >> 8]));
+        creatorHashes[nonce] = keccak(creator.publicKey.toBuffer(), Buffer.from([1, 100]));
+        [assetIds[nonce]] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from("asset"), merkleTree.publicKey.toBuffer(), u64(nonce)],
+            BUBBLEGUM_PROGRAM_ID,
+        );
+        setLeaf(nonce, owner, owner);
+    }
+
+    const createSellOrder = async (nonce: number, proof: AccountMeta[], destination?: PublicKey, currency?: PublicKey) => {
+        const currencyAccounts = currency ? [{pubkey: currency, isWritable: false, isSigner: false}] : [];
+        await program.methods.createCompressedSellOrder(
+            assetIds[nonce],
+            new anchor.BN(nonce),
+            nonce,
+            Array.from(tree.root()),
+            metadata[nonce],
+            new anchor.BN(1000),
+            destination || sellerTokenAccount.address,
+            null,
+        ).accounts({
+            payer: seller.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            vault: await getVault(assetIds[nonce]),
+            sellOrder: await getSellOrder(assetIds[nonce]),
+            treeAuthority: treeAuthority,
+            leafDelegate: seller.publicKey,
+            merkleTree: merkleTree.publicKey,
+            logWrapper: NOOP_PROGRAM_ID,
+            compressionProgram: COMPRESSION_PROGRAM_ID,
+            bubblegumProgram: BUBBLEGUM_PROGRAM_ID,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).remainingAccounts([...proof, ...currencyAccounts]).signers([seller]).rpc();
+    }
+
+    it('Prepare tests variables', async () => {
+        admin = anchor.web3.Keypair.generate()
+        creator = anchor.web3.Keypair.generate()
+        seller = anchor.web3.Keypair.generate()
+        buyer = anchor.web3.Keypair.generate()
+        merkleTree = anchor.web3.Keypair.generate()
+        for (let keypair of [admin, creator, seller, buyer]) {
+            let fromAirdropSignature = await provider.connection.requestAirdrop(
+                keypair.publicKey,
+                anchor.web3.LAMPORTS_PER_SOL,
+            );
+            await provider.connection.confirmTransaction(fromAirdropSignature);
+        }
+
+        [comptoirPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                admin.publicKey.toBuffer()
+            ],
+            program.programId,
+        )
+        comptoirMint = await splToken.Token.createMint(
+            provider.connection,
+            admin,
+            admin.publicKey,
+            null,
+            6,
+            splToken.TOKEN_PROGRAM_ID,
+        );
+        adminTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(admin.publicKey);
+        creatorTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(creator.publicKey);
+        sellerTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(seller.publicKey);
+        buyerTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(buyer.publicKey);
+        await comptoirMint.mintTo(buyerTokenAccount.address, admin, [], 10000);
+        [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                comptoirMint.publicKey.toBuffer(),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+        await program.methods.createComptoir(comptoirMint.publicKey, 200, adminTokenAccount.address, admin.publicKey)
+            .accounts({
+                payer: admin.publicKey,
+                comptoir: comptoirPDA,
+                mint: comptoirMint.publicKey,
+                escrow: escrowPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc();
+
+        [collectionPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                Buffer.from("AURY"),
+                comptoirPDA.toBuffer(),
+            ],
+            program.programId,
+        );
+        await program.methods.createCollection("AURY", "AURY", creator.publicKey, null, false).accounts({
+            authority: admin.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([admin]).rpc();
+
+        // The creator owns the tree and signs the mints, its creator entry can be minted verified
+        [treeAuthority] = await anchor.web3.PublicKey.findProgramAddress(
+            [merkleTree.publicKey.toBuffer()],
+            BUBBLEGUM_PROGRAM_ID,
+        );
+        const treeArgs = Buffer.alloc(9);
+        treeArgs.writeUInt32LE(MAX_DEPTH, 0);
+        treeArgs.writeUInt32LE(MAX_BUFFER_SIZE, 4);
+        await provider.sendAndConfirm(new anchor.web3.Transaction().add(
+            anchor.web3.SystemProgram.createAccount({
+                fromPubkey: creator.publicKey,
+                newAccountPubkey: merkleTree.publicKey,
+                lamports: await provider.connection.getMinimumBalanceForRentExemption(TREE_SIZE),
+                space: TREE_SIZE,
+                programId: COMPRESSION_PROGRAM_ID,
+            }),
+            bubblegumInstruction("create_tree", [
+                {pubkey: treeAuthority, isWritable: true, isSigner: false},
+                {pubkey: merkleTree.publicKey, isWritable: true, isSigner: false},
+                {pubkey: creator.publicKey, isWritable: true, isSigner: true},
+                {pubkey: creator.publicKey, isWritable: false, isSigner: true},
+                {pubkey: NOOP_PROGRAM_ID, isWritable: false, isSigner: false},
+                {pubkey: COMPRESSION_PROGRAM_ID, isWritable: false, isSigner: false},
+                {pubkey: anchor.web3.SystemProgram.programId, isWritable: false, isSigner: false},
+            ], treeArgs),
+        ), [creator, merkleTree]);
+
+        await mint(0, seller.publicKey);
+        await mint(1, seller.publicKey);
+    });
+
+    it('fail: list with a wrong proof', async () => {
+        let proof = tree.proof(0);
+        proof[0] = {pubkey: anchor.web3.Keypair.generate().publicKey, isWritable: false, isSigner: false};
+        await assert.rejects(createSellOrder(0, proof));
+        assert.equal(await provider.connection.getAccountInfo(await getSellOrder(assetIds[0])), null);
+    });
+
+    it('fail: list with an asset id of another leaf', async () => {
+        await assert.rejects(
+            program.methods.createCompressedSellOrder(
+                assetIds[1],
+                new anchor.BN(0),
+                0,
+                Array.from(tree.root()),
+                metadata[0],
+                new anchor.BN(1000),
+                sellerTokenAccount.address,
+                null,
+            ).accounts({
+                payer: seller.publicKey,
+                comptoir: comptoirPDA,
+                collection: collectionPDA,
+                vault: await getVault(assetIds[1]),
+                sellOrder: await getSellOrder(assetIds[1]),
+                treeAuthority: treeAuthority,
+                leafDelegate: seller.publicKey,
+                merkleTree: merkleTree.publicKey,
+                logWrapper: NOOP_PROGRAM_ID,
+                compressionProgram: COMPRESSION_PROGRAM_ID,
+                bubblegumProgram: BUBBLEGUM_PROGRAM_ID,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).remainingAccounts(tree.proof(0)).signers([seller]).rpc(),
+            (err: any) => err.error.errorCode.code === "ErrWrongAssetId"
+        );
+    });
+
+    it('list a compressed item', async () => {
+        await createSellOrder(0, tree.proof(0));
+        const vault = await getVault(assetIds[0]);
+        setLeaf(0, vault, vault);
+
+        let sellOrder = await program.account.compressedSellOrder.fetch(await getSellOrder(assetIds[0]));
+        assert.equal(sellOrder.assetId.toString(), assetIds[0].toString());
+        assert.equal(sellOrder.price.toNumber(), 1000);
+        assert.equal(sellOrder.authority.toString(), seller.publicKey.toString());
+        assert.equal(sellOrder.paymentMint.toString(), comptoirMint.publicKey.toString());
+        assert.deepEqual(Buffer.from(sellOrder.dataHash), dataHashes[0]);
+    });
+
+    it('remove the compressed sell order', async () => {
+        const sellOrder = await getSellOrder(assetIds[0]);
+        await program.methods.removeCompressedSellOrder(Array.from(tree.root())).accounts({
+            authority: seller.publicKey,
+            sellOrder: sellOrder,
+            vault: await getVault(assetIds[0]),
+            treeAuthority: treeAuthority,
+            merkleTree: merkleTree.publicKey,
+            logWrapper: NOOP_PROGRAM_ID,
+            compressionProgram: COMPRESSION_PROGRAM_ID,
+            bubblegumProgram: BUBBLEGUM_PROGRAM_ID,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).remainingAccounts(tree.proof(0)).signers([seller]).rpc();
+        setLeaf(0, seller.publicKey, seller.publicKey);
+
+        assert.equal(await provider.connection.getAccountInfo(sellOrder), null);
+    });
+
+    it('buy a compressed item', async () => {
+        await createSellOrder(0, tree.proof(0));
+        const vault = await getVault(assetIds[0]);
+        setLeaf(0, vault, vault);
+
+        const sellOrder = await getSellOrder(assetIds[0]);
+        await program.methods.buyCompressed(Array.from(tree.root()), metadata[0], new anchor.BN(1000)).accounts({
+            buyer: buyer.publicKey,
+            buyerPayingTokenAccount: buyerTokenAccount.address,
+            comptoir: comptoirPDA,
+            comptoirDestAccount: adminTokenAccount.address,
+            collection: collectionPDA,
+            authority: seller.publicKey,
+            sellerFundsDestAccount: sellerTokenAccount.address,
+            sellOrder: sellOrder,
+            vault: vault,
+            treeAuthority: treeAuthority,
+            merkleTree: merkleTree.publicKey,
+            logWrapper: NOOP_PROGRAM_ID,
+            compressionProgram: COMPRESSION_PROGRAM_ID,
+            bubblegumProgram: BUBBLEGUM_PROGRAM_ID,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        }).remainingAccounts([
+            {pubkey: creatorTokenAccount.address, isWritable: true, isSigner: false},
+            ...tree.proof(0),
+        ]).signers([buyer]).rpc();
+        setLeaf(0, buyer.publicKey, buyer.publicKey);
+
+        assert.equal(await provider.connection.getAccountInfo(sellOrder), null);
+        assert.equal((await comptoirMint.getAccountInfo(buyerTokenAccount.address)).amount.toNumber(), 9000);
+        assert.equal((await comptoirMint.getAccountInfo(sellerTokenAccount.address)).amount.toNumber(), 930);
+        assert.equal((await comptoirMint.getAccountInfo(adminTokenAccount.address)).amount.toNumber(), 20);
+        assert.equal((await comptoirMint.getAccountInfo(creatorTokenAccount.address)).amount.toNumber(), 50);
+    });
+
+    it('make and execute a buy offer on a compressed item', async () => {
+        const [buyOfferPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                buyer.publicKey.toBuffer(),
+                assetIds[1].toBuffer(),
+                Buffer.from("2000"),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+        await program.methods.createCompressedBuyOffer(assetIds[1], new anchor.BN(2000), null).accounts({
+            payer: buyer.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            escrow: escrowPDA,
+            buyerPayingAccount: buyerTokenAccount.address,
+            buyOffer: buyOfferPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([buyer]).rpc();
+        assert.equal((await comptoirMint.getAccountInfo(escrowPDA)).amount.toNumber(), 2000);
+
+        let signature = await program.methods.executeCompressedOffer(new anchor.BN(1), 1, Array.from(tree.root()), metadata[1]).accounts({
+            seller: seller.publicKey,
+            buyer: buyer.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            comptoirDestAccount: adminTokenAccount.address,
+            escrow: escrowPDA,
+            sellerFundsDestAccount: sellerTokenAccount.address,
+            buyOffer: buyOfferPDA,
+            treeAuthority: treeAuthority,
+            leafDelegate: seller.publicKey,
+            merkleTree: merkleTree.publicKey,
+            logWrapper: NOOP_PROGRAM_ID,
+            compressionProgram: COMPRESSION_PROGRAM_ID,
+            bubblegumProgram: BUBBLEGUM_PROGRAM_ID,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        }).remainingAccounts([
+            {pubkey: creatorTokenAccount.address, isWritable: true, isSigner: false},
+            ...tree.proof(1),
+        ]).signers([seller]).rpc();
+        setLeaf(1, buyer.publicKey, buyer.publicKey);
+
+        assert.equal(await provider.connection.getAccountInfo(buyOfferPDA), null);
+        assert.equal((await comptoirMint.getAccountInfo(escrowPDA)).amount.toNumber(), 0);
+        assert.equal((await comptoirMint.getAccountInfo(sellerTokenAccount.address)).amount.toNumber(), 930 + 1860);
+        assert.equal((await comptoirMint.getAccountInfo(adminTokenAccount.address)).amount.toNumber(), 20 + 40);
+        assert.equal((await comptoirMint.getAccountInfo(creatorTokenAccount.address)).amount.toNumber(), 50 + 100);
+
+        let transaction = await provider.connection.getTransaction(signature, {commitment: "confirmed"});
+        let events = [];
+        new anchor.EventParser(program.programId, program.coder).parseLogs(transaction.meta.logMessages, (event) => events.push(event));
+        let executedEvent = events.find((event) => event.name === "BuyOfferExecutedEvent");
+        assert.equal(executedEvent.data.mint.toString(), assetIds[1].toString());
+        assert.equal(executedEvent.data.price.toNumber(), 2000);
+        assert.equal(executedEvent.data.sellerAmount.toNumber(), 1860);
+        assert.equal(executedEvent.data.comptoirAmount.toNumber(), 40);
+        assert.equal(executedEvent.data.creators[0].amount.toNumber(), 100);
+    });
+
+    it('fail: list an item sold to someone else', async () => {
+        // The leaf now belongs to the buyer, bubblegum refuses the transfer signed by the seller
+        await assert.rejects(createSellOrder(0, tree.proof(0)));
+    });
+    it('list and buy a compressed item in a registered currency', async () => {
+        const currencyMint = await splToken.Token.createMint(
+            provider.connection,
+            admin,
+            admin.publicKey,
+            null,
+            6,
+            splToken.TOKEN_PROGRAM_ID,
+        );
+        const adminCurrencyAccount = await currencyMint.getOrCreateAssociatedAccountInfo(admin.publicKey);
+        const creatorCurrencyAccount = await currencyMint.getOrCreateAssociatedAccountInfo(creator.publicKey);
+        const sellerCurrencyAccount = await currencyMint.getOrCreateAssociatedAccountInfo(seller.publicKey);
+        const buyerCurrencyAccount = await currencyMint.getOrCreateAssociatedAccountInfo(buyer.publicKey);
+        await currencyMint.mintTo(buyerCurrencyAccount.address, admin, [], 10000);
+        const [currencyPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from("COMPTOIR"), comptoirPDA.toBuffer(), currencyMint.publicKey.toBuffer(), Buffer.from("CURRENCY")],
+            program.programId,
+        );
+        const [currencyEscrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from("COMPTOIR"), comptoirPDA.toBuffer(), currencyMint.publicKey.toBuffer(), Buffer.from("ESCROW")],
+            program.programId,
+        );
+        await program.methods.addCurrency(adminCurrencyAccount.address).accounts({
+            authority: admin.publicKey,
+            comptoir: comptoirPDA,
+            mint: currencyMint.publicKey,
+            currency: currencyPDA,
+            escrow: currencyEscrowPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([admin]).rpc();
+
+        await mint(2, seller.publicKey);
+        await createSellOrder(2, tree.proof(2), sellerCurrencyAccount.address, currencyPDA);
+        const vault = await getVault(assetIds[2]);
+        setLeaf(2, vault, vault);
+        const sellOrder = await getSellOrder(assetIds[2]);
+        assert.equal(
+            (await program.account.compressedSellOrder.fetch(sellOrder)).paymentMint.toString(),
+            currencyMint.publicKey.toString(),
+        );
+
+        const buy = (comptoirDestAccount: PublicKey, paying: PublicKey, sellerDest: PublicKey, remaining: AccountMeta[]) =>
+            program.methods.buyCompressed(Array.from(tree.root()), metadata[2], new anchor.BN(1000)).accounts({
+                buyer: buyer.publicKey,
+                buyerPayingTokenAccount: paying,
+                comptoir: comptoirPDA,
+                comptoirDestAccount: comptoirDestAccount,
+                collection: collectionPDA,
+                authority: seller.publicKey,
+                sellerFundsDestAccount: sellerDest,
+                sellOrder: sellOrder,
+                vault: vault,
+                treeAuthority: treeAuthority,
+                merkleTree: merkleTree.publicKey,
+                logWrapper: NOOP_PROGRAM_ID,
+                compressionProgram: COMPRESSION_PROGRAM_ID,
+                bubblegumProgram: BUBBLEGUM_PROGRAM_ID,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+            }).remainingAccounts(remaining).signers([buyer]).rpc();
+
+        // The order is priced in the currency, it cannot be paid in the comptoir mint
+        await assert.rejects(
+            buy(adminTokenAccount.address, buyerTokenAccount.address, sellerCurrencyAccount.address, [
+                {pubkey: creatorTokenAccount.address, isWritable: true, isSigner: false},
+                ...tree.proof(2),
+            ]),
+            (err: any) => err.error.errorCode.code === "ErrCurrencyNotAccepted"
+        );
+        await assert.rejects(
+            buy(adminTokenAccount.address, buyerCurrencyAccount.address, sellerCurrencyAccount.address, [
+                {pubkey: creatorCurrencyAccount.address, isWritable: true, isSigner: false},
+                ...tree.proof(2),
+                {pubkey: currencyPDA, isWritable: false, isSigner: false},
+            ]),
+            (err: any) => err.error.errorCode.code === "ErrWrongFeesDestination"
+        );
+
+        await buy(adminCurrencyAccount.address, buyerCurrencyAccount.address, sellerCurrencyAccount.address, [
+            {pubkey: creatorCurrencyAccount.address, isWritable: true, isSigner: false},
+            ...tree.proof(2),
+            {pubkey: currencyPDA, isWritable: false, isSigner: false},
+        ]);
+        setLeaf(2, buyer.publicKey, buyer.publicKey);
+
+        assert.equal(await provider.connection.getAccountInfo(sellOrder), null);
+        assert.equal((await currencyMint.getAccountInfo(buyerCurrencyAccount.address)).amount.toNumber(), 9000);
+        assert.equal((await currencyMint.getAccountInfo(sellerCurrencyAccount.address)).amount.toNumber(), 930);
+        assert.equal((await currencyMint.getAccountInfo(adminCurrencyAccount.address)).amount.toNumber(), 20);
+        assert.equal((await currencyMint.getAccountInfo(creatorCurrencyAccount.address)).amount.toNumber(), 50);
+    });
+});
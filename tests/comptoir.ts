@@ -128,7 +128,7 @@ describe('comptoir with mint', () => {
         );
 
         [programNftVaultPDA] = await anchor.web3.PublicKey.findProgramAddress(
-            [Buffer.from('COMPTOIR'), Buffer.from("vault"), nftMint.publicKey.toBuffer()],
+            [Buffer.from('COMPTOIR'), Buffer.from("vault"), comptoirPDA.toBuffer(), nftMint.publicKey.toBuffer()],
             program.programId,
         );
         [sellOrderPDA] = await anchor.web3.PublicKey.findProgramAddress(
@@ -343,11 +343,56 @@ describe('comptoir with mint', () => {
         ).signers([admin]).rpc()
     });
 
+    it('certified collection gating', async () => {
+        let certifiedCollection = anchor.web3.Keypair.generate().publicKey
+        let [tmpSellOrderPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                sellerNftAssociatedTokenAccount.toBuffer(),
+                Buffer.from("999"),
+            ],
+            program.programId,
+        );
+
+        await program.methods.setCertifiedCollection(certifiedCollection).accounts({
+            authority: admin.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+        }).signers([admin]).rpc()
+
+        let updatedCollection = await program.account.collection.fetch(collectionPDA)
+        assert.equal(updatedCollection.certifiedCollection.toString(), certifiedCollection.toString());
+
+        // The nft is not part of any certified collection
+        await assert.rejects(
+            program.methods.createSellOrder(new anchor.BN(999), new anchor.BN(1), sellerTokenAccount.address, null, null).accounts({
+                payer: seller.publicKey,
+                sellerNftTokenAccount: sellerNftAssociatedTokenAccount,
+                comptoir: comptoirPDA,
+                collection: collectionPDA,
+                mint: nftMint.publicKey,
+                metadata: metadataPDA,
+                vault: programNftVaultPDA,
+                sellOrder: tmpSellOrderPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([seller]).rpc()
+        );
+
+        // reset
+        await program.methods.setCertifiedCollection(null).accounts({
+            authority: admin.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+        }).signers([admin]).rpc()
+    });
+
     it('create sell order', async () => {
         let price = new anchor.BN(1000);
         let quantity = new anchor.BN(4);
 
-        await program.methods.createSellOrder(price, quantity, sellerTokenAccount.address).accounts(
+        await program.methods.createSellOrder(price, quantity, sellerTokenAccount.address, null, null).accounts(
             {
                 payer: seller.publicKey,
                 sellerNftTokenAccount: sellerNftAssociatedTokenAccount,
@@ -423,7 +468,7 @@ describe('comptoir with mint', () => {
         await comptoirMint.mintTo(buyerComptoirAta.address, admin, [], 1000)
 
         let quantity_to_buy = new anchor.BN(1)
-        await program.methods.buy(quantity_to_buy).accounts({
+        await program.methods.buy(quantity_to_buy, new anchor.BN(1000), null).accounts({
             buyer: buyer.publicKey,
             buyerNftTokenAccount: buyerNftAta.address,
             buyerPayingTokenAccount: buyerComptoirAta.address,
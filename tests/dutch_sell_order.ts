@@ -0,0 +1,262 @@
+import * as anchor from '@project-serum/anchor';
+import {Program, web3} from '@project-serum/anchor';
+import {Comptoir} from '../target/types/comptoir';
+import * as splToken from '@solana/spl-token';
+import {PublicKey} from "@solana/web3.js";
+import {ASSOCIATED_TOKEN_PROGRAM_ID, Token, TOKEN_PROGRAM_ID} from "@solana/spl-token";
+import assert from "assert";
+import {nft_data, nft_json_url} from "./data";
+import {createMint} from "./utils/utils";
+
+let provider = anchor.getProvider()
+anchor.setProvider(provider);
+
+const program = anchor.workspace.Comptoir as Program<Comptoir>;
+
+describe('dutch auction sell orders', () => {
+    let admin: web3.Keypair;
+    let adminTokenAccount: splToken.AccountInfo;
+    let creator: web3.Keypair;
+    let creatorTokenAccount: splToken.AccountInfo;
+    let seller: web3.Keypair;
+    let sellerTokenAccount: splToken.AccountInfo;
+    let buyer: web3.Keypair;
+    let buyerTokenAccount: splToken.AccountInfo;
+    let buyerNftAccount: splToken.AccountInfo;
+    let comptoirPDA: PublicKey;
+    let comptoirMint: splToken.Token;
+    let fee = 200;
+    let collectionName = "AURY"
+    let collectionPDA: PublicKey;
+    let nftMint: splToken.Token;
+    let metadataPDA: PublicKey;
+    let sellerNftAssociatedTokenAccount: PublicKey;
+    let programNftVaultPDA: PublicKey;
+    let escrowPDA: PublicKey;
+
+    const getSellOrderPDA = async (price: anchor.BN): Promise<PublicKey> => {
+        return (await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                sellerNftAssociatedTokenAccount.toBuffer(),
+                Buffer.from(price.toString()),
+            ],
+            program.programId,
+        ))[0];
+    }
+
+    const createSellOrder = async (price: anchor.BN, quantity: anchor.BN, schedule: any) => {
+        await program.methods.createSellOrder(price, quantity, sellerTokenAccount.address, schedule, null).accounts({
+            payer: seller.publicKey,
+            sellerNftTokenAccount: sellerNftAssociatedTokenAccount,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            mint: nftMint.publicKey,
+            metadata: metadataPDA,
+            vault: programNftVaultPDA,
+            sellOrder: await getSellOrderPDA(price),
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([seller]).rpc()
+    }
+
+    const buy = async (sellOrderPDA: PublicKey, quantity: anchor.BN, maxTotalPrice: anchor.BN, maxUnitPrice: anchor.BN | null) => {
+        await program.methods.buy(quantity, maxTotalPrice, maxUnitPrice).accounts({
+            buyer: buyer.publicKey,
+            buyerNftTokenAccount: buyerNftAccount.address,
+            buyerPayingTokenAccount: buyerTokenAccount.address,
+            comptoir: comptoirPDA,
+            comptoirDestAccount: adminTokenAccount.address,
+            collection: collectionPDA,
+            metadata: metadataPDA,
+            vault: programNftVaultPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        }).remainingAccounts([
+            {pubkey: creatorTokenAccount.address, isWritable: true, isSigner: false},
+            {pubkey: sellOrderPDA, isWritable: true, isSigner: false},
+            {pubkey: sellerTokenAccount.address, isWritable: true, isSigner: false},
+        ]).signers([buyer]).rpc()
+    }
+
+    it('Prepare tests variables', async () => {
+        admin = anchor.web3.Keypair.generate()
+        creator = anchor.web3.Keypair.generate()
+        seller = anchor.web3.Keypair.generate()
+        buyer = anchor.web3.Keypair.generate()
+        for (let keypair of [admin, creator, seller, buyer]) {
+            let fromAirdropSignature = await provider.connection.requestAirdrop(
+                keypair.publicKey,
+                anchor.web3.LAMPORTS_PER_SOL,
+            );
+            await provider.connection.confirmTransaction(fromAirdropSignature);
+        }
+
+        [comptoirPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                admin.publicKey.toBuffer()
+            ],
+            program.programId,
+        )
+
+        comptoirMint = await splToken.Token.createMint(
+            provider.connection,
+            admin,
+            admin.publicKey,
+            null,
+            6,
+            splToken.TOKEN_PROGRAM_ID,
+        );
+
+        [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                comptoirMint.publicKey.toBuffer(),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+
+        adminTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(admin.publicKey);
+        creatorTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(creator.publicKey);
+        sellerTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(seller.publicKey);
+        buyerTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(buyer.publicKey);
+        await comptoirMint.mintTo(buyerTokenAccount.address, admin, [], 4200)
+
+        [collectionPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                Buffer.from(collectionName),
+                comptoirPDA.toBuffer(),
+            ],
+            program.programId,
+        );
+
+        const data = nft_data(creator.publicKey);
+        const lamports = await Token.getMinBalanceRentForExemptMint(
+            provider.connection
+        );
+        const [mint, metadataAddr, tx] = await createMint(
+            creator.publicKey,
+            seller.publicKey,
+            lamports,
+            data,
+            nft_json_url
+        );
+        await provider.sendAndConfirm(tx, [mint, creator]);
+
+        metadataPDA = metadataAddr
+        nftMint = new Token(provider.connection, mint.publicKey, TOKEN_PROGRAM_ID, admin)
+
+        sellerNftAssociatedTokenAccount = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            nftMint.publicKey,
+            seller.publicKey
+        );
+        buyerNftAccount = await nftMint.getOrCreateAssociatedAccountInfo(buyer.publicKey);
+
+        [programNftVaultPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from('COMPTOIR'), Buffer.from("vault"), comptoirPDA.toBuffer(), nftMint.publicKey.toBuffer()],
+            program.programId,
+        );
+
+        await program.methods.createComptoir(comptoirMint.publicKey, fee, adminTokenAccount.address, admin.publicKey)
+            .accounts({
+                payer: admin.publicKey,
+                comptoir: comptoirPDA,
+                mint: comptoirMint.publicKey,
+                escrow: escrowPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc();
+
+        await program.methods.createCollection(collectionName, collectionName, creator.publicKey, null, false).accounts(
+            {
+                authority: admin.publicKey,
+                comptoir: comptoirPDA,
+                collection: collectionPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc()
+    });
+
+    it('fail: end price above start price', async () => {
+        let now = Math.floor(Date.now() / 1000);
+        await assert.rejects(
+            createSellOrder(new anchor.BN(1000), new anchor.BN(1), {
+                endPrice: new anchor.BN(2000),
+                startTime: new anchor.BN(now),
+                endTime: new anchor.BN(now + 100),
+                decay: {linear: {}},
+            })
+        );
+    });
+
+    it('fail: schedule longer than the timestamps can hold', async () => {
+        await assert.rejects(
+            createSellOrder(new anchor.BN(1000), new anchor.BN(1), {
+                endPrice: new anchor.BN(500),
+                startTime: new anchor.BN("-9223372036854775808"),
+                endTime: new anchor.BN("9223372036854775807"),
+                decay: {linear: {}},
+            }),
+            (err: any) => err.error.errorCode.code === "ErrInvalidPriceScheduleTimes"
+        );
+    });
+
+    it('buy stepped sell order at its current step price', async () => {
+        let now = Math.floor(Date.now() / 1000);
+        let price = new anchor.BN(2000);
+        await createSellOrder(price, new anchor.BN(2), {
+            endPrice: new anchor.BN(1000),
+            startTime: new anchor.BN(now - 100),
+            endTime: new anchor.BN(now + 100),
+            decay: {stepped: {stepDuration: new anchor.BN(100)}},
+        })
+
+        let sellOrderPDA = await getSellOrderPDA(price);
+        let sellOrder = await program.account.sellOrder.fetch(sellOrderPDA)
+        assert.equal(sellOrder.price.toNumber(), 2000);
+        assert.equal(sellOrder.priceSchedule.endPrice.toNumber(), 1000);
+
+        await assert.rejects(buy(sellOrderPDA, new anchor.BN(2), new anchor.BN(5000), new anchor.BN(1400)));
+        await assert.rejects(buy(sellOrderPDA, new anchor.BN(2), new anchor.BN(2999), null));
+        await buy(sellOrderPDA, new anchor.BN(2), new anchor.BN(3000), new anchor.BN(1500))
+
+        let updatedBuyerTokenAccount = await comptoirMint.getAccountInfo(buyerTokenAccount.address)
+        assert.equal(updatedBuyerTokenAccount.amount.toNumber(), 1200);
+
+        let updatedSellerTokenAccount = await comptoirMint.getAccountInfo(sellerTokenAccount.address)
+        assert.equal(updatedSellerTokenAccount.amount.toNumber(), 2640);
+
+        let updatedAdminTokenAccount = await comptoirMint.getAccountInfo(adminTokenAccount.address)
+        assert.equal(updatedAdminTokenAccount.amount.toNumber(), 60);
+
+        let updatedCreatorTokenAccount = await comptoirMint.getAccountInfo(creatorTokenAccount.address)
+        assert.equal(updatedCreatorTokenAccount.amount.toNumber(), 300);
+    });
+
+    it('buy linear sell order at its end price once the schedule is over', async () => {
+        let now = Math.floor(Date.now() / 1000);
+        let price = new anchor.BN(3000);
+        await createSellOrder(price, new anchor.BN(1), {
+            endPrice: new anchor.BN(1200),
+            startTime: new anchor.BN(now - 200),
+            endTime: new anchor.BN(now - 100),
+            decay: {linear: {}},
+        })
+
+        await buy(await getSellOrderPDA(price), new anchor.BN(1), new anchor.BN(1200), null)
+
+        let updatedBuyerTokenAccount = await comptoirMint.getAccountInfo(buyerTokenAccount.address)
+        assert.equal(updatedBuyerTokenAccount.amount.toNumber(), 0);
+
+        let updatedBuyerNftAccount = await nftMint.getAccountInfo(buyerNftAccount.address)
+        assert.equal(updatedBuyerNftAccount.amount.toNumber(), 3);
+    });
+});
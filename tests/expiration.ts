@@ -0,0 +1,302 @@
+import * as anchor from '@project-serum/anchor';
+import {Program, web3} from '@project-serum/anchor';
+import {Comptoir} from '../target/types/comptoir';
+import * as splToken from '@solana/spl-token';
+import {PublicKey} from "@solana/web3.js";
+import {ASSOCIATED_TOKEN_PROGRAM_ID, Token, TOKEN_PROGRAM_ID} from "@solana/spl-token";
+import assert from "assert";
+import {nft_data, nft_json_url} from "./data";
+import {createMint} from "./utils/utils";
+
+let provider = anchor.getProvider()
+anchor.setProvider(provider);
+
+const program = anchor.workspace.Comptoir as Program<Comptoir>;
+
+const sleep = (ms: number) => new Promise(resolve => setTimeout(resolve, ms));
+
+describe('expiring sell orders and buy offers', () => {
+    let admin: web3.Keypair;
+    let adminTokenAccount: splToken.AccountInfo;
+    let creator: web3.Keypair;
+    let creatorTokenAccount: splToken.AccountInfo;
+    let seller: web3.Keypair;
+    let sellerTokenAccount: splToken.AccountInfo;
+    let buyer: web3.Keypair;
+    let buyerTokenAccount: splToken.AccountInfo;
+    let buyerNftAccount: PublicKey;
+    let comptoirPDA: PublicKey;
+    let comptoirMint: splToken.Token;
+    let collectionName = "AURY"
+    let collectionPDA: PublicKey;
+    let nftMint: splToken.Token;
+    let metadataPDA: PublicKey;
+    let sellerNftAssociatedTokenAccount: PublicKey;
+    let programNftVaultPDA: PublicKey;
+    let escrowPDA: PublicKey;
+    let sellOrderPDA: PublicKey;
+    let buyOfferPDA: PublicKey;
+    let expiresAt: number;
+
+    it('Prepare tests variables', async () => {
+        admin = anchor.web3.Keypair.generate()
+        creator = anchor.web3.Keypair.generate()
+        seller = anchor.web3.Keypair.generate()
+        buyer = anchor.web3.Keypair.generate()
+        for (let keypair of [admin, creator, seller, buyer]) {
+            let fromAirdropSignature = await provider.connection.requestAirdrop(
+                keypair.publicKey,
+                anchor.web3.LAMPORTS_PER_SOL,
+            );
+            await provider.connection.confirmTransaction(fromAirdropSignature);
+        }
+
+        [comptoirPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                admin.publicKey.toBuffer()
+            ],
+            program.programId,
+        )
+
+        comptoirMint = await splToken.Token.createMint(
+            provider.connection,
+            admin,
+            admin.publicKey,
+            null,
+            6,
+            splToken.TOKEN_PROGRAM_ID,
+        );
+
+        [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                comptoirMint.publicKey.toBuffer(),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+
+        adminTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(admin.publicKey);
+        creatorTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(creator.publicKey);
+        sellerTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(seller.publicKey);
+        buyerTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(buyer.publicKey);
+        await comptoirMint.mintTo(buyerTokenAccount.address, admin, [], 1000)
+
+        [collectionPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                Buffer.from(collectionName),
+                comptoirPDA.toBuffer(),
+            ],
+            program.programId,
+        );
+
+        const data = nft_data(creator.publicKey);
+        const lamports = await Token.getMinBalanceRentForExemptMint(
+            provider.connection
+        );
+        const [mint, metadataAddr, tx] = await createMint(
+            creator.publicKey,
+            seller.publicKey,
+            lamports,
+            data,
+            nft_json_url
+        );
+        await provider.sendAndConfirm(tx, [mint, creator]);
+
+        metadataPDA = metadataAddr
+        nftMint = new Token(provider.connection, mint.publicKey, TOKEN_PROGRAM_ID, admin)
+
+        sellerNftAssociatedTokenAccount = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            nftMint.publicKey,
+            seller.publicKey
+        );
+        buyerNftAccount = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            nftMint.publicKey,
+            buyer.publicKey
+        );
+
+        [programNftVaultPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from('COMPTOIR'), Buffer.from("vault"), comptoirPDA.toBuffer(), nftMint.publicKey.toBuffer()],
+            program.programId,
+        );
+        [sellOrderPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                sellerNftAssociatedTokenAccount.toBuffer(),
+                Buffer.from("1000"),
+            ],
+            program.programId,
+        );
+        [buyOfferPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                buyer.publicKey.toBuffer(),
+                nftMint.publicKey.toBuffer(),
+                Buffer.from("1000"),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+
+        await program.methods.createComptoir(comptoirMint.publicKey, 200, adminTokenAccount.address, admin.publicKey)
+            .accounts({
+                payer: admin.publicKey,
+                comptoir: comptoirPDA,
+                mint: comptoirMint.publicKey,
+                escrow: escrowPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc();
+
+        await program.methods.createCollection(collectionName, collectionName, creator.publicKey, null, false).accounts(
+            {
+                authority: admin.publicKey,
+                comptoir: comptoirPDA,
+                collection: collectionPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc()
+    });
+
+    it('create expiring sell order and buy offer', async () => {
+        expiresAt = Math.floor(Date.now() / 1000) + 6;
+
+        await program.methods.createSellOrder(new anchor.BN(1000), new anchor.BN(2), sellerTokenAccount.address, null, new anchor.BN(expiresAt)).accounts({
+            payer: seller.publicKey,
+            sellerNftTokenAccount: sellerNftAssociatedTokenAccount,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            mint: nftMint.publicKey,
+            metadata: metadataPDA,
+            vault: programNftVaultPDA,
+            sellOrder: sellOrderPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([seller]).rpc()
+
+        await program.methods.createBuyOffer(new anchor.BN(1000), new anchor.BN(1), new anchor.BN(expiresAt)).accounts({
+            payer: buyer.publicKey,
+            nftMint: nftMint.publicKey,
+            metadata: metadataPDA,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            escrow: escrowPDA,
+            buyerPayingAccount: buyerTokenAccount.address,
+            buyerNftAccount: buyerNftAccount,
+            buyOffer: buyOfferPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([buyer]).rpc()
+
+        let sellOrder = await program.account.sellOrder.fetch(sellOrderPDA)
+        assert.equal(sellOrder.expiresAt.toNumber(), expiresAt);
+        let buyOffer = await program.account.buyOffer.fetch(buyOfferPDA)
+        assert.equal(buyOffer.expiresAt.toNumber(), expiresAt);
+    });
+
+    it('fail: close sell order before expiration', async () => {
+        await assert.rejects(
+            program.methods.closeExpiredSellOrder().accounts({
+                authority: seller.publicKey,
+                sellerNftTokenAccount: sellerNftAssociatedTokenAccount,
+                sellOrder: sellOrderPDA,
+                vault: programNftVaultPDA,
+                tokenProgram: TOKEN_PROGRAM_ID,
+            }).rpc()
+        );
+    });
+
+    it('fail: buy expired sell order', async () => {
+        await sleep((expiresAt + 2) * 1000 - Date.now());
+
+        let buyerComptoirAta = await comptoirMint.getOrCreateAssociatedAccountInfo(admin.publicKey)
+        let adminNftAta = await nftMint.getOrCreateAssociatedAccountInfo(admin.publicKey)
+        await comptoirMint.mintTo(buyerComptoirAta.address, admin, [], 1000)
+        await assert.rejects(
+            program.methods.buy(new anchor.BN(1), new anchor.BN(1000), null).accounts({
+                buyer: admin.publicKey,
+                buyerNftTokenAccount: adminNftAta.address,
+                buyerPayingTokenAccount: buyerComptoirAta.address,
+                comptoir: comptoirPDA,
+                comptoirDestAccount: adminTokenAccount.address,
+                collection: collectionPDA,
+                metadata: metadataPDA,
+                vault: programNftVaultPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+            }).remainingAccounts([
+                {pubkey: creatorTokenAccount.address, isWritable: true, isSigner: false},
+                {pubkey: sellOrderPDA, isWritable: true, isSigner: false},
+                {pubkey: sellerTokenAccount.address, isWritable: true, isSigner: false},
+            ]).signers([admin]).rpc()
+        );
+    });
+
+    it('fail: execute expired buy offer', async () => {
+        await assert.rejects(
+            program.methods.executeOffer(new anchor.BN(1), []).accounts({
+                seller: seller.publicKey,
+                buyer: buyer.publicKey,
+                comptoir: comptoirPDA,
+                collection: collectionPDA,
+                comptoirDestAccount: adminTokenAccount.address,
+                escrow: escrowPDA,
+                sellerFundsDestAccount: sellerTokenAccount.address,
+                destination: buyerNftAccount,
+                sellerNftAccount: sellerNftAssociatedTokenAccount,
+                metadata: metadataPDA,
+                buyOffer: buyOfferPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).remainingAccounts([
+                {pubkey: creatorTokenAccount.address, isWritable: true, isSigner: false},
+            ]).signers([seller]).rpc()
+        );
+    });
+
+    it('anyone can close an expired sell order', async () => {
+        await program.methods.closeExpiredSellOrder().accounts({
+            authority: seller.publicKey,
+            sellerNftTokenAccount: sellerNftAssociatedTokenAccount,
+            sellOrder: sellOrderPDA,
+            vault: programNftVaultPDA,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        }).rpc()
+
+        let sellerNftAccount = await nftMint.getAccountInfo(sellerNftAssociatedTokenAccount)
+        assert.equal(sellerNftAccount.amount.toNumber(), 5);
+        let closedSellOrder = await provider.connection.getAccountInfo(sellOrderPDA);
+        assert.equal(closedSellOrder, null);
+    });
+
+    it('anyone can close an expired buy offer', async () => {
+        await program.methods.closeExpiredBuyOffer().accounts({
+            buyer: buyer.publicKey,
+            buyerPayingAccount: buyerTokenAccount.address,
+            comptoir: comptoirPDA,
+            escrow: escrowPDA,
+            buyOffer: buyOfferPDA,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        }).rpc()
+
+        let updatedBuyerTokenAccount = await comptoirMint.getAccountInfo(buyerTokenAccount.address)
+        assert.equal(updatedBuyerTokenAccount.amount.toNumber(), 1000);
+        let escrow = await comptoirMint.getAccountInfo(escrowPDA)
+        assert.equal(escrow.amount.toNumber(), 0);
+        let closedBuyOffer = await provider.connection.getAccountInfo(buyOfferPDA);
+        assert.equal(closedBuyOffer, null);
+    });
+});
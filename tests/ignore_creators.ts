@@ -1,15 +1,18 @@
 import * as anchor from '@project-serum/anchor';
-import {Provider, web3} from '@project-serum/anchor';
+import {Program, web3} from '@project-serum/anchor';
+import {Comptoir} from '../target/types/comptoir';
 import * as splToken from '@solana/spl-token';
 import {Token, TOKEN_PROGRAM_ID} from "@solana/spl-token";
 import assert from "assert";
 import {nft_data, nft_json_url} from "./data";
 import {createMint} from "./utils/utils";
-import {Comptoir, Collection, getCollectionPDA, getSellOrderPDA} from '@aurory/comptoirjs';
+import {PublicKey} from "@solana/web3.js";
 
 let provider = anchor.AnchorProvider.local()
 anchor.setProvider(provider);
 
+const program = anchor.workspace.Comptoir as Program<Comptoir>;
+
 describe('ignore creators tests', () => {
     let creator: web3.Keypair;
     let creatorTokenAccount: splToken.AccountInfo;
@@ -19,10 +22,11 @@ describe('ignore creators tests', () => {
     let nftMint: splToken.Token;
     let metadataPDA: web3.PublicKey;
     let sellerNftAssociatedTokenAccount: web3.PublicKey;
-
-    let comptoir: Comptoir;
-    let collection: Collection;
-
+    let comptoirPDA: PublicKey;
+    let escrowPDA: PublicKey;
+    let collectionPDA: PublicKey;
+    let programNftVaultPDA: PublicKey;
+    let sellOrderPDA: PublicKey;
 
     it('Prepare tests variables', async () => {
         creator = anchor.web3.Keypair.generate()
@@ -74,25 +78,79 @@ describe('ignore creators tests', () => {
         metadataPDA = metadataAddr
         nftMint = new Token(provider.connection, mint.publicKey, TOKEN_PROGRAM_ID, seller)
 
-        sellerNftAssociatedTokenAccount = (await nftMint.getOrCreateAssociatedAccountInfo(seller.publicKey)).address
+        sellerNftAssociatedTokenAccount = (await nftMint.getOrCreateAssociatedAccountInfo(seller.publicKey)).address;
 
-        comptoir = new Comptoir(provider)
-        await comptoir.createComptoir(seller, comptoirMint.publicKey, 5, sellerTokenAccount.address)
-        await comptoir.createCollection(seller, "AURY", creator.publicKey, "AURY", true)
-
-        let collectionPDA = await getCollectionPDA(comptoir.comptoirPDA, "AURY")
-        collection = new Collection(provider, collectionPDA, comptoir)
+        [comptoirPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                seller.publicKey.toBuffer()
+            ],
+            program.programId,
+        );
+        [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                comptoirMint.publicKey.toBuffer(),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+        await program.methods.createComptoir(comptoirMint.publicKey, 5, sellerTokenAccount.address, seller.publicKey)
+            .accounts({
+                payer: seller.publicKey,
+                comptoir: comptoirPDA,
+                mint: comptoirMint.publicKey,
+                escrow: escrowPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([seller]).rpc();
+
+        [collectionPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                Buffer.from("AURY"),
+                comptoirPDA.toBuffer(),
+            ],
+            program.programId,
+        );
+        await program.methods.createCollection("AURY", "AURY", creator.publicKey, null, true).accounts({
+            authority: seller.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([seller]).rpc();
+
+        [programNftVaultPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from('COMPTOIR'), Buffer.from("vault"), comptoirPDA.toBuffer(), nftMint.publicKey.toBuffer()],
+            program.programId,
+        );
+        [sellOrderPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                sellerNftAssociatedTokenAccount.toBuffer(),
+                Buffer.from("2000"),
+            ],
+            program.programId,
+        );
     });
 
     it('sell order ignore creators', async function () {
-        await collection.sellAsset(
-            nftMint.publicKey,
-            sellerNftAssociatedTokenAccount,
-            sellerTokenAccount.address,
-            new anchor.BN(2000),
-            new anchor.BN(2),
-            seller
-        )
+        await program.methods.createSellOrder(new anchor.BN(2000), new anchor.BN(2), sellerTokenAccount.address, null, null).accounts({
+            payer: seller.publicKey,
+            sellerNftTokenAccount: sellerNftAssociatedTokenAccount,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            mint: nftMint.publicKey,
+            metadata: metadataPDA,
+            vault: programNftVaultPDA,
+            sellOrder: sellOrderPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([seller]).rpc()
 
         let buyer = anchor.web3.Keypair.generate()
         let fromAirdropSignature = await provider.connection.requestAirdrop(
@@ -106,16 +164,22 @@ describe('ignore creators tests', () => {
 
         let buyerNftATA = await nftMint.createAssociatedTokenAccount(buyer.publicKey)
 
-        await collection.buy(
-            nftMint.publicKey,
-            [
-                await getSellOrderPDA(sellerNftAssociatedTokenAccount, new anchor.BN(2000)),
-            ],
-            buyerNftATA,
-            buyerTokenATA,
-            new anchor.BN(2),
-            buyer,
-        )
+        // Creators are ignored, the remaining accounts start with the sell orders
+        await program.methods.buy(new anchor.BN(2), new anchor.BN(4000), null).accounts({
+            buyer: buyer.publicKey,
+            buyerNftTokenAccount: buyerNftATA,
+            buyerPayingTokenAccount: buyerTokenATA,
+            comptoir: comptoirPDA,
+            comptoirDestAccount: sellerTokenAccount.address,
+            collection: collectionPDA,
+            metadata: metadataPDA,
+            vault: programNftVaultPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        }).remainingAccounts([
+            {pubkey: sellOrderPDA, isWritable: true, isSigner: false},
+            {pubkey: sellerTokenAccount.address, isWritable: true, isSigner: false},
+        ]).signers([buyer]).rpc()
 
         let buyerNftAccountAfterSell = await nftMint.getAccountInfo(buyerNftATA)
         assert.equal(buyerNftAccountAfterSell.amount.toNumber(), 2)
@@ -0,0 +1,262 @@
+import * as anchor from '@project-serum/anchor';
+import {Program, web3} from '@project-serum/anchor';
+import {Comptoir} from '../target/types/comptoir';
+import {PublicKey} from "@solana/web3.js";
+import {TOKEN_PROGRAM_ID} from "@solana/spl-token";
+import assert from "assert";
+import {nft_data, nft_json_url} from "./data";
+import {createMetadata} from "./utils/utils";
+import auryKey from "./keys/aury-teST1ieLrLdr4MJPZ7i8mgSCLQ7rTrPRjNnyFdHFaz9.json";
+
+let provider = anchor.getProvider()
+anchor.setProvider(provider);
+
+const program = anchor.workspace.Comptoir as Program<Comptoir>;
+
+// Accounts in their original layout are loaded by the test validator, see tests/data/legacy
+describe('legacy accounts migration', () => {
+    const aury = web3.Keypair.fromSecretKey(Uint8Array.from(auryKey));
+    const comptoirPDA = new PublicKey("DGn5hEBueZ2PkTayFQRXe4kETFwhajg6eFRdhFcASfAf");
+    const paymentMint = new PublicKey("9JNpo6k3VG8Fx6QFibDaqPoZMXrHhYTmkGoHnuMoYptG");
+    const nftMint = new PublicKey("BPBzh8qUDbSvsLDALXZEvK55bAPFVAtvxJ5Lbk4A5FAv");
+    const sellerPaymentAccount = new PublicKey("8CGqMvQc9DkeQtPqQc125foqcn2vnSLUhohZPp4zXh7c");
+    const legacySellOrder = new PublicKey("F7Fhd445MGwEEvzL4Dj4ueofEXFQtqqBktoszER6hMBA");
+    const legacyVaultSellOrder = new PublicKey("EGYC9jiCxfN6tWrfNGauLakR7LiKuke1p93a6vKtWxwq");
+    const legacyVault = new PublicKey("85q42B5myUjt39ufXGgTkUi2WomAfWDdH8ce8kV6LC4p");
+    const escrowPDA = new PublicKey("44mZWXf9tCPyZuo858ygQXpURekK9WJi2P3EQkfmr592");
+    const legacyBuyOffer = new PublicKey("6kAfqZJ6eDD7z3XbvJqoSM8cS9sdg5PzCeSRuBaB5xTh");
+    const legacyCollection = new PublicKey("HyA4TnjQWxRkEe6RDwfoF7r9gbnzHVtizdn66KYtQ9w1");
+    let metadataPDA: PublicKey;
+    let collectionPDA: PublicKey;
+    let stranger: web3.Keypair;
+    let vaultPDA: PublicKey;
+
+    const migrateSellOrderVault = async (sellOrder: PublicKey) => {
+        await program.methods.migrateSellOrderVault().accounts({
+            payer: aury.publicKey,
+            comptoir: comptoirPDA,
+            sellOrder: sellOrder,
+            mint: nftMint,
+            legacyVault: legacyVault,
+            vault: vaultPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([aury]).rpc();
+    }
+
+    const getBalance = async (account: PublicKey): Promise<number> => {
+        return Number((await provider.connection.getTokenAccountBalance(account)).value.amount);
+    }
+
+    const migrateBuyOffer = async (buyer: web3.Keypair) => {
+        await program.methods.migrateBuyOffer().accounts({
+            buyer: buyer.publicKey,
+            metadata: metadataPDA,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            buyOffer: legacyBuyOffer,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).signers([buyer]).rpc();
+    }
+
+    it('Prepare tests variables', async () => {
+        stranger = anchor.web3.Keypair.generate()
+        for (let keypair of [aury, stranger]) {
+            let fromAirdropSignature = await provider.connection.requestAirdrop(
+                keypair.publicKey,
+                anchor.web3.LAMPORTS_PER_SOL,
+            );
+            await provider.connection.confirmTransaction(fromAirdropSignature);
+        }
+
+        let account = await provider.connection.getAccountInfo(legacySellOrder);
+        assert.equal(account.data.length, 152);
+        account = await provider.connection.getAccountInfo(legacyBuyOffer);
+        assert.equal(account.data.length, 144);
+        [vaultPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from('COMPTOIR'), Buffer.from("vault"), comptoirPDA.toBuffer(), nftMint.toBuffer()],
+            program.programId,
+        );
+
+        // The fixture mint authority is the aury key, which can still give the item its metadata
+        let tx;
+        [metadataPDA, tx] = await createMetadata(aury.publicKey, nftMint, "LGC", nft_data(aury.publicKey), nft_json_url);
+        await provider.sendAndConfirm(tx, [aury]);
+
+        [collectionPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                Buffer.from("LGC"),
+                comptoirPDA.toBuffer(),
+            ],
+            program.programId,
+        );
+        await program.methods.createCollection("LGC", "LGC", aury.publicKey, null, false).accounts({
+            authority: aury.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([aury]).rpc();
+    });
+
+    it('fail: legacy sell orders cannot be loaded', async () => {
+        await assert.rejects(program.account.sellOrder.fetch(legacySellOrder));
+    });
+
+    it('migrate a legacy sell order', async () => {
+        await program.methods.migrateSellOrder().accounts({
+            payer: aury.publicKey,
+            comptoir: comptoirPDA,
+            sellOrder: legacySellOrder,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).signers([aury]).rpc();
+
+        let account = await provider.connection.getAccountInfo(legacySellOrder);
+        assert.equal(account.data.length, 271);
+        assert.ok(account.lamports >= await provider.connection.getMinimumBalanceForRentExemption(271));
+
+        let sellOrder = await program.account.sellOrder.fetch(legacySellOrder);
+        assert.equal(sellOrder.comptoir.toString(), comptoirPDA.toString());
+        assert.equal(sellOrder.price.toNumber(), 1000);
+        assert.equal(sellOrder.quantity.toNumber(), 1);
+        assert.equal(sellOrder.mint.toString(), nftMint.toString());
+        assert.equal(sellOrder.authority.toString(), aury.publicKey.toString());
+        assert.equal(sellOrder.destination.toString(), sellerPaymentAccount.toString());
+        assert.equal(sellOrder.priceSchedule, null);
+        assert.equal(sellOrder.expiresAt, null);
+        assert.equal(sellOrder.vaultMigrated, false);
+        assert.equal(sellOrder.paymentMint.toString(), paymentMint.toString());
+        assert.equal(sellOrder.oraclePrice, null);
+    });
+
+    it('fail: migrate a sell order twice', async () => {
+        await assert.rejects(
+            program.methods.migrateSellOrder().accounts({
+                payer: aury.publicKey,
+                comptoir: comptoirPDA,
+                sellOrder: legacySellOrder,
+                systemProgram: anchor.web3.SystemProgram.programId,
+            }).signers([aury]).rpc(),
+            (err: any) => err.error.errorCode.code === "ErrWrongLegacyAccount"
+        );
+    });
+
+    it('migrate the vault of a sell order still in the legacy layout', async () => {
+        // The legacy vault holds the items of both legacy sell orders
+        assert.equal(await getBalance(legacyVault), 3);
+
+        await migrateSellOrderVault(legacyVaultSellOrder);
+
+        let account = await provider.connection.getAccountInfo(legacyVaultSellOrder);
+        assert.equal(account.data.length, 271);
+        let sellOrder = await program.account.sellOrder.fetch(legacyVaultSellOrder);
+        assert.equal(sellOrder.price.toNumber(), 2000);
+        assert.equal(sellOrder.quantity.toNumber(), 2);
+        assert.equal(sellOrder.vaultMigrated, true);
+        assert.equal(sellOrder.paymentMint.toString(), paymentMint.toString());
+        assert.equal(await getBalance(vaultPDA), 2);
+        assert.equal(await getBalance(legacyVault), 1);
+    });
+
+    it('migrate the vault of an upgraded sell order', async () => {
+        await migrateSellOrderVault(legacySellOrder);
+
+        let sellOrder = await program.account.sellOrder.fetch(legacySellOrder);
+        assert.equal(sellOrder.vaultMigrated, true);
+        assert.equal(await getBalance(vaultPDA), 3);
+        assert.equal(await getBalance(legacyVault), 0);
+    });
+
+    it('fail: migrate the vault of a sell order twice', async () => {
+        await assert.rejects(
+            migrateSellOrderVault(legacyVaultSellOrder),
+            (err: any) => err.error.errorCode.code === "ErrSellOrderVaultAlreadyMigrated"
+        );
+    });
+
+    it('fail: legacy buy offers cannot be loaded', async () => {
+        await assert.rejects(program.account.buyOffer.fetch(legacyBuyOffer));
+    });
+
+    it('fail: migrate a buy offer of someone else', async () => {
+        await assert.rejects(
+            migrateBuyOffer(stranger),
+            (err: any) => err.error.errorCode.code === "ErrWrongBuyOfferAuthority"
+        );
+    });
+
+    it('migrate a legacy buy offer', async () => {
+        await migrateBuyOffer(aury);
+
+        let account = await provider.connection.getAccountInfo(legacyBuyOffer);
+        assert.equal(account.data.length, 258);
+
+        let buyOffer = await program.account.buyOffer.fetch(legacyBuyOffer);
+        assert.equal(buyOffer.comptoir.toString(), comptoirPDA.toString());
+        assert.equal(buyOffer.mint.toString(), nftMint.toString());
+        assert.equal(buyOffer.proposedPrice.toNumber(), 1000);
+        assert.equal(buyOffer.authority.toString(), aury.publicKey.toString());
+        assert.equal(buyOffer.expiresAt, null);
+        assert.equal(buyOffer.collection.toString(), collectionPDA.toString());
+        assert.equal(buyOffer.quantity.toNumber(), 1);
+        assert.equal(buyOffer.traitHash, null);
+        assert.equal(buyOffer.paymentMint.toString(), paymentMint.toString());
+    });
+
+    it('remove a migrated buy offer refunds the buyer', async () => {
+        await program.methods.removeBuyOffer().accounts({
+            buyer: aury.publicKey,
+            buyerPayingAccount: sellerPaymentAccount,
+            comptoir: comptoirPDA,
+            escrow: escrowPDA,
+            buyOffer: legacyBuyOffer,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([aury]).rpc();
+
+        let balance = await provider.connection.getTokenAccountBalance(sellerPaymentAccount);
+        assert.equal(balance.value.amount, "1000");
+        assert.equal(await provider.connection.getAccountInfo(legacyBuyOffer), null);
+    });
+
+    it('fail: legacy collections cannot be loaded', async () => {
+        await assert.rejects(program.account.collection.fetch(legacyCollection));
+    });
+
+    it('migrate a legacy collection', async () => {
+        await program.methods.migrateCollection().accounts({
+            payer: stranger.publicKey,
+            collection: legacyCollection,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).signers([stranger]).rpc();
+
+        let account = await provider.connection.getAccountInfo(legacyCollection);
+        assert.equal(account.data.length, 177);
+
+        let collection = await program.account.collection.fetch(legacyCollection);
+        assert.equal(collection.comptoirKey.toString(), comptoirPDA.toString());
+        assert.equal(collection.name, "LEGACY_COLLECTION");
+        assert.equal(collection.symbol, "LEGACYSYM");
+        assert.equal(collection.requiredVerifier.toString(), aury.publicKey.toString());
+        assert.equal(collection.fees, 100);
+        assert.equal(collection.ignoreCreatorFee, false);
+        assert.equal(collection.traitsRoot, null);
+        assert.equal(collection.certifiedCollection, null);
+        assert.equal(collection.customCurrencies, false);
+    });
+
+    it('update a migrated collection', async () => {
+        let root = Buffer.alloc(32, 1);
+        await program.methods.setCollectionTraitsRoot(Array.from(root)).accounts({
+            authority: aury.publicKey,
+            comptoir: comptoirPDA,
+            collection: legacyCollection,
+        }).signers([aury]).rpc()
+
+        let collection = await program.account.collection.fetch(legacyCollection)
+        assert.equal(Buffer.from(collection.traitsRoot).toString('hex'), root.toString('hex'));
+    });
+});
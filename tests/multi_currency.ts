@@ -0,0 +1,303 @@
+import * as anchor from '@project-serum/anchor';
+import {Program, web3} from '@project-serum/anchor';
+import {Comptoir} from '../target/types/comptoir';
+import * as splToken from '@solana/spl-token';
+import {PublicKey} from "@solana/web3.js";
+import {ASSOCIATED_TOKEN_PROGRAM_ID, Token, TOKEN_PROGRAM_ID} from "@solana/spl-token";
+import assert from "assert";
+import {nft_data, nft_json_url} from "./data";
+import {createMint} from "./utils/utils";
+
+let provider = anchor.getProvider()
+anchor.setProvider(provider);
+
+const program = anchor.workspace.Comptoir as Program<Comptoir>;
+
+describe('comptoir with several currencies', () => {
+    let admin: web3.Keypair;
+    let adminTokenAccount: splToken.AccountInfo;
+    let adminCurrencyAccount: splToken.AccountInfo;
+    let creator: web3.Keypair;
+    let creatorCurrencyAccount: splToken.AccountInfo;
+    let seller: web3.Keypair;
+    let sellerCurrencyAccount: splToken.AccountInfo;
+    let comptoirPDA: PublicKey;
+    let comptoirMint: splToken.Token;
+    let currencyMint: splToken.Token;
+    let collectionName = "AURY"
+    let collectionPDA: PublicKey;
+    let nftMint: splToken.Token;
+    let metadataPDA: PublicKey;
+    let sellerNftAssociatedTokenAccount: PublicKey;
+    let programNftVaultPDA: PublicKey;
+    let escrowPDA: PublicKey;
+    let currencyEscrowPDA: PublicKey;
+    let currencyPDA: PublicKey;
+    let collectionCurrencyPDA: PublicKey;
+
+    const getSellOrderPDA = async (price: number): Promise<PublicKey> => {
+        return (await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                sellerNftAssociatedTokenAccount.toBuffer(),
+                Buffer.from(price.toString()),
+            ],
+            program.programId,
+        ))[0];
+    }
+
+    const createSellOrder = async (price: number, currency: PublicKey) => {
+        await program.methods.createSellOrder(new anchor.BN(price), new anchor.BN(1), sellerCurrencyAccount.address, null, null).accounts({
+            payer: seller.publicKey,
+            sellerNftTokenAccount: sellerNftAssociatedTokenAccount,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            mint: nftMint.publicKey,
+            metadata: metadataPDA,
+            vault: programNftVaultPDA,
+            sellOrder: await getSellOrderPDA(price),
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).remainingAccounts([
+            {pubkey: currency, isWritable: false, isSigner: false},
+        ]).signers([seller]).rpc()
+    }
+
+    it('Prepare tests variables', async () => {
+        admin = anchor.web3.Keypair.generate()
+        creator = anchor.web3.Keypair.generate()
+        seller = anchor.web3.Keypair.generate()
+        for (let keypair of [admin, creator, seller]) {
+            let fromAirdropSignature = await provider.connection.requestAirdrop(
+                keypair.publicKey,
+                anchor.web3.LAMPORTS_PER_SOL,
+            );
+            await provider.connection.confirmTransaction(fromAirdropSignature);
+        }
+
+        [comptoirPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                admin.publicKey.toBuffer()
+            ],
+            program.programId,
+        )
+
+        comptoirMint = await splToken.Token.createMint(
+            provider.connection,
+            admin,
+            admin.publicKey,
+            null,
+            6,
+            splToken.TOKEN_PROGRAM_ID,
+        );
+        currencyMint = await splToken.Token.createMint(
+            provider.connection,
+            admin,
+            admin.publicKey,
+            null,
+            6,
+            splToken.TOKEN_PROGRAM_ID,
+        );
+
+        [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                comptoirMint.publicKey.toBuffer(),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+        [currencyEscrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                currencyMint.publicKey.toBuffer(),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+        [currencyPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                currencyMint.publicKey.toBuffer(),
+                Buffer.from("CURRENCY"),
+            ],
+            program.programId,
+        );
+
+        adminTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(admin.publicKey);
+        adminCurrencyAccount = await currencyMint.getOrCreateAssociatedAccountInfo(admin.publicKey);
+        creatorCurrencyAccount = await currencyMint.getOrCreateAssociatedAccountInfo(creator.publicKey);
+        sellerCurrencyAccount = await currencyMint.getOrCreateAssociatedAccountInfo(seller.publicKey);
+
+        [collectionPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                Buffer.from(collectionName),
+                comptoirPDA.toBuffer(),
+            ],
+            program.programId,
+        );
+        [collectionCurrencyPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                collectionPDA.toBuffer(),
+                currencyMint.publicKey.toBuffer(),
+                Buffer.from("CURRENCY"),
+            ],
+            program.programId,
+        );
+
+        const data = nft_data(creator.publicKey);
+        const lamports = await Token.getMinBalanceRentForExemptMint(
+            provider.connection
+        );
+        const [mint, metadataAddr, tx] = await createMint(
+            creator.publicKey,
+            seller.publicKey,
+            lamports,
+            data,
+            nft_json_url
+        );
+        await provider.sendAndConfirm(tx, [mint, creator]);
+
+        metadataPDA = metadataAddr
+        nftMint = new Token(provider.connection, mint.publicKey, TOKEN_PROGRAM_ID, admin)
+
+        sellerNftAssociatedTokenAccount = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            nftMint.publicKey,
+            seller.publicKey
+        );
+
+        [programNftVaultPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from('COMPTOIR'), Buffer.from("vault"), comptoirPDA.toBuffer(), nftMint.publicKey.toBuffer()],
+            program.programId,
+        );
+
+        await program.methods.createComptoir(comptoirMint.publicKey, 200, adminTokenAccount.address, admin.publicKey)
+            .accounts({
+                payer: admin.publicKey,
+                comptoir: comptoirPDA,
+                mint: comptoirMint.publicKey,
+                escrow: escrowPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc();
+
+        await program.methods.createCollection(collectionName, collectionName, creator.publicKey, null, false).accounts(
+            {
+                authority: admin.publicKey,
+                comptoir: comptoirPDA,
+                collection: collectionPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc()
+    });
+
+    it('add a currency', async () => {
+        await program.methods.addCurrency(adminCurrencyAccount.address).accounts({
+            authority: admin.publicKey,
+            comptoir: comptoirPDA,
+            mint: currencyMint.publicKey,
+            currency: currencyPDA,
+            escrow: currencyEscrowPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([admin]).rpc()
+
+        let currency = await program.account.currency.fetch(currencyPDA)
+        assert.equal(currency.mint.toString(), currencyMint.publicKey.toString());
+        assert.equal(currency.feesDestination.toString(), adminCurrencyAccount.address.toString());
+    });
+
+    it('buy a sell order priced in the added currency', async () => {
+        let price = 1000
+        await createSellOrder(price, currencyPDA)
+        let sellOrderPDA = await getSellOrderPDA(price)
+        let sellOrder = await program.account.sellOrder.fetch(sellOrderPDA)
+        assert.equal(sellOrder.paymentMint.toString(), currencyMint.publicKey.toString());
+
+        let buyer = anchor.web3.Keypair.generate()
+        let fromAirdropSignature = await provider.connection.requestAirdrop(
+            buyer.publicKey,
+            anchor.web3.LAMPORTS_PER_SOL,
+        )
+        await provider.connection.confirmTransaction(fromAirdropSignature);
+        let buyerNftAta = await nftMint.getOrCreateAssociatedAccountInfo(buyer.publicKey)
+        let buyerComptoirAta = await comptoirMint.getOrCreateAssociatedAccountInfo(buyer.publicKey)
+        let buyerCurrencyAta = await currencyMint.getOrCreateAssociatedAccountInfo(buyer.publicKey)
+        await comptoirMint.mintTo(buyerComptoirAta.address, admin, [], 1000)
+        await currencyMint.mintTo(buyerCurrencyAta.address, admin, [], 1000)
+
+        const buy = (payingAccount: PublicKey, comptoirDestAccount: PublicKey, creatorAccount: PublicKey, withCurrency: boolean) => {
+            let remainingAccounts = [
+                {pubkey: creatorAccount, isWritable: true, isSigner: false},
+                {pubkey: sellOrderPDA, isWritable: true, isSigner: false},
+                {pubkey: sellerCurrencyAccount.address, isWritable: true, isSigner: false},
+            ]
+            if (withCurrency) {
+                remainingAccounts.push({pubkey: currencyPDA, isWritable: false, isSigner: false})
+            }
+            return program.methods.buy(new anchor.BN(1), new anchor.BN(price), null).accounts({
+                buyer: buyer.publicKey,
+                buyerNftTokenAccount: buyerNftAta.address,
+                buyerPayingTokenAccount: payingAccount,
+                comptoir: comptoirPDA,
+                comptoirDestAccount: comptoirDestAccount,
+                collection: collectionPDA,
+                metadata: metadataPDA,
+                vault: programNftVaultPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+            }).remainingAccounts(remainingAccounts).signers([buyer]).rpc()
+        }
+
+        let creatorComptoirAta = await comptoirMint.getOrCreateAssociatedAccountInfo(creator.publicKey)
+        await assert.rejects(buy(buyerComptoirAta.address, adminTokenAccount.address, creatorComptoirAta.address, false));
+        await assert.rejects(buy(buyerCurrencyAta.address, adminTokenAccount.address, creatorCurrencyAccount.address, true));
+        await buy(buyerCurrencyAta.address, adminCurrencyAccount.address, creatorCurrencyAccount.address, true)
+
+        let updatedAdminCurrencyAccount = await currencyMint.getAccountInfo(adminCurrencyAccount.address)
+        assert.equal(updatedAdminCurrencyAccount.amount.toNumber(), 20);
+        let updatedSellerCurrencyAccount = await currencyMint.getAccountInfo(sellerCurrencyAccount.address)
+        assert.equal(updatedSellerCurrencyAccount.amount.toNumber(), 880);
+        let updatedCreatorCurrencyAccount = await currencyMint.getAccountInfo(creatorCurrencyAccount.address)
+        assert.equal(updatedCreatorCurrencyAccount.amount.toNumber(), 100);
+        let updatedBuyerComptoirAta = await comptoirMint.getAccountInfo(buyerComptoirAta.address)
+        assert.equal(updatedBuyerComptoirAta.amount.toNumber(), 1000);
+    });
+
+    it('collection currencies replace the comptoir ones', async () => {
+        await program.methods.setCollectionCustomCurrencies(true).accounts({
+            authority: admin.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+        }).signers([admin]).rpc()
+
+        await assert.rejects(createSellOrder(2000, currencyPDA));
+
+        await program.methods.addCollectionCurrency(adminCurrencyAccount.address).accounts({
+            authority: admin.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            mint: currencyMint.publicKey,
+            currency: collectionCurrencyPDA,
+            escrow: currencyEscrowPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([admin]).rpc()
+
+        await createSellOrder(2000, collectionCurrencyPDA)
+        let sellOrder = await program.account.sellOrder.fetch(await getSellOrderPDA(2000))
+        assert.equal(sellOrder.paymentMint.toString(), currencyMint.publicKey.toString());
+    });
+});
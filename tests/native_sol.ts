@@ -0,0 +1,243 @@
+import * as anchor from '@project-serum/anchor';
+import {Program, web3} from '@project-serum/anchor';
+import {Comptoir} from '../target/types/comptoir';
+import {PublicKey} from "@solana/web3.js";
+import {ASSOCIATED_TOKEN_PROGRAM_ID, NATIVE_MINT, Token, TOKEN_PROGRAM_ID} from "@solana/spl-token";
+import assert from "assert";
+import {nft_data, nft_json_url} from "./data";
+import {createMint} from "./utils/utils";
+
+let provider = anchor.getProvider()
+anchor.setProvider(provider);
+
+const program = anchor.workspace.Comptoir as Program<Comptoir>;
+
+describe('native SOL comptoir', () => {
+    let admin: web3.Keypair;
+    let creator: web3.Keypair;
+    let seller: web3.Keypair;
+    let buyer: web3.Keypair;
+    let comptoirPDA: PublicKey;
+    let collectionName = "AURY"
+    let collectionPDA: PublicKey;
+    let nftMint: Token;
+    let metadataPDA: PublicKey;
+    let sellerNftAssociatedTokenAccount: PublicKey;
+    let buyerNftAccount: PublicKey;
+    let programNftVaultPDA: PublicKey;
+    let escrowPDA: PublicKey;
+    let sellOrderPDA: PublicKey;
+    let buyOfferPDA: PublicKey;
+    let price = 10_000_000;
+
+    it('Prepare tests variables', async () => {
+        admin = anchor.web3.Keypair.generate()
+        creator = anchor.web3.Keypair.generate()
+        seller = anchor.web3.Keypair.generate()
+        buyer = anchor.web3.Keypair.generate()
+        for (let keypair of [admin, creator, seller, buyer]) {
+            let fromAirdropSignature = await provider.connection.requestAirdrop(
+                keypair.publicKey,
+                anchor.web3.LAMPORTS_PER_SOL,
+            );
+            await provider.connection.confirmTransaction(fromAirdropSignature);
+        }
+
+        [comptoirPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                admin.publicKey.toBuffer()
+            ],
+            program.programId,
+        );
+        [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                NATIVE_MINT.toBuffer(),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+        [collectionPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                Buffer.from(collectionName),
+                comptoirPDA.toBuffer(),
+            ],
+            program.programId,
+        );
+
+        const data = nft_data(creator.publicKey);
+        const lamports = await Token.getMinBalanceRentForExemptMint(
+            provider.connection
+        );
+        const [mint, metadataAddr, tx] = await createMint(
+            creator.publicKey,
+            seller.publicKey,
+            lamports,
+            data,
+            nft_json_url
+        );
+        await provider.sendAndConfirm(tx, [mint, creator]);
+
+        metadataPDA = metadataAddr
+        nftMint = new Token(provider.connection, mint.publicKey, TOKEN_PROGRAM_ID, admin)
+
+        sellerNftAssociatedTokenAccount = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            nftMint.publicKey,
+            seller.publicKey
+        );
+        buyerNftAccount = (await nftMint.getOrCreateAssociatedAccountInfo(buyer.publicKey)).address;
+
+        [programNftVaultPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from('COMPTOIR'), Buffer.from("vault"), comptoirPDA.toBuffer(), nftMint.publicKey.toBuffer()],
+            program.programId,
+        );
+        [sellOrderPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                sellerNftAssociatedTokenAccount.toBuffer(),
+                Buffer.from(price.toString()),
+            ],
+            program.programId,
+        );
+        [buyOfferPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                buyer.publicKey.toBuffer(),
+                nftMint.publicKey.toBuffer(),
+                Buffer.from(price.toString()),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+
+        // Fees go straight to the admin wallet
+        await program.methods.createComptoir(NATIVE_MINT, 200, admin.publicKey, admin.publicKey)
+            .accounts({
+                payer: admin.publicKey,
+                comptoir: comptoirPDA,
+                mint: NATIVE_MINT,
+                escrow: escrowPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc();
+
+        await program.methods.createCollection(collectionName, collectionName, creator.publicKey, null, false).accounts(
+            {
+                authority: admin.publicKey,
+                comptoir: comptoirPDA,
+                collection: collectionPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc()
+    });
+
+    it('buy with lamports', async () => {
+        await program.methods.createSellOrder(new anchor.BN(price), new anchor.BN(1), seller.publicKey, null, null).accounts({
+            payer: seller.publicKey,
+            sellerNftTokenAccount: sellerNftAssociatedTokenAccount,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            mint: nftMint.publicKey,
+            metadata: metadataPDA,
+            vault: programNftVaultPDA,
+            sellOrder: sellOrderPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([seller]).rpc()
+
+        const buy = (remainingAccounts: any[]) => program.methods.buy(new anchor.BN(1), new anchor.BN(price), null).accounts({
+            buyer: buyer.publicKey,
+            buyerNftTokenAccount: buyerNftAccount,
+            buyerPayingTokenAccount: buyer.publicKey,
+            comptoir: comptoirPDA,
+            comptoirDestAccount: admin.publicKey,
+            collection: collectionPDA,
+            metadata: metadataPDA,
+            vault: programNftVaultPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        }).remainingAccounts(remainingAccounts).signers([buyer]).rpc()
+
+        // Failures surface as typed errors instead of program panics
+        await assert.rejects(buy([
+            {pubkey: creator.publicKey, isWritable: true, isSigner: false},
+            {pubkey: sellOrderPDA, isWritable: true, isSigner: false},
+            {pubkey: buyer.publicKey, isWritable: true, isSigner: false},
+        ]), (err: any) => err.error.errorCode.code === "ErrWrongSellerDestination");
+        await assert.rejects(buy([
+            {pubkey: creator.publicKey, isWritable: true, isSigner: false},
+            {pubkey: sellOrderPDA, isWritable: true, isSigner: false},
+        ]), (err: any) => err.error.errorCode.code === "ErrMissingRemainingAccounts");
+
+        let sellerBalance = await provider.connection.getBalance(seller.publicKey);
+        let adminBalance = await provider.connection.getBalance(admin.publicKey);
+        let creatorBalance = await provider.connection.getBalance(creator.publicKey);
+
+        let signature = await buy([
+            {pubkey: creator.publicKey, isWritable: true, isSigner: false},
+            {pubkey: sellOrderPDA, isWritable: true, isSigner: false},
+            {pubkey: seller.publicKey, isWritable: true, isSigner: false},
+        ])
+
+        assert.equal(await provider.connection.getBalance(seller.publicKey), sellerBalance + 8_800_000);
+        assert.equal(await provider.connection.getBalance(admin.publicKey), adminBalance + 200_000);
+        assert.equal(await provider.connection.getBalance(creator.publicKey), creatorBalance + 1_000_000);
+        let updatedBuyerNftAccount = await nftMint.getAccountInfo(buyerNftAccount)
+        assert.equal(updatedBuyerNftAccount.amount.toNumber(), 1);
+
+        // The sale event carries the fee breakdown
+        let transaction = await provider.connection.getTransaction(signature, {commitment: "confirmed"});
+        let events = [];
+        new anchor.EventParser(program.programId, program.coder).parseLogs(transaction.meta.logMessages, (event) => events.push(event));
+        let boughtEvent = events.find((event) => event.name === "BoughtSellOrderEvent");
+        assert.equal(boughtEvent.data.mint.toString(), nftMint.publicKey.toString());
+        assert.equal(boughtEvent.data.price.toNumber(), price);
+        assert.equal(boughtEvent.data.sellerAmount.toNumber(), 8_800_000);
+        assert.equal(boughtEvent.data.comptoirAmount.toNumber(), 200_000);
+        assert.equal(boughtEvent.data.creators[0].amount.toNumber(), 1_000_000);
+    });
+
+    it('buy offer locks lamports and refunds them on removal', async () => {
+        await program.methods.createBuyOffer(new anchor.BN(price), new anchor.BN(1), null).accounts({
+            payer: buyer.publicKey,
+            nftMint: nftMint.publicKey,
+            metadata: metadataPDA,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            escrow: escrowPDA,
+            buyerPayingAccount: buyer.publicKey,
+            buyerNftAccount: buyerNftAccount,
+            buyOffer: buyOfferPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([buyer]).rpc()
+
+        let rent = await provider.connection.getMinimumBalanceForRentExemption(258);
+        assert.equal(await provider.connection.getBalance(buyOfferPDA), rent + price);
+
+        let buyerBalance = await provider.connection.getBalance(buyer.publicKey);
+        await program.methods.removeBuyOffer().accounts({
+            buyer: buyer.publicKey,
+            buyerPayingAccount: buyer.publicKey,
+            comptoir: comptoirPDA,
+            escrow: escrowPDA,
+            buyOffer: buyOfferPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([buyer]).rpc()
+
+        // The buyer pays the transaction fee
+        assert.equal(await provider.connection.getBalance(buyer.publicKey), buyerBalance + rent + price - 5000);
+    });
+});
@@ -1,17 +1,19 @@
 import * as anchor from '@project-serum/anchor';
-import {web3} from '@project-serum/anchor';
+import {Program, web3} from '@project-serum/anchor';
+import {Comptoir} from '../target/types/comptoir';
 import * as splToken from '@solana/spl-token';
-import {Token, TOKEN_PROGRAM_ID} from "@solana/spl-token";
+import {ASSOCIATED_TOKEN_PROGRAM_ID, Token, TOKEN_PROGRAM_ID} from "@solana/spl-token";
 import assert from "assert";
 import {nft_data, nft_json_url} from "./data";
 import {createMint} from "./utils/utils";
-import {Comptoir, Collection, getBuyOfferPDA, getCollectionPDA, getEscrowPDA} from "@aurory/comptoirjs";
 import {PublicKey} from "@solana/web3.js";
 
 
 let provider = anchor.getProvider()
 anchor.setProvider(provider);
 
+const program = anchor.workspace.Comptoir as Program<Comptoir>;
+
 describe('comptoir with mint', () => {
     let admin: web3.Keypair;
     let adminTokenAccount: PublicKey;
@@ -26,10 +28,28 @@ describe('comptoir with mint', () => {
     let comptoirMint: splToken.Token;
     let nftMint: splToken.Token;
     let metadataPDA: web3.PublicKey;
-
-    let comptoir: Comptoir;
-    let collection: Collection;
-
+    let comptoirPDA: PublicKey;
+    let escrowPDA: PublicKey;
+    let collectionPDA: PublicKey;
+    let buyOfferPDA: PublicKey;
+
+    const createBuyOffer = async (price: anchor.BN) => {
+        await program.methods.createBuyOffer(price, new anchor.BN(1), null).accounts({
+            payer: buyer.publicKey,
+            nftMint: nftMint.publicKey,
+            metadata: metadataPDA,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            escrow: escrowPDA,
+            buyerPayingAccount: buyerTokenAccount,
+            buyerNftAccount: buyerNftTokenAccount,
+            buyOffer: buyOfferPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([buyer]).rpc()
+    }
 
     it('Prepare tests variables', async () => {
         creator = anchor.web3.Keypair.generate()
@@ -106,40 +126,78 @@ describe('comptoir with mint', () => {
         buyerNftTokenAccount = (await nftMint.getOrCreateAssociatedAccountInfo(buyer.publicKey)).address
         sellerNftTokenAccount = (await nftMint.getOrCreateAssociatedAccountInfo(seller.publicKey)).address
 
-        await comptoirMint.mintTo(buyerTokenAccount, admin, [], 1000)
-        comptoir = new Comptoir(provider)
-        await comptoir.createComptoir(admin, comptoirMint.publicKey, 500, adminTokenAccount)
-        await comptoir.createCollection(admin, "AURY", creator.publicKey, "AURY", false)
-
-        let collectionPDA = await getCollectionPDA(comptoir.comptoirPDA, "AURY")
-        collection = new Collection(provider, collectionPDA, comptoir)
+        await comptoirMint.mintTo(buyerTokenAccount, admin, [], 1000);
+        [comptoirPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                admin.publicKey.toBuffer()
+            ],
+            program.programId,
+        );
+        [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                comptoirMint.publicKey.toBuffer(),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+        await program.methods.createComptoir(comptoirMint.publicKey, 500, adminTokenAccount, admin.publicKey)
+            .accounts({
+                payer: admin.publicKey,
+                comptoir: comptoirPDA,
+                mint: comptoirMint.publicKey,
+                escrow: escrowPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc();
+
+        [collectionPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                Buffer.from("AURY"),
+                comptoirPDA.toBuffer(),
+            ],
+            program.programId,
+        );
+        await program.methods.createCollection("AURY", "AURY", creator.publicKey, null, false).accounts({
+            authority: admin.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([admin]).rpc();
+
+        [buyOfferPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                buyer.publicKey.toBuffer(),
+                nftMint.publicKey.toBuffer(),
+                Buffer.from("1000"),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
     });
 
 
     it('remove nft offer', async () => {
         let price = new anchor.BN(1000)
-        await collection.createBuyOffer(
-            nftMint.publicKey,
-            price,
-            buyerNftTokenAccount,
-            buyerTokenAccount,
-            buyer,
-        )
-
-        let escrowPDA = await getEscrowPDA(comptoir.comptoirPDA, comptoirMint.publicKey)
-        let buyOfferPDA = await getBuyOfferPDA(
-            comptoir.comptoirPDA,
-            buyer.publicKey,
-            nftMint.publicKey,
-            price,
-        )
-
-        await collection.removeBuyOffer(
-            nftMint.publicKey,
-            buyOfferPDA,
-            buyerTokenAccount,
-            buyer,
-        )
+        await createBuyOffer(price)
+
+        await program.methods.removeBuyOffer().accounts({
+            buyer: buyer.publicKey,
+            buyerPayingAccount: buyerTokenAccount,
+            comptoir: comptoirPDA,
+            escrow: escrowPDA,
+            buyOffer: buyOfferPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([buyer]).rpc()
 
         let escrowAccount = await comptoirMint.getAccountInfo(escrowPDA)
         assert.equal(escrowAccount.amount, 0);
@@ -151,25 +209,10 @@ describe('comptoir with mint', () => {
     });
 
     it('create nft offer', async () => {
-        let price = new anchor.BN(1000)
-        await collection.createBuyOffer(
-            nftMint.publicKey,
-            price,
-            buyerNftTokenAccount,
-            buyerTokenAccount,
-            buyer,
-        )
-
-        let escrowPDA = await getEscrowPDA(comptoir.comptoirPDA, comptoirMint.publicKey)
-        let buyOfferPDA = await getBuyOfferPDA(
-            comptoir.comptoirPDA,
-            buyer.publicKey,
-            nftMint.publicKey,
-            price,
-        )
+        await createBuyOffer(new anchor.BN(1000))
 
-        let buyOffer = await comptoir.program.account.buyOffer.fetch(buyOfferPDA)
-        assert.equal(buyOffer.comptoir.toString(), comptoir.comptoirPDA.toString());
+        let buyOffer = await program.account.buyOffer.fetch(buyOfferPDA)
+        assert.equal(buyOffer.comptoir.toString(), comptoirPDA.toString());
         assert.equal(buyOffer.mint.toString(), nftMint.publicKey.toString());
         assert.equal(buyOffer.proposedPrice.toString(), "1000");
         assert.equal(buyOffer.authority.toString(), buyer.publicKey.toString());
@@ -183,23 +226,24 @@ describe('comptoir with mint', () => {
     });
 
     it('execute nft offer', async () => {
-        let escrowPDA = await getEscrowPDA(comptoir.comptoirPDA, comptoirMint.publicKey)
-        let buyOfferPDA = await getBuyOfferPDA(
-            comptoir.comptoirPDA,
-            buyer.publicKey,
-            nftMint.publicKey,
-            new anchor.BN(1000),
-        )
-
-        await collection.executeOffer(
-            nftMint.publicKey,
-            buyOfferPDA,
-            buyer.publicKey,
-            buyerNftTokenAccount,
-            sellerTokenAccount,
-            sellerNftTokenAccount,
-            seller,
-        )
+        await program.methods.executeOffer(new anchor.BN(1), []).accounts({
+            seller: seller.publicKey,
+            buyer: buyer.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            comptoirDestAccount: adminTokenAccount,
+            escrow: escrowPDA,
+            sellerFundsDestAccount: sellerTokenAccount,
+            destination: buyerNftTokenAccount,
+            sellerNftAccount: sellerNftTokenAccount,
+            metadata: metadataPDA,
+            buyOffer: buyOfferPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).remainingAccounts([
+            {pubkey: creatorTokenAccount, isWritable: true, isSigner: false},
+        ]).signers([seller]).rpc()
 
         let escrowAccount = await comptoirMint.getAccountInfo(escrowPDA)
         assert.equal(escrowAccount.amount, 0);
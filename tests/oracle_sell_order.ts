@@ -0,0 +1,226 @@
+import * as anchor from '@project-serum/anchor';
+import {Program, web3} from '@project-serum/anchor';
+import {Comptoir} from '../target/types/comptoir';
+import * as splToken from '@solana/spl-token';
+import {PublicKey} from "@solana/web3.js";
+import {ASSOCIATED_TOKEN_PROGRAM_ID, Token, TOKEN_PROGRAM_ID} from "@solana/spl-token";
+import assert from "assert";
+import {nft_data, nft_json_url} from "./data";
+import {createMint} from "./utils/utils";
+
+let provider = anchor.getProvider()
+anchor.setProvider(provider);
+
+const program = anchor.workspace.Comptoir as Program<Comptoir>;
+
+describe('oracle priced sell orders', () => {
+    let admin: web3.Keypair;
+    let adminTokenAccount: splToken.AccountInfo;
+    let creator: web3.Keypair;
+    let creatorTokenAccount: splToken.AccountInfo;
+    let seller: web3.Keypair;
+    let sellerTokenAccount: splToken.AccountInfo;
+    let buyer: web3.Keypair;
+    let buyerTokenAccount: splToken.AccountInfo;
+    let buyerNftAccount: splToken.AccountInfo;
+    let comptoirPDA: PublicKey;
+    let comptoirMint: splToken.Token;
+    let fee = 200;
+    let collectionName = "AURY"
+    let collectionPDA: PublicKey;
+    let nftMint: splToken.Token;
+    let metadataPDA: PublicKey;
+    let sellerNftAssociatedTokenAccount: PublicKey;
+    let programNftVaultPDA: PublicKey;
+    let escrowPDA: PublicKey;
+
+    // Mock pyth price accounts loaded by the test validator, all at 2 reference units per token
+    const priceFeed = new PublicKey("HG2FcidUTU5SqyaFo7ZDSp9QQvZyGGrhuSH4KwQ1xaJ6");
+    const widePriceFeed = new PublicKey("DN5SHL9BkhxG54WqTyXor1RUqaS1na5GHCaqvnWkss7C");
+    const stalePriceFeed = new PublicKey("6LkPnSwSRpomTAKpK5znSQLFPgdyUe4jH79Xga6zXAgv");
+
+    const getSellOrderPDA = async (price: anchor.BN): Promise<PublicKey> => {
+        return (await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                sellerNftAssociatedTokenAccount.toBuffer(),
+                Buffer.from(price.toString()),
+            ],
+            program.programId,
+        ))[0];
+    }
+
+    const createOracleSellOrder = async (price: anchor.BN, feed: PublicKey) => {
+        await program.methods.createOracleSellOrder(price, new anchor.BN(1), sellerTokenAccount.address, null, null, {
+            priceFeed: feed,
+            maxStaleness: new anchor.BN(60),
+            maxConfidenceBps: 100,
+        }).accounts({
+            payer: seller.publicKey,
+            sellerNftTokenAccount: sellerNftAssociatedTokenAccount,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            mint: nftMint.publicKey,
+            metadata: metadataPDA,
+            vault: programNftVaultPDA,
+            sellOrder: await getSellOrderPDA(price),
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([seller]).rpc()
+    }
+
+    const buy = async (sellOrderPDA: PublicKey, feed: PublicKey, maxTotalPrice: anchor.BN) => {
+        await program.methods.buy(new anchor.BN(1), maxTotalPrice, null).accounts({
+            buyer: buyer.publicKey,
+            buyerNftTokenAccount: buyerNftAccount.address,
+            buyerPayingTokenAccount: buyerTokenAccount.address,
+            comptoir: comptoirPDA,
+            comptoirDestAccount: adminTokenAccount.address,
+            collection: collectionPDA,
+            metadata: metadataPDA,
+            vault: programNftVaultPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        }).remainingAccounts([
+            {pubkey: creatorTokenAccount.address, isWritable: true, isSigner: false},
+            {pubkey: sellOrderPDA, isWritable: true, isSigner: false},
+            {pubkey: feed, isWritable: false, isSigner: false},
+            {pubkey: sellerTokenAccount.address, isWritable: true, isSigner: false},
+        ]).signers([buyer]).rpc()
+    }
+
+    it('Prepare tests variables', async () => {
+        admin = anchor.web3.Keypair.generate()
+        creator = anchor.web3.Keypair.generate()
+        seller = anchor.web3.Keypair.generate()
+        buyer = anchor.web3.Keypair.generate()
+        for (let keypair of [admin, creator, seller, buyer]) {
+            let fromAirdropSignature = await provider.connection.requestAirdrop(
+                keypair.publicKey,
+                anchor.web3.LAMPORTS_PER_SOL,
+            );
+            await provider.connection.confirmTransaction(fromAirdropSignature);
+        }
+
+        [comptoirPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                admin.publicKey.toBuffer()
+            ],
+            program.programId,
+        )
+
+        comptoirMint = await splToken.Token.createMint(
+            provider.connection,
+            admin,
+            admin.publicKey,
+            null,
+            6,
+            splToken.TOKEN_PROGRAM_ID,
+        );
+
+        [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                comptoirMint.publicKey.toBuffer(),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+
+        adminTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(admin.publicKey);
+        creatorTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(creator.publicKey);
+        sellerTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(seller.publicKey);
+        buyerTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(buyer.publicKey);
+        await comptoirMint.mintTo(buyerTokenAccount.address, admin, [], 10_000_000)
+
+        [collectionPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                Buffer.from(collectionName),
+                comptoirPDA.toBuffer(),
+            ],
+            program.programId,
+        );
+
+        const data = nft_data(creator.publicKey);
+        const lamports = await Token.getMinBalanceRentForExemptMint(
+            provider.connection
+        );
+        const [mint, metadataAddr, tx] = await createMint(
+            creator.publicKey,
+            seller.publicKey,
+            lamports,
+            data,
+            nft_json_url
+        );
+        await provider.sendAndConfirm(tx, [mint, creator]);
+
+        metadataPDA = metadataAddr
+        nftMint = new Token(provider.connection, mint.publicKey, TOKEN_PROGRAM_ID, admin)
+
+        sellerNftAssociatedTokenAccount = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            nftMint.publicKey,
+            seller.publicKey
+        );
+        buyerNftAccount = await nftMint.getOrCreateAssociatedAccountInfo(buyer.publicKey);
+
+        [programNftVaultPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from('COMPTOIR'), Buffer.from("vault"), comptoirPDA.toBuffer(), nftMint.publicKey.toBuffer()],
+            program.programId,
+        );
+
+        await program.methods.createComptoir(comptoirMint.publicKey, fee, adminTokenAccount.address, admin.publicKey)
+            .accounts({
+                payer: admin.publicKey,
+                comptoir: comptoirPDA,
+                mint: comptoirMint.publicKey,
+                escrow: escrowPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc();
+
+        await program.methods.createCollection(collectionName, collectionName, creator.publicKey, null, false).accounts(
+            {
+                authority: admin.publicKey,
+                comptoir: comptoirPDA,
+                collection: collectionPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc()
+    });
+
+    it('buy oracle priced sell order at the converted price', async () => {
+        let price = new anchor.BN(10_000_000);
+        await createOracleSellOrder(price, priceFeed)
+        let sellOrderPDA = await getSellOrderPDA(price)
+
+        await assert.rejects(buy(sellOrderPDA, widePriceFeed, new anchor.BN(5_000_000)));
+        await assert.rejects(buy(sellOrderPDA, priceFeed, new anchor.BN(4_999_999)));
+        await buy(sellOrderPDA, priceFeed, new anchor.BN(5_000_000))
+
+        let updatedSellerTokenAccount = await comptoirMint.getAccountInfo(sellerTokenAccount.address)
+        assert.equal(updatedSellerTokenAccount.amount.toNumber(), 4_400_000);
+        let updatedAdminTokenAccount = await comptoirMint.getAccountInfo(adminTokenAccount.address)
+        assert.equal(updatedAdminTokenAccount.amount.toNumber(), 100_000);
+        let updatedBuyerTokenAccount = await comptoirMint.getAccountInfo(buyerTokenAccount.address)
+        assert.equal(updatedBuyerTokenAccount.amount.toNumber(), 5_000_000);
+    });
+
+    it('fail: stale price feed', async () => {
+        let price = new anchor.BN(2_000_000);
+        await createOracleSellOrder(price, stalePriceFeed)
+        await assert.rejects(buy(await getSellOrderPDA(price), stalePriceFeed, new anchor.BN(5_000_000)));
+    });
+
+    it('fail: price feed too uncertain', async () => {
+        let price = new anchor.BN(3_000_000);
+        await createOracleSellOrder(price, widePriceFeed)
+        await assert.rejects(buy(await getSellOrderPDA(price), widePriceFeed, new anchor.BN(5_000_000)));
+    });
+});
@@ -0,0 +1,404 @@
+import * as anchor from '@project-serum/anchor';
+import {Program, web3} from '@project-serum/anchor';
+import {Comptoir} from '../target/types/comptoir';
+import * as splToken from '@solana/spl-token';
+import {PublicKey} from "@solana/web3.js";
+import {ASSOCIATED_TOKEN_PROGRAM_ID, Token, TOKEN_PROGRAM_ID} from "@solana/spl-token";
+import assert from "assert";
+import {nft_data, nft_json_url} from "./data";
+import {createMint} from "./utils/utils";
+
+let provider = anchor.getProvider()
+anchor.setProvider(provider);
+
+const program = anchor.workspace.Comptoir as Program<Comptoir>;
+
+describe('order book', () => {
+    let admin: web3.Keypair;
+    let adminTokenAccount: splToken.AccountInfo;
+    let creator: web3.Keypair;
+    let creatorTokenAccount: splToken.AccountInfo;
+    let seller: web3.Keypair;
+    let sellerTokenAccount: splToken.AccountInfo;
+    let buyer: web3.Keypair;
+    let buyerTokenAccount: splToken.AccountInfo;
+    let buyerNftAccount: PublicKey;
+    let comptoirPDA: PublicKey;
+    let comptoirMint: splToken.Token;
+    let collectionName = "AURY"
+    let collectionPDA: PublicKey;
+    let nftMint: splToken.Token;
+    let metadataPDA: PublicKey;
+    let sellerNftAssociatedTokenAccount: PublicKey;
+    let programNftVaultPDA: PublicKey;
+    let escrowPDA: PublicKey;
+    let orderBookPDA: PublicKey;
+    let buyOfferPDA: PublicKey;
+
+    const getSellOrderPDA = async (price: number): Promise<PublicKey> => {
+        return (await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                sellerNftAssociatedTokenAccount.toBuffer(),
+                Buffer.from(price.toString()),
+            ],
+            program.programId,
+        ))[0];
+    }
+
+    const createBookedSellOrder = async (price: number, quantity: number) => {
+        let sellOrderPDA = await getSellOrderPDA(price)
+        await program.methods.createSellOrder(new anchor.BN(price), new anchor.BN(quantity), sellerTokenAccount.address, null, null).accounts({
+            payer: seller.publicKey,
+            sellerNftTokenAccount: sellerNftAssociatedTokenAccount,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            mint: nftMint.publicKey,
+            metadata: metadataPDA,
+            vault: programNftVaultPDA,
+            sellOrder: sellOrderPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([seller]).rpc()
+
+        await program.methods.addSellOrderToBook().accounts({
+            authority: seller.publicKey,
+            orderBook: orderBookPDA,
+            sellOrder: sellOrderPDA,
+        }).signers([seller]).rpc()
+    }
+
+    const getBuyOfferPDA = async (price: number): Promise<PublicKey> => {
+        return (await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                buyer.publicKey.toBuffer(),
+                nftMint.publicKey.toBuffer(),
+                Buffer.from(price.toString()),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        ))[0];
+    }
+
+    const createBookedBuyOffer = async (price: number, quantity: number) => {
+        let offerPDA = await getBuyOfferPDA(price)
+        await program.methods.createBuyOffer(new anchor.BN(price), new anchor.BN(quantity), null).accounts({
+            payer: buyer.publicKey,
+            nftMint: nftMint.publicKey,
+            metadata: metadataPDA,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            escrow: escrowPDA,
+            buyerPayingAccount: buyerTokenAccount.address,
+            buyerNftAccount: buyerNftAccount,
+            buyOffer: offerPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([buyer]).rpc()
+
+        await program.methods.addBuyOfferToBook().accounts({
+            authority: buyer.publicKey,
+            orderBook: orderBookPDA,
+            buyOffer: offerPDA,
+        }).signers([buyer]).rpc()
+    }
+
+    const matchOrders = async (sellOrderPDA: PublicKey, currency: PublicKey[] = []): Promise<string> => {
+        return await program.methods.matchOrders().accounts({
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            orderBook: orderBookPDA,
+            comptoirDestAccount: adminTokenAccount.address,
+            metadata: metadataPDA,
+            vault: programNftVaultPDA,
+            escrow: escrowPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        }).remainingAccounts([
+            {pubkey: creatorTokenAccount.address, isWritable: true, isSigner: false},
+            {pubkey: sellOrderPDA, isWritable: true, isSigner: false},
+            {pubkey: sellerTokenAccount.address, isWritable: true, isSigner: false},
+            {pubkey: buyOfferPDA, isWritable: true, isSigner: false},
+            {pubkey: buyerNftAccount, isWritable: true, isSigner: false},
+            {pubkey: buyerTokenAccount.address, isWritable: true, isSigner: false},
+            {pubkey: buyer.publicKey, isWritable: true, isSigner: false},
+            ...currency.map((pubkey) => ({pubkey, isWritable: false, isSigner: false})),
+        ]).rpc()
+    }
+
+    it('Prepare tests variables', async () => {
+        admin = anchor.web3.Keypair.generate()
+        creator = anchor.web3.Keypair.generate()
+        seller = anchor.web3.Keypair.generate()
+        buyer = anchor.web3.Keypair.generate()
+        for (let keypair of [admin, creator, seller, buyer]) {
+            let fromAirdropSignature = await provider.connection.requestAirdrop(
+                keypair.publicKey,
+                anchor.web3.LAMPORTS_PER_SOL,
+            );
+            await provider.connection.confirmTransaction(fromAirdropSignature);
+        }
+
+        [comptoirPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                admin.publicKey.toBuffer()
+            ],
+            program.programId,
+        )
+
+        comptoirMint = await splToken.Token.createMint(
+            provider.connection,
+            admin,
+            admin.publicKey,
+            null,
+            6,
+            splToken.TOKEN_PROGRAM_ID,
+        );
+
+        [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                comptoirMint.publicKey.toBuffer(),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+
+        adminTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(admin.publicKey);
+        creatorTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(creator.publicKey);
+        sellerTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(seller.publicKey);
+        buyerTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(buyer.publicKey);
+        await comptoirMint.mintTo(buyerTokenAccount.address, admin, [], 2000)
+
+        [collectionPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                Buffer.from(collectionName),
+                comptoirPDA.toBuffer(),
+            ],
+            program.programId,
+        );
+
+        const data = nft_data(creator.publicKey);
+        const lamports = await Token.getMinBalanceRentForExemptMint(
+            provider.connection
+        );
+        const [mint, metadataAddr, tx] = await createMint(
+            creator.publicKey,
+            seller.publicKey,
+            lamports,
+            data,
+            nft_json_url
+        );
+        await provider.sendAndConfirm(tx, [mint, creator]);
+        nftMint = new Token(provider.connection, mint.publicKey, TOKEN_PROGRAM_ID, admin)
+        metadataPDA = metadataAddr
+        buyerNftAccount = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            nftMint.publicKey,
+            buyer.publicKey
+        );
+
+        sellerNftAssociatedTokenAccount = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            nftMint.publicKey,
+            seller.publicKey
+        );
+        [programNftVaultPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from('COMPTOIR'), Buffer.from("vault"), comptoirPDA.toBuffer(), nftMint.publicKey.toBuffer()],
+            program.programId,
+        );
+        [orderBookPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                nftMint.publicKey.toBuffer(),
+                comptoirMint.publicKey.toBuffer(),
+                Buffer.from("ORDER_BOOK"),
+            ],
+            program.programId,
+        );
+        [buyOfferPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                buyer.publicKey.toBuffer(),
+                nftMint.publicKey.toBuffer(),
+                Buffer.from("250"),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+
+        await program.methods.createComptoir(comptoirMint.publicKey, 200, adminTokenAccount.address, admin.publicKey)
+            .accounts({
+                payer: admin.publicKey,
+                comptoir: comptoirPDA,
+                mint: comptoirMint.publicKey,
+                escrow: escrowPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc();
+
+        await program.methods.createCollection(collectionName, collectionName, creator.publicKey, null, false).accounts(
+            {
+                authority: admin.publicKey,
+                comptoir: comptoirPDA,
+                collection: collectionPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc()
+    });
+
+    it('create the order book and book orders', async () => {
+        await program.methods.createOrderBook(comptoirMint.publicKey).accounts({
+            authority: admin.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            mint: nftMint.publicKey,
+            metadata: metadataPDA,
+            orderBook: orderBookPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([admin]).rpc()
+
+        await createBookedSellOrder(300, 2)
+        await createBookedSellOrder(200, 1)
+
+        await createBookedBuyOffer(250, 2)
+
+        let orderBook = await program.account.orderBook.fetch(orderBookPDA)
+        assert.deepEqual(orderBook.asks.map(e => e.price.toNumber()), [200, 300]);
+        assert.deepEqual(orderBook.bids.map(e => e.price.toNumber()), [250]);
+    });
+
+    it('match crosses the best ask with the best bid at the resting price', async () => {
+        await assert.rejects(matchOrders(await getSellOrderPDA(300)));
+        let signature = await matchOrders(await getSellOrderPDA(200))
+
+        // The match is reported both as a sale and as an offer execution
+        let transaction = await provider.connection.getTransaction(signature, {commitment: "confirmed"});
+        let events = [];
+        new anchor.EventParser(program.programId, program.coder).parseLogs(transaction.meta.logMessages, (event) => events.push(event));
+        assert.deepEqual(events.map((event) => event.name), ["BoughtSellOrderEvent", "BuyOfferExecutedEvent"]);
+        for (let event of events) {
+            assert.equal(event.data.quantity.toNumber(), 1);
+            assert.equal(event.data.price.toNumber(), 200);
+            assert.equal(event.data.sellerAmount.toNumber(), 176);
+            assert.equal(event.data.comptoirAmount.toNumber(), 4);
+            assert.equal(event.data.creators[0].amount.toNumber(), 20);
+        }
+        assert.equal(events[0].data.sellOrder.toString(), (await getSellOrderPDA(200)).toString());
+        assert.equal(events[0].data.buyer.toString(), buyer.publicKey.toString());
+        assert.equal(events[1].data.buyOffer.toString(), buyOfferPDA.toString());
+        assert.equal(events[1].data.seller.toString(), seller.publicKey.toString());
+
+        let buyerNft = await nftMint.getAccountInfo(buyerNftAccount)
+        assert.equal(buyerNft.amount.toNumber(), 1);
+        let buyOffer = await program.account.buyOffer.fetch(buyOfferPDA)
+        assert.equal(buyOffer.quantity.toNumber(), 1);
+        let updatedSellerTokenAccount = await comptoirMint.getAccountInfo(sellerTokenAccount.address)
+        assert.equal(updatedSellerTokenAccount.amount.toNumber(), 176);
+        let updatedBuyerTokenAccount = await comptoirMint.getAccountInfo(buyerTokenAccount.address)
+        assert.equal(updatedBuyerTokenAccount.amount.toNumber(), 1550);
+        let escrow = await comptoirMint.getAccountInfo(escrowPDA)
+        assert.equal(escrow.amount.toNumber(), 250);
+
+        let orderBook = await program.account.orderBook.fetch(orderBookPDA)
+        assert.deepEqual(orderBook.asks.map(e => e.price.toNumber()), [300]);
+        assert.deepEqual(orderBook.bids.map(e => e.price.toNumber()), [250]);
+
+        await assert.rejects(matchOrders(await getSellOrderPDA(300)));
+    });
+
+    it('fail: book more orders of one owner than allowed on a side', async () => {
+        // The buyer already has the rest of its first offer in the book
+        for (let price of [10, 11, 12]) {
+            await createBookedBuyOffer(price, 1)
+        }
+        await assert.rejects(
+            createBookedBuyOffer(13, 1),
+            (err: any) => err.error.errorCode.code === "ErrOrderBookOwnerLimit"
+        );
+
+        let orderBook = await program.account.orderBook.fetch(orderBookPDA)
+        assert.deepEqual(orderBook.bids.map(e => e.price.toNumber()), [250, 12, 11, 10]);
+        assert.ok(orderBook.bids.every(e => e.owner.toString() === buyer.publicKey.toString()));
+    });
+
+    it('pull live orders out of a book whose currency is not accepted anymore', async () => {
+        const removeFromOrderBook = async (order: PublicKey, currency: PublicKey[]) => {
+            await program.methods.removeFromOrderBook().accounts({
+                authority: seller.publicKey,
+                comptoir: comptoirPDA,
+                collection: collectionPDA,
+                orderBook: orderBookPDA,
+                order: order,
+            }).remainingAccounts(
+                currency.map((pubkey) => ({pubkey, isWritable: false, isSigner: false}))
+            ).signers([seller]).rpc()
+        }
+
+        // Live orders of an accepted book can only be removed by their authority
+        await assert.rejects(
+            removeFromOrderBook(await getBuyOfferPDA(10), []),
+            (err: any) => err.error.errorCode.code === "ErrWrongOrderAccounts"
+        );
+
+        const newComptoirMint = await splToken.Token.createMint(
+            provider.connection,
+            admin,
+            admin.publicKey,
+            null,
+            6,
+            splToken.TOKEN_PROGRAM_ID,
+        );
+        const newAdminTokenAccount = await newComptoirMint.getOrCreateAssociatedAccountInfo(admin.publicKey);
+        const [newEscrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from("COMPTOIR"), comptoirPDA.toBuffer(), newComptoirMint.publicKey.toBuffer(), Buffer.from("ESCROW")],
+            program.programId,
+        );
+        await program.methods.updateComptoirMint(newComptoirMint.publicKey, newAdminTokenAccount.address).accounts({
+            authority: admin.publicKey,
+            comptoir: comptoirPDA,
+            mint: newComptoirMint.publicKey,
+            escrow: newEscrowPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([admin]).rpc()
+
+        // The former comptoir mint was never registered as a currency, its currency account is empty
+        const [oldCurrencyPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from("COMPTOIR"), comptoirPDA.toBuffer(), comptoirMint.publicKey.toBuffer(), Buffer.from("CURRENCY")],
+            program.programId,
+        );
+        await assert.rejects(
+            matchOrders(await getSellOrderPDA(300), [oldCurrencyPDA]),
+            (err: any) => err.error.errorCode.code === "ErrOfferCurrencyChanged"
+        );
+        await assert.rejects(
+            removeFromOrderBook(await getBuyOfferPDA(10), []),
+            (err: any) => err.error.errorCode.code === "ErrMissingRemainingAccounts"
+        );
+
+        await removeFromOrderBook(await getBuyOfferPDA(10), [oldCurrencyPDA]);
+        await removeFromOrderBook(await getSellOrderPDA(300), [oldCurrencyPDA]);
+
+        let orderBook = await program.account.orderBook.fetch(orderBookPDA)
+        assert.deepEqual(orderBook.asks.map(e => e.price.toNumber()), []);
+        assert.deepEqual(orderBook.bids.map(e => e.price.toNumber()), [250, 12, 11]);
+        // Removing from the book leaves the orders themselves untouched
+        assert.equal((await program.account.buyOffer.fetch(await getBuyOfferPDA(10))).quantity.toNumber(), 1);
+    });
+});
@@ -0,0 +1,402 @@
+import * as anchor from '@project-serum/anchor';
+import {Program, web3} from '@project-serum/anchor';
+import {Comptoir} from '../target/types/comptoir';
+import * as splToken from '@solana/spl-token';
+import {PublicKey} from "@solana/web3.js";
+import {ASSOCIATED_TOKEN_PROGRAM_ID, Token, TOKEN_PROGRAM_ID} from "@solana/spl-token";
+import assert from "assert";
+import {nft_data, nft_json_url} from "./data";
+import {createMint, createProgrammableMint, getMasterEdition, getTokenRecord} from "./utils/utils";
+import auryKey from "./keys/aury-teST1ieLrLdr4MJPZ7i8mgSCLQ7rTrPRjNnyFdHFaz9.json";
+
+let provider = anchor.getProvider()
+anchor.setProvider(provider);
+
+const program = anchor.workspace.Comptoir as Program<Comptoir>;
+
+const TOKEN_METADATA_PROGRAM_ID = new PublicKey("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+const AUTH_RULES_PROGRAM_ID = new PublicKey("auth9SigNpDKz4sJJ1DfCTuZrZNSAgh9sFD3rboVmgg");
+
+// The programmable NFT of the aury key is loaded by the test validator, see tests/data/pnft, and only covers the checks
+// made before transferring. The other programmable NFTs are minted by token metadata and really move
+describe('programmable NFTs', () => {
+    const aury = web3.Keypair.fromSecretKey(Uint8Array.from(auryKey));
+    const pnftMint = new PublicKey("2qARPKxtzEjk67PbARujNCGq1pgtm7MMe3Gh1bef6RZs");
+    const pnftTokenAccount = new PublicKey("4dboaVXEdYMBn18FBPmDcTPLcZxGcx5ejekRDTYcsUe7");
+    const pnftMetadata = new PublicKey("C7iVZD2QJRTY4YP3YnwjtQix3ZcSshYnMrc8B86zVsEK");
+    const pnftEdition = new PublicKey("9CASwCXoLijYgUsF9rHWzMHwqpd4X9t3arqs4ej18965");
+    const ruleSet = new PublicKey("8XmyF6UGawxrWRf4erKiS2RnxDnk6kDGLdnYLwnzXFeP");
+    let admin: web3.Keypair;
+    let adminTokenAccount: splToken.AccountInfo;
+    let creator: web3.Keypair;
+    let seller: web3.Keypair;
+    let comptoirPDA: PublicKey;
+    let comptoirMint: splToken.Token;
+    let escrowPDA: PublicKey;
+    let pnftCollectionPDA: PublicKey;
+    let nftCollectionPDA: PublicKey;
+    let nftMint: PublicKey;
+    let nftMetadata: PublicKey;
+    let sellerNftAccount: PublicKey;
+    let buyer: web3.Keypair;
+    let adminPaymentAccount: PublicKey;
+    let creatorPaymentAccount: PublicKey;
+    let sellerPaymentAccount: PublicKey;
+    let buyerPaymentAccount: PublicKey;
+    let programmableMints: PublicKey[] = [];
+    let programmableMetadata: PublicKey[] = [];
+
+    const getVault = async (mint: PublicKey): Promise<[PublicKey, PublicKey]> => {
+        const [vaultAuthority] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from('COMPTOIR'), Buffer.from("vault"), comptoirPDA.toBuffer(), mint.toBuffer()],
+            program.programId,
+        );
+        const vault = await Token.getAssociatedTokenAddress(ASSOCIATED_TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID, mint, vaultAuthority, true);
+        return [vaultAuthority, vault];
+    }
+
+    const getSellOrder = async (tokenAccount: PublicKey): Promise<PublicKey> => {
+        return (await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from("COMPTOIR"), tokenAccount.toBuffer(), Buffer.from("1000")],
+            program.programId,
+        ))[0];
+    }
+
+    const getAssociatedAccount = async (mint: PublicKey, owner: PublicKey): Promise<PublicKey> => {
+        return Token.getAssociatedTokenAddress(ASSOCIATED_TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID, mint, owner);
+    }
+
+    const getAmount = async (tokenAccount: PublicKey): Promise<number> => {
+        return Number((await provider.connection.getTokenAccountBalance(tokenAccount)).value.amount);
+    }
+
+    const createPnftSellOrder = async (
+        owner: web3.Keypair,
+        collection: PublicKey,
+        mint: PublicKey,
+        tokenAccount: PublicKey,
+        metadata: PublicKey,
+        authorizationRules: PublicKey,
+    ) => {
+        const [vaultAuthority, vault] = await getVault(mint);
+        await program.methods.createPnftSellOrder(new anchor.BN(1000), sellerPaymentAccount, null).accounts({
+            payer: owner.publicKey,
+            sellerNftTokenAccount: tokenAccount,
+            comptoir: comptoirPDA,
+            collection: collection,
+            mint: mint,
+            metadata: metadata,
+            edition: mint.equals(pnftMint) ? pnftEdition : await getMasterEdition(mint),
+            sellerTokenRecord: await getTokenRecord(mint, tokenAccount),
+            vaultAuthority: vaultAuthority,
+            vault: vault,
+            vaultTokenRecord: await getTokenRecord(mint, vault),
+            sellOrder: await getSellOrder(tokenAccount),
+            tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+            authorizationRulesProgram: AUTH_RULES_PROGRAM_ID,
+            authorizationRules: authorizationRules,
+            sysvarInstructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([owner]).rpc();
+    }
+
+    it('Prepare tests variables', async () => {
+        admin = anchor.web3.Keypair.generate()
+        creator = anchor.web3.Keypair.generate()
+        seller = anchor.web3.Keypair.generate()
+        buyer = anchor.web3.Keypair.generate()
+        for (let keypair of [admin, creator, seller, buyer, aury]) {
+            let fromAirdropSignature = await provider.connection.requestAirdrop(
+                keypair.publicKey,
+                anchor.web3.LAMPORTS_PER_SOL,
+            );
+            await provider.connection.confirmTransaction(fromAirdropSignature);
+        }
+
+        [comptoirPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                admin.publicKey.toBuffer()
+            ],
+            program.programId,
+        )
+        comptoirMint = await splToken.Token.createMint(
+            provider.connection,
+            admin,
+            admin.publicKey,
+            null,
+            6,
+            splToken.TOKEN_PROGRAM_ID,
+        );
+        [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                comptoirMint.publicKey.toBuffer(),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+        adminTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(admin.publicKey);
+        adminPaymentAccount = adminTokenAccount.address;
+        creatorPaymentAccount = (await comptoirMint.getOrCreateAssociatedAccountInfo(creator.publicKey)).address;
+        sellerPaymentAccount = (await comptoirMint.getOrCreateAssociatedAccountInfo(seller.publicKey)).address;
+        buyerPaymentAccount = (await comptoirMint.getOrCreateAssociatedAccountInfo(buyer.publicKey)).address;
+        await comptoirMint.mintTo(buyerPaymentAccount, admin, [], 2000);
+
+        await program.methods.createComptoir(comptoirMint.publicKey, 200, adminTokenAccount.address, admin.publicKey)
+            .accounts({
+                payer: admin.publicKey,
+                comptoir: comptoirPDA,
+                mint: comptoirMint.publicKey,
+                escrow: escrowPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc();
+
+        for (let [symbol, verifier] of [["PNFT", aury.publicKey], ["AURY", creator.publicKey]] as [string, PublicKey][]) {
+            const [collectionPDA] = await anchor.web3.PublicKey.findProgramAddress(
+                [
+                    Buffer.from("COMPTOIR"),
+                    Buffer.from(symbol),
+                    comptoirPDA.toBuffer(),
+                ],
+                program.programId,
+            );
+            await program.methods.createCollection(symbol, symbol, verifier, null, false).accounts({
+                authority: admin.publicKey,
+                comptoir: comptoirPDA,
+                collection: collectionPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc();
+            if (symbol == "PNFT") {
+                pnftCollectionPDA = collectionPDA
+            } else {
+                nftCollectionPDA = collectionPDA
+            }
+        }
+
+        const lamports = await Token.getMinBalanceRentForExemptMint(
+            provider.connection
+        );
+        const [mint, metadataAddr, tx] = await createMint(
+            creator.publicKey,
+            seller.publicKey,
+            lamports,
+            nft_data(creator.publicKey),
+            nft_json_url
+        );
+        await provider.sendAndConfirm(tx, [mint, creator]);
+        nftMint = mint.publicKey
+        nftMetadata = metadataAddr
+        sellerNftAccount = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            nftMint,
+            seller.publicKey
+        );
+
+        for (let i = 0; i < 2; i++) {
+            const [programmableMint, metadata, programmableTx] = await createProgrammableMint(
+                creator.publicKey,
+                seller.publicKey,
+                "AURY",
+                nft_data(creator.publicKey),
+                nft_json_url,
+            );
+            await provider.sendAndConfirm(programmableTx, [programmableMint, creator]);
+            programmableMints.push(programmableMint.publicKey);
+            programmableMetadata.push(metadata);
+        }
+    });
+
+    it('fail: list a regular NFT as programmable', async () => {
+        await assert.rejects(
+            createPnftSellOrder(seller, nftCollectionPDA, nftMint, sellerNftAccount, nftMetadata, TOKEN_METADATA_PROGRAM_ID),
+            (err: any) => err.error.errorCode.code === "ErrNotProgrammable"
+        );
+    });
+
+    it('fail: list a programmable NFT with the metadata of another item', async () => {
+        await assert.rejects(
+            createPnftSellOrder(aury, pnftCollectionPDA, pnftMint, pnftTokenAccount, nftMetadata, ruleSet),
+        );
+    });
+
+    it('fail: list a programmable NFT without its rule set', async () => {
+        await assert.rejects(
+            createPnftSellOrder(aury, pnftCollectionPDA, pnftMint, pnftTokenAccount, pnftMetadata, TOKEN_METADATA_PROGRAM_ID),
+            (err: any) => err.error.errorCode.code === "ErrWrongProgrammableAccounts"
+        );
+    });
+
+    it('fail: list a programmable NFT with another rule set', async () => {
+        await assert.rejects(
+            createPnftSellOrder(aury, pnftCollectionPDA, pnftMint, pnftTokenAccount, pnftMetadata, web3.Keypair.generate().publicKey),
+            (err: any) => err.error.errorCode.code === "ErrWrongProgrammableAccounts"
+        );
+    });
+
+    it('fail: list a programmable NFT in a collection it is not part of', async () => {
+        await assert.rejects(
+            createPnftSellOrder(aury, nftCollectionPDA, pnftMint, pnftTokenAccount, pnftMetadata, ruleSet),
+            (err: any) => err.error.errorCode.code === "ErrNftNotPartOfCollection"
+        );
+    });
+
+    it('list a programmable NFT', async () => {
+        const mint = programmableMints[0];
+        const sellerAccount = await getAssociatedAccount(mint, seller.publicKey);
+        await createPnftSellOrder(seller, nftCollectionPDA, mint, sellerAccount, programmableMetadata[0], TOKEN_METADATA_PROGRAM_ID);
+
+        const [, vault] = await getVault(mint);
+        assert.equal(await getAmount(vault), 1);
+        assert.equal(await getAmount(sellerAccount), 0);
+        const sellOrder = await program.account.sellOrder.fetch(await getSellOrder(sellerAccount));
+        assert.equal(sellOrder.mint.toString(), mint.toString());
+        assert.equal(sellOrder.quantity.toNumber(), 1);
+        assert.equal(sellOrder.destination.toString(), sellerPaymentAccount.toString());
+    });
+
+    it('remove a programmable NFT sell order', async () => {
+        const mint = programmableMints[0];
+        const sellerAccount = await getAssociatedAccount(mint, seller.publicKey);
+        const sellOrder = await getSellOrder(sellerAccount);
+        const [vaultAuthority, vault] = await getVault(mint);
+        await program.methods.removePnftSellOrder().accounts({
+            authority: seller.publicKey,
+            sellerNftTokenAccount: sellerAccount,
+            sellOrder: sellOrder,
+            mint: mint,
+            metadata: programmableMetadata[0],
+            edition: await getMasterEdition(mint),
+            sellerTokenRecord: await getTokenRecord(mint, sellerAccount),
+            vaultAuthority: vaultAuthority,
+            vault: vault,
+            vaultTokenRecord: await getTokenRecord(mint, vault),
+            tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+            authorizationRulesProgram: AUTH_RULES_PROGRAM_ID,
+            authorizationRules: TOKEN_METADATA_PROGRAM_ID,
+            sysvarInstructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        }).signers([seller]).rpc();
+
+        assert.equal(await getAmount(sellerAccount), 1);
+        assert.equal(await getAmount(vault), 0);
+        assert.equal(await provider.connection.getAccountInfo(sellOrder), null);
+    });
+
+    it('buy a programmable NFT', async () => {
+        const mint = programmableMints[0];
+        const sellerAccount = await getAssociatedAccount(mint, seller.publicKey);
+        await createPnftSellOrder(seller, nftCollectionPDA, mint, sellerAccount, programmableMetadata[0], TOKEN_METADATA_PROGRAM_ID);
+
+        const buyerAccount = await getAssociatedAccount(mint, buyer.publicKey);
+        const [vaultAuthority, vault] = await getVault(mint);
+        await program.methods.buyPnft(new anchor.BN(1000)).accounts({
+            buyer: buyer.publicKey,
+            buyerNftTokenAccount: buyerAccount,
+            buyerPayingTokenAccount: buyerPaymentAccount,
+            comptoir: comptoirPDA,
+            comptoirDestAccount: adminPaymentAccount,
+            collection: nftCollectionPDA,
+            authority: seller.publicKey,
+            sellerFundsDestAccount: sellerPaymentAccount,
+            sellOrder: await getSellOrder(sellerAccount),
+            mint: mint,
+            metadata: programmableMetadata[0],
+            edition: await getMasterEdition(mint),
+            buyerTokenRecord: await getTokenRecord(mint, buyerAccount),
+            vaultAuthority: vaultAuthority,
+            vault: vault,
+            vaultTokenRecord: await getTokenRecord(mint, vault),
+            tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+            authorizationRulesProgram: AUTH_RULES_PROGRAM_ID,
+            authorizationRules: TOKEN_METADATA_PROGRAM_ID,
+            sysvarInstructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        }).remainingAccounts([
+            {pubkey: creatorPaymentAccount, isWritable: true, isSigner: false},
+        ]).signers([buyer]).rpc();
+
+        assert.equal(await getAmount(buyerAccount), 1);
+        assert.equal(await getAmount(vault), 0);
+        assert.equal(await getAmount(buyerPaymentAccount), 1000);
+        assert.equal(await getAmount(sellerPaymentAccount), 880);
+        assert.equal(await getAmount(adminPaymentAccount), 20);
+        assert.equal(await getAmount(creatorPaymentAccount), 100);
+    });
+
+    it('sell a programmable NFT to a collection offer', async () => {
+        const [buyOffer] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                buyer.publicKey.toBuffer(),
+                nftCollectionPDA.toBuffer(),
+                Buffer.from("1000"),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+        await program.methods.createCollectionBuyOffer(new anchor.BN(1000), new anchor.BN(1), null).accounts({
+            payer: buyer.publicKey,
+            comptoir: comptoirPDA,
+            collection: nftCollectionPDA,
+            escrow: escrowPDA,
+            buyerPayingAccount: buyerPaymentAccount,
+            buyOffer: buyOffer,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([buyer]).rpc();
+
+        const mint = programmableMints[1];
+        const sellerAccount = await getAssociatedAccount(mint, seller.publicKey);
+        const buyerAccount = await getAssociatedAccount(mint, buyer.publicKey);
+        await program.methods.executePnftOffer([]).accounts({
+            seller: seller.publicKey,
+            buyer: buyer.publicKey,
+            comptoir: comptoirPDA,
+            collection: nftCollectionPDA,
+            comptoirDestAccount: adminPaymentAccount,
+            escrow: escrowPDA,
+            sellerFundsDestAccount: sellerPaymentAccount,
+            destination: buyerAccount,
+            sellerNftAccount: sellerAccount,
+            mint: mint,
+            metadata: programmableMetadata[1],
+            edition: await getMasterEdition(mint),
+            sellerTokenRecord: await getTokenRecord(mint, sellerAccount),
+            destinationTokenRecord: await getTokenRecord(mint, buyerAccount),
+            buyOffer: buyOffer,
+            tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+            authorizationRulesProgram: AUTH_RULES_PROGRAM_ID,
+            authorizationRules: TOKEN_METADATA_PROGRAM_ID,
+            sysvarInstructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        }).remainingAccounts([
+            {pubkey: creatorPaymentAccount, isWritable: true, isSigner: false},
+        ]).signers([seller]).rpc();
+
+        assert.equal(await getAmount(buyerAccount), 1);
+        assert.equal(await getAmount(sellerAccount), 0);
+        assert.equal(await getAmount(escrowPDA), 0);
+        assert.equal(await getAmount(buyerPaymentAccount), 0);
+        assert.equal(await getAmount(sellerPaymentAccount), 1760);
+        assert.equal(await getAmount(adminPaymentAccount), 40);
+        assert.equal(await getAmount(creatorPaymentAccount), 200);
+        assert.equal(await provider.connection.getAccountInfo(buyOffer), null);
+    });
+});
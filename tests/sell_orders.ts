@@ -1,14 +1,18 @@
 import * as anchor from '@project-serum/anchor';
-import {web3} from '@project-serum/anchor';
+import {Program, web3} from '@project-serum/anchor';
+import {Comptoir} from '../target/types/comptoir';
 import * as splToken from '@solana/spl-token';
 import {Token, TOKEN_PROGRAM_ID} from "@solana/spl-token";
 import assert from "assert";
 import {nft_data, nft_json_url} from "./data";
 import {createMint} from "./utils/utils";
-import {Comptoir, Collection, getSellOrderPDA, getCollectionPDA, getNftVaultPDA} from "@aurory/comptoirjs";
+import {PublicKey} from "@solana/web3.js";
+
 let provider = anchor.getProvider()
 anchor.setProvider(provider);
 
+const program = anchor.workspace.Comptoir as Program<Comptoir>;
+
 describe('multi sell orders test', () => {
     let creator: web3.Keypair;
     let creatorTokenAccount: splToken.AccountInfo;
@@ -18,10 +22,37 @@ describe('multi sell orders test', () => {
     let nftMint: splToken.Token;
     let metadataPDA: web3.PublicKey;
     let sellerNftAssociatedTokenAccount: web3.PublicKey;
+    let comptoirPDA: PublicKey;
+    let escrowPDA: PublicKey;
+    let collectionPDA: PublicKey;
+    let programNftVaultPDA: PublicKey;
 
-    let comptoir: Comptoir;
-    let collection: Collection;
-
+    const getSellOrderPDA = async (price: anchor.BN): Promise<PublicKey> => {
+        return (await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                sellerNftAssociatedTokenAccount.toBuffer(),
+                Buffer.from(price.toString()),
+            ],
+            program.programId,
+        ))[0];
+    }
+
+    const sellAsset = async (price: anchor.BN, quantity: anchor.BN) => {
+        await program.methods.createSellOrder(price, quantity, sellerTokenAccount.address, null, null).accounts({
+            payer: seller.publicKey,
+            sellerNftTokenAccount: sellerNftAssociatedTokenAccount,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            mint: nftMint.publicKey,
+            metadata: metadataPDA,
+            vault: programNftVaultPDA,
+            sellOrder: await getSellOrderPDA(price),
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([seller]).rpc()
+    }
 
     it('Prepare tests variables', async () => {
         creator = anchor.web3.Keypair.generate()
@@ -72,40 +103,65 @@ describe('multi sell orders test', () => {
         metadataPDA = metadataAddr
         nftMint = new Token(provider.connection, mint.publicKey, TOKEN_PROGRAM_ID, creator)
 
-        sellerNftAssociatedTokenAccount = (await nftMint.getOrCreateAssociatedAccountInfo(seller.publicKey)).address
+        sellerNftAssociatedTokenAccount = (await nftMint.getOrCreateAssociatedAccountInfo(seller.publicKey)).address;
 
-        comptoir = new Comptoir(provider)
-        await comptoir.createComptoir(seller, comptoirMint.publicKey, 5, sellerTokenAccount.address)
-        await comptoir.createCollection(seller,"AURY", creator.publicKey, "AURY", false)
-
-        let collectionPDA = await getCollectionPDA(comptoir.comptoirPDA, "AURY")
-        collection = new Collection(provider, collectionPDA, comptoir)
+        [comptoirPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                seller.publicKey.toBuffer()
+            ],
+            program.programId,
+        );
+        [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                comptoirMint.publicKey.toBuffer(),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+        await program.methods.createComptoir(comptoirMint.publicKey, 5, sellerTokenAccount.address, seller.publicKey)
+            .accounts({
+                payer: seller.publicKey,
+                comptoir: comptoirPDA,
+                mint: comptoirMint.publicKey,
+                escrow: escrowPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([seller]).rpc();
+
+        [collectionPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                Buffer.from("AURY"),
+                comptoirPDA.toBuffer(),
+            ],
+            program.programId,
+        );
+        await program.methods.createCollection("AURY", "AURY", creator.publicKey, null, false).accounts({
+            authority: seller.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([seller]).rpc();
+
+        [programNftVaultPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from('COMPTOIR'), Buffer.from("vault"), comptoirPDA.toBuffer(), nftMint.publicKey.toBuffer()],
+            program.programId,
+        );
     });
 
     it('sell and buy multiple orders', async function () {
-        await collection.sellAsset(
-            nftMint.publicKey,
-            sellerNftAssociatedTokenAccount,
-            sellerTokenAccount.address,
-            new anchor.BN(2000),
-            new anchor.BN(2),
-            seller,
-       )
-
-        await collection.sellAsset(
-            nftMint.publicKey,
-            sellerNftAssociatedTokenAccount,
-            sellerTokenAccount.address,
-            new anchor.BN(2200),
-            new anchor.BN(2),
-            seller,
-        )
+        await sellAsset(new anchor.BN(2000), new anchor.BN(2))
+        await sellAsset(new anchor.BN(2200), new anchor.BN(2))
 
         let sellerAfterSell = await nftMint.getAccountInfo(sellerNftAssociatedTokenAccount)
         assert.equal(sellerAfterSell.amount.toNumber(), 1)
 
-        let nftVaultAddr = await getNftVaultPDA(nftMint.publicKey)
-        let vaultAfterSell = await nftMint.getAccountInfo(nftVaultAddr)
+        let vaultAfterSell = await nftMint.getAccountInfo(programNftVaultPDA)
 
         assert.equal(vaultAfterSell.amount.toNumber(), 4)
 
@@ -121,17 +177,24 @@ describe('multi sell orders test', () => {
 
         let buyerNftATA = await nftMint.createAssociatedTokenAccount(buyer.publicKey)
 
-        await collection.buy(
-            nftMint.publicKey,
-            [
-                await getSellOrderPDA(sellerNftAssociatedTokenAccount, new anchor.BN(2000)),
-                await getSellOrderPDA(sellerNftAssociatedTokenAccount, new anchor.BN(2200)),
-            ],
-            buyerNftATA,
-            buyerTokenATA,
-            new anchor.BN(4),
-            buyer,
-        )
+        await program.methods.buy(new anchor.BN(4), new anchor.BN(8400), null).accounts({
+            buyer: buyer.publicKey,
+            buyerNftTokenAccount: buyerNftATA,
+            buyerPayingTokenAccount: buyerTokenATA,
+            comptoir: comptoirPDA,
+            comptoirDestAccount: sellerTokenAccount.address,
+            collection: collectionPDA,
+            metadata: metadataPDA,
+            vault: programNftVaultPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        }).remainingAccounts([
+            {pubkey: creatorTokenAccount.address, isWritable: true, isSigner: false},
+            {pubkey: await getSellOrderPDA(new anchor.BN(2000)), isWritable: true, isSigner: false},
+            {pubkey: sellerTokenAccount.address, isWritable: true, isSigner: false},
+            {pubkey: await getSellOrderPDA(new anchor.BN(2200)), isWritable: true, isSigner: false},
+            {pubkey: sellerTokenAccount.address, isWritable: true, isSigner: false},
+        ]).signers([buyer]).rpc()
 
         let buyerNftAccountAfterSell = await nftMint.getAccountInfo(buyerNftATA)
         assert.equal(buyerNftAccountAfterSell.amount.toNumber(), 4)
@@ -0,0 +1,320 @@
+import * as anchor from '@project-serum/anchor';
+import {Program, web3} from '@project-serum/anchor';
+import {Comptoir} from '../target/types/comptoir';
+import * as splToken from '@solana/spl-token';
+import {PublicKey} from "@solana/web3.js";
+import {ASSOCIATED_TOKEN_PROGRAM_ID, Token, TOKEN_PROGRAM_ID} from "@solana/spl-token";
+import assert from "assert";
+import {nft_data, nft_json_url} from "./data";
+import {createMint} from "./utils/utils";
+
+let provider = anchor.getProvider()
+anchor.setProvider(provider);
+
+const program = anchor.workspace.Comptoir as Program<Comptoir>;
+
+describe('swap offers', () => {
+    let admin: web3.Keypair;
+    let adminTokenAccount: splToken.AccountInfo;
+    let creator: web3.Keypair;
+    let offerer: web3.Keypair;
+    let offererTokenAccount: splToken.AccountInfo;
+    let counterparty: web3.Keypair;
+    let counterpartyTokenAccount: splToken.AccountInfo;
+    let comptoirPDA: PublicKey;
+    let comptoirMint: splToken.Token;
+    let collectionName = "AURY"
+    let collectionPDA: PublicKey;
+    let escrowPDA: PublicKey;
+    // Offered by the offerer
+    let offeredMint: splToken.Token;
+    let offeredVault: PublicKey;
+    let offererOfferedAccount: PublicKey;
+    let counterpartyOfferedAccount: PublicKey;
+    // Requested from the counterparty
+    let requestedMint: splToken.Token;
+    let requestedMetadata: PublicKey;
+    let counterpartyRequestedAccount: PublicKey;
+    let offererRequestedAccount: PublicKey;
+    let tokenAmount = 1000;
+
+    const getSwapOfferPDA = async (swapId: anchor.BN): Promise<PublicKey> => {
+        return (await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                offerer.publicKey.toBuffer(),
+                Buffer.from("SWAP"),
+                swapId.toArrayLike(Buffer, "le", 8),
+            ],
+            program.programId,
+        ))[0];
+    }
+
+    const createSwapOffer = async (swapId: anchor.BN) => {
+        let swapOffer = await getSwapOfferPDA(swapId);
+        await program.methods.createSwapOffer(swapId, [{collection: {collection: collectionPDA}}], new anchor.BN(tokenAmount), null).accounts({
+            payer: offerer.publicKey,
+            payerPayingAccount: offererTokenAccount.address,
+            comptoir: comptoirPDA,
+            escrow: escrowPDA,
+            swapOffer: swapOffer,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([offerer]).rpc()
+        await program.methods.addSwapItem().accounts({
+            payer: offerer.publicKey,
+            payerNftTokenAccount: offererOfferedAccount,
+            comptoir: comptoirPDA,
+            swapOffer: swapOffer,
+            mint: offeredMint.publicKey,
+            vault: offeredVault,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([offerer]).rpc()
+        return swapOffer
+    }
+
+    it('Prepare tests variables', async () => {
+        admin = anchor.web3.Keypair.generate()
+        creator = anchor.web3.Keypair.generate()
+        offerer = anchor.web3.Keypair.generate()
+        counterparty = anchor.web3.Keypair.generate()
+        for (let keypair of [admin, creator, offerer, counterparty]) {
+            let fromAirdropSignature = await provider.connection.requestAirdrop(
+                keypair.publicKey,
+                anchor.web3.LAMPORTS_PER_SOL,
+            );
+            await provider.connection.confirmTransaction(fromAirdropSignature);
+        }
+
+        [comptoirPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                admin.publicKey.toBuffer()
+            ],
+            program.programId,
+        )
+
+        comptoirMint = await splToken.Token.createMint(
+            provider.connection,
+            admin,
+            admin.publicKey,
+            null,
+            6,
+            splToken.TOKEN_PROGRAM_ID,
+        );
+
+        [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                comptoirMint.publicKey.toBuffer(),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+
+        adminTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(admin.publicKey);
+        offererTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(offerer.publicKey);
+        counterpartyTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(counterparty.publicKey);
+        await comptoirMint.mintTo(offererTokenAccount.address, admin, [], 10_000);
+
+        [collectionPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                Buffer.from(collectionName),
+                comptoirPDA.toBuffer(),
+            ],
+            program.programId,
+        );
+
+        const lamports = await Token.getMinBalanceRentForExemptMint(
+            provider.connection
+        );
+        const [offered, , offeredTx] = await createMint(
+            creator.publicKey,
+            offerer.publicKey,
+            lamports,
+            nft_data(creator.publicKey),
+            nft_json_url
+        );
+        await provider.sendAndConfirm(offeredTx, [offered, creator]);
+        const [requested, requestedMetadataAddr, requestedTx] = await createMint(
+            creator.publicKey,
+            counterparty.publicKey,
+            lamports,
+            nft_data(creator.publicKey),
+            nft_json_url
+        );
+        await provider.sendAndConfirm(requestedTx, [requested, creator]);
+
+        offeredMint = new Token(provider.connection, offered.publicKey, TOKEN_PROGRAM_ID, admin)
+        requestedMint = new Token(provider.connection, requested.publicKey, TOKEN_PROGRAM_ID, admin)
+        requestedMetadata = requestedMetadataAddr
+
+        offererOfferedAccount = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            offeredMint.publicKey,
+            offerer.publicKey
+        );
+        counterpartyOfferedAccount = (await offeredMint.getOrCreateAssociatedAccountInfo(counterparty.publicKey)).address;
+        counterpartyRequestedAccount = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            requestedMint.publicKey,
+            counterparty.publicKey
+        );
+        offererRequestedAccount = (await requestedMint.getOrCreateAssociatedAccountInfo(offerer.publicKey)).address;
+
+        [offeredVault] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from('COMPTOIR'), Buffer.from("vault"), comptoirPDA.toBuffer(), offeredMint.publicKey.toBuffer()],
+            program.programId,
+        );
+
+        await program.methods.createComptoir(comptoirMint.publicKey, 200, adminTokenAccount.address, admin.publicKey)
+            .accounts({
+                payer: admin.publicKey,
+                comptoir: comptoirPDA,
+                mint: comptoirMint.publicKey,
+                escrow: escrowPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc();
+
+        await program.methods.createCollection(collectionName, collectionName, creator.publicKey, null, false).accounts(
+            {
+                authority: admin.publicKey,
+                comptoir: comptoirPDA,
+                collection: collectionPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc()
+    });
+
+    it('remove swap offer returns the items and tokens', async () => {
+        let swapOffer = await createSwapOffer(new anchor.BN(0));
+        let updatedOffererTokenAccount = await comptoirMint.getAccountInfo(offererTokenAccount.address)
+        assert.equal(updatedOffererTokenAccount.amount.toNumber(), 10_000 - tokenAmount);
+
+        await program.methods.removeSwapOffer().accounts({
+            authority: offerer.publicKey,
+            authorityPayingAccount: offererTokenAccount.address,
+            comptoir: comptoirPDA,
+            escrow: escrowPDA,
+            swapOffer: swapOffer,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        }).remainingAccounts([
+            {pubkey: offeredVault, isWritable: true, isSigner: false},
+            {pubkey: offererOfferedAccount, isWritable: true, isSigner: false},
+        ]).signers([offerer]).rpc()
+
+        updatedOffererTokenAccount = await comptoirMint.getAccountInfo(offererTokenAccount.address)
+        assert.equal(updatedOffererTokenAccount.amount.toNumber(), 10_000);
+        let offererNftAccount = await offeredMint.getAccountInfo(offererOfferedAccount)
+        assert.equal(offererNftAccount.amount.toNumber(), 1);
+    });
+
+    it('accept swap offer with a collection item', async () => {
+        let swapOffer = await createSwapOffer(new anchor.BN(1));
+
+        let signature = await program.methods.acceptSwapOffer().accounts({
+            counterparty: counterparty.publicKey,
+            counterpartyFundsAccount: counterpartyTokenAccount.address,
+            comptoir: comptoirPDA,
+            comptoirDestAccount: adminTokenAccount.address,
+            escrow: escrowPDA,
+            authority: offerer.publicKey,
+            swapOffer: swapOffer,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        }).remainingAccounts([
+            {pubkey: offeredVault, isWritable: true, isSigner: false},
+            {pubkey: counterpartyOfferedAccount, isWritable: true, isSigner: false},
+            {pubkey: counterpartyRequestedAccount, isWritable: true, isSigner: false},
+            {pubkey: offererRequestedAccount, isWritable: true, isSigner: false},
+            {pubkey: requestedMetadata, isWritable: false, isSigner: false},
+            {pubkey: collectionPDA, isWritable: false, isSigner: false},
+        ]).signers([counterparty]).rpc()
+
+        let counterpartyNftAccount = await offeredMint.getAccountInfo(counterpartyOfferedAccount)
+        assert.equal(counterpartyNftAccount.amount.toNumber(), 1);
+        let offererNftAccount = await requestedMint.getAccountInfo(offererRequestedAccount)
+        assert.equal(offererNftAccount.amount.toNumber(), 1);
+        let updatedCounterpartyTokenAccount = await comptoirMint.getAccountInfo(counterpartyTokenAccount.address)
+        assert.equal(updatedCounterpartyTokenAccount.amount.toNumber(), 980);
+        let updatedAdminTokenAccount = await comptoirMint.getAccountInfo(adminTokenAccount.address)
+        assert.equal(updatedAdminTokenAccount.amount.toNumber(), 20);
+        assert.equal(await provider.connection.getAccountInfo(swapOffer), null);
+
+        let transaction = await provider.connection.getTransaction(signature, {commitment: "confirmed"});
+        let events = [];
+        new anchor.EventParser(program.programId, program.coder).parseLogs(transaction.meta.logMessages, (event) => events.push(event));
+        let acceptedEvent = events.find((event) => event.name === "SwapOfferAcceptedEvent");
+        assert.equal(acceptedEvent.data.counterparty.toString(), counterparty.publicKey.toString());
+        assert.deepEqual(acceptedEvent.data.offeredMints.map((mint) => mint.toString()), [offeredMint.publicKey.toString()]);
+        assert.deepEqual(acceptedEvent.data.receivedMints.map((mint) => mint.toString()), [requestedMint.publicKey.toString()]);
+        assert.equal(acceptedEvent.data.comptoirAmount.toNumber(), 20);
+    });
+    it('lock the swap tokens in a registered currency', async () => {
+        const currencyMint = await splToken.Token.createMint(
+            provider.connection,
+            admin,
+            admin.publicKey,
+            null,
+            6,
+            splToken.TOKEN_PROGRAM_ID,
+        );
+        const adminCurrencyAccount = await currencyMint.getOrCreateAssociatedAccountInfo(admin.publicKey);
+        const offererCurrencyAccount = await currencyMint.getOrCreateAssociatedAccountInfo(offerer.publicKey);
+        await currencyMint.mintTo(offererCurrencyAccount.address, admin, [], tokenAmount);
+        const [currencyPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from("COMPTOIR"), comptoirPDA.toBuffer(), currencyMint.publicKey.toBuffer(), Buffer.from("CURRENCY")],
+            program.programId,
+        );
+        const [currencyEscrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from("COMPTOIR"), comptoirPDA.toBuffer(), currencyMint.publicKey.toBuffer(), Buffer.from("ESCROW")],
+            program.programId,
+        );
+        await program.methods.addCurrency(adminCurrencyAccount.address).accounts({
+            authority: admin.publicKey,
+            comptoir: comptoirPDA,
+            mint: currencyMint.publicKey,
+            currency: currencyPDA,
+            escrow: currencyEscrowPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([admin]).rpc()
+
+        const swapId = new anchor.BN(2);
+        const swapOffer = await getSwapOfferPDA(swapId);
+        const createInCurrency = (escrow: PublicKey) =>
+            program.methods.createSwapOffer(swapId, [{collection: {collection: collectionPDA}}], new anchor.BN(tokenAmount), null).accounts({
+                payer: offerer.publicKey,
+                payerPayingAccount: offererCurrencyAccount.address,
+                comptoir: comptoirPDA,
+                escrow: escrow,
+                swapOffer: swapOffer,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).remainingAccounts([
+                {pubkey: currencyPDA, isWritable: false, isSigner: false},
+            ]).signers([offerer]).rpc();
+
+        await assert.rejects(
+            createInCurrency(escrowPDA),
+            (err: any) => err.error.errorCode.code === "ErrWrongCurrency"
+        );
+        await createInCurrency(currencyEscrowPDA);
+
+        let storedSwap = await program.account.swapOffer.fetch(swapOffer)
+        assert.equal(storedSwap.paymentMint.toString(), currencyMint.publicKey.toString());
+        assert.equal((await currencyMint.getAccountInfo(currencyEscrowPDA)).amount.toNumber(), tokenAmount);
+    });
+});
@@ -0,0 +1,566 @@
+import * as anchor from '@project-serum/anchor';
+import {Program, web3} from '@project-serum/anchor';
+import {Comptoir} from '../target/types/comptoir';
+import * as splToken from '@solana/spl-token';
+import {PublicKey, TransactionInstruction} from "@solana/web3.js";
+import {ASSOCIATED_TOKEN_PROGRAM_ID, Token, TOKEN_PROGRAM_ID} from "@solana/spl-token";
+import assert from "assert";
+import {nft_data, nft_json_url} from "./data";
+import {createMint} from "./utils/utils";
+import auryKey from "./keys/aury-teST1ieLrLdr4MJPZ7i8mgSCLQ7rTrPRjNnyFdHFaz9.json";
+
+let provider = anchor.getProvider()
+anchor.setProvider(provider);
+
+const program = anchor.workspace.Comptoir as Program<Comptoir>;
+
+const TOKEN_2022_PROGRAM_ID = new PublicKey("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+// The Token-2022 mints of the aury key are loaded by the test validator, see tests/data/token22. The currency takes a
+// 1% transfer fee, the hook mint has a transfer hook
+describe('Token-2022 sell orders', () => {
+    const aury = web3.Keypair.fromSecretKey(Uint8Array.from(auryKey));
+    const itemMint = new PublicKey("Gusfga8r9Zw65hcinyD7GJ2UD294yC2MkYDNefKmb1e3");
+    const itemMetadata = new PublicKey("DRV3oBm921xgUwDq5iVWr5cKmeCtLi3cc7dRep8ZhKrz");
+    const hookMint = new PublicKey("EGuKugmAWD5EBfgQk86RT2sJCQk22bWDyvWxGWPjApGF");
+    const hookMetadata = new PublicKey("HmBMiQnjCJcCys8WM2ymm8jq47HcWYGh3yPhEF2RtG8d");
+    const currencyMint = new PublicKey("AaNhJoXdeviETtjjzeJE4EpyZtfdfMoaqL8pmzTtQxFM");
+    let admin: web3.Keypair;
+    let seller: web3.Keypair;
+    let buyer: web3.Keypair;
+    let comptoirPDA: PublicKey;
+    let comptoirMint: splToken.Token;
+    let escrowPDA: PublicKey;
+    let collectionPDA: PublicKey;
+    let currencyPDA: PublicKey;
+    let adminCurrencyAccount: PublicKey;
+    let sellerCurrencyAccount: PublicKey;
+    let buyerCurrencyAccount: PublicKey;
+    let creatorCurrencyAccount: PublicKey;
+    let sellerItemAccount: PublicKey;
+    let buyerItemAccount: PublicKey;
+    let sellOrderPDA: PublicKey;
+
+    const getAssociatedAccount = async (owner: PublicKey, mint: PublicKey): Promise<PublicKey> => {
+        return (await anchor.web3.PublicKey.findProgramAddress(
+            [owner.toBuffer(), TOKEN_2022_PROGRAM_ID.toBuffer(), mint.toBuffer()],
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+        ))[0];
+    }
+
+    // The token library of the tests predates Token-2022, its instructions are built by hand
+    const createAssociatedAccount = async (owner: PublicKey, mint: PublicKey, amount: number): Promise<PublicKey> => {
+        const account = await getAssociatedAccount(owner, mint);
+        const tx = new anchor.web3.Transaction().add(new TransactionInstruction({
+            programId: ASSOCIATED_TOKEN_PROGRAM_ID,
+            keys: [
+                {pubkey: aury.publicKey, isSigner: true, isWritable: true},
+                {pubkey: account, isSigner: false, isWritable: true},
+                {pubkey: owner, isSigner: false, isWritable: false},
+                {pubkey: mint, isSigner: false, isWritable: false},
+                {pubkey: anchor.web3.SystemProgram.programId, isSigner: false, isWritable: false},
+                {pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false},
+            ],
+            data: Buffer.from([1]),
+        }));
+        if (amount > 0) {
+            tx.add(new TransactionInstruction({
+                programId: TOKEN_2022_PROGRAM_ID,
+                keys: [
+                    {pubkey: mint, isSigner: false, isWritable: true},
+                    {pubkey: account, isSigner: false, isWritable: true},
+                    {pubkey: aury.publicKey, isSigner: true, isWritable: false},
+                ],
+                data: Buffer.concat([Buffer.from([7]), new anchor.BN(amount).toArrayLike(Buffer, "le", 8)]),
+            }));
+        }
+        await provider.sendAndConfirm(tx, [aury]);
+        return account;
+    }
+
+    const getBalance = async (account: PublicKey): Promise<number> => {
+        return Number((await provider.connection.getTokenAccountBalance(account)).value.amount);
+    }
+
+    const getVault = async (mint: PublicKey): Promise<[PublicKey, PublicKey]> => {
+        const [vaultAuthority] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from('COMPTOIR'), Buffer.from("vault"), comptoirPDA.toBuffer(), mint.toBuffer()],
+            program.programId,
+        );
+        return [vaultAuthority, await getAssociatedAccount(vaultAuthority, mint)];
+    }
+
+    const createSellOrder = async (mint: PublicKey, metadata: PublicKey, price: number, quantity: number, sellOrder: PublicKey) => {
+        const [vaultAuthority, vault] = await getVault(mint);
+        await program.methods.createToken22SellOrder(new anchor.BN(price), new anchor.BN(quantity), sellerCurrencyAccount, null).accounts({
+            payer: seller.publicKey,
+            sellerNftTokenAccount: sellerItemAccount,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            mint: mint,
+            metadata: metadata,
+            vaultAuthority: vaultAuthority,
+            vault: vault,
+            sellOrder: sellOrder,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).remainingAccounts([
+            {pubkey: currencyPDA, isWritable: false, isSigner: false},
+        ]).signers([seller]).rpc();
+    }
+
+    const buy = async (quantity: number, maxTotalPrice: number) => {
+        const [vaultAuthority, vault] = await getVault(itemMint);
+        await program.methods.buyToken22(new anchor.BN(quantity), new anchor.BN(maxTotalPrice)).accounts({
+            buyer: buyer.publicKey,
+            buyerNftTokenAccount: buyerItemAccount,
+            buyerPayingTokenAccount: buyerCurrencyAccount,
+            comptoir: comptoirPDA,
+            comptoirDestAccount: adminCurrencyAccount,
+            collection: collectionPDA,
+            sellerFundsDestAccount: sellerCurrencyAccount,
+            sellOrder: sellOrderPDA,
+            mint: itemMint,
+            metadata: itemMetadata,
+            vaultAuthority: vaultAuthority,
+            vault: vault,
+            paymentMint: currencyMint,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+            paymentTokenProgram: TOKEN_2022_PROGRAM_ID,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).remainingAccounts([
+            {pubkey: creatorCurrencyAccount, isWritable: true, isSigner: false},
+            {pubkey: currencyPDA, isWritable: false, isSigner: false},
+        ]).signers([buyer]).rpc();
+    }
+
+    it('Prepare tests variables', async () => {
+        admin = anchor.web3.Keypair.generate()
+        seller = anchor.web3.Keypair.generate()
+        buyer = anchor.web3.Keypair.generate()
+        for (let keypair of [admin, seller, buyer, aury]) {
+            let fromAirdropSignature = await provider.connection.requestAirdrop(
+                keypair.publicKey,
+                anchor.web3.LAMPORTS_PER_SOL,
+            );
+            await provider.connection.confirmTransaction(fromAirdropSignature);
+        }
+
+        [comptoirPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                admin.publicKey.toBuffer()
+            ],
+            program.programId,
+        )
+        comptoirMint = await splToken.Token.createMint(
+            provider.connection,
+            admin,
+            admin.publicKey,
+            null,
+            6,
+            splToken.TOKEN_PROGRAM_ID,
+        );
+        [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                comptoirMint.publicKey.toBuffer(),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+        const adminTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(admin.publicKey);
+        await program.methods.createComptoir(comptoirMint.publicKey, 200, adminTokenAccount.address, admin.publicKey)
+            .accounts({
+                payer: admin.publicKey,
+                comptoir: comptoirPDA,
+                mint: comptoirMint.publicKey,
+                escrow: escrowPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc();
+
+        [collectionPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                Buffer.from("T22"),
+                comptoirPDA.toBuffer(),
+            ],
+            program.programId,
+        );
+        await program.methods.createCollection("T22", "T22", aury.publicKey, null, false).accounts({
+            authority: admin.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([admin]).rpc();
+
+        adminCurrencyAccount = await createAssociatedAccount(admin.publicKey, currencyMint, 0);
+        sellerCurrencyAccount = await createAssociatedAccount(seller.publicKey, currencyMint, 0);
+        creatorCurrencyAccount = await createAssociatedAccount(aury.publicKey, currencyMint, 0);
+        buyerCurrencyAccount = await createAssociatedAccount(buyer.publicKey, currencyMint, 1000000);
+        sellerItemAccount = await createAssociatedAccount(seller.publicKey, itemMint, 5);
+        buyerItemAccount = await createAssociatedAccount(buyer.publicKey, itemMint, 0);
+
+        [currencyPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                currencyMint.toBuffer(),
+                Buffer.from("CURRENCY"),
+            ],
+            program.programId,
+        );
+        await program.methods.addToken22Currency(adminCurrencyAccount).accounts({
+            authority: admin.publicKey,
+            comptoir: comptoirPDA,
+            mint: currencyMint,
+            currency: currencyPDA,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([admin]).rpc();
+
+        [sellOrderPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from("COMPTOIR"), sellerItemAccount.toBuffer(), Buffer.from("10000")],
+            program.programId,
+        );
+    });
+
+    it('list Token-2022 items for a Token-2022 currency', async () => {
+        await createSellOrder(itemMint, itemMetadata, 10000, 3, sellOrderPDA);
+
+        const [, vault] = await getVault(itemMint);
+        assert.equal(await getBalance(vault), 3);
+        assert.equal(await getBalance(sellerItemAccount), 2);
+        let sellOrder = await program.account.sellOrder.fetch(sellOrderPDA);
+        assert.equal(sellOrder.quantity.toNumber(), 3);
+        assert.equal(sellOrder.paymentMint.toString(), currencyMint.toString());
+    });
+
+    it('fail: buy more items than listed', async () => {
+        await assert.rejects(
+            buy(4, 40000),
+            (err: any) => err.error.errorCode.code === "ErrCouldNotBuyEnoughItem"
+        );
+    });
+
+    it('fail: buy above the maximum price', async () => {
+        await assert.rejects(
+            buy(2, 19999),
+            (err: any) => err.error.errorCode.code === "ErrMaxTotalPriceExceeded"
+        );
+    });
+
+    it('buy, the recipients bearing the currency transfer fee', async () => {
+        await buy(2, 20000);
+
+        // 20000 split in 1000 for the creator, 400 for the comptoir and 18600 for the seller, less 1% each
+        assert.equal(await getBalance(buyerItemAccount), 2);
+        assert.equal(await getBalance(buyerCurrencyAccount), 1000000 - 20000);
+        assert.equal(await getBalance(sellerCurrencyAccount), 18600 - 186);
+        assert.equal(await getBalance(adminCurrencyAccount), 400 - 4);
+        assert.equal(await getBalance(creatorCurrencyAccount), 1000 - 10);
+        let sellOrder = await program.account.sellOrder.fetch(sellOrderPDA);
+        assert.equal(sellOrder.quantity.toNumber(), 1);
+    });
+
+    it('remove the rest of the sell order', async () => {
+        const [vaultAuthority, vault] = await getVault(itemMint);
+        await program.methods.removeToken22SellOrder(new anchor.BN(1)).accounts({
+            authority: seller.publicKey,
+            sellerNftTokenAccount: sellerItemAccount,
+            sellOrder: sellOrderPDA,
+            mint: itemMint,
+            vaultAuthority: vaultAuthority,
+            vault: vault,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+        }).signers([seller]).rpc();
+
+        assert.equal(await getBalance(sellerItemAccount), 3);
+        assert.equal(await provider.connection.getAccountInfo(sellOrderPDA), null);
+    });
+
+    it('fail: list an item with a transfer hook without its hook accounts', async () => {
+        const [sellOrder] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from("COMPTOIR"), sellerItemAccount.toBuffer(), Buffer.from("999")],
+            program.programId,
+        );
+        await assert.rejects(
+            createSellOrder(hookMint, hookMetadata, 999, 1, sellOrder),
+            (err: any) => err.error.errorCode.code === "ErrWrongTransferHookAccounts"
+        );
+    });
+});
+
+// The Token-2022 currency is the comptoir mint, its escrow holding offers and swap tokens. Items are SPL NFTs
+describe('Token-2022 comptoir', () => {
+    const aury = web3.Keypair.fromSecretKey(Uint8Array.from(auryKey));
+    const currencyMint = new PublicKey("AaNhJoXdeviETtjjzeJE4EpyZtfdfMoaqL8pmzTtQxFM");
+    let admin: web3.Keypair;
+    let creator: web3.Keypair;
+    let seller: web3.Keypair;
+    let buyer: web3.Keypair;
+    let comptoirPDA: PublicKey;
+    let escrowPDA: PublicKey;
+    let collectionPDA: PublicKey;
+    let adminCurrencyAccount: PublicKey;
+    let creatorCurrencyAccount: PublicKey;
+    let sellerCurrencyAccount: PublicKey;
+    let buyerCurrencyAccount: PublicKey;
+    let nftMints: splToken.Token[] = [];
+    let metadataPDAs: PublicKey[] = [];
+
+    const getAssociatedAccount = async (owner: PublicKey, mint: PublicKey): Promise<PublicKey> => {
+        return (await anchor.web3.PublicKey.findProgramAddress(
+            [owner.toBuffer(), TOKEN_2022_PROGRAM_ID.toBuffer(), mint.toBuffer()],
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+        ))[0];
+    }
+
+    const createAssociatedAccount = async (owner: PublicKey, amount: number): Promise<PublicKey> => {
+        const account = await getAssociatedAccount(owner, currencyMint);
+        const tx = new anchor.web3.Transaction().add(new TransactionInstruction({
+            programId: ASSOCIATED_TOKEN_PROGRAM_ID,
+            keys: [
+                {pubkey: aury.publicKey, isSigner: true, isWritable: true},
+                {pubkey: account, isSigner: false, isWritable: true},
+                {pubkey: owner, isSigner: false, isWritable: false},
+                {pubkey: currencyMint, isSigner: false, isWritable: false},
+                {pubkey: anchor.web3.SystemProgram.programId, isSigner: false, isWritable: false},
+                {pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false},
+            ],
+            data: Buffer.from([1]),
+        }));
+        if (amount > 0) {
+            tx.add(new TransactionInstruction({
+                programId: TOKEN_2022_PROGRAM_ID,
+                keys: [
+                    {pubkey: currencyMint, isSigner: false, isWritable: true},
+                    {pubkey: account, isSigner: false, isWritable: true},
+                    {pubkey: aury.publicKey, isSigner: true, isWritable: false},
+                ],
+                data: Buffer.concat([Buffer.from([7]), new anchor.BN(amount).toArrayLike(Buffer, "le", 8)]),
+            }));
+        }
+        await provider.sendAndConfirm(tx, [aury]);
+        return account;
+    }
+
+    const getBalance = async (account: PublicKey): Promise<number> => {
+        return Number((await provider.connection.getTokenAccountBalance(account)).value.amount);
+    }
+
+    const getOfferPDA = async (price: number): Promise<PublicKey> => {
+        return (await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                buyer.publicKey.toBuffer(),
+                collectionPDA.toBuffer(),
+                Buffer.from(price.toString()),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        ))[0];
+    }
+
+    const createOffer = async (price: number, quantity: number) => {
+        await program.methods.createToken22CollectionBuyOffer(new anchor.BN(price), new anchor.BN(quantity), null).accounts({
+            payer: buyer.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            escrow: escrowPDA,
+            buyerPayingAccount: buyerCurrencyAccount,
+            buyOffer: await getOfferPDA(price),
+            paymentMint: currencyMint,
+            paymentTokenProgram: TOKEN_2022_PROGRAM_ID,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([buyer]).rpc();
+    }
+
+    it('Prepare tests variables', async () => {
+        admin = anchor.web3.Keypair.generate()
+        creator = anchor.web3.Keypair.generate()
+        seller = anchor.web3.Keypair.generate()
+        buyer = anchor.web3.Keypair.generate()
+        for (let keypair of [admin, creator, seller, buyer, aury]) {
+            let fromAirdropSignature = await provider.connection.requestAirdrop(
+                keypair.publicKey,
+                anchor.web3.LAMPORTS_PER_SOL,
+            );
+            await provider.connection.confirmTransaction(fromAirdropSignature);
+        }
+
+        [comptoirPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from("COMPTOIR"), admin.publicKey.toBuffer()],
+            program.programId,
+        );
+        [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from("COMPTOIR"), comptoirPDA.toBuffer(), currencyMint.toBuffer(), Buffer.from("ESCROW")],
+            program.programId,
+        );
+        [collectionPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [Buffer.from("COMPTOIR"), Buffer.from("T22C"), comptoirPDA.toBuffer()],
+            program.programId,
+        );
+
+        adminCurrencyAccount = await createAssociatedAccount(admin.publicKey, 0);
+        creatorCurrencyAccount = await createAssociatedAccount(creator.publicKey, 0);
+        sellerCurrencyAccount = await createAssociatedAccount(seller.publicKey, 0);
+        buyerCurrencyAccount = await createAssociatedAccount(buyer.publicKey, 100000);
+
+        const data = nft_data(creator.publicKey);
+        const lamports = await Token.getMinBalanceRentForExemptMint(provider.connection);
+        for (let i = 0; i < 2; i++) {
+            const [mint, metadataAddr, tx] = await createMint(creator.publicKey, seller.publicKey, lamports, data, nft_json_url);
+            await provider.sendAndConfirm(tx, [mint, creator]);
+            nftMints.push(new Token(provider.connection, mint.publicKey, TOKEN_PROGRAM_ID, admin));
+            metadataPDAs.push(metadataAddr);
+        }
+    });
+
+    it('create a comptoir in a Token-2022 mint', async () => {
+        await program.methods.createComptoir(currencyMint, 200, adminCurrencyAccount, admin.publicKey)
+            .accounts({
+                payer: admin.publicKey,
+                comptoir: comptoirPDA,
+                mint: currencyMint,
+                escrow: escrowPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_2022_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc();
+        await program.methods.createCollection("T22C", "T22C", creator.publicKey, null, false).accounts({
+            authority: admin.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([admin]).rpc();
+
+        let comptoir = await program.account.comptoir.fetch(comptoirPDA);
+        assert.equal(comptoir.mint.toString(), currencyMint.toString());
+        let escrow = await provider.connection.getAccountInfo(escrowPDA);
+        assert.equal(escrow.owner.toString(), TOKEN_2022_PROGRAM_ID.toString());
+        assert.equal(await getBalance(escrowPDA), 0);
+    });
+
+    it('create an offer, the buyer paying the transfer fee to the escrow', async () => {
+        await createOffer(10000, 1);
+
+        // 10102 less its 1% fee of 102 leaves the 10000 of the offer in escrow
+        assert.equal(await getBalance(escrowPDA), 10000);
+        assert.equal(await getBalance(buyerCurrencyAccount), 100000 - 10102);
+        let buyOffer = await program.account.buyOffer.fetch(await getOfferPDA(10000));
+        assert.equal(buyOffer.paymentMint.toString(), currencyMint.toString());
+    });
+
+    it('execute the offer, the recipients bearing the transfer fee', async () => {
+        const sellerNftAccount = await nftMints[0].getOrCreateAssociatedAccountInfo(seller.publicKey);
+        const buyerNftAccount = await nftMints[0].getOrCreateAssociatedAccountInfo(buyer.publicKey);
+        await program.methods.executeToken22Offer(new anchor.BN(1), []).accounts({
+            seller: seller.publicKey,
+            buyer: buyer.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            comptoirDestAccount: adminCurrencyAccount,
+            escrow: escrowPDA,
+            sellerFundsDestAccount: sellerCurrencyAccount,
+            destination: buyerNftAccount.address,
+            sellerNftAccount: sellerNftAccount.address,
+            metadata: metadataPDAs[0],
+            buyOffer: await getOfferPDA(10000),
+            paymentMint: currencyMint,
+            paymentTokenProgram: TOKEN_2022_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        }).remainingAccounts([
+            {pubkey: creatorCurrencyAccount, isWritable: true, isSigner: false},
+        ]).signers([seller]).rpc();
+
+        // 10000 split in 1000 for the creator, 200 for the comptoir and 8800 for the seller, less 1% each
+        assert.equal((await nftMints[0].getAccountInfo(buyerNftAccount.address)).amount.toNumber(), 1);
+        assert.equal(await getBalance(escrowPDA), 0);
+        assert.equal(await getBalance(sellerCurrencyAccount), 8800 - 88);
+        assert.equal(await getBalance(adminCurrencyAccount), 200 - 2);
+        assert.equal(await getBalance(creatorCurrencyAccount), 1000 - 10);
+        assert.equal(await provider.connection.getAccountInfo(await getOfferPDA(10000)), null);
+    });
+
+    it('remove an offer, the buyer bearing the fee of the refund', async () => {
+        await createOffer(5000, 2);
+        const before = await getBalance(buyerCurrencyAccount);
+        assert.equal(await getBalance(escrowPDA), 10000);
+
+        await program.methods.removeToken22BuyOffer().accounts({
+            buyer: buyer.publicKey,
+            buyerPayingAccount: buyerCurrencyAccount,
+            comptoir: comptoirPDA,
+            escrow: escrowPDA,
+            buyOffer: await getOfferPDA(5000),
+            paymentMint: currencyMint,
+            paymentTokenProgram: TOKEN_2022_PROGRAM_ID,
+        }).signers([buyer]).rpc();
+
+        assert.equal(await getBalance(escrowPDA), 0);
+        assert.equal(await getBalance(buyerCurrencyAccount), before + 10000 - 100);
+        assert.equal(await provider.connection.getAccountInfo(await getOfferPDA(5000)), null);
+    });
+
+    it('swap tokens of the comptoir mint for an item', async () => {
+        const [swapOfferPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                buyer.publicKey.toBuffer(),
+                Buffer.from("SWAP"),
+                new anchor.BN(1).toArrayLike(Buffer, "le", 8),
+            ],
+            program.programId,
+        );
+        await program.methods.createToken22SwapOffer(new anchor.BN(1), [{mint: {mint: nftMints[1].publicKey}}], new anchor.BN(3000), null).accounts({
+            payer: buyer.publicKey,
+            payerPayingAccount: buyerCurrencyAccount,
+            comptoir: comptoirPDA,
+            escrow: escrowPDA,
+            swapOffer: swapOfferPDA,
+            paymentMint: currencyMint,
+            paymentTokenProgram: TOKEN_2022_PROGRAM_ID,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([buyer]).rpc();
+        assert.equal(await getBalance(escrowPDA), 3000);
+
+        const sellerBefore = await getBalance(sellerCurrencyAccount);
+        const adminBefore = await getBalance(adminCurrencyAccount);
+        const sellerNftAccount = await nftMints[1].getOrCreateAssociatedAccountInfo(seller.publicKey);
+        const buyerNftAccount = await nftMints[1].getOrCreateAssociatedAccountInfo(buyer.publicKey);
+        await program.methods.acceptToken22SwapOffer().accounts({
+            counterparty: seller.publicKey,
+            counterpartyFundsAccount: sellerCurrencyAccount,
+            comptoir: comptoirPDA,
+            comptoirDestAccount: adminCurrencyAccount,
+            escrow: escrowPDA,
+            authority: buyer.publicKey,
+            swapOffer: swapOfferPDA,
+            paymentMint: currencyMint,
+            paymentTokenProgram: TOKEN_2022_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+        }).remainingAccounts([
+            {pubkey: sellerNftAccount.address, isWritable: true, isSigner: false},
+            {pubkey: buyerNftAccount.address, isWritable: true, isSigner: false},
+        ]).signers([seller]).rpc();
+
+        // 3000 split in 60 for the comptoir and 2940 for the seller, less 1% each
+        assert.equal((await nftMints[1].getAccountInfo(buyerNftAccount.address)).amount.toNumber(), 1);
+        assert.equal(await getBalance(escrowPDA), 0);
+        assert.equal(await getBalance(sellerCurrencyAccount), sellerBefore + 2940 - 30);
+        assert.equal(await getBalance(adminCurrencyAccount), adminBefore + 60 - 1);
+    });
+});
@@ -0,0 +1,222 @@
+import * as anchor from '@project-serum/anchor';
+import {Program, web3} from '@project-serum/anchor';
+import {Comptoir} from '../target/types/comptoir';
+import * as splToken from '@solana/spl-token';
+import {PublicKey} from "@solana/web3.js";
+import {Token, TOKEN_PROGRAM_ID} from "@solana/spl-token";
+import assert from "assert";
+import {keccak_256} from "js-sha3";
+import {nft_data, nft_json_url} from "./data";
+import {createMint} from "./utils/utils";
+
+let provider = anchor.getProvider()
+anchor.setProvider(provider);
+
+const program = anchor.workspace.Comptoir as Program<Comptoir>;
+
+const traitHash = (trait: string): Buffer => Buffer.from(keccak_256.arrayBuffer(trait));
+
+const traitLeaf = (mint: PublicKey, hash: Buffer): Buffer =>
+    Buffer.from(keccak_256.arrayBuffer(Buffer.concat([Buffer.from([0]), mint.toBuffer(), hash])));
+
+const hashPair = (a: Buffer, b: Buffer): Buffer => {
+    let [first, second] = Buffer.compare(a, b) <= 0 ? [a, b] : [b, a];
+    return Buffer.from(keccak_256.arrayBuffer(Buffer.concat([Buffer.from([1]), first, second])));
+}
+
+describe('trait buy offers', () => {
+    let admin: web3.Keypair;
+    let adminTokenAccount: splToken.AccountInfo;
+    let creator: web3.Keypair;
+    let creatorTokenAccount: splToken.AccountInfo;
+    let seller: web3.Keypair;
+    let sellerTokenAccount: splToken.AccountInfo;
+    let buyer: web3.Keypair;
+    let buyerTokenAccount: splToken.AccountInfo;
+    let comptoirPDA: PublicKey;
+    let comptoirMint: splToken.Token;
+    let collectionName = "AURY"
+    let collectionPDA: PublicKey;
+    let nftMints: splToken.Token[] = [];
+    let metadataPDAs: PublicKey[] = [];
+    let leaves: Buffer[] = [];
+    let escrowPDA: PublicKey;
+    let buyOfferPDA: PublicKey;
+    let legendary = traitHash("rarity:Legendary");
+
+    const executeOffer = async (index: number, proof: Buffer[]) => {
+        let sellerNftAccount = await nftMints[index].getOrCreateAssociatedAccountInfo(seller.publicKey)
+        let buyerNftAccount = await nftMints[index].getOrCreateAssociatedAccountInfo(buyer.publicKey)
+
+        await program.methods.executeOffer(new anchor.BN(1), proof.map(node => Array.from(node))).accounts({
+            seller: seller.publicKey,
+            buyer: buyer.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            comptoirDestAccount: adminTokenAccount.address,
+            escrow: escrowPDA,
+            sellerFundsDestAccount: sellerTokenAccount.address,
+            destination: buyerNftAccount.address,
+            sellerNftAccount: sellerNftAccount.address,
+            metadata: metadataPDAs[index],
+            buyOffer: buyOfferPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).remainingAccounts([
+            {pubkey: creatorTokenAccount.address, isWritable: true, isSigner: false},
+        ]).signers([seller]).rpc()
+    }
+
+    it('Prepare tests variables', async () => {
+        admin = anchor.web3.Keypair.generate()
+        creator = anchor.web3.Keypair.generate()
+        seller = anchor.web3.Keypair.generate()
+        buyer = anchor.web3.Keypair.generate()
+        for (let keypair of [admin, creator, seller, buyer]) {
+            let fromAirdropSignature = await provider.connection.requestAirdrop(
+                keypair.publicKey,
+                anchor.web3.LAMPORTS_PER_SOL,
+            );
+            await provider.connection.confirmTransaction(fromAirdropSignature);
+        }
+
+        [comptoirPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                admin.publicKey.toBuffer()
+            ],
+            program.programId,
+        )
+
+        comptoirMint = await splToken.Token.createMint(
+            provider.connection,
+            admin,
+            admin.publicKey,
+            null,
+            6,
+            splToken.TOKEN_PROGRAM_ID,
+        );
+
+        [escrowPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                comptoirMint.publicKey.toBuffer(),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+
+        adminTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(admin.publicKey);
+        creatorTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(creator.publicKey);
+        sellerTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(seller.publicKey);
+        buyerTokenAccount = await comptoirMint.getOrCreateAssociatedAccountInfo(buyer.publicKey);
+        await comptoirMint.mintTo(buyerTokenAccount.address, admin, [], 1000)
+
+        [collectionPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                Buffer.from(collectionName),
+                comptoirPDA.toBuffer(),
+            ],
+            program.programId,
+        );
+
+        const data = nft_data(creator.publicKey);
+        const lamports = await Token.getMinBalanceRentForExemptMint(
+            provider.connection
+        );
+        for (let i = 0; i < 2; i++) {
+            const [mint, metadataAddr, tx] = await createMint(
+                creator.publicKey,
+                seller.publicKey,
+                lamports,
+                data,
+                nft_json_url
+            );
+            await provider.sendAndConfirm(tx, [mint, creator]);
+            nftMints.push(new Token(provider.connection, mint.publicKey, TOKEN_PROGRAM_ID, admin));
+            metadataPDAs.push(metadataAddr);
+        }
+        leaves.push(traitLeaf(nftMints[0].publicKey, legendary));
+        leaves.push(traitLeaf(nftMints[1].publicKey, traitHash("rarity:Common")));
+
+        [buyOfferPDA] = await anchor.web3.PublicKey.findProgramAddress(
+            [
+                Buffer.from("COMPTOIR"),
+                comptoirPDA.toBuffer(),
+                buyer.publicKey.toBuffer(),
+                collectionPDA.toBuffer(),
+                legendary,
+                Buffer.from("1000"),
+                Buffer.from("ESCROW"),
+            ],
+            program.programId,
+        );
+
+        await program.methods.createComptoir(comptoirMint.publicKey, 200, adminTokenAccount.address, admin.publicKey)
+            .accounts({
+                payer: admin.publicKey,
+                comptoir: comptoirPDA,
+                mint: comptoirMint.publicKey,
+                escrow: escrowPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc();
+
+        await program.methods.createCollection(collectionName, collectionName, creator.publicKey, null, false).accounts(
+            {
+                authority: admin.publicKey,
+                comptoir: comptoirPDA,
+                collection: collectionPDA,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+            }).signers([admin]).rpc()
+    });
+
+    it('set collection traits root', async () => {
+        let root = hashPair(leaves[0], leaves[1]);
+        await program.methods.setCollectionTraitsRoot(Array.from(root)).accounts({
+            authority: admin.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+        }).signers([admin]).rpc()
+
+        let collection = await program.account.collection.fetch(collectionPDA)
+        assert.equal(Buffer.from(collection.traitsRoot).toString('hex'), root.toString('hex'));
+    });
+
+    it('create trait offer', async () => {
+        await program.methods.createTraitBuyOffer(new anchor.BN(1000), new anchor.BN(1), Array.from(legendary), null).accounts({
+            payer: buyer.publicKey,
+            comptoir: comptoirPDA,
+            collection: collectionPDA,
+            escrow: escrowPDA,
+            buyerPayingAccount: buyerTokenAccount.address,
+            buyOffer: buyOfferPDA,
+            systemProgram: anchor.web3.SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        }).signers([buyer]).rpc()
+
+        let buyOffer = await program.account.buyOffer.fetch(buyOfferPDA)
+        assert.equal(Buffer.from(buyOffer.traitHash).toString('hex'), legendary.toString('hex'));
+    });
+
+    it('fail: item without the trait', async () => {
+        await assert.rejects(executeOffer(1, [leaves[0]]));
+    });
+
+    it('item with the trait fills the offer', async () => {
+        await executeOffer(0, [leaves[1]])
+
+        let closedBuyOffer = await provider.connection.getAccountInfo(buyOfferPDA);
+        assert.equal(closedBuyOffer, null);
+        let buyerNftAccount = await nftMints[0].getOrCreateAssociatedAccountInfo(buyer.publicKey)
+        assert.equal(buyerNftAccount.amount.toNumber(), 1);
+        let updatedSellerTokenAccount = await comptoirMint.getAccountInfo(sellerTokenAccount.address)
+        assert.equal(updatedSellerTokenAccount.amount.toNumber(), 880);
+    });
+});
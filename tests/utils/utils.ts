@@ -1,5 +1,12 @@
 import { programs } from '@metaplex/js';
-import { Keypair, PublicKey, SystemProgram } from '@solana/web3.js';
+import {
+    Keypair,
+    PublicKey,
+    SystemProgram,
+    SYSVAR_INSTRUCTIONS_PUBKEY,
+    SYSVAR_RENT_PUBKEY,
+    TransactionInstruction,
+} from '@solana/web3.js';
 import {
     ASSOCIATED_TOKEN_PROGRAM_ID,
     MintLayout,
@@ -7,8 +14,7 @@ import {
 } from '@solana/spl-token';
 import { Token } from '@solana/spl-token';
 
-const { Metadata, MetadataDataData, CreateMetadata, Creator } =
-    programs.metadata;
+const { Metadata } = programs.metadata;
 const Transaction = programs.Transaction;
 
 export async function createMint(
@@ -63,33 +69,183 @@ export async function createMint(
         )
     );
 
-    const metadataPDA = await Metadata.getPDA(mint.publicKey);
-    const metadataData = new MetadataDataData({
-        name: data.name,
-        symbol: 'AURY',
-        uri: json_url,
-        sellerFeeBasisPoints: data.seller_fee_basis_points,
-        creators: [
-            new Creator({
-                address: fee_payer.toString(),
-                verified: true,
-                share: 100,
-            }),
-        ],
-    });
-    const tx_metadata = new CreateMetadata(
-        {
-            feePayer: fee_payer,
-        },
-        {
-            metadata: metadataPDA,
-            metadataData,
-            updateAuthority: fee_payer,
-            mint: mint.publicKey,
-            mintAuthority: fee_payer,
-        }
-    );
+    const [metadataPDA, tx_metadata] = await createMetadata(fee_payer, mint.publicKey, 'AURY', data, json_url);
 
     const tx = Transaction.fromCombined([tx_mint, tx_metadata]);
     return [mint, metadataPDA, tx];
-}
\ No newline at end of file
+}
+
+const TOKEN_METADATA_PROGRAM_ID = new PublicKey('metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s');
+// Token metadata instructions, the creation ones before V3 are refused by the current program
+const CREATE_METADATA_ACCOUNT_V3 = 33;
+const CREATE = 42;
+const MINT = 43;
+const PROGRAMMABLE_NON_FUNGIBLE = 4;
+
+// Borsh encoding of the few types the token metadata instructions take
+const borshString = (value: string): Buffer => {
+    const bytes = Buffer.from(value);
+    const length = Buffer.alloc(4);
+    length.writeUInt32LE(bytes.length);
+    return Buffer.concat([length, bytes]);
+}
+
+const borshU16 = (value: number): Buffer => {
+    const bytes = Buffer.alloc(2);
+    bytes.writeUInt16LE(value);
+    return bytes;
+}
+
+const borshU64 = (value: number): Buffer => {
+    const bytes = Buffer.alloc(8);
+    bytes.writeUInt32LE(value % 0x100000000);
+    bytes.writeUInt32LE(Math.floor(value / 0x100000000), 4);
+    return bytes;
+}
+
+// The single creator is verified, it must sign as the update authority
+const borshCreators = (creator: PublicKey): Buffer => {
+    return Buffer.concat([
+        Buffer.from([1, 1, 0, 0, 0]),
+        creator.toBuffer(),
+        Buffer.from([1, 100]),
+    ]);
+}
+
+export async function getMasterEdition(mint: PublicKey): Promise<PublicKey> {
+    return (await PublicKey.findProgramAddress(
+        [Buffer.from('metadata'), TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer(), Buffer.from('edition')],
+        TOKEN_METADATA_PROGRAM_ID,
+    ))[0];
+}
+
+export async function getTokenRecord(mint: PublicKey, token: PublicKey): Promise<PublicKey> {
+    return (await PublicKey.findProgramAddress(
+        [Buffer.from('metadata'), TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer(), Buffer.from('token_record'), token.toBuffer()],
+        TOKEN_METADATA_PROGRAM_ID,
+    ))[0];
+}
+
+// The fee payer is the mint authority, update authority and only verified creator
+export async function createMetadata(
+    fee_payer: PublicKey,
+    mint: PublicKey,
+    symbol: string,
+    data: any,
+    json_url: string
+): Promise<[PublicKey, programs.Transaction]> {
+    const metadataPDA = await Metadata.getPDA(mint);
+    const instructionData = Buffer.concat([
+        Buffer.from([CREATE_METADATA_ACCOUNT_V3]),
+        borshString(data.name),
+        borshString(symbol),
+        borshString(json_url),
+        borshU16(data.seller_fee_basis_points),
+        borshCreators(fee_payer),
+        Buffer.from([
+            0, // No collection
+            0, // No uses
+            1, // Mutable
+            0, // No collection details
+        ]),
+    ]);
+    const tx_metadata = new Transaction({ feePayer: fee_payer });
+    tx_metadata.add(new TransactionInstruction({
+        programId: TOKEN_METADATA_PROGRAM_ID,
+        keys: [
+            { pubkey: metadataPDA, isSigner: false, isWritable: true },
+            { pubkey: mint, isSigner: false, isWritable: false },
+            { pubkey: fee_payer, isSigner: true, isWritable: false },
+            { pubkey: fee_payer, isSigner: true, isWritable: true },
+            { pubkey: fee_payer, isSigner: true, isWritable: false },
+            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+            { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+        ],
+        data: instructionData,
+    }));
+    return [metadataPDA, tx_metadata];
+}
+
+// Creates a programmable NFT without rule set through token metadata and mints it to the associated account of the
+// owner, where it stays frozen. The fee payer is the mint authority, update authority and only verified creator
+export async function createProgrammableMint(
+    fee_payer: PublicKey,
+    dest_owner: PublicKey,
+    symbol: string,
+    data: any,
+    json_url: string
+): Promise<[Keypair, PublicKey, programs.Transaction]> {
+    const mint = Keypair.generate();
+    const metadataPDA = await Metadata.getPDA(mint.publicKey);
+    const edition = await getMasterEdition(mint.publicKey);
+    const ata = await Token.getAssociatedTokenAddress(
+        ASSOCIATED_TOKEN_PROGRAM_ID,
+        TOKEN_PROGRAM_ID,
+        mint.publicKey,
+        dest_owner,
+    );
+    const createData = Buffer.concat([
+        Buffer.from([CREATE, 0]), // CreateArgs::V1
+        borshString(data.name),
+        borshString(symbol),
+        borshString(json_url),
+        borshU16(data.seller_fee_basis_points),
+        borshCreators(fee_payer),
+        Buffer.from([
+            0, // Primary sale not happened
+            1, // Mutable
+            PROGRAMMABLE_NON_FUNGIBLE,
+            0, // No collection
+            0, // No uses
+            0, // No collection details
+            0, // No rule set
+            1, 0, // Decimals
+            1, 0, // Print supply: zero
+        ]),
+    ]);
+    const mintData = Buffer.concat([
+        Buffer.from([MINT, 0]), // MintArgs::V1
+        borshU64(1),
+        Buffer.from([0]), // No authorization data
+    ]);
+    const tx = new Transaction({ feePayer: fee_payer });
+    tx.add(
+        new TransactionInstruction({
+            programId: TOKEN_METADATA_PROGRAM_ID,
+            keys: [
+                { pubkey: metadataPDA, isSigner: false, isWritable: true },
+                { pubkey: edition, isSigner: false, isWritable: true },
+                { pubkey: mint.publicKey, isSigner: true, isWritable: true },
+                { pubkey: fee_payer, isSigner: true, isWritable: false },
+                { pubkey: fee_payer, isSigner: true, isWritable: true },
+                { pubkey: fee_payer, isSigner: true, isWritable: false },
+                { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+                { pubkey: SYSVAR_INSTRUCTIONS_PUBKEY, isSigner: false, isWritable: false },
+                { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+            ],
+            data: createData,
+        }),
+        new TransactionInstruction({
+            programId: TOKEN_METADATA_PROGRAM_ID,
+            keys: [
+                { pubkey: ata, isSigner: false, isWritable: true },
+                { pubkey: dest_owner, isSigner: false, isWritable: false },
+                { pubkey: metadataPDA, isSigner: false, isWritable: true },
+                { pubkey: edition, isSigner: false, isWritable: true },
+                { pubkey: await getTokenRecord(mint.publicKey, ata), isSigner: false, isWritable: true },
+                { pubkey: mint.publicKey, isSigner: false, isWritable: true },
+                { pubkey: fee_payer, isSigner: true, isWritable: false },
+                { pubkey: TOKEN_METADATA_PROGRAM_ID, isSigner: false, isWritable: false }, // No delegate record
+                { pubkey: fee_payer, isSigner: true, isWritable: true },
+                { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+                { pubkey: SYSVAR_INSTRUCTIONS_PUBKEY, isSigner: false, isWritable: false },
+                { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+                { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+                { pubkey: TOKEN_METADATA_PROGRAM_ID, isSigner: false, isWritable: false }, // No rules program
+                { pubkey: TOKEN_METADATA_PROGRAM_ID, isSigner: false, isWritable: false }, // No rule set
+            ],
+            data: mintData,
+        }),
+    );
+    return [mint, metadataPDA, tx];
+}